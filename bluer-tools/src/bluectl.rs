@@ -0,0 +1,351 @@
+//! Interactive Bluetooth shell.
+
+use clap::Parser;
+use futures::{pin_mut, StreamExt};
+use std::{collections::HashMap, time::Duration};
+use tokio::{
+    io::{stdin, AsyncBufReadExt, BufReader},
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
+
+use bluer::{
+    agent::{Agent, ReqError, ReqResult},
+    gatt::remote::Characteristic,
+    Adapter, AdapterEvent, Address, Device, Session, Uuid,
+};
+
+type AnyResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Parser)]
+#[clap(name = "bluectl", about = "Interactive Bluetooth shell similar to bluetoothctl")]
+struct Opt {
+    /// Name of Bluetooth adapter to use initially.
+    #[clap(long, short)]
+    adapter: Option<String>,
+}
+
+const HELP: &str = "\
+Commands:
+  list                           list available adapters
+  select <adapter>               select adapter to use
+  show                           show information about the selected adapter
+  power <on|off>                 switch power of the selected adapter
+  discoverable <on|off>          set adapter discoverable
+  scan <on|off>                  start or stop device discovery
+  devices                        list known devices
+  info <address>                 show information about a device
+  pair <address>                 pair with a device
+  trust <address>                trust a device
+  untrust <address>              revoke trust of a device
+  connect <address>              connect to a device
+  disconnect <address>           disconnect from a device
+  remove <address>               remove a device
+  gatt <address>                 list GATT services and characteristics of a device
+  read <address> <uuid>          read a GATT characteristic
+  write <address> <uuid> <hex>   write a GATT characteristic
+  notify <address> <uuid>        print notifications of a GATT characteristic
+  help                           show this help
+  quit                           exit the shell";
+
+/// Question asked by the pairing agent, answered by the next input line.
+struct Prompt {
+    question: String,
+    answer_tx: oneshot::Sender<String>,
+}
+
+/// Sends a question to the user and waits for the answer.
+async fn ask(prompt_tx: &mpsc::Sender<Prompt>, question: String) -> ReqResult<String> {
+    let (answer_tx, answer_rx) = oneshot::channel();
+    prompt_tx.send(Prompt { question, answer_tx }).await.map_err(|_| ReqError::Canceled)?;
+    answer_rx.await.map_err(|_| ReqError::Canceled)
+}
+
+fn is_yes(answer: &str) -> bool {
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn agent(prompt_tx: mpsc::Sender<Prompt>) -> Agent {
+    let pin_tx = prompt_tx.clone();
+    let passkey_tx = prompt_tx.clone();
+    let confirm_tx = prompt_tx.clone();
+    let authorize_tx = prompt_tx.clone();
+    let service_tx = prompt_tx;
+    Agent {
+        request_default: true,
+        request_pin_code: Some(Box::new(move |req| {
+            let tx = pin_tx.clone();
+            Box::pin(async move { ask(&tx, format!("Enter PIN code for {}:", req.device)).await })
+        })),
+        display_pin_code: Some(Box::new(|req| {
+            Box::pin(async move {
+                println!("PIN code for {}: {}", req.device, req.pincode);
+                Ok(())
+            })
+        })),
+        request_passkey: Some(Box::new(move |req| {
+            let tx = passkey_tx.clone();
+            Box::pin(async move {
+                let answer = ask(&tx, format!("Enter passkey for {}:", req.device)).await?;
+                answer.trim().parse().map_err(|_| ReqError::Rejected)
+            })
+        })),
+        display_passkey: Some(Box::new(|req| {
+            Box::pin(async move {
+                println!("Passkey for {}: {:06} (entered {} digits)", req.device, req.passkey, req.entered);
+                Ok(())
+            })
+        })),
+        request_confirmation: Some(Box::new(move |req| {
+            let tx = confirm_tx.clone();
+            Box::pin(async move {
+                let question = format!("Confirm passkey {:06} for {} (yes/no):", req.passkey, req.device);
+                match is_yes(&ask(&tx, question).await?) {
+                    true => Ok(()),
+                    false => Err(ReqError::Rejected),
+                }
+            })
+        })),
+        request_authorization: Some(Box::new(move |req| {
+            let tx = authorize_tx.clone();
+            Box::pin(async move {
+                match is_yes(&ask(&tx, format!("Authorize pairing with {} (yes/no):", req.device)).await?) {
+                    true => Ok(()),
+                    false => Err(ReqError::Rejected),
+                }
+            })
+        })),
+        authorize_service: Some(Box::new(move |req| {
+            let tx = service_tx.clone();
+            Box::pin(async move {
+                let question = format!("Authorize service {} for {} (yes/no):", req.service, req.device);
+                match is_yes(&ask(&tx, question).await?) {
+                    true => Ok(()),
+                    false => Err(ReqError::Rejected),
+                }
+            })
+        })),
+        ..Default::default()
+    }
+}
+
+struct Shell {
+    session: Session,
+    adapter: Option<Adapter>,
+    scan: Option<JoinHandle<()>>,
+    notify: HashMap<(Address, Uuid), JoinHandle<()>>,
+}
+
+impl Shell {
+    fn adapter(&self) -> AnyResult<&Adapter> {
+        self.adapter.as_ref().ok_or_else(|| "no adapter selected".into())
+    }
+
+    fn device(&self, addr: Option<&str>) -> AnyResult<Device> {
+        let addr: Address = addr.ok_or("device address required")?.parse()?;
+        Ok(self.adapter()?.device(addr)?)
+    }
+
+    async fn characteristic(&self, addr: Option<&str>, uuid: Option<&str>) -> AnyResult<Characteristic> {
+        let device = self.device(addr)?;
+        let uuid: Uuid = uuid.ok_or("characteristic UUID required")?.parse()?;
+        for service in device.services().await? {
+            for char in service.characteristics().await? {
+                if char.uuid().await? == uuid {
+                    return Ok(char);
+                }
+            }
+        }
+        Err("characteristic not found".into())
+    }
+
+    /// Executes a command line and returns whether the shell should continue.
+    async fn execute(&mut self, line: &str) -> AnyResult<bool> {
+        let mut args = line.split_whitespace();
+        let Some(cmd) = args.next() else { return Ok(true) };
+        let arg1 = args.next();
+        let arg2 = args.next();
+        let arg3 = args.next();
+
+        match cmd {
+            "help" => println!("{HELP}"),
+            "quit" | "exit" => return Ok(false),
+            "list" => {
+                for name in self.session.adapter_names().await? {
+                    let adapter = self.session.adapter(&name)?;
+                    println!("{} {} {}", name, adapter.address().await?, adapter.alias().await?);
+                }
+            }
+            "select" => {
+                let name = arg1.ok_or("adapter name required")?;
+                let adapter = self.session.adapter(name)?;
+                adapter.address().await?;
+                self.adapter = Some(adapter);
+            }
+            "show" => {
+                let adapter = self.adapter()?;
+                println!("Adapter {}", adapter.name());
+                for prop in adapter.all_properties().await? {
+                    println!("    {prop:?}");
+                }
+            }
+            "power" => self.adapter()?.set_powered(parse_on_off(arg1)?).await?,
+            "discoverable" => self.adapter()?.set_discoverable(parse_on_off(arg1)?).await?,
+            "scan" => {
+                if let Some(scan) = self.scan.take() {
+                    scan.abort();
+                }
+                if parse_on_off(arg1)? {
+                    let events = self.adapter()?.discover_devices().await?;
+                    self.scan = Some(tokio::spawn(async move {
+                        pin_mut!(events);
+                        while let Some(evt) = events.next().await {
+                            match evt {
+                                AdapterEvent::DeviceAdded(addr) => println!("[NEW] Device {addr}"),
+                                AdapterEvent::DeviceRemoved(addr) => println!("[DEL] Device {addr}"),
+                                _ => (),
+                            }
+                        }
+                    }));
+                }
+            }
+            "devices" => {
+                let adapter = self.adapter()?;
+                for addr in adapter.device_addresses().await? {
+                    let device = adapter.device(addr)?;
+                    println!("Device {} {}", addr, device.name().await?.unwrap_or_default());
+                }
+            }
+            "info" => {
+                let device = self.device(arg1)?;
+                println!("Device {}", device.address());
+                for prop in device.all_properties().await? {
+                    println!("    {prop:?}");
+                }
+            }
+            "pair" => self.device(arg1)?.pair().await?,
+            "trust" => self.device(arg1)?.set_trusted(true).await?,
+            "untrust" => self.device(arg1)?.set_trusted(false).await?,
+            "connect" => self.device(arg1)?.connect().await?,
+            "disconnect" => self.device(arg1)?.disconnect().await?,
+            "remove" => {
+                let addr: Address = arg1.ok_or("device address required")?.parse()?;
+                self.adapter()?.remove_device(addr).await?;
+            }
+            "gatt" => {
+                let device = self.device(arg1)?;
+                for service in device.services().await? {
+                    println!("Service {} {}", service.id(), service.uuid().await?);
+                    for char in service.characteristics().await? {
+                        println!(
+                            "    Characteristic {} {} {:?}",
+                            char.id(),
+                            char.uuid().await?,
+                            char.flags().await?
+                        );
+                    }
+                }
+            }
+            "read" => {
+                let char = self.characteristic(arg1, arg2).await?;
+                println!("{}", hex::encode(char.read().await?));
+            }
+            "write" => {
+                let char = self.characteristic(arg1, arg2).await?;
+                let value = hex::decode(arg3.ok_or("value required")?)?;
+                char.write(&value).await?;
+            }
+            "notify" => {
+                let char = self.characteristic(arg1, arg2).await?;
+                let key = (self.device(arg1)?.address(), char.uuid().await?);
+                if let Some(task) = self.notify.remove(&key) {
+                    task.abort();
+                } else {
+                    let values = char.notify().await?;
+                    let (addr, uuid) = key;
+                    self.notify.insert(
+                        key,
+                        tokio::spawn(async move {
+                            pin_mut!(values);
+                            while let Some(value) = values.next().await {
+                                println!("[NOTIFY] {addr} {uuid}: {}", hex::encode(value));
+                            }
+                        }),
+                    );
+                }
+            }
+            other => return Err(format!("unknown command: {other}").into()),
+        }
+
+        Ok(true)
+    }
+}
+
+fn parse_on_off(arg: Option<&str>) -> AnyResult<bool> {
+    match arg {
+        Some("on") => Ok(true),
+        Some("off") => Ok(false),
+        _ => Err("on or off required".into()),
+    }
+}
+
+#[tokio::main]
+async fn main() -> AnyResult<()> {
+    env_logger::init();
+    let opt = Opt::parse();
+
+    let session = Session::new().await?;
+    let adapter = match opt.adapter {
+        Some(name) => session.adapter(&name)?,
+        None => session.default_adapter().await?,
+    };
+    println!("Using adapter {} with address {}", adapter.name(), adapter.address().await?);
+
+    let (prompt_tx, mut prompt_rx) = mpsc::channel(1);
+    let _agent = session.register_agent(agent(prompt_tx)).await?;
+
+    let mut shell = Shell { session, adapter: Some(adapter), scan: None, notify: HashMap::new() };
+    let mut lines = BufReader::new(stdin()).lines();
+    let mut pending: Option<Prompt> = None;
+
+    println!("Type \"help\" for a list of commands.");
+    loop {
+        tokio::select! {
+            Some(prompt) = prompt_rx.recv(), if pending.is_none() => {
+                println!("[AGENT] {}", prompt.question);
+                pending = Some(prompt);
+            }
+            line = lines.next_line() => {
+                let Some(line) = line? else { break };
+                if let Some(prompt) = pending.take() {
+                    let _ = prompt.answer_tx.send(line);
+                    continue;
+                }
+
+                // Commands run concurrently with agent prompts, so that pairing can be answered.
+                let cmd = shell.execute(&line);
+                pin_mut!(cmd);
+                let result = loop {
+                    tokio::select! {
+                        result = &mut cmd => break result,
+                        Some(prompt) = prompt_rx.recv() => {
+                            println!("[AGENT] {}", prompt.question);
+                            let answer = tokio::time::timeout(Duration::from_secs(60), lines.next_line()).await;
+                            match answer {
+                                Ok(Ok(Some(answer))) => { let _ = prompt.answer_tx.send(answer); }
+                                _ => drop(prompt),
+                            }
+                        }
+                    }
+                };
+                match result {
+                    Ok(true) => (),
+                    Ok(false) => break,
+                    Err(err) => eprintln!("Error: {err}"),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+//! Benchmarks decoding and dispatching property change events under a flood of RSSI updates.
+
+use bluer::bench::{
+    handle_properties_changed, properties_changed_message, rssi_and_manufacturer_data_changed, rssi_changed,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+fn property_events(c: &mut Criterion) {
+    let mut group = c.benchmark_group("property_events");
+
+    let rssi = properties_changed_message(rssi_changed(-60));
+    let manufacturer_data = properties_changed_message(rssi_and_manufacturer_data_changed(-60, vec![0x02; 23]));
+    for subscriptions in [1, 4] {
+        group.bench_with_input(BenchmarkId::new("rssi", subscriptions), &subscriptions, |b, &subscriptions| {
+            b.iter(|| handle_properties_changed(black_box(&rssi), subscriptions))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("rssi_and_manufacturer_data", subscriptions),
+            &subscriptions,
+            |b, &subscriptions| {
+                b.iter(|| handle_properties_changed(black_box(&manufacturer_data), subscriptions))
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, property_events);
+criterion_main!(benches);
@@ -1,48 +1,67 @@
 //! Bluetooth adapter.
 
 use dbus::{
-    arg::{PropMap, RefArg, Variant},
+    arg::{prop_cast, PropMap, RefArg, Variant},
     nonblock::{Proxy, SyncConnection},
     Path,
 };
 use futures::{
+    channel::oneshot,
     future,
     stream::{self, SelectAll},
-    Stream, StreamExt,
+    Future, Stream, StreamExt,
 };
 use std::{
     collections::{BTreeSet, HashMap, HashSet},
     fmt::{Debug, Formatter},
-    sync::Arc,
+    mem,
+    sync::{Arc, Mutex as SyncMutex},
+    time::Duration,
 };
 use strum::{Display, EnumString};
-use tokio::sync::mpsc;
+use tokio::{
+    sync::mpsc,
+    time::{sleep, Instant},
+};
 use tokio_stream::wrappers::ReceiverStream;
 use uuid::Uuid;
 
 use crate::{
     adv,
-    adv::{Advertisement, AdvertisementHandle, Capabilities, Feature, PlatformFeature, SecondaryChannel},
-    all_dbus_objects, device,
-    device::Device,
-    gatt,
-    monitor::MonitorManager,
-    Address, AddressType, Error, ErrorKind, Event, InternalErrorKind, Modalias, Result, SessionInner,
-    SingleSessionToken, SERVICE_NAME, TIMEOUT,
+    adv::{Capabilities, Feature, PlatformFeature, SecondaryChannel},
+    all_dbus_objects, coalesce_events, device,
+    device::{Device, DeviceEvent, DeviceProperty, DeviceSummary},
+    path_loss, Address, AddressType, CancellationToken, DistanceEstimator, Error, ErrorKind, EventWatcher,
+    InternalErrorKind, Modalias, ObjectEvent, Profile, ProximityEvent, Result, SessionInner, SingleSessionToken,
+    StreamEvent, SERVICE_NAME, TIMEOUT,
 };
 
+#[cfg(feature = "adv")]
+use crate::adv::{Advertisement, AdvertisementHandle, AdvertisingManager};
+#[cfg(feature = "agent")]
+use crate::agent::{Agent, AgentHandle, RegisteredAgent};
+#[cfg(feature = "gatt-server")]
+use crate::gatt;
+#[cfg(feature = "monitor")]
+use crate::monitor::{self, MonitorManager};
+
 pub(crate) const INTERFACE: &str = "org.bluez.Adapter1";
 pub(crate) const PATH: &str = "/org/bluez";
 pub(crate) const PREFIX: &str = "/org/bluez/";
 
+/// Initial delay between attempts to start discovery.
+const DISCOVERY_RETRY_INITIAL_DELAY: Duration = Duration::from_millis(50);
+/// Maximum delay between attempts to start discovery.
+const DISCOVERY_RETRY_MAX_DELAY: Duration = Duration::from_secs(2);
+
 /// Default adapter name.
 pub(crate) const DEFAULT_NAME: &str = "hci0";
 
 /// Interface to a Bluetooth adapter.
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 #[derive(Clone)]
 pub struct Adapter {
-    inner: Arc<SessionInner>,
+    pub(crate) inner: Arc<SessionInner>,
     dbus_path: Path<'static>,
     name: Arc<String>,
 }
@@ -56,21 +75,33 @@ impl Debug for Adapter {
 impl Adapter {
     /// Create Bluetooth adapter interface for adapter with specified name.
     pub(crate) fn new(inner: Arc<SessionInner>, name: &str) -> Result<Self> {
-        Ok(Self {
-            inner,
-            dbus_path: Path::new(PREFIX.to_string() + name)
-                .map_err(|_| Error::new(ErrorKind::InvalidName(name.to_string())))?,
-            name: Arc::new(name.to_string()),
-        })
+        Ok(Self { inner, dbus_path: Self::make_dbus_path(name)?, name: Arc::new(name.to_string()) })
+    }
+
+    /// Create Bluetooth adapter interface for adapter with specified D-Bus object path.
+    pub(crate) fn from_dbus_path(inner: Arc<SessionInner>, path: &Path) -> Result<Self> {
+        match Self::parse_dbus_path(path) {
+            Some(name) if Self::is_valid_name(name) => Self::new(inner, name),
+            _ => Err(Error::new(ErrorKind::InvalidAdapterPath(path.to_string()))),
+        }
+    }
+
+    /// Whether the adapter name is non-empty and consists only of ASCII letters,
+    /// digits and underscores, so that it forms a single D-Bus object path element.
+    fn is_valid_name(name: &str) -> bool {
+        !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
     }
 
     fn proxy(&self) -> Proxy<'_, &SyncConnection> {
         Proxy::new(SERVICE_NAME, &self.dbus_path, TIMEOUT, &*self.inner.connection)
     }
 
-    pub(crate) fn dbus_path(adapter_name: &str) -> Result<Path<'static>> {
+    pub(crate) fn make_dbus_path(adapter_name: &str) -> Result<Path<'static>> {
+        if !Self::is_valid_name(adapter_name) {
+            return Err(Error::new(ErrorKind::InvalidName(adapter_name.to_string())));
+        }
         Path::new(format!("{PREFIX}{adapter_name}",))
-            .map_err(|_| Error::new(ErrorKind::InvalidName((*adapter_name).to_string())))
+            .map_err(|_| Error::new(ErrorKind::InvalidName(adapter_name.to_string())))
     }
 
     pub(crate) fn parse_dbus_path_prefix<'a>(path: &'a Path) -> Option<(&'a str, &'a str)> {
@@ -97,6 +128,120 @@ impl Adapter {
         &self.name
     }
 
+    /// Whether the Bluetooth daemon exports the experimental interfaces of the adapter.
+    pub(crate) async fn has_experimental_interfaces(&self) -> Result<bool> {
+        use dbus::nonblock::stdintf::org_freedesktop_dbus::Introspectable;
+        let xml = self.proxy().introspect().await?;
+        introspection_has_method(&xml, INTERFACE, "ConnectDevice")
+    }
+
+    /// Whether the adapter is present.
+    ///
+    /// An adapter interface can be created for any valid adapter name,
+    /// but all operations fail if no such adapter is present,
+    /// for example because it has been unplugged.
+    pub async fn exists(&self) -> Result<bool> {
+        let objects = all_dbus_objects(&self.inner.connection).await?;
+        Ok(objects.get(&self.dbus_path).is_some_and(|interfaces| interfaces.contains_key(INTERFACE)))
+    }
+
+    /// D-Bus object path of the adapter.
+    ///
+    /// Together with [Session::dbus_connection](crate::Session::dbus_connection) this
+    /// allows access to BlueZ interfaces of the adapter that are not wrapped by this library.
+    pub fn dbus_path(&self) -> &Path<'static> {
+        &self.dbus_path
+    }
+
+    /// Spawns a task owned by the session of the adapter.
+    pub(crate) fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        self.inner.spawn(task)
+    }
+
+    /// Calls a method of a BlueZ D-Bus interface of the adapter.
+    ///
+    /// This allows calling methods that are not wrapped by this library.
+    /// The call is made using the D-Bus connection of the session.
+    pub async fn call_method_raw<A, R>(&self, interface: &str, method: &str, args: A) -> Result<R>
+    where
+        A: dbus::arg::AppendAll + std::fmt::Debug,
+        R: dbus::arg::ReadAll + std::fmt::Debug + 'static,
+    {
+        self.call_method_with_interface(method, args, interface).await
+    }
+
+    /// Tracks the connection state of the devices of the adapter.
+    ///
+    /// Yields the address of a device and `true` when a device accepted by `accept`
+    /// connects, including devices that are already connected when the stream is started,
+    /// and `false` when such a device disconnects or is removed.
+    /// The stream ends when the adapter is removed.
+    #[cfg(feature = "gatt-server")]
+    pub(crate) async fn connection_changes<F, Fut>(
+        &self, accept: F,
+    ) -> Result<impl Stream<Item = (Address, bool)>>
+    where
+        F: Fn(Device) -> Fut + Send + 'static,
+        Fut: Future<Output = bool> + Send,
+    {
+        let mut adapter_events = self.events().await?;
+        let known = self.device_addresses().await?;
+        let (tx, rx) = mpsc::channel(16);
+
+        let adapter = self.clone();
+        self.inner.spawn(async move {
+            let mut changes = SelectAll::new();
+            let mut pending = known;
+            let mut connected = HashSet::new();
+
+            loop {
+                for address in pending.drain(..) {
+                    let Ok(device) = adapter.device(address) else { continue };
+                    let Ok(events) = device.events().await else { continue };
+                    changes.push(events.map(move |evt| (address, evt)).boxed());
+                    if device.is_connected().await.unwrap_or_default()
+                        && accept(device).await
+                        && connected.insert(address)
+                    {
+                        let _ = tx.send((address, true)).await;
+                    }
+                }
+
+                tokio::select! {
+                    evt = adapter_events.next() => match evt {
+                        Some(AdapterEvent::DeviceAdded(address)) => pending.push(address),
+                        Some(AdapterEvent::DeviceRemoved(address)) => {
+                            if connected.remove(&address) {
+                                let _ = tx.send((address, false)).await;
+                            }
+                        }
+                        Some(_) => (),
+                        None => break,
+                    },
+                    Some((address, evt)) = changes.next(), if !changes.is_empty() => {
+                        let DeviceEvent::PropertyChanged(DeviceProperty::Connected(is_connected)) = evt else {
+                            continue;
+                        };
+                        match is_connected {
+                            true => {
+                                let Ok(device) = adapter.device(address) else { continue };
+                                if !accept(device).await || !connected.insert(address) {
+                                    continue;
+                                }
+                            }
+                            false if connected.remove(&address) => (),
+                            false => continue,
+                        }
+                        let _ = tx.send((address, is_connected)).await;
+                    },
+                    () = tx.closed() => break,
+                }
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
     /// Bluetooth addresses of discovered Bluetooth devices.
     pub async fn device_addresses(&self) -> Result<Vec<Address>> {
         let mut addrs = Vec::new();
@@ -111,6 +256,37 @@ impl Adapter {
         Ok(addrs)
     }
 
+    /// Devices whose device properties, as reported by the Bluetooth daemon, satisfy the predicate.
+    ///
+    /// This queries the properties of all devices using a single D-Bus call.
+    async fn devices_where(&self, predicate: impl Fn(&PropMap) -> bool) -> Result<Vec<Device>> {
+        let mut devices = Vec::new();
+        for (path, interfaces) in all_dbus_objects(&self.inner.connection).await? {
+            match (Device::parse_dbus_path(&path), interfaces.get(device::INTERFACE)) {
+                (Some((adapter, addr)), Some(props)) if adapter == *self.name && predicate(props) => {
+                    devices.push(self.device(addr)?)
+                }
+                _ => (),
+            }
+        }
+        Ok(devices)
+    }
+
+    /// Devices that are currently connected.
+    ///
+    /// In contrast to querying each device returned by [device_addresses](Self::device_addresses),
+    /// this uses a single D-Bus call.
+    pub async fn connected_devices(&self) -> Result<Vec<Device>> {
+        self.devices_where(|props| prop_cast::<bool>(props, "Connected").copied().unwrap_or_default()).await
+    }
+
+    /// Devices that are paired with the adapter.
+    ///
+    /// Use [Device::is_bonded] to determine whether the keys of a paired device have been stored.
+    pub async fn paired_devices(&self) -> Result<Vec<Device>> {
+        self.devices_where(|props| prop_cast::<bool>(props, "Paired").copied().unwrap_or_default()).await
+    }
+
     /// Starts monitoring of advertisements.
     ///
     /// Once a monitoring job is activated by BlueZ, the client can expect to get
@@ -119,10 +295,36 @@ impl Adapter {
     ///
     /// Use the returned [`MonitorManager`] to target advertisements
     /// and drop it to stop monitoring advertisements.
+    #[cfg(feature = "monitor")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "monitor")))]
     pub async fn monitor(&self) -> Result<MonitorManager> {
         MonitorManager::new(self.inner.clone(), self.name()).await
     }
 
+    /// Advertisement monitor types supported by the adapter.
+    ///
+    /// This is empty if advertisement monitoring is not available.
+    #[cfg(feature = "monitor")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "monitor")))]
+    pub async fn supported_monitor_types(&self) -> Result<BTreeSet<monitor::Type>> {
+        let types: Option<Vec<String>> =
+            self.get_opt_property_with_interface("SupportedMonitorTypes", monitor::MANAGER_INTERFACE).await?;
+        Ok(types.unwrap_or_default().iter().filter_map(|s| s.parse().ok()).collect())
+    }
+
+    /// Advertisement monitoring features supported by the controller of the adapter.
+    ///
+    /// If [`Feature::ControllerPatterns`](monitor::Feature::ControllerPatterns) is supported,
+    /// monitoring is offloaded to the controller and passive scanning using an advertisement
+    /// monitor is preferable to active scanning.
+    #[cfg(feature = "monitor")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "monitor")))]
+    pub async fn supported_monitor_features(&self) -> Result<BTreeSet<monitor::Feature>> {
+        let features: Option<Vec<String>> =
+            self.get_opt_property_with_interface("SupportedFeatures", monitor::MANAGER_INTERFACE).await?;
+        Ok(features.unwrap_or_default().iter().filter_map(|s| s.parse().ok()).collect())
+    }
+
     /// Get interface to Bluetooth device of specified address.
     pub fn device(&self, address: Address) -> Result<Device> {
         Device::new(self.inner.clone(), self.name.clone(), address)
@@ -168,19 +370,34 @@ impl Adapter {
     ///
     /// The discovery filter can be configured using [set_discovery_filter](Self::set_discovery_filter).
     pub async fn discover_devices(&self) -> Result<impl Stream<Item = AdapterEvent>> {
+        self.discover_devices_with_options(DiscoveryOptions::default()).await
+    }
+
+    /// Starts device discovery like [discover_devices](Self::discover_devices) using the
+    /// specified options.
+    pub async fn discover_devices_with_options(
+        &self, options: DiscoveryOptions,
+    ) -> Result<impl Stream<Item = AdapterEvent>> {
         let token = self.discovery_session().await?;
-        let change_events = self
-            .events()
-            .await?
+        // Subscribe before listing the known devices, so that no device added in between is missed.
+        let events = self.events().await?;
+        let known = self.device_addresses().await?;
+        let remover = match options.remove_transient_devices {
+            true => Some(TransientDeviceRemover::new(self.clone(), &known)),
+            false => None,
+        };
+        let change_events = events
             .map(move |evt| {
                 let _token = &token;
+                if let (Some(remover), AdapterEvent::DeviceAdded(addr)) = (&remover, &evt) {
+                    remover.added(*addr);
+                }
                 evt
             })
             .take_while(|evt| {
                 future::ready(!matches!(evt, AdapterEvent::PropertyChanged(AdapterProperty::Discovering(false))))
             });
 
-        let known = self.device_addresses().await?;
         let known_events = stream::iter(known).map(AdapterEvent::DeviceAdded);
 
         let all_events = known_events.chain(change_events);
@@ -188,6 +405,15 @@ impl Adapter {
         Ok(all_events)
     }
 
+    /// Starts device discovery like [discover_devices](Self::discover_devices), but ends the
+    /// returned stream and stops discovery when the specified cancellation token is cancelled.
+    pub async fn discover_devices_cancellable(
+        &self, token: &CancellationToken,
+    ) -> Result<impl Stream<Item = AdapterEvent>> {
+        let discovery = self.discover_devices().await?;
+        Ok(discovery.take_until(token.clone().cancelled_owned()))
+    }
+
     /// This method starts the device discovery session and notifies of device property changes.
     ///
     /// This includes an inquiry procedure and remote device name resolving.
@@ -206,7 +432,7 @@ impl Adapter {
         let mut discovery = self.discover_devices().await?;
         let adapter = self.clone();
 
-        tokio::spawn(async move {
+        self.inner.spawn(async move {
             let mut changes = SelectAll::new();
 
             loop {
@@ -247,9 +473,10 @@ impl Adapter {
             .single_session(
                 &self.dbus_path,
                 async move {
-                    self.call_method("SetDiscoveryFilter", (self.discovery_filter().await.into_dict(),)).await?;
-                    self.call_method("StartDiscovery", ()).await?;
-                    Ok(())
+                    let filter = self.discovery_filter().await;
+                    let deadline = filter.start_retry_timeout.map(|timeout| Instant::now() + timeout);
+                    self.call_method("SetDiscoveryFilter", (filter.into_dict(),)).await?;
+                    self.start_discovery(deadline).await
                 },
                 async move {
                     log::trace!("{}: {}.StopDiscovery ()", &dbus_path, SERVICE_NAME);
@@ -263,34 +490,618 @@ impl Adapter {
         Ok(token)
     }
 
+    /// Starts discovery, retrying on transient errors until the deadline.
+    async fn start_discovery(&self, deadline: Option<Instant>) -> Result<()> {
+        let mut delay = DISCOVERY_RETRY_INITIAL_DELAY;
+        loop {
+            match self.call_method("StartDiscovery", ()).await {
+                Err(err)
+                    if matches!(err.kind, ErrorKind::NotReady | ErrorKind::InProgress)
+                        && deadline.is_some_and(|deadline| Instant::now() + delay < deadline) =>
+                {
+                    log::debug!("{}: starting discovery failed, retrying in {:?}: {}", &self.name, delay, &err);
+                    sleep(delay).await;
+                    delay = (delay * 2).min(DISCOVERY_RETRY_MAX_DELAY);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Makes the adapter discoverable for the duration of the returned session.
+    ///
+    /// The adapter stops being discoverable after `timeout` has elapsed.
+    /// Specify a zero duration to remain discoverable until the session is dropped.
+    ///
+    /// When the returned [DiscoverableSession] is dropped, the previous discoverable
+    /// state and timeout of the adapter are restored.
+    /// Use [DiscoverableSession::set_pairable] to also make the adapter pairable.
+    ///
+    /// Multiple concurrent sessions share the same state; the timeout of the
+    /// first session applies and the previous state is restored once all sessions
+    /// have been dropped.
+    pub async fn discoverable_session(&self, timeout: Duration) -> Result<DiscoverableSession> {
+        let timeout = match timeout.as_secs() {
+            0 if !timeout.is_zero() => 1,
+            secs => secs.min(u32::MAX.into()) as u32,
+        };
+        let token = self
+            .state_session("discoverable", async move {
+                let prev_discoverable = self.is_discoverable().await?;
+                let prev_timeout = self.discoverable_timeout().await?;
+                self.set_discoverable_timeout(timeout).await?;
+                self.set_discoverable(true).await?;
+                let adapter = self.clone();
+                Ok(async move {
+                    // Restore the timeout first, since it applies when discoverability is set.
+                    log::trace!("{}: restoring discoverable state", &adapter.name);
+                    let _ = adapter.set_discoverable_timeout(prev_timeout).await;
+                    let _ = adapter.set_discoverable(prev_discoverable).await;
+                })
+            })
+            .await?;
+        Ok(DiscoverableSession { adapter: self.clone(), _discoverable: token, pairable: None })
+    }
+
+    /// Reads a snapshot of the configuration of the adapter.
+    ///
+    /// All settings of the returned configuration are specified.
+    pub async fn config(&self) -> Result<AdapterConfig> {
+        Ok(AdapterConfig {
+            alias: Some(self.alias().await?),
+            powered: Some(self.is_powered().await?),
+            discoverable: Some(self.is_discoverable().await?),
+            discoverable_timeout: Some(self.discoverable_timeout().await?),
+            pairable: Some(self.is_pairable().await?),
+            pairable_timeout: Some(self.pairable_timeout().await?),
+            _non_exhaustive: (),
+        })
+    }
+
+    /// Applies the specified configuration to the adapter.
+    ///
+    /// Settings that are [None] are left unchanged.
+    /// The adapter is powered on before and powered off after the other settings
+    /// are applied.
+    ///
+    /// If applying a setting fails, all settings changed so far are restored to their
+    /// previous values in reverse order and the error is returned.
+    /// Restoring is best effort; failures to restore a setting are logged.
+    pub async fn apply_config(&self, config: &AdapterConfig) -> Result<()> {
+        let prev = self.config().await?;
+        let mut applied: Vec<AdapterConfigStep> = Vec::new();
+
+        for step in AdapterConfigStep::order(config) {
+            if let Err(err) = step.apply(self, config).await {
+                log::debug!("{}: applying {:?} failed: {}", &self.name, step, &err);
+                for step in applied.into_iter().rev() {
+                    if let Err(err) = step.apply(self, &prev).await {
+                        log::warn!("{}: restoring {:?} failed: {}", &self.name, step, &err);
+                    }
+                }
+                return Err(err);
+            }
+            applied.push(step);
+        }
+
+        Ok(())
+    }
+
+    /// Powers on the adapter for the duration of the returned session.
+    ///
+    /// If `power_off` is true and the adapter was powered off when the session
+    /// was started, the adapter is powered off again when the session is dropped.
+    ///
+    /// Concurrent powered sessions of the same adapter started through the same [Session](crate::Session)
+    /// share their state; the `power_off` argument of the first session applies and the adapter is
+    /// powered off once all of them have been dropped.
+    /// Powered sessions started through other [Session](crate::Session)s, for example in other
+    /// processes, are not tracked, so the adapter may be powered off while they are still held.
+    /// This allows short-lived tools to use the adapter without leaving it powered on.
+    pub async fn powered_session(&self, power_off: bool) -> Result<PoweredSession> {
+        let token = self
+            .state_session("powered", async move {
+                let prev_powered = self.is_powered().await?;
+                if !prev_powered {
+                    self.set_powered(true).await?;
+                }
+                let adapter = self.clone();
+                Ok(async move {
+                    if power_off && !prev_powered {
+                        log::trace!("{}: powering off", &adapter.name);
+                        let _ = adapter.set_powered(false).await;
+                    }
+                })
+            })
+            .await?;
+        Ok(PoweredSession { adapter: self.clone(), _powered: token })
+    }
+
+    /// Starts a single session changing the adapter state.
+    ///
+    /// `start_fn` changes the state and returns a future restoring it,
+    /// which is executed once the session has been terminated.
+    async fn state_session<F>(
+        &self, kind: &str, start_fn: impl Future<Output = Result<F>>,
+    ) -> Result<SingleSessionToken>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        // Sessions are identified by a path below the adapter path that is not
+        // used by BlueZ, so that they are independent of the discovery session.
+        let path = Path::new(format!("{}/{}", &self.dbus_path, kind)).unwrap();
+        let (restore_tx, restore_rx) = oneshot::channel();
+        self.inner
+            .single_session(
+                &path,
+                async move {
+                    let restore_fn = start_fn.await?;
+                    let _ = restore_tx.send(restore_fn);
+                    Ok(())
+                },
+                async move {
+                    if let Ok(restore_fn) = restore_rx.await {
+                        restore_fn.await;
+                    }
+                },
+            )
+            .await
+    }
+
     dbus_interface!();
     dbus_default_interface!(INTERFACE);
 
     /// Streams adapter property and device changes.
     ///
+    /// Events that are not consumed in time are subject to the
+    /// [event overflow policy](crate::Session::set_event_buffer) of the session.
+    ///
     /// The stream ends when the adapter is removed.
     pub async fn events(&self) -> Result<impl Stream<Item = AdapterEvent>> {
         let name = self.name.clone();
         let events = self.inner.events(self.dbus_path.clone(), true).await?;
-        let stream = events.flat_map(move |event| match event {
-            Event::ObjectAdded { object, .. } => match Device::parse_dbus_path(&object) {
-                Some((adapter, address)) if adapter == *name => {
-                    stream::once(async move { AdapterEvent::DeviceAdded(address) }).boxed()
-                }
-                _ => stream::empty().boxed(),
+        Ok(events.flat_map(move |event| stream::iter(Self::adapter_events(&name, event))))
+    }
+
+    /// Streams adapter property and device changes like [events](Self::events), but delivers
+    /// a [StreamEvent::Lagged] item when events have been lost because they were not consumed in time.
+    ///
+    /// The stream ends when the adapter is removed.
+    pub async fn events_with_lag(&self) -> Result<impl Stream<Item = StreamEvent<AdapterEvent>>> {
+        let name = self.name.clone();
+        let events = self.inner.events(self.dbus_path.clone(), true).await?.with_lag();
+        Ok(events.flat_map(move |event| match event {
+            StreamEvent::Event(event) => stream::iter(
+                Self::adapter_events(&name, event).into_iter().map(StreamEvent::Event).collect::<Vec<_>>(),
+            ),
+            StreamEvent::Lagged(n) => stream::iter(vec![StreamEvent::Lagged(n)]),
+        }))
+    }
+
+    /// Converts an object event of the adapter into adapter events.
+    fn adapter_events(name: &str, event: ObjectEvent) -> Vec<AdapterEvent> {
+        match event {
+            ObjectEvent::ObjectAdded { object, .. } => match Device::parse_dbus_path(&object) {
+                Some((adapter, address)) if adapter == name => vec![AdapterEvent::DeviceAdded(address)],
+                _ => Vec::new(),
             },
-            Event::ObjectRemoved { object, .. } => match Device::parse_dbus_path(&object) {
-                Some((adapter, address)) if adapter == *name => {
-                    stream::once(async move { AdapterEvent::DeviceRemoved(address) }).boxed()
-                }
-                _ => stream::empty().boxed(),
+            ObjectEvent::ObjectRemoved { object, .. } => match Device::parse_dbus_path(&object) {
+                Some((adapter, address)) if adapter == name => vec![AdapterEvent::DeviceRemoved(address)],
+                _ => Vec::new(),
             },
-            Event::PropertiesChanged { changed, .. } => stream::iter(
-                AdapterProperty::from_prop_map(changed).into_iter().map(AdapterEvent::PropertyChanged),
-            )
-            .boxed(),
+            ObjectEvent::PropertiesChanged { changed, .. } => {
+                AdapterProperty::from_prop_map(changed).into_iter().map(AdapterEvent::PropertyChanged).collect()
+            }
+        }
+    }
+
+    /// Starts device discovery like [discover_devices](Self::discover_devices), but only reports
+    /// devices matching the discovery filter of this session.
+    ///
+    /// Since the Bluetooth daemon merges the discovery filters of all clients,
+    /// [discover_devices](Self::discover_devices) may report devices that do not match
+    /// the filter set using [set_discovery_filter](Self::set_discovery_filter).
+    /// This re-applies the filter to each added device using [DiscoveryFilter::matches].
+    /// Events other than [DeviceAdded](AdapterEvent::DeviceAdded) are passed through unchanged.
+    pub async fn discover_devices_matching(&self) -> Result<impl Stream<Item = AdapterEvent>> {
+        let filter = self.discovery_filter().await;
+        let discovery = self.discover_devices().await?;
+        let adapter = self.clone();
+        Ok(discovery.filter_map(move |evt| {
+            let adapter = adapter.clone();
+            let filter = filter.clone();
+            async move {
+                match evt {
+                    AdapterEvent::DeviceAdded(address) => {
+                        let summary = adapter.device(address).ok()?.summary().await.ok()?;
+                        filter.matches(&summary).then_some(evt)
+                    }
+                    evt => Some(evt),
+                }
+            }
+        }))
+    }
+
+    /// Starts device discovery like [discover_devices](Self::discover_devices) and reports the
+    /// signal strength of discovered devices along with the path loss and distance estimated
+    /// by the specified estimator.
+    ///
+    /// An event is delivered when a device is found and each time its signal strength changes.
+    /// Enable [duplicate data](DiscoveryFilter::duplicate_data) in the discovery filter to
+    /// receive updates for every advertisement.
+    ///
+    /// Discovery is stopped when the returned stream is dropped.
+    pub async fn discover_proximity(
+        &self, estimator: DistanceEstimator,
+    ) -> Result<impl Stream<Item = ProximityEvent>> {
+        let (tx, rx) = mpsc::channel(16);
+        let mut discovery = self.discover_devices().await?;
+        let adapter = self.clone();
+
+        self.inner.spawn(async move {
+            let mut changes = SelectAll::new();
+            let mut tx_powers: HashMap<Address, Option<i16>> = HashMap::new();
+
+            loop {
+                let evt = tokio::select! {
+                    evt = discovery.next() => match evt {
+                        Some(AdapterEvent::DeviceAdded(addr)) if !tx_powers.contains_key(&addr) => {
+                            let Ok(dev) = adapter.device(addr) else { continue };
+                            let Ok(dev_evts) = dev.events().await else { continue };
+                            changes.push(dev_evts.map(move |evt| (addr, evt)));
+                            let tx_power = dev.tx_power().await.ok().flatten();
+                            tx_powers.insert(addr, tx_power);
+                            match dev.rssi().await {
+                                Ok(Some(rssi)) => ProximityEvent::new(addr, rssi, tx_power, &estimator),
+                                _ => continue,
+                            }
+                        }
+                        Some(AdapterEvent::DeviceRemoved(addr)) => {
+                            tx_powers.remove(&addr);
+                            continue;
+                        }
+                        Some(_) => continue,
+                        None => break,
+                    },
+                    Some((addr, evt)) = changes.next(), if !changes.is_empty() => {
+                        let Some(tx_power) = tx_powers.get_mut(&addr) else { continue };
+                        match evt {
+                            DeviceEvent::PropertyChanged(DeviceProperty::Rssi(rssi)) => {
+                                ProximityEvent::new(addr, rssi, *tx_power, &estimator)
+                            }
+                            DeviceEvent::PropertyChanged(DeviceProperty::TxPower(power)) => {
+                                *tx_power = Some(power);
+                                continue;
+                            }
+                            _ => continue,
+                        }
+                    },
+                    () = tx.closed() => break,
+                };
+                if tx.send(evt).await.is_err() {
+                    break;
+                }
+            }
         });
-        Ok(stream)
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    /// Starts device discovery restricted to classic Bluetooth (BR/EDR) devices
+    /// matching the specified class-of-device filter.
+    ///
+    /// The transport of the discovery filter of this session is set to
+    /// [BR/EDR inquiry](DiscoveryTransport::BrEdr), keeping all other criteria,
+    /// and the previous discovery filter is restored when the returned stream is dropped.
+    /// Thus this fails with [ErrorKind::DiscoveryActive] if a discovery of this session is
+    /// already running.
+    ///
+    /// Since the class of device is not part of the discovery filter of the Bluetooth daemon,
+    /// it is checked for each added device by this library.
+    /// A device that does not report a class of device when it is added is reported once
+    /// its class of device becomes known and matches.
+    /// Devices that never report a class of device, such as Bluetooth LE devices found by
+    /// concurrent discoveries of other clients, are omitted.
+    /// Events other than [DeviceAdded](AdapterEvent::DeviceAdded) are passed through unchanged.
+    pub async fn discover_classic_devices(
+        &self, class_filter: ClassOfDeviceFilter,
+    ) -> Result<impl Stream<Item = AdapterEvent>> {
+        let prev_filter = self.discovery_filter().await;
+        let filter = DiscoveryFilter { transport: DiscoveryTransport::BrEdr, ..prev_filter.clone() };
+        self.set_discovery_filter(filter).await?;
+        let mut discovery = match self.discover_devices().await {
+            Ok(discovery) => discovery.boxed(),
+            Err(err) => {
+                self.restore_discovery_filter(prev_filter).await;
+                return Err(err);
+            }
+        };
+
+        let (tx, rx) = mpsc::channel(16);
+        let adapter = self.clone();
+        self.inner.spawn(async move {
+            let mut changes = SelectAll::new();
+            let mut unclassified = HashSet::new();
+
+            loop {
+                let evt = tokio::select! {
+                    evt = discovery.next() => match evt {
+                        Some(AdapterEvent::DeviceAdded(address)) => {
+                            let Ok(device) = adapter.device(address) else { continue };
+                            match device.class().await {
+                                Ok(Some(class)) if class_filter.matches(class) => AdapterEvent::DeviceAdded(address),
+                                Ok(Some(_)) | Err(_) => continue,
+                                Ok(None) => {
+                                    if unclassified.insert(address) {
+                                        let Ok(events) = device.events().await else { continue };
+                                        changes.push(events.map(move |evt| (address, evt)).boxed());
+                                    }
+                                    continue;
+                                }
+                            }
+                        }
+                        Some(evt @ AdapterEvent::DeviceRemoved(address)) => {
+                            unclassified.remove(&address);
+                            evt
+                        }
+                        Some(evt) => evt,
+                        None => break,
+                    },
+                    Some((address, evt)) = changes.next(), if !changes.is_empty() => {
+                        let DeviceEvent::PropertyChanged(DeviceProperty::Class(class)) = evt else { continue };
+                        if !unclassified.remove(&address) || !class_filter.matches(class) {
+                            continue;
+                        }
+                        AdapterEvent::DeviceAdded(address)
+                    },
+                    () = tx.closed() => break,
+                };
+                if tx.send(evt).await.is_err() {
+                    break;
+                }
+            }
+
+            drop(discovery);
+            adapter.restore_discovery_filter(prev_filter).await;
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    /// Restores a discovery filter that has been replaced temporarily.
+    ///
+    /// This is done even if a discovery of this session is still active, since the
+    /// filter of a running discovery is not affected by changing it.
+    async fn restore_discovery_filter(&self, filter: DiscoveryFilter) {
+        self.inner.adapter_discovery_filter.lock().await.insert(self.name().to_string(), filter);
+    }
+
+    /// Streams adapter property and device changes, coalescing rapid successive changes
+    /// of the same adapter property.
+    ///
+    /// When an adapter property changes, further changes of that property are collected for
+    /// the duration of `window` and then only its most recent value is delivered.
+    /// Device added and removed events are delivered immediately.
+    ///
+    /// The stream ends when the adapter is removed.
+    pub async fn events_coalesced(&self, window: Duration) -> Result<impl Stream<Item = AdapterEvent>> {
+        let events = self.events().await?;
+        Ok(coalesce_events(&self.inner, events, window, |evt| match evt {
+            AdapterEvent::PropertyChanged(prop) => Some(mem::discriminant(prop)),
+            _ => None,
+        }))
+    }
+
+    /// Streams the powered state of the adapter.
+    ///
+    /// The current state is delivered immediately, followed by all changes.
+    /// Since the stream is subscribed to before the current state is queried,
+    /// no change can be missed.
+    ///
+    /// The stream ends when the adapter is removed.
+    pub async fn powered_stream(&self) -> Result<impl Stream<Item = bool>> {
+        let events = self.events().await?;
+        let current = self.is_powered().await?;
+        Ok(Self::property_stream(current, events, |prop| match prop {
+            AdapterProperty::Powered(powered) => Some(powered),
+            _ => None,
+        }))
+    }
+
+    /// Streams the discovering state of the adapter.
+    ///
+    /// The current state is delivered immediately, followed by all changes.
+    /// Since the stream is subscribed to before the current state is queried,
+    /// no change can be missed.
+    ///
+    /// The stream ends when the adapter is removed.
+    pub async fn discovering_stream(&self) -> Result<impl Stream<Item = bool>> {
+        let events = self.events().await?;
+        let current = self.is_discovering().await?;
+        Ok(Self::property_stream(current, events, |prop| match prop {
+            AdapterProperty::Discovering(discovering) => Some(discovering),
+            _ => None,
+        }))
+    }
+
+    /// Prepends the current value of a property to its changes, skipping repeated values.
+    fn property_stream<T>(
+        current: T, events: impl Stream<Item = AdapterEvent>, value: impl Fn(AdapterProperty) -> Option<T>,
+    ) -> impl Stream<Item = T>
+    where
+        T: Clone + PartialEq,
+    {
+        let changes = events.filter_map(move |evt| {
+            future::ready(match evt {
+                AdapterEvent::PropertyChanged(prop) => value(prop),
+                _ => None,
+            })
+        });
+        stream::once(future::ready(current))
+            .chain(changes)
+            .scan(None, |last: &mut Option<T>, value| {
+                let changed = last.as_ref() != Some(&value);
+                *last = Some(value.clone());
+                future::ready(Some(changed.then_some(value)))
+            })
+            .filter_map(future::ready)
+    }
+
+    /// Creates a shared watcher for adapter property and device changes.
+    ///
+    /// In contrast to [events](Self::events) the returned [EventWatcher] can be
+    /// subscribed to by multiple consumers, which all share a single event subscription.
+    pub async fn event_watcher(&self) -> Result<EventWatcher<AdapterEvent>> {
+        Ok(EventWatcher::new(&self.inner, self.events().await?))
+    }
+
+    /// List of profiles and services that represent the available local services.
+    ///
+    /// This is the same as [uuids](Self::uuids), but with each UUID converted to a [Profile].
+    pub async fn profiles(&self) -> Result<Option<HashSet<Profile>>> {
+        Ok(self.uuids().await?.map(|uuids| uuids.into_iter().map(Profile::from).collect()))
+    }
+
+    /// Exports the keys of all devices bonded with this adapter.
+    ///
+    /// The keys are read from the storage of the Bluetooth daemon and can be
+    /// imported on another adapter using [import_bonds](Self::import_bonds).
+    /// Reading the storage usually requires root privileges.
+    #[cfg(feature = "mgmt")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mgmt")))]
+    pub async fn export_bonds(&self) -> Result<Vec<crate::mgmt::Bond>> {
+        Ok(crate::mgmt::Bond::read_storage(self.address().await?)?)
+    }
+
+    /// Imports the keys of bonded devices into this adapter.
+    ///
+    /// The keys of the bonds, together with the keys stored by the Bluetooth daemon for
+    /// this adapter, are loaded into the controller using the Load Link Keys,
+    /// Load Long Term Keys and Load Identity Resolving Keys commands of the
+    /// [management interface](crate::mgmt).
+    /// Imported keys take precedence over stored keys of the same device.
+    /// This allows migrating bonds between controllers, for example when replacing
+    /// a factory-provisioned controller.
+    ///
+    /// The storage of the Bluetooth daemon is not modified, thus the imported keys are
+    /// lost when the daemon restarts or the controller is reset.
+    /// Use [Bond::write_storage](crate::mgmt::Bond::write_storage) to persist them.
+    /// Root privileges are required.
+    #[cfg(feature = "mgmt")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mgmt")))]
+    pub async fn import_bonds(&self, bonds: &[crate::mgmt::Bond]) -> Result<()> {
+        use crate::mgmt::{adapter_index, collect_keys, Bond, Management};
+
+        let index = adapter_index(self.name())?;
+        let adapter_address = self.address().await?;
+
+        // Loading keys replaces all keys of the controller, thus stored bonds must be included.
+        let mut all_bonds = match Bond::read_storage(adapter_address) {
+            Ok(stored) => stored,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+        all_bonds.extend(bonds.iter().cloned());
+
+        let (link_keys, long_term_keys, irks) = collect_keys(&all_bonds);
+        let mgmt = Management::new()?;
+        mgmt.load_link_keys(index, &link_keys, false).await?;
+        mgmt.load_long_term_keys(index, &long_term_keys).await?;
+        mgmt.load_identity_resolving_keys(index, &irks).await?;
+        Ok(())
+    }
+
+    /// Reads information about the controller of this adapter.
+    ///
+    /// This includes the manufacturer, HCI version and supported settings of the controller.
+    /// Requires the `CAP_NET_ADMIN` capability.
+    #[cfg(feature = "mgmt")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mgmt")))]
+    pub async fn controller_info(&self) -> Result<crate::mgmt::ControllerInfo> {
+        let index = crate::mgmt::adapter_index(self.name())?;
+        Ok(crate::mgmt::Management::new()?.controller_info(index).await?)
+    }
+
+    /// Reads the advertising features supported by the controller of this adapter.
+    ///
+    /// This can be used to detect, for example, whether the controller supports extended
+    /// advertising and to degrade gracefully otherwise.
+    /// Requires the `CAP_NET_ADMIN` capability.
+    #[cfg(feature = "mgmt")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mgmt")))]
+    pub async fn controller_advertising_features(&self) -> Result<crate::mgmt::AdvertisingFeatures> {
+        let index = crate::mgmt::adapter_index(self.name())?;
+        Ok(crate::mgmt::Management::new()?.advertising_features(index).await?)
+    }
+
+    /// Reads the default system configuration parameters of the controller of this adapter.
+    ///
+    /// These parameters include connection interval ranges, advertising intervals and
+    /// page scan parameters.
+    /// Requires the `CAP_NET_ADMIN` capability.
+    #[cfg(feature = "mgmt")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mgmt")))]
+    pub async fn system_config(
+        &self,
+    ) -> Result<std::collections::BTreeMap<crate::mgmt::SystemConfigParameter, u16>> {
+        let index = crate::mgmt::adapter_index(self.name())?;
+        Ok(crate::mgmt::Management::new()?.system_config(index).await?)
+    }
+
+    /// Sets default system configuration parameters of the controller of this adapter.
+    ///
+    /// This allows products to use non-default radio timing.
+    /// Parameters not specified are left unchanged.
+    /// See [Management::set_system_config](crate::mgmt::Management::set_system_config) for
+    /// the units of the values.
+    ///
+    /// Some parameters can only be changed while the adapter is powered off.
+    /// The configuration is not persisted and is reset by the Bluetooth daemon when it
+    /// restarts; use its `main.conf` for permanent changes.
+    /// Requires the `CAP_NET_ADMIN` capability.
+    #[cfg(feature = "mgmt")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mgmt")))]
+    pub async fn set_system_config(
+        &self, config: impl IntoIterator<Item = (crate::mgmt::SystemConfigParameter, u16)>,
+    ) -> Result<()> {
+        let index = crate::mgmt::adapter_index(self.name())?;
+        Ok(crate::mgmt::Management::new()?.set_system_config(index, config).await?)
+    }
+
+    /// Reads the out-of-band pairing data of this adapter for the transport of the
+    /// specified address type.
+    ///
+    /// Transfer the data to the remote device using an out-of-band channel, such as NFC or
+    /// a QR code, to enable secure pairing without user interaction.
+    /// The data is valid until the adapter is powered off or new data is read.
+    /// Requires the `CAP_NET_ADMIN` capability.
+    #[cfg(feature = "mgmt")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mgmt")))]
+    pub async fn local_oob_data(&self, address_type: AddressType) -> Result<crate::mgmt::OobData> {
+        let index = crate::mgmt::adapter_index(self.name())?;
+        Ok(crate::mgmt::Management::new()?.read_local_oob_data(index, address_type).await?)
+    }
+
+    /// Provides out-of-band pairing data received from a remote device.
+    ///
+    /// The data is used when subsequently pairing with the device, for example
+    /// using [Device::pair].
+    /// Requires the `CAP_NET_ADMIN` capability.
+    #[cfg(feature = "mgmt")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mgmt")))]
+    pub async fn add_remote_oob_data(
+        &self, address: Address, address_type: AddressType, data: &crate::mgmt::OobData,
+    ) -> Result<()> {
+        let index = crate::mgmt::adapter_index(self.name())?;
+        Ok(crate::mgmt::Management::new()?.add_remote_oob_data(index, address, address_type, data).await?)
+    }
+
+    /// Removes out-of-band pairing data of a remote device.
+    #[cfg(feature = "mgmt")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mgmt")))]
+    pub async fn remove_remote_oob_data(&self, address: Address, address_type: AddressType) -> Result<()> {
+        let index = crate::mgmt::adapter_index(self.name())?;
+        Ok(crate::mgmt::Management::new()?.remove_remote_oob_data(index, address, address_type).await?)
     }
 
     /// Registers an advertisement object to be sent over the LE
@@ -311,9 +1122,63 @@ impl Adapter {
     /// If the maximum number of advertisement instances is
     /// reached it will result in NotPermitted error.
     ///
+    /// The advertisement is not validated before it is registered;
+    /// use [validate_advertisement](Self::validate_advertisement) to obtain a precise error
+    /// before passing it to the Bluetooth daemon.
+    ///
     /// Drop the returned [AdvertisementHandle] to unregister the advertisement.
+    #[cfg(feature = "adv")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "adv")))]
     pub async fn advertise(&self, le_advertisement: Advertisement) -> Result<AdvertisementHandle> {
-        le_advertisement.register(self.inner.clone(), self.name.clone()).await
+        le_advertisement.register(self.inner.clone(), self.name.clone(), false).await
+    }
+
+    /// Registers an advertisement object like [advertise](Self::advertise) and
+    /// keeps it registered across adapter power cycles and restarts of the Bluetooth daemon.
+    ///
+    /// When the adapter is powered off or removed or the Bluetooth daemon exits,
+    /// the advertisement enters the [Suspended](crate::RegistrationState::Suspended) state.
+    /// It is registered again once the adapter is powered on or reappears and then enters
+    /// the [Reregistered](crate::RegistrationState::Reregistered) state.
+    /// Use [AdvertisementHandle::registration_events] to observe these state changes.
+    ///
+    /// Drop the returned [AdvertisementHandle] to unregister the advertisement.
+    #[cfg(feature = "adv")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "adv")))]
+    pub async fn advertise_resilient(&self, le_advertisement: Advertisement) -> Result<AdvertisementHandle> {
+        le_advertisement.register(self.inner.clone(), self.name.clone(), true).await
+    }
+
+    /// Checks that the advertisement is accepted by the Bluetooth daemon and fits into
+    /// the advertising data length supported by the controller.
+    ///
+    /// This uses [Advertisement::validate_with_capabilities] if the advertising capabilities
+    /// of the controller are available and [Advertisement::validate] otherwise.
+    /// An invalid advertisement results in an InvalidArguments error.
+    #[cfg(feature = "adv")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "adv")))]
+    pub async fn validate_advertisement(&self, le_advertisement: &Advertisement) -> Result<()> {
+        match self.supported_advertising_capabilities().await.ok().flatten() {
+            Some(capabilities) => le_advertisement.validate_with_capabilities(&capabilities),
+            None => le_advertisement.validate(),
+        }
+    }
+
+    /// Creates a manager for advertisements that queues registrations when all
+    /// advertising instances of the controller are in use.
+    ///
+    /// See [AdvertisingManager] for details.
+    #[cfg(feature = "adv")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "adv")))]
+    pub async fn advertising_manager(&self) -> Result<AdvertisingManager> {
+        AdvertisingManager::new(self.clone()).await
+    }
+
+    /// Registers an authorization agent using the session of this adapter.
+    #[cfg(feature = "agent")]
+    pub(crate) async fn register_agent(&self, agent: Agent) -> Result<AgentHandle> {
+        let reg_agent = RegisteredAgent::new(agent, Arc::downgrade(&self.inner));
+        reg_agent.register(self.inner.clone()).await
     }
 
     /// Registers a local GATT services hierarchy (GATT Server).
@@ -321,11 +1186,49 @@ impl Adapter {
     /// Registering a service allows applications to publish a *local* GATT service,
     /// which then becomes available to remote devices.
     ///
+    /// The application is not validated before it is registered; use
+    /// [Application::validate](gatt::local::Application::validate) to check the flags of its
+    /// characteristics and descriptors beforehand.
+    ///
     /// Drop the returned [ApplicationHandle](gatt::local::ApplicationHandle) to unregister the application.
+    #[cfg(feature = "gatt-server")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gatt-server")))]
     pub async fn serve_gatt_application(
         &self, gatt_application: gatt::local::Application,
     ) -> Result<gatt::local::ApplicationHandle> {
-        gatt_application.register(self.inner.clone(), self.name.clone()).await
+        gatt_application.register(self.inner.clone(), self.name.clone(), false).await
+    }
+
+    /// Registers a local GATT services hierarchy like [serve_gatt_application](Self::serve_gatt_application)
+    /// and keeps it registered across adapter removal and restarts of the Bluetooth daemon.
+    ///
+    /// When the adapter is removed or the Bluetooth daemon exits,
+    /// the application enters the [Suspended](crate::RegistrationState::Suspended) state.
+    /// Powering off the adapter does not affect the registration of the application.
+    /// It is registered again once the adapter reappears and then enters
+    /// the [Reregistered](crate::RegistrationState::Reregistered) state.
+    /// Use [ApplicationHandle::registration_events](gatt::local::ApplicationHandle::registration_events)
+    /// to observe these state changes.
+    ///
+    /// Drop the returned [ApplicationHandle](gatt::local::ApplicationHandle) to unregister the application.
+    #[cfg(feature = "gatt-server")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gatt-server")))]
+    pub async fn serve_gatt_application_resilient(
+        &self, gatt_application: gatt::local::Application,
+    ) -> Result<gatt::local::ApplicationHandle> {
+        gatt_application.register(self.inner.clone(), self.name.clone(), true).await
+    }
+
+    /// Queries the properties of the GATT manager of the adapter.
+    ///
+    /// Current versions of the Bluetooth daemon expose no GATT manager properties.
+    /// This allows detecting capabilities added by future versions, whose registration
+    /// options can then be specified in [Application::options](gatt::local::Application::options).
+    #[cfg(feature = "gatt-server")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gatt-server")))]
+    pub async fn gatt_manager_properties(&self) -> Result<PropMap> {
+        use dbus::nonblock::stdintf::org_freedesktop_dbus::Properties;
+        Ok(self.proxy().get_all(gatt::local::MANAGER_INTERFACE).await?)
     }
 
     /// Registers local GATT profiles (GATT Client).
@@ -336,6 +1239,8 @@ impl Adapter {
     /// supporting it.
     ///
     /// Drop the returned [ProfileHandle](gatt::local::ProfileHandle) to unregister the application.
+    #[cfg(feature = "gatt-server")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gatt-server")))]
     pub async fn register_gatt_profile(
         &self, gatt_profile: gatt::local::Profile,
     ) -> Result<gatt::local::ProfileHandle> {
@@ -351,7 +1256,7 @@ impl Adapter {
     ///
     /// It will remove also the pairing information.
     pub async fn remove_device(&self, address: Address) -> Result<()> {
-        let path = Device::dbus_path(self.name(), address)?;
+        let path = Device::make_dbus_path(self.name(), address)?;
         self.call_method("RemoveDevice", ((path),)).await?;
         Ok(())
     }
@@ -380,6 +1285,8 @@ impl Adapter {
     ///     connection.
     ///
     /// This method is experimental.
+    /// If the Bluetooth daemon has not been started with experimental interfaces enabled,
+    /// an error of kind [ErrorKind::ExperimentalDisabled] is returned.
     pub async fn connect_device(&self, address: Address, address_type: AddressType) -> Result<Device> {
         let mut m = PropMap::new();
         m.insert("Address".to_string(), Variant(address.to_string().box_clone()));
@@ -389,7 +1296,7 @@ impl Adapter {
             }
             AddressType::BrEdr => (),
         }
-        let (_path,): (Path,) = self.call_method("ConnectDevice", (m,)).await?;
+        let (_path,): (Path,) = self.call_method("ConnectDevice", (m,)).await.map_err(Error::experimental)?;
 
         self.device(address)
     }
@@ -655,8 +1562,24 @@ define_properties!(
     }
 );
 
+/// Whether the D-Bus introspection data of an object declares the method on the interface.
+fn introspection_has_method(xml: &str, interface: &str, method: &str) -> Result<bool> {
+    // Introspection data of the Bluetooth daemon starts with a document type declaration.
+    let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    let doc = roxmltree::Document::parse_with_options(xml, options).map_err(|err| Error {
+        kind: ErrorKind::Internal(InternalErrorKind::InvalidValue),
+        message: format!("invalid introspection data: {err}"),
+    })?;
+    Ok(doc
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("interface") && node.attribute("name") == Some(interface))
+        .flat_map(|node| node.children())
+        .any(|node| node.has_tag_name("method") && node.attribute("name") == Some(method)))
+}
+
 /// Bluetooth adapter event.
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AdapterEvent {
@@ -668,8 +1591,241 @@ pub enum AdapterEvent {
     PropertyChanged(AdapterProperty),
 }
 
+/// Removes the transient devices found by a discovery when dropped.
+struct TransientDeviceRemover {
+    adapter: Adapter,
+    known: HashSet<Address>,
+    added: SyncMutex<HashSet<Address>>,
+}
+
+impl TransientDeviceRemover {
+    fn new(adapter: Adapter, known: &[Address]) -> Self {
+        Self { adapter, known: known.iter().cloned().collect(), added: SyncMutex::new(HashSet::new()) }
+    }
+
+    fn added(&self, address: Address) {
+        if !self.known.contains(&address) {
+            self.added.lock().unwrap().insert(address);
+        }
+    }
+
+    async fn is_transient(device: &Device) -> Result<bool> {
+        Ok(!device.is_paired().await? && !device.is_trusted().await? && !device.is_connected().await?)
+    }
+}
+
+impl Drop for TransientDeviceRemover {
+    fn drop(&mut self) {
+        let added = mem::take(&mut *self.added.lock().unwrap());
+        if added.is_empty() {
+            return;
+        }
+
+        let adapter = self.adapter.clone();
+        self.adapter.spawn(async move {
+            for address in added {
+                let Ok(device) = adapter.device(address) else { continue };
+                if Self::is_transient(&device).await.unwrap_or_default() {
+                    log::trace!("{}: removing transient device {}", &adapter.name, address);
+                    let _ = adapter.remove_device(address).await;
+                }
+            }
+        });
+    }
+}
+
+/// Session keeping an adapter discoverable.
+///
+/// Drop to restore the previous discoverable and pairable state of the adapter.
+///
+/// Use [Adapter::discoverable_session] to start a discoverable session.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[must_use = "DiscoverableSession must be held for the adapter to remain discoverable"]
+pub struct DiscoverableSession {
+    adapter: Adapter,
+    _discoverable: SingleSessionToken,
+    pairable: Option<SingleSessionToken>,
+}
+
+impl Debug for DiscoverableSession {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "DiscoverableSession {{ adapter: {}, pairable: {} }}", self.adapter.name(), self.is_pairable())
+    }
+}
+
+impl DiscoverableSession {
+    /// The adapter that is discoverable.
+    pub fn adapter(&self) -> &Adapter {
+        &self.adapter
+    }
+
+    /// Whether this session has made the adapter pairable.
+    pub fn is_pairable(&self) -> bool {
+        self.pairable.is_some()
+    }
+
+    /// Makes the adapter pairable for the remaining duration of this session.
+    ///
+    /// The pairable timeout of the adapter is disabled while the session is active.
+    /// The previous pairable state and timeout are restored when the session is dropped.
+    pub async fn set_pairable(&mut self) -> Result<()> {
+        if self.pairable.is_some() {
+            return Ok(());
+        }
+
+        let adapter = &self.adapter;
+        let token = adapter
+            .state_session("pairable", async move {
+                let prev_pairable = adapter.is_pairable().await?;
+                let prev_timeout = adapter.pairable_timeout().await?;
+                adapter.set_pairable_timeout(0).await?;
+                adapter.set_pairable(true).await?;
+                let adapter = adapter.clone();
+                Ok(async move {
+                    log::trace!("{}: restoring pairable state", &adapter.name);
+                    let _ = adapter.set_pairable_timeout(prev_timeout).await;
+                    let _ = adapter.set_pairable(prev_pairable).await;
+                })
+            })
+            .await?;
+        self.pairable = Some(token);
+        Ok(())
+    }
+}
+
+/// Session keeping an adapter powered on.
+///
+/// Drop to release the session; the adapter is powered off once the last session
+/// of the [Session](crate::Session) has been dropped, if requested.
+///
+/// Use [Adapter::powered_session] to start a powered session.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[must_use = "PoweredSession must be held for the adapter to remain powered"]
+pub struct PoweredSession {
+    adapter: Adapter,
+    _powered: SingleSessionToken,
+}
+
+impl Debug for PoweredSession {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "PoweredSession {{ adapter: {} }}", self.adapter.name())
+    }
+}
+
+impl PoweredSession {
+    /// The adapter that is powered.
+    pub fn adapter(&self) -> &Adapter {
+        &self.adapter
+    }
+}
+
+/// Configuration of an adapter.
+///
+/// Use [Adapter::config] to obtain a snapshot of the current configuration
+/// and [Adapter::apply_config] to apply a configuration.
+/// Settings that are [None] are left unchanged when the configuration is applied.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdapterConfig {
+    /// Friendly name of the adapter.
+    ///
+    /// An empty string resets the alias to the system provided name.
+    pub alias: Option<String>,
+    /// Whether the adapter is powered.
+    pub powered: Option<bool>,
+    /// Whether the adapter is discoverable.
+    pub discoverable: Option<bool>,
+    /// Discoverable timeout in seconds.
+    ///
+    /// Zero disables the timeout.
+    pub discoverable_timeout: Option<u32>,
+    /// Whether the adapter is pairable.
+    pub pairable: Option<bool>,
+    /// Pairable timeout in seconds.
+    ///
+    /// Zero disables the timeout.
+    pub pairable_timeout: Option<u32>,
+    #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _non_exhaustive: (),
+}
+
+/// Single setting of an [AdapterConfig].
+#[derive(Clone, Copy, Debug)]
+enum AdapterConfigStep {
+    Alias,
+    Powered,
+    Discoverable,
+    DiscoverableTimeout,
+    Pairable,
+    PairableTimeout,
+}
+
+impl AdapterConfigStep {
+    /// Settings of the configuration in the order they must be applied.
+    ///
+    /// The adapter is powered on first and powered off last, since the discoverable
+    /// setting can only be changed while the adapter is powered.
+    fn order(config: &AdapterConfig) -> Vec<Self> {
+        let mut steps = Vec::new();
+        if config.powered == Some(true) {
+            steps.push(Self::Powered);
+        }
+        if config.alias.is_some() {
+            steps.push(Self::Alias);
+        }
+        if config.pairable_timeout.is_some() {
+            steps.push(Self::PairableTimeout);
+        }
+        if config.pairable.is_some() {
+            steps.push(Self::Pairable);
+        }
+        if config.discoverable_timeout.is_some() {
+            steps.push(Self::DiscoverableTimeout);
+        }
+        if config.discoverable.is_some() {
+            steps.push(Self::Discoverable);
+        }
+        if config.powered == Some(false) {
+            steps.push(Self::Powered);
+        }
+        steps
+    }
+
+    /// Applies the setting from the configuration, if it is specified.
+    async fn apply(self, adapter: &Adapter, config: &AdapterConfig) -> Result<()> {
+        match self {
+            Self::Alias => match &config.alias {
+                Some(alias) => adapter.set_alias(alias.clone()).await,
+                None => Ok(()),
+            },
+            Self::Powered => match config.powered {
+                Some(powered) => adapter.set_powered(powered).await,
+                None => Ok(()),
+            },
+            Self::Discoverable => match config.discoverable {
+                Some(discoverable) => adapter.set_discoverable(discoverable).await,
+                None => Ok(()),
+            },
+            Self::DiscoverableTimeout => match config.discoverable_timeout {
+                Some(timeout) => adapter.set_discoverable_timeout(timeout).await,
+                None => Ok(()),
+            },
+            Self::Pairable => match config.pairable {
+                Some(pairable) => adapter.set_pairable(pairable).await,
+                None => Ok(()),
+            },
+            Self::PairableTimeout => match config.pairable_timeout {
+                Some(timeout) => adapter.set_pairable_timeout(timeout).await,
+                None => Ok(()),
+            },
+        }
+    }
+}
+
 /// Transport parameter determines the type of scan.
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Display, EnumString)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
@@ -691,11 +1847,66 @@ impl Default for DiscoveryTransport {
     }
 }
 
+/// Class-of-device filter for the discovery of classic Bluetooth (BR/EDR) devices.
+///
+/// The class of device is split into the major device class, the minor device class and
+/// the service classes as defined by the Bluetooth assigned numbers.
+/// The default filter matches all devices.
+///
+/// Use [Adapter::discover_classic_devices] to apply the filter to discovered devices.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Default, Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassOfDeviceFilter {
+    /// Required major device class, for example `0x06` for imaging devices
+    /// such as printers and scanners.
+    pub major_class: Option<u8>,
+    /// Required minor device class.
+    ///
+    /// Its interpretation depends on the major device class.
+    pub minor_class: Option<u8>,
+    /// Bit mask of service classes that must all be present,
+    /// for example `0x0008` for rendering and `0x0010` for capturing devices.
+    pub service_classes: u16,
+    #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _non_exhaustive: (),
+}
+
+impl ClassOfDeviceFilter {
+    /// Checks whether the class of device matches this filter.
+    pub fn matches(&self, class: u32) -> bool {
+        let minor = ((class >> 2) & 0x3f) as u8;
+        let major = ((class >> 8) & 0x1f) as u8;
+        let services = ((class >> 13) & 0x7ff) as u16;
+        self.major_class.map_or(true, |major_class| major_class == major)
+            && self.minor_class.map_or(true, |minor_class| minor_class == minor)
+            && services & self.service_classes == self.service_classes
+    }
+}
+
+/// Options for device discovery that are handled by this library.
+///
+/// Use [Adapter::discover_devices_with_options] to start a discovery with these options.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Default, Clone, Debug, Eq, PartialEq)]
+pub struct DiscoveryOptions {
+    /// Remove transient devices found by the discovery once the discovery stream is dropped.
+    ///
+    /// Device objects that were created by BlueZ during the discovery are removed,
+    /// unless the device has been paired, trusted or is connected.
+    /// This prevents the device object cache of BlueZ from growing without bound
+    /// in long-running scanners.
+    pub remove_transient_devices: bool,
+    #[doc(hidden)]
+    pub _non_exhaustive: (),
+}
+
 /// Bluetooth device discovery filter.
 ///
 /// The default discovery filter does not restrict any devices and provides
 /// [duplicate data](Self::duplicate_data).
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 #[derive(Default, Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DiscoveryFilter {
@@ -768,16 +1979,77 @@ pub struct DiscoveryFilter {
     /// it work as a logical OR, also setting empty
     /// string "" pattern will match any device found.
     pub pattern: Option<String>,
+    /// Retry starting the discovery for up to the specified duration when the adapter
+    /// is not ready or a previous discovery is still being stopped.
+    ///
+    /// These transient conditions commonly occur when the adapter has just been
+    /// powered on or another client has stopped discovery concurrently.
+    /// Retries are performed with an exponentially increasing delay.
+    /// If not set, such errors are returned immediately.
+    ///
+    /// This is handled by this library and not passed to BlueZ.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub start_retry_timeout: Option<Duration>,
     #[doc(hidden)]
     #[cfg_attr(feature = "serde", serde(skip))]
     pub _non_exhaustive: (),
 }
 
 impl DiscoveryFilter {
+    /// Checks whether the device matches the criteria of this filter.
+    ///
+    /// Since the Bluetooth daemon merges the discovery filters of all clients,
+    /// a discovery may report devices that do not match the filter of this client.
+    /// This re-applies the service UUID, RSSI, pathloss and pattern criteria to a device.
+    /// The transport is not checked.
+    ///
+    /// Use [Adapter::discover_devices_matching] to apply the filter to discovered devices.
+    pub fn matches(&self, device: &DeviceSummary) -> bool {
+        if !self.uuids.is_empty()
+            && !self
+                .uuids
+                .iter()
+                .any(|uuid| device.uuids.contains(uuid) || device.service_data.contains_key(uuid))
+        {
+            return false;
+        }
+
+        if let Some(rssi) = self.rssi {
+            if !device.rssi.is_some_and(|device_rssi| device_rssi >= rssi) {
+                return false;
+            }
+        }
+
+        if let Some(pathloss) = self.pathloss {
+            match (device.rssi, device.tx_power) {
+                (Some(rssi), Some(tx_power)) if i32::from(path_loss(rssi, tx_power)) <= i32::from(pathloss) => (),
+                _ => return false,
+            }
+        }
+
+        if let Some(pattern) = self.pattern.as_deref().filter(|pattern| !pattern.is_empty()) {
+            let name_matches = device.name.as_deref().is_some_and(|name| name.starts_with(pattern));
+            if !device.address.to_string().starts_with(pattern) && !name_matches {
+                return false;
+            }
+        }
+
+        true
+    }
+
     fn into_dict(self) -> HashMap<&'static str, Variant<Box<dyn RefArg>>> {
         let mut hm: HashMap<&'static str, Variant<Box<dyn RefArg>>> = HashMap::new();
-        let Self { uuids, rssi, pathloss, transport, duplicate_data, discoverable, pattern, _non_exhaustive } =
-            self;
+        let Self {
+            uuids,
+            rssi,
+            pathloss,
+            transport,
+            duplicate_data,
+            discoverable,
+            pattern,
+            start_retry_timeout: _,
+            _non_exhaustive,
+        } = self;
         hm.insert("UUIDs", Variant(Box::new(uuids.into_iter().map(|uuid| uuid.to_string()).collect::<Vec<_>>())));
         if let Some(rssi) = rssi {
             hm.insert("RSSI", Variant(Box::new(rssi)));
@@ -804,3 +2076,45 @@ impl DiscoveryFilter {
         hm
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTROSPECTION: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+"http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect"><arg name="xml" type="s" direction="out"/></method>
+  </interface>
+  <interface name="org.bluez.Adapter1">
+    <method name="StartDiscovery"></method>
+    <method name="ConnectDevice">
+      <arg name="properties" type="a{sv}" direction="in"/>
+      <annotation name="org.freedesktop.DBus.Experimental" value="true"/>
+    </method>
+  </interface>
+  <interface name="org.bluez.Device1">
+    <method name="Pair"/>
+  </interface>
+  <node name="dev_00_11_22_33_44_55"/>
+</node>"#;
+
+    #[test]
+    fn introspection_methods() {
+        assert!(introspection_has_method(INTROSPECTION, INTERFACE, "ConnectDevice").unwrap());
+        assert!(introspection_has_method(INTROSPECTION, INTERFACE, "StartDiscovery").unwrap());
+        assert!(!introspection_has_method(INTROSPECTION, INTERFACE, "Pair").unwrap());
+        assert!(!introspection_has_method(INTROSPECTION, "org.bluez.Device1", "ConnectDevice").unwrap());
+
+        let without = INTROSPECTION.replace("ConnectDevice", "RemoveDevice");
+        assert!(!introspection_has_method(&without, INTERFACE, "ConnectDevice").unwrap());
+
+        // Methods are found regardless of formatting.
+        let compact =
+            r#"<node><interface name='org.bluez.Adapter1'><method name='ConnectDevice' /></interface></node>"#;
+        assert!(introspection_has_method(compact, INTERFACE, "ConnectDevice").unwrap());
+
+        assert!(introspection_has_method("<node><interface>", INTERFACE, "ConnectDevice").is_err());
+    }
+}
@@ -1,44 +1,169 @@
 //! Bluetooth LE advertising.
 
-use dbus::{
-    arg::{PropMap, RefArg, Variant},
-    nonblock::Proxy,
+use dbus::arg::{RefArg, Variant};
+use std::collections::HashMap;
+use strum::{Display, EnumString};
+
+use crate::{read_dict, Result};
+
+#[cfg(feature = "adv")]
+use crate::{
+    registration_state_stream, Adapter, AdapterEvent, AdapterProperty, Error, ErrorKind, RegistrationKeeper,
+    RegistrationState, SessionInner, UuidExt, SERVICE_NAME, TIMEOUT,
 };
+#[cfg(feature = "adv")]
+use dbus::{arg::PropMap, nonblock::Proxy};
+#[cfg(feature = "adv")]
 use dbus_crossroads::{Crossroads, IfaceBuilder, IfaceToken};
-use futures::channel::oneshot;
+#[cfg(feature = "adv")]
+use futures::{channel::oneshot, pin_mut, Stream, StreamExt};
+#[cfg(feature = "adv")]
 use std::{
-    collections::{BTreeMap, BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet},
     fmt,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     time::Duration,
 };
-use strum::{Display, EnumString};
+#[cfg(feature = "adv")]
+use tokio::{
+    sync::{mpsc, watch},
+    time::{sleep_until, Instant},
+};
+#[cfg(feature = "adv")]
 use uuid::Uuid;
 
-use crate::{read_dict, Adapter, Result, SessionInner, SERVICE_NAME, TIMEOUT};
-
 pub(crate) const MANAGER_INTERFACE: &str = "org.bluez.LEAdvertisingManager1";
+#[cfg(feature = "adv")]
 pub(crate) const ADVERTISEMENT_INTERFACE: &str = "org.bluez.LEAdvertisement1";
+#[cfg(feature = "adv")]
 pub(crate) const ADVERTISEMENT_PREFIX: &str = publish_path!("advertising/");
 
+/// Maximum length of legacy advertising data.
+#[cfg(feature = "adv")]
+const MAX_LEGACY_AD_LEN: usize = 31;
+/// Length of the length and type fields of an AD structure.
+#[cfg(feature = "adv")]
+const AD_HEADER_LEN: usize = 2;
+/// AD types that are generated by the Bluetooth daemon or controller and thus
+/// must not be specified in [Advertisement::advertising_data].
+///
+/// The second element names the field that generates the AD type.
+#[cfg(feature = "adv")]
+const RESERVED_AD_TYPES: &[(u8, &str)] = &[
+    (0x01, "Flags"),
+    (0x02, "ServiceUUIDs"),
+    (0x03, "ServiceUUIDs"),
+    (0x04, "ServiceUUIDs"),
+    (0x05, "ServiceUUIDs"),
+    (0x06, "ServiceUUIDs"),
+    (0x07, "ServiceUUIDs"),
+    (0x08, "LocalName"),
+    (0x09, "LocalName"),
+    (0x0a, "TxPower"),
+    (0x0d, "Class of Device"),
+    (0x0e, "Simple Pairing Hash C-192"),
+    (0x0f, "Simple Pairing Randomizer R-192"),
+    (0x10, "Device ID"),
+    (0x11, "Security Manager Out of Band Flags"),
+    (0x12, "Peripheral Connection Interval Range"),
+    (0x14, "SolicitUUIDs"),
+    (0x15, "SolicitUUIDs"),
+    (0x16, "ServiceData"),
+    (0x17, "Public Target Address"),
+    (0x18, "Random Target Address"),
+    (0x19, "Appearance"),
+    (0x1a, "MinInterval and MaxInterval"),
+    (0x1b, "LE Bluetooth Device Address"),
+    (0x1c, "LE Role"),
+    (0x1d, "Simple Pairing Hash C-256"),
+    (0x1e, "Simple Pairing Randomizer R-256"),
+    (0x1f, "SolicitUUIDs"),
+    (0x20, "ServiceData"),
+    (0x21, "ServiceData"),
+    (0x22, "LE Secure Connections Confirmation Value"),
+    (0x23, "LE Secure Connections Random Value"),
+    (0x27, "LE Supported Features"),
+    (0x28, "Channel Map Update Indication"),
+    (0xff, "ManufacturerData"),
+];
+/// Minimum advertising interval.
+#[cfg(feature = "adv")]
+const MIN_INTERVAL: Duration = Duration::from_millis(20);
+/// Maximum advertising interval.
+#[cfg(feature = "adv")]
+const MAX_INTERVAL: Duration = Duration::from_micros(10_485_759_375);
+
 /// Determines the type of advertising packet requested.
+#[cfg(feature = "adv")]
+#[cfg_attr(docsrs, doc(cfg(feature = "adv")))]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Display, EnumString)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type {
     /// Broadcast
+    ///
+    /// Non-connectable advertising, for example for beacons.
+    /// The advertisement must not be discoverable.
     #[strum(serialize = "broadcast")]
     Broadcast,
     /// Peripheral
+    ///
+    /// Connectable advertising.
     #[strum(serialize = "peripheral")]
     Peripheral,
 }
 
+#[cfg(feature = "adv")]
 impl Default for Type {
     fn default() -> Self {
         Self::Peripheral
     }
 }
 
+/// Preset of the advertising interval, trading discovery latency for power consumption.
+///
+/// Use [Advertisement::set_interval_preset] to apply a preset.
+#[cfg(feature = "adv")]
+#[cfg_attr(docsrs, doc(cfg(feature = "adv")))]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, PartialOrd, Ord, Hash, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum IntervalPreset {
+    /// Fast advertising with an interval of 30 ms to 60 ms for quick discovery.
+    #[strum(serialize = "fast")]
+    Fast,
+    /// Balanced advertising with an interval of 152.5 ms to 211.25 ms.
+    #[default]
+    #[strum(serialize = "balanced")]
+    Balanced,
+    /// Low-power advertising with an interval of 1022.5 ms to 1285 ms.
+    #[strum(serialize = "low-power")]
+    LowPower,
+}
+
+#[cfg(feature = "adv")]
+impl IntervalPreset {
+    /// Minimum advertising interval of the preset.
+    pub const fn min_interval(self) -> Duration {
+        match self {
+            Self::Fast => Duration::from_millis(30),
+            Self::Balanced => Duration::from_micros(152_500),
+            Self::LowPower => Duration::from_micros(1_022_500),
+        }
+    }
+
+    /// Maximum advertising interval of the preset.
+    pub const fn max_interval(self) -> Duration {
+        match self {
+            Self::Fast => Duration::from_millis(60),
+            Self::Balanced => Duration::from_micros(211_250),
+            Self::LowPower => Duration::from_millis(1_285),
+        }
+    }
+}
+
 /// Secondary channel for advertisement.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Display, EnumString)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -129,6 +254,8 @@ impl Capabilities {
 /// versions of the same UUID will be used in the advertising data as appropriate.
 ///
 /// Use [Adapter::advertise] to register a new advertisement.
+#[cfg(feature = "adv")]
+#[cfg_attr(docsrs, doc(cfg(feature = "adv")))]
 #[derive(Clone, Debug, Default, Eq, PartialEq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "serde", serde(default))]
@@ -157,9 +284,12 @@ pub struct Advertisement {
     ///
     /// Key is the advertising type and value is the
     /// data as byte array.
+    /// This allows vendor-specific advertising formats that are
+    /// not covered by the other fields.
     ///
     /// Note: Types already handled by other properties shall
     /// not be used.
+    /// Such types are rejected by [Advertisement::validate].
     pub advertising_data: BTreeMap<u8, Vec<u8>>,
     /// Advertise as general discoverable.
     ///
@@ -184,12 +314,20 @@ pub struct Advertisement {
     pub discoverable_timeout: Option<Duration>,
     /// List of system features to be included in the advertising
     /// packet.
+    ///
+    /// Include [Feature::TxPower] to advertise the TX power level,
+    /// see [set_tx_power_included](Self::set_tx_power_included).
     pub system_includes: BTreeSet<Feature>,
     /// Local name to be used in the advertising report.
     ///
     /// If the
     /// string is too big to fit into the packet it will be
     /// truncated.
+    ///
+    /// A truncated name is advertised as shortened local name.
+    /// Some scanners, notably iOS, only display the complete local name
+    /// of a device that is not connected; use [max_local_name_len](Self::max_local_name_len)
+    /// to check whether the name fits.
     pub local_name: Option<String>,
     /// Appearance to be used in the advertising report.
     pub appearance: Option<u16>,
@@ -216,6 +354,8 @@ pub struct Advertisement {
     /// are in the range [20ms, 10,485s]. If the provided
     /// MinInterval is larger than the provided MaxInterval,
     /// the registration will return failure.
+    ///
+    /// See [IntervalPreset] for common values.
     pub min_interval: Option<Duration>,
     /// Maximum advertising interval to be used by the
     /// advertising set, in milliseconds.
@@ -236,6 +376,7 @@ pub struct Advertisement {
     pub _non_exhaustive: (),
 }
 
+#[cfg(feature = "adv")]
 impl Advertisement {
     pub(crate) fn register_interface(cr: &mut Crossroads) -> IfaceToken<Self> {
         cr.register(ADVERTISEMENT_INTERFACE, |ib: &mut IfaceBuilder<Self>| {
@@ -293,26 +434,363 @@ impl Advertisement {
         })
     }
 
+    /// Checks that the advertisement is accepted by the Bluetooth daemon and fits into
+    /// a legacy advertising packet of 31 bytes.
+    ///
+    /// AD types in [advertising_data](Self::advertising_data) must not be reserved,
+    /// i.e. they must not be generated from other fields or by the Bluetooth daemon.
+    ///
+    /// Use [Adapter::validate_advertisement] to take the advertising capabilities
+    /// of the controller into account.
+    pub fn validate(&self) -> Result<()> {
+        self.validate_with_max_len(MAX_LEGACY_AD_LEN)
+    }
+
+    /// Checks that the advertisement is accepted by the Bluetooth daemon and fits into
+    /// the maximum advertising data length of the controller.
+    ///
+    /// The extended advertising data length is only used when a secondary channel is set,
+    /// since otherwise legacy advertising is performed.
+    pub fn validate_with_capabilities(&self, capabilities: &Capabilities) -> Result<()> {
+        let max_len = match self.secondary_channel {
+            Some(_) => usize::from(capabilities.max_advertisement_length),
+            None => usize::from(capabilities.max_advertisement_length).min(MAX_LEGACY_AD_LEN),
+        };
+        self.validate_with_max_len(max_len)
+    }
+
+    fn validate_with_max_len(&self, max_len: usize) -> Result<()> {
+        let invalid = |message: String| Err(Error { kind: ErrorKind::InvalidArguments, message });
+
+        if self.advertisement_type == Type::Broadcast {
+            if self.discoverable.is_some() {
+                return invalid("Discoverable must not be set for broadcast advertisements".to_string());
+            }
+            if self.discoverable_timeout.is_some() {
+                return invalid("DiscoverableTimeout must not be set for broadcast advertisements".to_string());
+            }
+        }
+        if self.discoverable_timeout.is_some() && self.discoverable != Some(true) {
+            return invalid("DiscoverableTimeout requires Discoverable to be enabled".to_string());
+        }
+        if self.appearance.is_some() && self.system_includes.contains(&Feature::Appearance) {
+            return invalid(
+                "Appearance must not be set when the appearance system feature is included".to_string(),
+            );
+        }
+        if self.local_name.is_some() && self.system_includes.contains(&Feature::LocalName) {
+            return invalid(
+                "LocalName must not be set when the local name system feature is included".to_string(),
+            );
+        }
+
+        for (name, value) in [
+            ("DiscoverableTimeout", self.discoverable_timeout),
+            ("Duration", self.duration),
+            ("Timeout", self.timeout),
+        ] {
+            if let Some(value) = value {
+                if value.as_secs() > u64::from(u16::MAX) {
+                    return invalid(format!("{name} of {} s exceeds {} s", value.as_secs(), u16::MAX));
+                }
+            }
+        }
+        for (name, value) in [("MinInterval", self.min_interval), ("MaxInterval", self.max_interval)] {
+            if let Some(value) = value {
+                if value < MIN_INTERVAL || value > MAX_INTERVAL {
+                    return invalid(format!(
+                        "{name} of {} ms is outside of range [{} ms, {} ms]",
+                        value.as_millis(),
+                        MIN_INTERVAL.as_millis(),
+                        MAX_INTERVAL.as_millis()
+                    ));
+                }
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_interval, self.max_interval) {
+            if min > max {
+                return invalid(format!(
+                    "MinInterval of {} ms is larger than MaxInterval of {} ms",
+                    min.as_millis(),
+                    max.as_millis()
+                ));
+            }
+        }
+        if let Some(tx_power) = self.tx_power {
+            if !(-127..=20).contains(&tx_power) {
+                return invalid(format!("TxPower of {tx_power} dBm is outside of range [-127 dBm, 20 dBm]"));
+            }
+        }
+
+        for (ad_type, data) in &self.advertising_data {
+            if let Some((_, field)) = RESERVED_AD_TYPES.iter().find(|(reserved, _)| reserved == ad_type) {
+                return invalid(format!(
+                    "Data must not contain AD type {ad_type:#04x}, which is reserved for {field}"
+                ));
+            }
+            if data.len() > usize::from(u8::MAX) - 1 {
+                return invalid(format!("Data of AD type {ad_type:#04x} with {} bytes is too long", data.len()));
+            }
+        }
+
+        let fields = self.advertising_data_lengths();
+        let len: usize = fields.iter().map(|(_, len)| len).sum();
+        if len > max_len {
+            let fields: Vec<_> = fields.iter().map(|(name, len)| format!("{name}: {len} bytes")).collect();
+            return invalid(format!(
+                "advertising data of {len} bytes exceeds maximum length of {max_len} bytes ({})",
+                fields.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Creates an empty advertisement for non-connectable broadcasting,
+    /// for example as a beacon.
+    pub fn broadcast() -> Self {
+        Self { advertisement_type: Type::Broadcast, ..Default::default() }
+    }
+
+    /// Whether remote devices can connect in response to the advertisement.
+    pub fn is_connectable(&self) -> bool {
+        self.advertisement_type == Type::Peripheral
+    }
+
+    /// Sets the minimum and maximum advertising interval from the specified preset.
+    pub fn set_interval_preset(&mut self, preset: IntervalPreset) {
+        self.min_interval = Some(preset.min_interval());
+        self.max_interval = Some(preset.max_interval());
+    }
+
+    /// Maximum length in bytes of a local name that fits completely into a legacy
+    /// advertising packet together with the other advertising data.
+    ///
+    /// A longer [local_name](Self::local_name) is truncated by the Bluetooth daemon and
+    /// advertised as shortened local name.
+    pub fn max_local_name_len(&self) -> usize {
+        let len: usize = self.advertising_data_lengths().iter().map(|(_, len)| len).sum();
+        MAX_LEGACY_AD_LEN.saturating_sub(len + AD_HEADER_LEN)
+    }
+
+    /// Whether the local name fits completely into a legacy advertising packet and
+    /// is thus advertised as complete local name.
+    ///
+    /// Returns [None] if no local name is set.
+    pub fn is_local_name_complete(&self) -> Option<bool> {
+        self.local_name.as_ref().map(|name| name.len() <= self.max_local_name_len())
+    }
+
+    /// Shortens the local name so that it fits completely into a legacy advertising packet.
+    ///
+    /// The name is truncated at a character boundary.
+    /// Unlike the truncation performed by the Bluetooth daemon, the resulting name
+    /// is advertised as complete local name.
+    pub fn shorten_local_name(&mut self) {
+        let max_len = self.max_local_name_len();
+        if let Some(name) = &mut self.local_name {
+            let len = (0..=max_len.min(name.len())).rev().find(|&len| name.is_char_boundary(len)).unwrap_or(0);
+            name.truncate(len);
+        }
+    }
+
+    /// Whether the TX power level is included in the advertising data.
+    pub fn is_tx_power_included(&self) -> bool {
+        self.system_includes.contains(&Feature::TxPower)
+    }
+
+    /// Sets whether the TX power level is included in the advertising data.
+    ///
+    /// Including the TX power level allows scanners to estimate the distance
+    /// but takes up 3 bytes of the advertising data.
+    pub fn set_tx_power_included(&mut self, included: bool) {
+        match included {
+            true => self.system_includes.insert(Feature::TxPower),
+            false => self.system_includes.remove(&Feature::TxPower),
+        };
+    }
+
+    /// Lengths of the AD structures that the Bluetooth daemon places into the advertising data.
+    ///
+    /// The local name is not included, since it is truncated or moved into
+    /// the scan response by the Bluetooth daemon.
+    fn advertising_data_lengths(&self) -> Vec<(&'static str, usize)> {
+        fn uuid_len(uuid: &Uuid) -> usize {
+            if uuid.as_u16().is_some() {
+                2
+            } else if uuid.as_u32().is_some() {
+                4
+            } else {
+                16
+            }
+        }
+
+        fn uuid_list_len(uuids: &BTreeSet<Uuid>) -> usize {
+            [2, 4, 16]
+                .into_iter()
+                .map(|size| uuids.iter().filter(|uuid| uuid_len(uuid) == size).count() * size)
+                .filter(|&len| len > 0)
+                .map(|len| AD_HEADER_LEN + len)
+                .sum()
+        }
+
+        let mut fields = Vec::new();
+        if self.advertisement_type == Type::Peripheral || self.discoverable == Some(true) {
+            fields.push(("Flags", AD_HEADER_LEN + 1));
+        }
+        if !self.service_uuids.is_empty() {
+            fields.push(("ServiceUUIDs", uuid_list_len(&self.service_uuids)));
+        }
+        if !self.solicit_uuids.is_empty() {
+            fields.push(("SolicitUUIDs", uuid_list_len(&self.solicit_uuids)));
+        }
+        if !self.manufacturer_data.is_empty() {
+            let len = self.manufacturer_data.values().map(|data| AD_HEADER_LEN + 2 + data.len()).sum();
+            fields.push(("ManufacturerData", len));
+        }
+        if !self.service_data.is_empty() {
+            let len =
+                self.service_data.iter().map(|(uuid, data)| AD_HEADER_LEN + uuid_len(uuid) + data.len()).sum();
+            fields.push(("ServiceData", len));
+        }
+        if !self.advertising_data.is_empty() {
+            let len = self.advertising_data.values().map(|data| AD_HEADER_LEN + data.len()).sum();
+            fields.push(("Data", len));
+        }
+        if self.appearance.is_some() || self.system_includes.contains(&Feature::Appearance) {
+            fields.push(("Appearance", AD_HEADER_LEN + 2));
+        }
+        if self.system_includes.contains(&Feature::TxPower) {
+            fields.push(("TxPower", AD_HEADER_LEN + 1));
+        }
+        fields
+    }
+
+    /// Names and values of the D-Bus properties exported for this advertisement.
+    fn exported_properties(&self) -> Vec<String> {
+        fn list<T: fmt::Display>(items: impl IntoIterator<Item = T>) -> String {
+            items.into_iter().map(|item| item.to_string()).collect::<Vec<_>>().join(", ")
+        }
+
+        let mut props = vec![format!("Type={}", self.advertisement_type)];
+        if !self.service_uuids.is_empty() {
+            props.push(format!("ServiceUUIDs=[{}]", list(&self.service_uuids)));
+        }
+        if !self.manufacturer_data.is_empty() {
+            props.push(format!(
+                "ManufacturerData=[{}]",
+                list(self.manufacturer_data.iter().map(|(id, data)| format!("{id:#06x}: {} bytes", data.len())))
+            ));
+        }
+        if !self.solicit_uuids.is_empty() {
+            props.push(format!("SolicitUUIDs=[{}]", list(&self.solicit_uuids)));
+        }
+        if !self.service_data.is_empty() {
+            props.push(format!(
+                "ServiceData=[{}]",
+                list(self.service_data.iter().map(|(uuid, data)| format!("{uuid}: {} bytes", data.len())))
+            ));
+        }
+        if !self.advertising_data.is_empty() {
+            props.push(format!(
+                "Data=[{}]",
+                list(self.advertising_data.iter().map(|(ty, data)| format!("{ty:#04x}: {} bytes", data.len())))
+            ));
+        }
+        if let Some(discoverable) = self.discoverable {
+            props.push(format!("Discoverable={discoverable}"));
+        }
+        if let Some(timeout) = self.discoverable_timeout {
+            props.push(format!("DiscoverableTimeout={}s", timeout.as_secs()));
+        }
+        if !self.system_includes.is_empty() {
+            props.push(format!("Includes=[{}]", list(&self.system_includes)));
+        }
+        if let Some(local_name) = &self.local_name {
+            props.push(format!("LocalName={local_name:?}"));
+        }
+        if let Some(appearance) = self.appearance {
+            props.push(format!("Appearance={appearance:#06x}"));
+        }
+        if let Some(duration) = self.duration {
+            props.push(format!("Duration={}s", duration.as_secs()));
+        }
+        if let Some(timeout) = self.timeout {
+            props.push(format!("Timeout={}s", timeout.as_secs()));
+        }
+        if let Some(secondary_channel) = self.secondary_channel {
+            props.push(format!("SecondaryChannel={secondary_channel}"));
+        }
+        if let Some(min_interval) = self.min_interval {
+            props.push(format!("MinInterval={}ms", min_interval.as_millis()));
+        }
+        if let Some(max_interval) = self.max_interval {
+            props.push(format!("MaxInterval={}ms", max_interval.as_millis()));
+        }
+        if let Some(tx_power) = self.tx_power {
+            props.push(format!("TxPower={tx_power}"));
+        }
+        props
+    }
+
     pub(crate) async fn register(
-        self, inner: Arc<SessionInner>, adapter_name: Arc<String>,
+        self, inner: Arc<SessionInner>, adapter_name: Arc<String>, resilient: bool,
     ) -> Result<AdvertisementHandle> {
         let name = dbus::Path::new(format!("{}{}", ADVERTISEMENT_PREFIX, Uuid::new_v4().as_simple())).unwrap();
+        let (keeper, state_rx) = match resilient {
+            true => {
+                let (keeper, state_rx) = RegistrationKeeper::new(&inner, adapter_name.clone(), true).await?;
+                (Some(keeper), state_rx)
+            }
+            false => (None, watch::channel(RegistrationState::Active).1),
+        };
         log::trace!("Publishing advertisement at {}", &name);
 
+        let exported_properties = self.exported_properties();
         {
             let mut cr = inner.crossroads.lock().await;
             cr.insert(name.clone(), &[inner.le_advertisment_token], self);
         }
+        inner.record_export(&name, &[ADVERTISEMENT_INTERFACE]);
 
         log::trace!("Registering advertisement at {}", &name);
         let proxy =
-            Proxy::new(SERVICE_NAME, Adapter::dbus_path(&adapter_name)?, TIMEOUT, inner.connection.clone());
-        proxy.method_call(MANAGER_INTERFACE, "RegisterAdvertisement", (name.clone(), PropMap::new())).await?;
+            Proxy::new(SERVICE_NAME, Adapter::make_dbus_path(&adapter_name)?, TIMEOUT, inner.connection.clone());
+        if let Err(err) = proxy
+            .method_call::<(), _, _, _>(
+                MANAGER_INTERFACE,
+                "RegisterAdvertisement",
+                (name.clone(), PropMap::new()),
+            )
+            .await
+        {
+            let _: Option<Self> = inner.crossroads.lock().await.remove(&name);
+            inner.record_unexport(&name);
+            return Err(Error::from(err).with_context(format_args!(
+                "Bluetooth daemon rejected advertisement on {} with properties {}",
+                &adapter_name,
+                exported_properties.join(", ")
+            )));
+        }
 
         let (drop_tx, drop_rx) = oneshot::channel();
+        let (unregistered_tx, unregistered_rx) = oneshot::channel();
         let unreg_name = name.clone();
-        tokio::spawn(async move {
-            let _ = drop_rx.await;
+        inner.clone().spawn(async move {
+            let _ = match keeper {
+                Some(keeper) => {
+                    keeper
+                        .run(
+                            drop_rx,
+                            MANAGER_INTERFACE,
+                            "RegisterAdvertisement",
+                            unreg_name.clone(),
+                            &PropMap::new(),
+                        )
+                        .await
+                }
+                None => drop_rx.await,
+            };
 
             log::trace!("Unregistering advertisement at {}", &unreg_name);
             let _: std::result::Result<(), dbus::Error> =
@@ -321,29 +799,357 @@ impl Advertisement {
             log::trace!("Unpublishing advertisement at {}", &unreg_name);
             let mut cr = inner.crossroads.lock().await;
             let _: Option<Self> = cr.remove(&unreg_name);
+            inner.record_unexport(&unreg_name);
+            let _ = unregistered_tx.send(());
         });
 
-        Ok(AdvertisementHandle { name, _drop_tx: drop_tx })
+        Ok(AdvertisementHandle { name, _drop_tx: drop_tx, unregistered_rx: Some(unregistered_rx), state_rx })
     }
 }
 
 /// Handle to active Bluetooth LE advertisement.
 ///
 /// Drop to unregister advertisement.
+#[cfg(feature = "adv")]
+#[cfg_attr(docsrs, doc(cfg(feature = "adv")))]
 #[must_use = "AdvertisementHandle must be held for advertisement to be broadcasted"]
 pub struct AdvertisementHandle {
     name: dbus::Path<'static>,
     _drop_tx: oneshot::Sender<()>,
+    unregistered_rx: Option<oneshot::Receiver<()>>,
+    state_rx: watch::Receiver<RegistrationState>,
 }
 
+#[cfg(feature = "adv")]
+impl AdvertisementHandle {
+    /// Current registration state of the advertisement.
+    ///
+    /// This is always [RegistrationState::Active], unless the advertisement was registered
+    /// using [Adapter::advertise_resilient].
+    pub fn registration_state(&self) -> RegistrationState {
+        *self.state_rx.borrow()
+    }
+
+    /// Streams changes of the registration state of the advertisement.
+    ///
+    /// The stream ends immediately, unless the advertisement was registered
+    /// using [Adapter::advertise_resilient].
+    pub fn registration_events(&self) -> impl Stream<Item = RegistrationState> {
+        registration_state_stream(&self.state_rx)
+    }
+
+    /// Unregisters the advertisement and waits until it has been unregistered.
+    pub(crate) async fn unregister(mut self) {
+        let unregistered_rx = self.unregistered_rx.take();
+        drop(self);
+        if let Some(unregistered_rx) = unregistered_rx {
+            let _ = unregistered_rx.await;
+        }
+    }
+}
+
+#[cfg(feature = "adv")]
 impl Drop for AdvertisementHandle {
     fn drop(&mut self) {
         // required for drop order
     }
 }
 
+#[cfg(feature = "adv")]
 impl fmt::Debug for AdvertisementHandle {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "AdvertisementHandle {{ {} }}", &self.name)
     }
 }
+
+/// State of an advertisement managed by an [AdvertisingManager].
+#[cfg(feature = "adv")]
+#[cfg_attr(docsrs, doc(cfg(feature = "adv")))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ManagedAdvertisementState {
+    /// Waiting for an advertising instance of the controller to become available.
+    Queued,
+    /// Registered and being broadcasted.
+    Active,
+    /// Registration failed.
+    ///
+    /// Registration is retried with exponential backoff unless the advertisement
+    /// was rejected as invalid.
+    Failed(Error),
+}
+
+/// Delay before the first retry of a failed registration of a managed advertisement.
+#[cfg(feature = "adv")]
+const MANAGED_RETRY_MIN: Duration = Duration::from_secs(1);
+
+/// Maximum delay between retries of a failed registration of a managed advertisement.
+#[cfg(feature = "adv")]
+const MANAGED_RETRY_MAX: Duration = Duration::from_secs(60);
+
+/// Advertisement or set of rotated advertisements managed by an [AdvertisingManager].
+#[cfg(feature = "adv")]
+struct ManagedEntry {
+    id: u64,
+    advertisements: Vec<Advertisement>,
+    rotation_interval: Duration,
+    index: usize,
+    handle: Option<AdvertisementHandle>,
+    next_rotation: Option<Instant>,
+    failures: u32,
+    next_retry: Option<Instant>,
+    state_tx: watch::Sender<ManagedAdvertisementState>,
+}
+
+#[cfg(feature = "adv")]
+impl ManagedEntry {
+    fn is_queued(&self) -> bool {
+        *self.state_tx.borrow() == ManagedAdvertisementState::Queued
+    }
+
+    async fn register(&mut self, adapter: &Adapter) {
+        match adapter.advertise(self.advertisements[self.index].clone()).await {
+            Ok(handle) => {
+                self.handle = Some(handle);
+                if self.advertisements.len() > 1 {
+                    self.next_rotation = Some(Instant::now() + self.rotation_interval);
+                }
+                self.failures = 0;
+                self.state_tx.send_replace(ManagedAdvertisementState::Active);
+            }
+            Err(err) => {
+                if !matches!(err.kind, ErrorKind::InvalidArguments | ErrorKind::InvalidLength) {
+                    let backoff =
+                        MANAGED_RETRY_MIN.saturating_mul(1 << self.failures.min(16)).min(MANAGED_RETRY_MAX);
+                    self.failures = self.failures.saturating_add(1);
+                    self.next_retry = Some(Instant::now() + backoff);
+                    log::warn!("Registering managed advertisement failed, retrying in {:?}: {}", backoff, &err);
+                } else {
+                    log::warn!("Registering managed advertisement failed: {}", &err);
+                }
+                self.state_tx.send_replace(ManagedAdvertisementState::Failed(err));
+            }
+        }
+    }
+
+    /// Requeues the entry if its registration failed and the retry is due.
+    fn requeue_if_due(&mut self, now: Instant) {
+        if self.next_retry.is_some_and(|t| t <= now) {
+            self.next_retry = None;
+            self.state_tx.send_replace(ManagedAdvertisementState::Queued);
+        }
+    }
+
+    async fn unregister(&mut self) {
+        self.next_rotation = None;
+        if let Some(handle) = self.handle.take() {
+            handle.unregister().await;
+        }
+    }
+}
+
+#[cfg(feature = "adv")]
+enum ManagerRequest {
+    Add(ManagedEntry),
+    Remove(u64),
+}
+
+/// Manages the advertisements of an adapter within the number of advertising instances
+/// supported by its controller.
+///
+/// Advertisements that do not fit into the available advertising instances are queued
+/// and registered in order once an instance becomes available.
+/// On constrained hardware several advertisements can be rotated over a single
+/// advertising instance using [rotate](Self::rotate).
+///
+/// Use [Adapter::advertising_manager] to obtain an advertising manager.
+/// Cloning the manager is cheap and all clones share the same queue.
+#[cfg(feature = "adv")]
+#[cfg_attr(docsrs, doc(cfg(feature = "adv")))]
+#[derive(Clone)]
+pub struct AdvertisingManager {
+    adapter: Adapter,
+    req_tx: mpsc::UnboundedSender<ManagerRequest>,
+    next_id: Arc<AtomicU64>,
+}
+
+#[cfg(feature = "adv")]
+impl fmt::Debug for AdvertisingManager {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AdvertisingManager {{ adapter: {} }}", self.adapter.name())
+    }
+}
+
+#[cfg(feature = "adv")]
+impl AdvertisingManager {
+    pub(crate) async fn new(adapter: Adapter) -> Result<Self> {
+        let events = adapter.events().await?;
+        let (req_tx, req_rx) = mpsc::unbounded_channel();
+        adapter.spawn(Self::perform(adapter.clone(), events, req_rx));
+        Ok(Self { adapter, req_tx, next_id: Arc::new(AtomicU64::new(0)) })
+    }
+
+    /// Number of advertising instances in use by all applications.
+    pub async fn active_instances(&self) -> Result<u8> {
+        self.adapter.active_advertising_instances().await
+    }
+
+    /// Number of advertising instances that are still available.
+    pub async fn available_instances(&self) -> Result<u8> {
+        self.adapter.supported_advertising_instances().await
+    }
+
+    /// Registers an advertisement as soon as an advertising instance is available.
+    ///
+    /// Drop the returned [ManagedAdvertisementHandle] to unregister the advertisement
+    /// or remove it from the queue.
+    pub fn advertise(&self, advertisement: Advertisement) -> Result<ManagedAdvertisementHandle> {
+        self.add(vec![advertisement], Duration::ZERO)
+    }
+
+    /// Rotates the specified advertisements over a single advertising instance.
+    ///
+    /// Each advertisement is broadcasted for the specified interval before it is
+    /// replaced by the next one.
+    /// The set of advertisements is queued like a single advertisement when no
+    /// advertising instance is available.
+    ///
+    /// Drop the returned [ManagedAdvertisementHandle] to stop the rotation.
+    pub fn rotate(
+        &self, advertisements: Vec<Advertisement>, interval: Duration,
+    ) -> Result<ManagedAdvertisementHandle> {
+        if advertisements.is_empty() || interval.is_zero() {
+            return Err(Error::new(ErrorKind::InvalidArguments));
+        }
+        self.add(advertisements, interval)
+    }
+
+    fn add(&self, advertisements: Vec<Advertisement>, interval: Duration) -> Result<ManagedAdvertisementHandle> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (state_tx, state_rx) = watch::channel(ManagedAdvertisementState::Queued);
+        let entry = ManagedEntry {
+            id,
+            advertisements,
+            rotation_interval: interval,
+            index: 0,
+            handle: None,
+            next_rotation: None,
+            failures: 0,
+            next_retry: None,
+            state_tx,
+        };
+        self.req_tx.send(ManagerRequest::Add(entry)).map_err(|_| Error::new(ErrorKind::Failed))?;
+        Ok(ManagedAdvertisementHandle { id, req_tx: self.req_tx.clone(), state_rx })
+    }
+
+    async fn perform(
+        adapter: Adapter, events: impl Stream<Item = AdapterEvent> + Send,
+        mut req_rx: mpsc::UnboundedReceiver<ManagerRequest>,
+    ) {
+        let events = events.fuse();
+        pin_mut!(events);
+        let mut entries: Vec<ManagedEntry> = Vec::new();
+
+        loop {
+            // Register queued advertisements in order while instances are available.
+            for entry in entries.iter_mut().filter(|entry| entry.is_queued()) {
+                match adapter.supported_advertising_instances().await {
+                    Ok(0) => break,
+                    Ok(_) => entry.register(&adapter).await,
+                    Err(err) => {
+                        log::warn!("Cannot query available advertising instances: {}", &err);
+                        break;
+                    }
+                }
+            }
+
+            let next_wakeup =
+                entries.iter().flat_map(|entry| [entry.next_rotation, entry.next_retry]).flatten().min();
+            tokio::select! {
+                req = req_rx.recv() => match req {
+                    Some(ManagerRequest::Add(entry)) => entries.push(entry),
+                    Some(ManagerRequest::Remove(id)) => {
+                        if let Some(pos) = entries.iter().position(|entry| entry.id == id) {
+                            entries.remove(pos).unregister().await;
+                        }
+                    }
+                    None => break,
+                },
+                evt = events.next() => match evt {
+                    Some(AdapterEvent::PropertyChanged(
+                        AdapterProperty::ActiveAdvertisingInstances(_)
+                        | AdapterProperty::SupportedAdvertisingInstances(_),
+                    )) => (),
+                    Some(_) => continue,
+                    None => break,
+                },
+                () = sleep_until(next_wakeup.unwrap_or_else(Instant::now)), if next_wakeup.is_some() => {
+                    // Re-register immediately to keep the freed advertising instance.
+                    let now = Instant::now();
+                    let due = |entry: &&mut ManagedEntry| entry.next_rotation.is_some_and(|t| t <= now);
+                    for entry in entries.iter_mut().filter(due) {
+                        entry.unregister().await;
+                        entry.index = (entry.index + 1) % entry.advertisements.len();
+                        entry.register(&adapter).await;
+                    }
+                    for entry in &mut entries {
+                        entry.requeue_if_due(now);
+                    }
+                }
+            }
+        }
+
+        for mut entry in entries {
+            entry.unregister().await;
+        }
+    }
+}
+
+/// Handle to an advertisement managed by an [AdvertisingManager].
+///
+/// Drop to unregister the advertisement or remove it from the queue.
+#[cfg(feature = "adv")]
+#[cfg_attr(docsrs, doc(cfg(feature = "adv")))]
+#[must_use = "ManagedAdvertisementHandle must be held for advertisement to be broadcasted"]
+pub struct ManagedAdvertisementHandle {
+    id: u64,
+    req_tx: mpsc::UnboundedSender<ManagerRequest>,
+    state_rx: watch::Receiver<ManagedAdvertisementState>,
+}
+
+#[cfg(feature = "adv")]
+impl fmt::Debug for ManagedAdvertisementHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ManagedAdvertisementHandle {{ {:?} }}", self.state())
+    }
+}
+
+#[cfg(feature = "adv")]
+impl ManagedAdvertisementHandle {
+    /// Current state of the advertisement.
+    pub fn state(&self) -> ManagedAdvertisementState {
+        self.state_rx.borrow().clone()
+    }
+
+    /// Waits until the advertisement has been registered.
+    ///
+    /// Fails if registration of the advertisement failed.
+    pub async fn wait_active(&self) -> Result<()> {
+        let mut state_rx = self.state_rx.clone();
+        let state = state_rx
+            .wait_for(|state| *state != ManagedAdvertisementState::Queued)
+            .await
+            .map_err(|_| Error::new(ErrorKind::Failed))?;
+        match &*state {
+            ManagedAdvertisementState::Failed(err) => Err(err.clone()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "adv")]
+impl Drop for ManagedAdvertisementHandle {
+    fn drop(&mut self) {
+        let _ = self.req_tx.send(ManagerRequest::Remove(self.id));
+    }
+}
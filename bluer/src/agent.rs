@@ -3,11 +3,16 @@
 use dbus::nonblock::Proxy;
 use dbus_crossroads::{Crossroads, IfaceBuilder, IfaceToken};
 use futures::{pin_mut, Future};
-use std::{fmt, pin::Pin, sync::Arc};
+use std::{
+    collections::HashSet,
+    fmt,
+    pin::Pin,
+    sync::{Arc, Weak},
+};
 use strum::IntoStaticStr;
 use tokio::{
     select,
-    sync::{oneshot, Mutex},
+    sync::{mpsc, oneshot, Mutex},
 };
 use uuid::Uuid;
 
@@ -161,6 +166,9 @@ pub struct AuthorizeService {
 pub type AuthorizeServiceFn =
     Box<dyn (Fn(AuthorizeService) -> Pin<Box<dyn Future<Output = ReqResult<()>> + Send>>) + Send + Sync>;
 
+/// Function handling an agent request.
+type ReqFn<A, R> = Box<dyn (Fn(A) -> Pin<Box<dyn Future<Output = ReqResult<R>> + Send>>) + Send + Sync>;
+
 /// Bluetooth authorization agent handler.
 ///
 /// Each handler that is set to [None] will reject the request.
@@ -271,16 +279,224 @@ impl Agent {
             (false, false, false) => "NoInputNoOutput",
         }
     }
+
+    /// Creates an agent that accepts pairing and service authorization requests
+    /// of the specified devices without user interaction.
+    ///
+    /// Numeric comparison requests, authorization requests and service authorization
+    /// requests of devices contained in `allowlist` are accepted, all others are rejected.
+    pub fn auto_accept(allowlist: impl IntoIterator<Item = Address>) -> Self {
+        let allowlist: Arc<HashSet<Address>> = Arc::new(allowlist.into_iter().collect());
+        let check = move |device: Address| {
+            let allowed = allowlist.contains(&device);
+            Box::pin(async move {
+                match allowed {
+                    true => Ok(()),
+                    false => {
+                        log::debug!("Rejecting agent request of {} not in allowlist", device);
+                        Err(ReqError::Rejected)
+                    }
+                }
+            }) as Pin<Box<dyn Future<Output = ReqResult<()>> + Send>>
+        };
+        let check1 = check.clone();
+        let check2 = check.clone();
+        Self {
+            request_confirmation: Some(Box::new(move |req| check(req.device))),
+            request_authorization: Some(Box::new(move |req| check1(req.device))),
+            authorize_service: Some(Box::new(move |req| check2(req.device))),
+            ..Default::default()
+        }
+    }
+
+    /// Creates an agent that responds to all pin code requests with the specified pin code.
+    ///
+    /// If the pin code is a number between 0 and 999999 it is also used to respond to
+    /// passkey requests.
+    /// This is useful for headless devices with a pin code printed on their label.
+    pub fn fixed_pin(pin: impl Into<String>) -> Self {
+        let pin = pin.into();
+        let passkey = pin.parse::<u32>().ok().filter(|passkey| *passkey <= 999999);
+        Self {
+            request_pin_code: Some(Box::new(move |_| {
+                let pin = pin.clone();
+                Box::pin(async move { Ok(pin) })
+            })),
+            request_passkey: passkey
+                .map(|passkey| -> RequestPasskeyFn { Box::new(move |_| Box::pin(async move { Ok(passkey) })) }),
+            ..Default::default()
+        }
+    }
+
+    /// Creates an agent that forwards all requests over a channel.
+    ///
+    /// Each request is sent as an [AgentRequest] over the returned receiver together with
+    /// a [Responder] for replying to it.
+    /// This allows applications, for example with a graphical user interface, to handle
+    /// requests on their own thread.
+    ///
+    /// Requests that arrive while the channel is full or closed are rejected.
+    pub fn channel(capacity: usize) -> (Self, mpsc::Receiver<AgentRequest>) {
+        let (tx, rx) = mpsc::channel(capacity);
+
+        fn forward<A: Send + 'static, R: Send + 'static>(
+            tx: &mpsc::Sender<AgentRequest>, wrap: fn(A, Responder<R>) -> AgentRequest,
+        ) -> ReqFn<A, R> {
+            let tx = tx.clone();
+            Box::new(move |arg| {
+                let (resp_tx, resp_rx) = oneshot::channel();
+                let sent = tx.try_send(wrap(arg, Responder { tx: resp_tx }));
+                Box::pin(async move {
+                    if sent.is_err() {
+                        log::warn!("Rejecting agent request since channel is full or closed");
+                        return Err(ReqError::Rejected);
+                    }
+                    resp_rx.await.unwrap_or(Err(ReqError::Canceled))
+                })
+            })
+        }
+
+        let agent = Self {
+            request_pin_code: Some(forward(&tx, AgentRequest::RequestPinCode)),
+            display_pin_code: Some(forward(&tx, AgentRequest::DisplayPinCode)),
+            request_passkey: Some(forward(&tx, AgentRequest::RequestPasskey)),
+            display_passkey: Some(forward(&tx, AgentRequest::DisplayPasskey)),
+            request_confirmation: Some(forward(&tx, AgentRequest::RequestConfirmation)),
+            request_authorization: Some(forward(&tx, AgentRequest::RequestAuthorization)),
+            authorize_service: Some(forward(&tx, AgentRequest::AuthorizeService)),
+            ..Default::default()
+        };
+        (agent, rx)
+    }
+}
+
+/// Agent request forwarded over a channel.
+///
+/// See [Agent::channel].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AgentRequest {
+    /// Pin code request.
+    RequestPinCode(RequestPinCode, Responder<String>),
+    /// Display pin code request.
+    DisplayPinCode(DisplayPinCode, Responder<()>),
+    /// Passkey request.
+    RequestPasskey(RequestPasskey, Responder<u32>),
+    /// Display passkey request.
+    DisplayPasskey(DisplayPasskey, Responder<()>),
+    /// Confirmation request.
+    RequestConfirmation(RequestConfirmation, Responder<()>),
+    /// Authorization request.
+    RequestAuthorization(RequestAuthorization, Responder<()>),
+    /// Authorize service request.
+    AuthorizeService(AuthorizeService, Responder<()>),
+}
+
+impl AgentRequest {
+    /// Address of device making the request.
+    pub fn device(&self) -> Address {
+        match self {
+            Self::RequestPinCode(req, _) => req.device,
+            Self::DisplayPinCode(req, _) => req.device,
+            Self::RequestPasskey(req, _) => req.device,
+            Self::DisplayPasskey(req, _) => req.device,
+            Self::RequestConfirmation(req, _) => req.device,
+            Self::RequestAuthorization(req, _) => req.device,
+            Self::AuthorizeService(req, _) => req.device,
+        }
+    }
+}
+
+/// Sends the response to an agent request forwarded over a channel.
+///
+/// Dropping the responder without responding cancels the request.
+pub struct Responder<T> {
+    tx: oneshot::Sender<ReqResult<T>>,
+}
+
+impl<T> fmt::Debug for Responder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Responder")
+    }
+}
+
+impl<T> Responder<T> {
+    /// Responds to the request.
+    pub fn respond(self, result: ReqResult<T>) {
+        let _ = self.tx.send(result);
+    }
+
+    /// Rejects the request.
+    pub fn reject(self) {
+        self.respond(Err(ReqError::Rejected))
+    }
+
+    /// Returns whether the request has been canceled by the Bluetooth daemon.
+    pub fn is_canceled(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
+
+impl Responder<()> {
+    /// Accepts the request.
+    pub fn accept(self) {
+        self.respond(Ok(()))
+    }
+}
+
+/// Kind of request made by the Bluetooth daemon to an authorization agent.
+///
+/// Agent requests are reported by [Device::pair_with_progress](crate::Device::pair_with_progress).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum AgentRequestKind {
+    /// A PIN code was requested.
+    RequestPinCode,
+    /// A PIN code is to be displayed.
+    DisplayPinCode,
+    /// A passkey was requested.
+    RequestPasskey,
+    /// A passkey is to be displayed.
+    DisplayPasskey,
+    /// Confirmation of a passkey was requested.
+    RequestConfirmation,
+    /// Authorization of pairing was requested.
+    RequestAuthorization,
+    /// Authorization of a connection to the specified service was requested.
+    AuthorizeService(Uuid),
 }
 
 pub(crate) struct RegisteredAgent {
     a: Agent,
+    inner: Weak<SessionInner>,
     cancel: Mutex<Option<oneshot::Sender<()>>>,
 }
 
 impl RegisteredAgent {
-    pub(crate) fn new(agent: Agent) -> Self {
-        Self { a: agent, cancel: Mutex::new(None) }
+    pub(crate) fn new(agent: Agent, inner: Weak<SessionInner>) -> Self {
+        Self { a: agent, inner, cancel: Mutex::new(None) }
+    }
+
+    /// Whether the service has been pre-authorized for the device.
+    fn is_service_authorized(&self, device: Address, service: Uuid) -> bool {
+        match self.inner.upgrade() {
+            Some(inner) => inner
+                .service_authorizations
+                .lock()
+                .unwrap()
+                .get(&device)
+                .map(|services| services.contains(&service))
+                .unwrap_or_default(),
+            None => false,
+        }
+    }
+
+    /// Informs subscribers of the session about a request for a device.
+    fn report_request(&self, adapter: &str, device: Address, kind: AgentRequestKind) {
+        if let Some(inner) = self.inner.upgrade() {
+            let _ = inner.agent_requests.send((adapter.to_string(), device, kind));
+        }
     }
 
     async fn get_cancel(&self) -> oneshot::Receiver<()> {
@@ -344,6 +560,7 @@ impl RegisteredAgent {
                 |ctx, cr, (device,): (dbus::Path<'static>,)| {
                     method_call(ctx, cr, |reg: Arc<Self>| async move {
                         let (adapter, device) = Self::parse_device_path(&device)?;
+                        reg.report_request(&adapter, device, AgentRequestKind::RequestPinCode);
                         Ok((reg
                             .call_with_cancel(&reg.a.request_pin_code, RequestPinCode { adapter, device })
                             .await?,))
@@ -357,6 +574,7 @@ impl RegisteredAgent {
                 |ctx, cr, (device, pincode): (dbus::Path<'static>, String)| {
                     method_call(ctx, cr, |reg: Arc<Self>| async move {
                         let (adapter, device) = Self::parse_device_path(&device)?;
+                        reg.report_request(&adapter, device, AgentRequestKind::DisplayPinCode);
                         reg.call(
                             &reg.a.display_pin_code,
                             DisplayPinCode { adapter, device, pincode, cancel: reg.get_cancel().await },
@@ -373,6 +591,7 @@ impl RegisteredAgent {
                 |ctx, cr, (device,): (dbus::Path<'static>,)| {
                     method_call(ctx, cr, |reg: Arc<Self>| async move {
                         let (adapter, device) = Self::parse_device_path(&device)?;
+                        reg.report_request(&adapter, device, AgentRequestKind::RequestPasskey);
                         Ok((reg
                             .call_with_cancel(&reg.a.request_passkey, RequestPasskey { adapter, device })
                             .await?,))
@@ -386,6 +605,7 @@ impl RegisteredAgent {
                 |ctx, cr, (device, passkey, entered): (dbus::Path<'static>, u32, u16)| {
                     method_call(ctx, cr, move |reg: Arc<Self>| async move {
                         let (adapter, device) = Self::parse_device_path(&device)?;
+                        reg.report_request(&adapter, device, AgentRequestKind::DisplayPasskey);
                         reg.call(
                             &reg.a.display_passkey,
                             DisplayPasskey { adapter, device, passkey, entered, cancel: reg.get_cancel().await },
@@ -402,6 +622,7 @@ impl RegisteredAgent {
                 |ctx, cr, (device, passkey): (dbus::Path<'static>, u32)| {
                     method_call(ctx, cr, move |reg: Arc<Self>| async move {
                         let (adapter, device) = Self::parse_device_path(&device)?;
+                        reg.report_request(&adapter, device, AgentRequestKind::RequestConfirmation);
                         reg.call_with_cancel(
                             &reg.a.request_confirmation,
                             RequestConfirmation { adapter, device, passkey },
@@ -418,6 +639,7 @@ impl RegisteredAgent {
                 |ctx, cr, (device,): (dbus::Path<'static>,)| {
                     method_call(ctx, cr, move |reg: Arc<Self>| async move {
                         let (adapter, device) = Self::parse_device_path(&device)?;
+                        reg.report_request(&adapter, device, AgentRequestKind::RequestAuthorization);
                         reg.call_with_cancel(
                             &reg.a.request_authorization,
                             RequestAuthorization { adapter, device },
@@ -441,6 +663,11 @@ impl RegisteredAgent {
                                 return Err(ReqError::Rejected.into());
                             }
                         };
+                        reg.report_request(&adapter, device, AgentRequestKind::AuthorizeService(service));
+                        if reg.is_service_authorized(device, service) {
+                            log::trace!("Service {} of {} is pre-authorized", &service, &device);
+                            return Ok(());
+                        }
                         reg.call_with_cancel(
                             &reg.a.authorize_service,
                             AuthorizeService { adapter, device, service },
@@ -463,6 +690,7 @@ impl RegisteredAgent {
             let mut cr = inner.crossroads.lock().await;
             cr.insert(name.clone(), &[inner.agent_token], Arc::new(self));
         }
+        inner.record_export(&name, &[INTERFACE]);
 
         log::trace!("Registering agent at {}", &name);
         let proxy = Proxy::new(SERVICE_NAME, MANAGER_PATH, TIMEOUT, inner.connection.clone());
@@ -471,7 +699,7 @@ impl RegisteredAgent {
 
         let (drop_tx, drop_rx) = oneshot::channel();
         let unreg_name = name.clone();
-        tokio::spawn(async move {
+        inner.clone().spawn(async move {
             let _ = drop_rx.await;
 
             log::trace!("Unregistering agent at {}", &unreg_name);
@@ -481,6 +709,7 @@ impl RegisteredAgent {
             log::trace!("Unpublishing agent at {}", &unreg_name);
             let mut cr = inner.crossroads.lock().await;
             let _: Option<Self> = cr.remove(&unreg_name);
+            inner.record_unexport(&unreg_name);
         });
 
         if request_default {
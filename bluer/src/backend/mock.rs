@@ -0,0 +1,469 @@
+//! In-memory mock backend.
+//!
+//! The mock backend implements the [backend traits](super) without a Bluetooth
+//! daemon or controller.
+//! Adapters, devices and GATT services are created and modified by the test using
+//! the methods of the mock types, for example to simulate a device coming into range
+//! or a characteristic sending a notification.
+//! Code that is generic over [Backend] can thus be unit tested.
+//!
+//! All clones of a mock object share their state.
+
+use futures::{channel::mpsc, Future, Stream};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use super::{AdapterBackend, Backend, DeviceBackend};
+use crate::{AdapterEvent, Address, Error, ErrorKind, Result};
+
+#[cfg(feature = "gatt-client")]
+use super::{CharacteristicBackend, GattDeviceBackend, ServiceBackend};
+#[cfg(feature = "gatt-client")]
+use uuid::Uuid;
+
+/// Mock Bluetooth backend.
+#[derive(Clone, Debug, Default)]
+pub struct MockBackend {
+    adapters: Arc<Mutex<BTreeMap<String, MockAdapter>>>,
+}
+
+impl MockBackend {
+    /// Creates a mock backend without adapters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a powered off adapter with the specified name and address.
+    ///
+    /// An existing adapter with the same name is replaced.
+    pub fn add_adapter(&self, name: &str, address: Address) -> MockAdapter {
+        let adapter = MockAdapter { name: name.to_string(), address, state: Default::default() };
+        self.adapters.lock().unwrap().insert(name.to_string(), adapter.clone());
+        adapter
+    }
+}
+
+impl Backend for MockBackend {
+    type Adapter = MockAdapter;
+
+    fn adapter_names(&self) -> impl Future<Output = Result<Vec<String>>> + Send {
+        let names = self.adapters.lock().unwrap().keys().cloned().collect();
+        async move { Ok(names) }
+    }
+
+    fn adapter(&self, adapter_name: &str) -> Result<MockAdapter> {
+        self.adapters.lock().unwrap().get(adapter_name).cloned().ok_or_else(|| Error::new(ErrorKind::NotFound))
+    }
+
+    fn default_adapter(&self) -> impl Future<Output = Result<MockAdapter>> + Send {
+        let adapter = self.adapters.lock().unwrap().values().next().cloned();
+        async move { adapter.ok_or_else(|| Error::new(ErrorKind::NotFound)) }
+    }
+}
+
+#[derive(Debug, Default)]
+struct AdapterState {
+    powered: bool,
+    devices: BTreeMap<Address, MockDevice>,
+    discoveries: Vec<mpsc::UnboundedSender<AdapterEvent>>,
+}
+
+impl AdapterState {
+    fn send(&mut self, evt: AdapterEvent) {
+        self.discoveries.retain(|tx| tx.unbounded_send(evt.clone()).is_ok());
+    }
+}
+
+/// Mock Bluetooth adapter.
+#[derive(Clone, Debug)]
+pub struct MockAdapter {
+    name: String,
+    address: Address,
+    state: Arc<Mutex<AdapterState>>,
+}
+
+impl MockAdapter {
+    /// Adds a disconnected, unpaired remote device with the specified address.
+    ///
+    /// Active discoveries report the device as added.
+    /// An existing device with the same address is replaced.
+    pub fn add_device(&self, address: Address) -> MockDevice {
+        let device = MockDevice { adapter_name: self.name.clone(), address, state: Default::default() };
+        let mut state = self.state.lock().unwrap();
+        state.devices.insert(address, device.clone());
+        state.send(AdapterEvent::DeviceAdded(address));
+        device
+    }
+}
+
+impl AdapterBackend for MockAdapter {
+    type Device = MockDevice;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn address(&self) -> impl Future<Output = Result<Address>> + Send {
+        let address = self.address;
+        async move { Ok(address) }
+    }
+
+    fn is_powered(&self) -> impl Future<Output = Result<bool>> + Send {
+        let powered = self.state.lock().unwrap().powered;
+        async move { Ok(powered) }
+    }
+
+    fn set_powered(&self, powered: bool) -> impl Future<Output = Result<()>> + Send {
+        self.state.lock().unwrap().powered = powered;
+        async move { Ok(()) }
+    }
+
+    fn device_addresses(&self) -> impl Future<Output = Result<Vec<Address>>> + Send {
+        let addresses = self.state.lock().unwrap().devices.keys().cloned().collect();
+        async move { Ok(addresses) }
+    }
+
+    fn device(&self, address: Address) -> Result<MockDevice> {
+        self.state.lock().unwrap().devices.get(&address).cloned().ok_or_else(|| Error::new(ErrorKind::NotFound))
+    }
+
+    /// Reports the known devices and then the devices added and removed during discovery.
+    ///
+    /// Fails with [ErrorKind::NotReady] if the adapter is powered off.
+    fn discover_devices(
+        &self,
+    ) -> impl Future<Output = Result<impl Stream<Item = AdapterEvent> + Send + 'static>> + Send {
+        let mut state = self.state.lock().unwrap();
+        let result = if state.powered {
+            let (tx, rx) = mpsc::unbounded();
+            for &address in state.devices.keys() {
+                let _ = tx.unbounded_send(AdapterEvent::DeviceAdded(address));
+            }
+            state.discoveries.push(tx);
+            Ok(rx)
+        } else {
+            Err(Error::new(ErrorKind::NotReady))
+        };
+        async move { result }
+    }
+
+    fn remove_device(&self, address: Address) -> impl Future<Output = Result<()>> + Send {
+        let mut state = self.state.lock().unwrap();
+        let result = match state.devices.remove(&address) {
+            Some(_) => {
+                state.send(AdapterEvent::DeviceRemoved(address));
+                Ok(())
+            }
+            None => Err(Error::new(ErrorKind::DoesNotExist)),
+        };
+        async move { result }
+    }
+}
+
+#[derive(Debug, Default)]
+struct DeviceState {
+    name: Option<String>,
+    rssi: Option<i16>,
+    connected: bool,
+    paired: bool,
+    connect_error: Option<ErrorKind>,
+    pair_error: Option<ErrorKind>,
+    #[cfg(feature = "gatt-client")]
+    services: Vec<MockService>,
+}
+
+/// Mock remote Bluetooth device.
+#[derive(Clone, Debug)]
+pub struct MockDevice {
+    adapter_name: String,
+    address: Address,
+    state: Arc<Mutex<DeviceState>>,
+}
+
+impl MockDevice {
+    /// Sets the remote name of the device.
+    pub fn set_name(&self, name: Option<&str>) {
+        self.state.lock().unwrap().name = name.map(|name| name.to_string());
+    }
+
+    /// Sets the received signal strength of the device.
+    pub fn set_rssi(&self, rssi: Option<i16>) {
+        self.state.lock().unwrap().rssi = rssi;
+    }
+
+    /// Sets whether the device is connected, for example to simulate a disconnection
+    /// by the remote device.
+    pub fn set_connected(&self, connected: bool) {
+        self.state.lock().unwrap().connected = connected;
+    }
+
+    /// Makes connection attempts fail with the specified error kind.
+    ///
+    /// Pass [None] to make them succeed again.
+    pub fn set_connect_error(&self, kind: Option<ErrorKind>) {
+        self.state.lock().unwrap().connect_error = kind;
+    }
+
+    /// Makes pairing attempts fail with the specified error kind.
+    ///
+    /// Pass [None] to make them succeed again.
+    pub fn set_pair_error(&self, kind: Option<ErrorKind>) {
+        self.state.lock().unwrap().pair_error = kind;
+    }
+
+    /// Adds a GATT service with the specified UUID to the device.
+    #[cfg(feature = "gatt-client")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gatt-client")))]
+    pub fn add_service(&self, uuid: Uuid) -> MockService {
+        let service = MockService { uuid, characteristics: Default::default() };
+        self.state.lock().unwrap().services.push(service.clone());
+        service
+    }
+}
+
+impl DeviceBackend for MockDevice {
+    fn adapter_name(&self) -> &str {
+        &self.adapter_name
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn name(&self) -> impl Future<Output = Result<Option<String>>> + Send {
+        let name = self.state.lock().unwrap().name.clone();
+        async move { Ok(name) }
+    }
+
+    fn rssi(&self) -> impl Future<Output = Result<Option<i16>>> + Send {
+        let rssi = self.state.lock().unwrap().rssi;
+        async move { Ok(rssi) }
+    }
+
+    fn is_connected(&self) -> impl Future<Output = Result<bool>> + Send {
+        let connected = self.state.lock().unwrap().connected;
+        async move { Ok(connected) }
+    }
+
+    fn connect(&self) -> impl Future<Output = Result<()>> + Send {
+        let mut state = self.state.lock().unwrap();
+        let result = match state.connect_error.clone() {
+            Some(kind) => Err(Error::new(kind)),
+            None => {
+                state.connected = true;
+                Ok(())
+            }
+        };
+        async move { result }
+    }
+
+    fn disconnect(&self) -> impl Future<Output = Result<()>> + Send {
+        self.state.lock().unwrap().connected = false;
+        async move { Ok(()) }
+    }
+
+    fn is_paired(&self) -> impl Future<Output = Result<bool>> + Send {
+        let paired = self.state.lock().unwrap().paired;
+        async move { Ok(paired) }
+    }
+
+    fn pair(&self) -> impl Future<Output = Result<()>> + Send {
+        let mut state = self.state.lock().unwrap();
+        let result = match state.pair_error.clone() {
+            Some(kind) => Err(Error::new(kind)),
+            None => {
+                state.paired = true;
+                Ok(())
+            }
+        };
+        async move { result }
+    }
+}
+
+#[cfg(feature = "gatt-client")]
+impl GattDeviceBackend for MockDevice {
+    type Service = MockService;
+
+    /// Fails with [ErrorKind::ServicesUnresolved] if the device is not connected.
+    fn services(&self) -> impl Future<Output = Result<Vec<MockService>>> + Send {
+        let state = self.state.lock().unwrap();
+        let result = if state.connected {
+            Ok(state.services.clone())
+        } else {
+            Err(Error::new(ErrorKind::ServicesUnresolved))
+        };
+        async move { result }
+    }
+}
+
+/// Mock remote GATT service.
+#[cfg(feature = "gatt-client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gatt-client")))]
+#[derive(Clone, Debug)]
+pub struct MockService {
+    uuid: Uuid,
+    characteristics: Arc<Mutex<Vec<MockCharacteristic>>>,
+}
+
+#[cfg(feature = "gatt-client")]
+impl MockService {
+    /// Adds a characteristic with the specified UUID and initial value to the service.
+    pub fn add_characteristic(&self, uuid: Uuid, value: Vec<u8>) -> MockCharacteristic {
+        let characteristic = MockCharacteristic {
+            uuid,
+            state: Arc::new(Mutex::new(CharacteristicState { value, notifications: Vec::new() })),
+        };
+        self.characteristics.lock().unwrap().push(characteristic.clone());
+        characteristic
+    }
+}
+
+#[cfg(feature = "gatt-client")]
+impl ServiceBackend for MockService {
+    type Characteristic = MockCharacteristic;
+
+    fn uuid(&self) -> impl Future<Output = Result<Uuid>> + Send {
+        let uuid = self.uuid;
+        async move { Ok(uuid) }
+    }
+
+    fn characteristics(&self) -> impl Future<Output = Result<Vec<MockCharacteristic>>> + Send {
+        let characteristics = self.characteristics.lock().unwrap().clone();
+        async move { Ok(characteristics) }
+    }
+}
+
+#[cfg(feature = "gatt-client")]
+#[derive(Debug)]
+struct CharacteristicState {
+    value: Vec<u8>,
+    notifications: Vec<mpsc::UnboundedSender<Vec<u8>>>,
+}
+
+/// Mock remote GATT characteristic.
+#[cfg(feature = "gatt-client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gatt-client")))]
+#[derive(Clone, Debug)]
+pub struct MockCharacteristic {
+    uuid: Uuid,
+    state: Arc<Mutex<CharacteristicState>>,
+}
+
+#[cfg(feature = "gatt-client")]
+impl MockCharacteristic {
+    /// Current value of the characteristic, including values written by the code under test.
+    pub fn value(&self) -> Vec<u8> {
+        self.state.lock().unwrap().value.clone()
+    }
+
+    /// Sets the value of the characteristic and sends it as a notification to
+    /// all notification streams.
+    pub fn notify_value(&self, value: Vec<u8>) {
+        let mut state = self.state.lock().unwrap();
+        state.notifications.retain(|tx| tx.unbounded_send(value.clone()).is_ok());
+        state.value = value;
+    }
+}
+
+#[cfg(feature = "gatt-client")]
+impl CharacteristicBackend for MockCharacteristic {
+    fn uuid(&self) -> impl Future<Output = Result<Uuid>> + Send {
+        let uuid = self.uuid;
+        async move { Ok(uuid) }
+    }
+
+    fn read(&self) -> impl Future<Output = Result<Vec<u8>>> + Send {
+        let value = self.value();
+        async move { Ok(value) }
+    }
+
+    fn write(&self, value: &[u8]) -> impl Future<Output = Result<()>> + Send {
+        self.state.lock().unwrap().value = value.to_vec();
+        async move { Ok(()) }
+    }
+
+    fn notify(&self) -> impl Future<Output = Result<impl Stream<Item = Vec<u8>> + Send + 'static>> + Send {
+        let (tx, rx) = mpsc::unbounded();
+        self.state.lock().unwrap().notifications.push(tx);
+        async move { Ok(rx) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, StreamExt};
+
+    use super::*;
+
+    const ADDRESS: Address = Address::new([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+
+    async fn connect_named<B: Backend>(backend: &B, name: &str) -> Result<Option<Address>> {
+        let adapter = backend.default_adapter().await?;
+        adapter.set_powered(true).await?;
+        let mut events = Box::pin(adapter.discover_devices().await?);
+        while let Some(AdapterEvent::DeviceAdded(address)) = events.next().await {
+            let device = adapter.device(address)?;
+            if device.name().await?.as_deref() == Some(name) {
+                device.connect().await?;
+                return Ok(Some(address));
+            }
+        }
+        Ok(None)
+    }
+
+    #[test]
+    fn generic_code_runs_against_mock() {
+        let backend = MockBackend::new();
+        let adapter = backend.add_adapter("hci0", Address::any());
+        let device = adapter.add_device(ADDRESS);
+        device.set_name(Some("sensor"));
+
+        assert_eq!(block_on(connect_named(&backend, "sensor")).unwrap(), Some(ADDRESS));
+        assert!(block_on(device.is_connected()).unwrap());
+
+        device.set_connect_error(Some(ErrorKind::ConnectionAttemptFailed));
+        device.set_connected(false);
+        let err = block_on(connect_named(&backend, "sensor")).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::ConnectionAttemptFailed);
+    }
+
+    #[test]
+    fn discovery_requires_power_and_reports_removal() {
+        let backend = MockBackend::new();
+        let adapter = backend.add_adapter("hci0", Address::any());
+        assert_eq!(block_on(adapter.discover_devices()).err().map(|err| err.kind), Some(ErrorKind::NotReady));
+
+        block_on(adapter.set_powered(true)).unwrap();
+        let mut events = Box::pin(block_on(adapter.discover_devices()).unwrap());
+        adapter.add_device(ADDRESS);
+        block_on(adapter.remove_device(ADDRESS)).unwrap();
+        assert!(matches!(block_on(events.next()), Some(AdapterEvent::DeviceAdded(ADDRESS))));
+        assert!(matches!(block_on(events.next()), Some(AdapterEvent::DeviceRemoved(ADDRESS))));
+        assert_eq!(block_on(adapter.remove_device(ADDRESS)).unwrap_err().kind, ErrorKind::DoesNotExist);
+    }
+
+    #[cfg(feature = "gatt-client")]
+    #[test]
+    fn characteristic_is_read_written_and_notified() {
+        let uuid = Uuid::from_u128(0x2a37_0000_1000_8000_0080_5f9b_34fb);
+        let backend = MockBackend::new();
+        let adapter = backend.add_adapter("hci0", Address::any());
+        let device = adapter.add_device(ADDRESS);
+        let characteristic = device.add_service(uuid).add_characteristic(uuid, vec![1]);
+
+        assert_eq!(block_on(device.services()).unwrap_err().kind, ErrorKind::ServicesUnresolved);
+        block_on(device.connect()).unwrap();
+        let services = block_on(device.services()).unwrap();
+        let chars = block_on(services[0].characteristics()).unwrap();
+        assert_eq!(block_on(chars[0].read()).unwrap(), vec![1]);
+
+        block_on(chars[0].write(&[2, 3])).unwrap();
+        assert_eq!(characteristic.value(), vec![2, 3]);
+
+        let mut values = Box::pin(block_on(chars[0].notify()).unwrap());
+        characteristic.notify_value(vec![4]);
+        assert_eq!(block_on(values.next()), Some(vec![4]));
+    }
+}
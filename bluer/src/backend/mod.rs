@@ -0,0 +1,296 @@
+//! Backend abstraction.
+//!
+//! The traits in this module describe the operations on sessions, adapters, devices
+//! and remote GATT services that are common to all Bluetooth stacks.
+//! They are implemented by [Session], [Adapter] and [Device] as well as by the
+//! [remote GATT service](crate::gatt::remote::Service) and
+//! [characteristic](crate::gatt::remote::Characteristic) types,
+//! which together form the BlueZ backend.
+//!
+//! Code that is generic over [Backend] can thus be run against BlueZ and,
+//! for example, a mock backend used for unit testing or an implementation
+//! for another Bluetooth stack.
+//!
+//! Only a commonly used subset of functionality is covered.
+//! Use the concrete types for access to BlueZ-specific functionality.
+//!
+//! An in-memory implementation for testing is provided by the [mock] module.
+
+use futures::{Future, Stream};
+
+use crate::{Adapter, AdapterEvent, Address, Device, Result, Session};
+
+#[cfg(feature = "gatt-client")]
+use crate::gatt::remote::{Characteristic, Service};
+#[cfg(feature = "gatt-client")]
+use uuid::Uuid;
+
+pub mod mock;
+
+/// A Bluetooth backend, providing access to Bluetooth adapters.
+///
+/// This is implemented by [Session] for BlueZ.
+pub trait Backend: Clone + Send + Sync + 'static {
+    /// Bluetooth adapter of this backend.
+    type Adapter: AdapterBackend;
+
+    /// Enumerate the names of the Bluetooth adapters.
+    fn adapter_names(&self) -> impl Future<Output = Result<Vec<String>>> + Send;
+
+    /// Bluetooth adapter with the specified name.
+    fn adapter(&self, adapter_name: &str) -> Result<Self::Adapter>;
+
+    /// Default Bluetooth adapter.
+    fn default_adapter(&self) -> impl Future<Output = Result<Self::Adapter>> + Send;
+}
+
+/// A Bluetooth adapter of a [Backend].
+///
+/// This is implemented by [Adapter] for BlueZ.
+pub trait AdapterBackend: Clone + Send + Sync + 'static {
+    /// Remote device of this backend.
+    type Device: DeviceBackend;
+
+    /// Name of the adapter.
+    fn name(&self) -> &str;
+
+    /// Address of the adapter.
+    fn address(&self) -> impl Future<Output = Result<Address>> + Send;
+
+    /// Whether the adapter is powered on.
+    fn is_powered(&self) -> impl Future<Output = Result<bool>> + Send;
+
+    /// Switches the adapter on or off.
+    fn set_powered(&self, powered: bool) -> impl Future<Output = Result<()>> + Send;
+
+    /// Addresses of remote devices known to the adapter.
+    fn device_addresses(&self) -> impl Future<Output = Result<Vec<Address>>> + Send;
+
+    /// Remote device with the specified address.
+    fn device(&self, address: Address) -> Result<Self::Device>;
+
+    /// Discovers remote devices.
+    ///
+    /// Discovery is stopped when the returned stream is dropped.
+    fn discover_devices(
+        &self,
+    ) -> impl Future<Output = Result<impl Stream<Item = AdapterEvent> + Send + 'static>> + Send;
+
+    /// Removes the remote device and its pairing information.
+    fn remove_device(&self, address: Address) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// A remote Bluetooth device of a [Backend].
+///
+/// This is implemented by [Device] for BlueZ.
+pub trait DeviceBackend: Clone + Send + Sync + 'static {
+    /// Name of the adapter the device belongs to.
+    fn adapter_name(&self) -> &str;
+
+    /// Address of the device.
+    fn address(&self) -> Address;
+
+    /// Remote name of the device.
+    fn name(&self) -> impl Future<Output = Result<Option<String>>> + Send;
+
+    /// Received signal strength indicator in dBm.
+    fn rssi(&self) -> impl Future<Output = Result<Option<i16>>> + Send;
+
+    /// Whether the device is connected.
+    fn is_connected(&self) -> impl Future<Output = Result<bool>> + Send;
+
+    /// Connects to the device.
+    fn connect(&self) -> impl Future<Output = Result<()>> + Send;
+
+    /// Disconnects from the device.
+    fn disconnect(&self) -> impl Future<Output = Result<()>> + Send;
+
+    /// Whether the device is paired.
+    fn is_paired(&self) -> impl Future<Output = Result<bool>> + Send;
+
+    /// Pairs with the device.
+    fn pair(&self) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// A remote Bluetooth device of a [Backend] providing GATT services.
+///
+/// This is implemented by [Device] for BlueZ.
+#[cfg(feature = "gatt-client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gatt-client")))]
+pub trait GattDeviceBackend: DeviceBackend {
+    /// Remote GATT service of this backend.
+    type Service: ServiceBackend;
+
+    /// Remote GATT services of the device.
+    fn services(&self) -> impl Future<Output = Result<Vec<Self::Service>>> + Send;
+}
+
+/// A remote GATT service of a [Backend].
+///
+/// This is implemented by [Service] for BlueZ.
+#[cfg(feature = "gatt-client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gatt-client")))]
+pub trait ServiceBackend: Clone + Send + Sync + 'static {
+    /// Remote GATT characteristic of this backend.
+    type Characteristic: CharacteristicBackend;
+
+    /// UUID of the service.
+    fn uuid(&self) -> impl Future<Output = Result<Uuid>> + Send;
+
+    /// Characteristics of the service.
+    fn characteristics(&self) -> impl Future<Output = Result<Vec<Self::Characteristic>>> + Send;
+}
+
+/// A remote GATT characteristic of a [Backend].
+///
+/// This is implemented by [Characteristic] for BlueZ.
+#[cfg(feature = "gatt-client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gatt-client")))]
+pub trait CharacteristicBackend: Clone + Send + Sync + 'static {
+    /// UUID of the characteristic.
+    fn uuid(&self) -> impl Future<Output = Result<Uuid>> + Send;
+
+    /// Reads the value of the characteristic.
+    fn read(&self) -> impl Future<Output = Result<Vec<u8>>> + Send;
+
+    /// Writes the value of the characteristic.
+    fn write(&self, value: &[u8]) -> impl Future<Output = Result<()>> + Send;
+
+    /// Starts notifications or indications of value changes.
+    ///
+    /// Notifications are stopped when the returned stream is dropped.
+    fn notify(&self) -> impl Future<Output = Result<impl Stream<Item = Vec<u8>> + Send + 'static>> + Send;
+}
+
+impl Backend for Session {
+    type Adapter = Adapter;
+
+    fn adapter_names(&self) -> impl Future<Output = Result<Vec<String>>> + Send {
+        Session::adapter_names(self)
+    }
+
+    fn adapter(&self, adapter_name: &str) -> Result<Adapter> {
+        Session::adapter(self, adapter_name)
+    }
+
+    fn default_adapter(&self) -> impl Future<Output = Result<Adapter>> + Send {
+        Session::default_adapter(self)
+    }
+}
+
+impl AdapterBackend for Adapter {
+    type Device = Device;
+
+    fn name(&self) -> &str {
+        Adapter::name(self)
+    }
+
+    fn address(&self) -> impl Future<Output = Result<Address>> + Send {
+        Adapter::address(self)
+    }
+
+    fn is_powered(&self) -> impl Future<Output = Result<bool>> + Send {
+        Adapter::is_powered(self)
+    }
+
+    fn set_powered(&self, powered: bool) -> impl Future<Output = Result<()>> + Send {
+        Adapter::set_powered(self, powered)
+    }
+
+    fn device_addresses(&self) -> impl Future<Output = Result<Vec<Address>>> + Send {
+        Adapter::device_addresses(self)
+    }
+
+    fn device(&self, address: Address) -> Result<Device> {
+        Adapter::device(self, address)
+    }
+
+    fn discover_devices(
+        &self,
+    ) -> impl Future<Output = Result<impl Stream<Item = AdapterEvent> + Send + 'static>> + Send {
+        Adapter::discover_devices(self)
+    }
+
+    fn remove_device(&self, address: Address) -> impl Future<Output = Result<()>> + Send {
+        Adapter::remove_device(self, address)
+    }
+}
+
+impl DeviceBackend for Device {
+    fn adapter_name(&self) -> &str {
+        Device::adapter_name(self)
+    }
+
+    fn address(&self) -> Address {
+        Device::address(self)
+    }
+
+    fn name(&self) -> impl Future<Output = Result<Option<String>>> + Send {
+        Device::name(self)
+    }
+
+    fn rssi(&self) -> impl Future<Output = Result<Option<i16>>> + Send {
+        Device::rssi(self)
+    }
+
+    fn is_connected(&self) -> impl Future<Output = Result<bool>> + Send {
+        Device::is_connected(self)
+    }
+
+    fn connect(&self) -> impl Future<Output = Result<()>> + Send {
+        Device::connect(self)
+    }
+
+    fn disconnect(&self) -> impl Future<Output = Result<()>> + Send {
+        Device::disconnect(self)
+    }
+
+    fn is_paired(&self) -> impl Future<Output = Result<bool>> + Send {
+        Device::is_paired(self)
+    }
+
+    fn pair(&self) -> impl Future<Output = Result<()>> + Send {
+        Device::pair(self)
+    }
+}
+
+#[cfg(feature = "gatt-client")]
+impl GattDeviceBackend for Device {
+    type Service = Service;
+
+    fn services(&self) -> impl Future<Output = Result<Vec<Service>>> + Send {
+        Device::services(self)
+    }
+}
+
+#[cfg(feature = "gatt-client")]
+impl ServiceBackend for Service {
+    type Characteristic = Characteristic;
+
+    fn uuid(&self) -> impl Future<Output = Result<Uuid>> + Send {
+        Service::uuid(self)
+    }
+
+    fn characteristics(&self) -> impl Future<Output = Result<Vec<Characteristic>>> + Send {
+        Service::characteristics(self)
+    }
+}
+
+#[cfg(feature = "gatt-client")]
+impl CharacteristicBackend for Characteristic {
+    fn uuid(&self) -> impl Future<Output = Result<Uuid>> + Send {
+        Characteristic::uuid(self)
+    }
+
+    fn read(&self) -> impl Future<Output = Result<Vec<u8>>> + Send {
+        Characteristic::read(self)
+    }
+
+    fn write(&self, value: &[u8]) -> impl Future<Output = Result<()>> + Send {
+        Characteristic::write(self, value)
+    }
+
+    fn notify(&self) -> impl Future<Output = Result<impl Stream<Item = Vec<u8>> + Send + 'static>> + Send {
+        Characteristic::notify(self)
+    }
+}
@@ -0,0 +1,64 @@
+//! Internal functions exposed to the benchmarks.
+//!
+//! This module is not part of the public API.
+
+use dbus::{
+    arg::{PropMap, RefArg, Variant},
+    blocking::stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged,
+    message::SignalArgs,
+    Message, Path,
+};
+use futures::{FutureExt, StreamExt};
+
+use crate::{session::ObjectEvent, DeviceProperty};
+
+/// Builds a properties changed signal of a device.
+pub fn properties_changed_message(changed: PropMap) -> Message {
+    let path = Path::from("/org/bluez/hci0/dev_00_11_22_33_44_55");
+    PropertiesPropertiesChanged {
+        interface_name: "org.bluez.Device1".to_string(),
+        changed_properties: changed,
+        invalidated_properties: Vec::new(),
+    }
+    .to_emit_message(&path)
+}
+
+/// Builds changed properties containing the RSSI.
+pub fn rssi_changed(rssi: i16) -> PropMap {
+    PropMap::from([("RSSI".to_string(), Variant(Box::new(rssi) as Box<dyn RefArg>))])
+}
+
+/// Builds changed properties containing the RSSI and manufacturer data.
+pub fn rssi_and_manufacturer_data_changed(rssi: i16, data: Vec<u8>) -> PropMap {
+    let manufacturer_data =
+        std::collections::HashMap::from([(0x004cu16, Variant(Box::new(data) as Box<dyn RefArg>))]);
+    PropMap::from([
+        ("RSSI".to_string(), Variant(Box::new(rssi) as Box<dyn RefArg>)),
+        ("ManufacturerData".to_string(), Variant(Box::new(manufacturer_data) as Box<dyn RefArg>)),
+    ])
+}
+
+/// Decodes a properties changed signal, dispatches it to the specified number of
+/// subscriptions and decodes the device properties received by each subscription.
+///
+/// Returns the total number of decoded properties.
+pub fn handle_properties_changed(msg: &Message, subscriptions: usize) -> usize {
+    let Some(PropertiesPropertiesChanged { interface_name, changed_properties, .. }) =
+        PropertiesPropertiesChanged::from_message(msg)
+    else {
+        return 0;
+    };
+    let evt = ObjectEvent::PropertiesChanged {
+        object: msg.path().unwrap().into_static(),
+        interface: interface_name,
+        changed: changed_properties,
+    };
+
+    let mut n = 0;
+    for mut rx in evt.dispatch_to(subscriptions) {
+        while let Some(Some(ObjectEvent::PropertiesChanged { changed, .. })) = rx.next().now_or_never() {
+            n += DeviceProperty::from_prop_map(changed).len();
+        }
+    }
+    n
+}
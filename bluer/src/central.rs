@@ -0,0 +1,226 @@
+//! High-level Bluetooth LE central.
+//!
+//! A [Central] discovers devices providing a GATT service, connects to them,
+//! subscribes to notifications of characteristics of that service and
+//! reconnects when a connection is lost.
+//! This covers the common use case of gateways collecting data from sensors.
+
+use futures::{stream::SelectAll, Stream, StreamExt};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{sync::mpsc, time::sleep};
+use tokio_stream::wrappers::ReceiverStream;
+use uuid::Uuid;
+
+use crate::{Adapter, AdapterEvent, Address, Device, DeviceEvent, DeviceProperty, Error, ErrorKind, Result};
+
+/// Notification received by a [Central].
+///
+/// Contains the address of the device, the UUID of the characteristic and the notified value.
+pub type CentralNotification = (Address, Uuid, Vec<u8>);
+
+/// Scans for, connects to and subscribes to devices providing a GATT service.
+///
+/// Discovery is configured using [Adapter::set_discovery_filter].
+/// Setting the service UUID in the discovery filter reduces the number of devices
+/// that need to be examined.
+#[derive(Clone, Debug)]
+pub struct Central {
+    adapter: Adapter,
+    service: Uuid,
+    characteristics: HashSet<Uuid>,
+    reconnect_delay: Duration,
+}
+
+impl Central {
+    /// Creates a new central for devices advertising the specified service UUID.
+    ///
+    /// By default all characteristics of the service that support notifications are
+    /// subscribed to and reconnection is attempted after five seconds.
+    pub fn new(adapter: Adapter, service: Uuid) -> Self {
+        Self { adapter, service, characteristics: HashSet::new(), reconnect_delay: Duration::from_secs(5) }
+    }
+
+    /// Subscribes to notifications of the characteristic with the specified UUID.
+    ///
+    /// If characteristics are specified, only these characteristics are subscribed to.
+    pub fn characteristic(mut self, uuid: Uuid) -> Self {
+        self.characteristics.insert(uuid);
+        self
+    }
+
+    /// Sets the delay between a lost or failed connection and the next connection attempt.
+    pub fn reconnect_delay(mut self, reconnect_delay: Duration) -> Self {
+        self.reconnect_delay = reconnect_delay;
+        self
+    }
+
+    /// Starts discovery and returns a stream of received notifications.
+    ///
+    /// Each device advertising the service UUID is connected to and its characteristics are
+    /// subscribed to, including devices whose service UUIDs become known after they have been discovered.
+    /// When a connection is lost, it is re-established after the reconnect delay.
+    /// A device is released when it is removed by the Bluetooth daemon and
+    /// handled again when it is rediscovered.
+    ///
+    /// Discovery is stopped when the returned stream is dropped and devices
+    /// that have been connected by the central are disconnected.
+    pub async fn run(self) -> Result<impl Stream<Item = CentralNotification>> {
+        let discovery = self.adapter.discover_devices().await?;
+        let (tx, rx) = mpsc::channel(64);
+        self.adapter.clone().spawn(self.perform(discovery, tx));
+        Ok(ReceiverStream::new(rx))
+    }
+
+    async fn perform(
+        self, discovery: impl Stream<Item = AdapterEvent> + Send, tx: mpsc::Sender<CentralNotification>,
+    ) {
+        let discovery = discovery.fuse();
+        futures::pin_mut!(discovery);
+        let this = Arc::new(self);
+        let active = Arc::new(Mutex::new(HashSet::new()));
+        let mut changes = SelectAll::new();
+
+        loop {
+            tokio::select! {
+                evt = discovery.next() => match evt {
+                    Some(AdapterEvent::DeviceAdded(address)) => {
+                        if active.lock().unwrap().contains(&address) {
+                            continue;
+                        }
+                        let Ok(device) = this.adapter.device(address) else { continue };
+                        match device.uuids().await {
+                            Ok(Some(uuids)) if uuids.contains(&this.service) => {
+                                Self::start(&this, device, &active, &tx);
+                            }
+                            Ok(_) => {
+                                // The service UUIDs may become known later, for example from a scan response.
+                                let Ok(events) = device.events().await else { continue };
+                                changes.push(events.map(move |evt| (address, evt)).boxed());
+                            }
+                            Err(_) => (),
+                        }
+                    }
+                    Some(_) => (),
+                    None => break,
+                },
+                Some((address, evt)) = changes.next(), if !changes.is_empty() => {
+                    let DeviceEvent::PropertyChanged(DeviceProperty::Uuids(uuids)) = evt else { continue };
+                    if !uuids.contains(&this.service) {
+                        continue;
+                    }
+                    let Ok(device) = this.adapter.device(address) else { continue };
+                    Self::start(&this, device, &active, &tx);
+                },
+                () = tx.closed() => break,
+            }
+        }
+    }
+
+    /// Starts handling the device, unless it is already being handled.
+    fn start(
+        this: &Arc<Self>, device: Device, active: &Arc<Mutex<HashSet<Address>>>,
+        tx: &mpsc::Sender<CentralNotification>,
+    ) {
+        let address = device.address();
+        if !active.lock().unwrap().insert(address) {
+            return;
+        }
+
+        log::trace!("Central handling device {}", address);
+        let this = this.clone();
+        let active = active.clone();
+        let tx = tx.clone();
+        this.adapter.clone().spawn(async move {
+            this.handle_device(device, tx).await;
+            active.lock().unwrap().remove(&address);
+            log::trace!("Central released device {}", address);
+        });
+    }
+
+    /// Keeps the device connected and forwards its notifications until it is removed
+    /// or the notification stream is dropped.
+    ///
+    /// In the latter case the device is disconnected, if it has been connected by the central.
+    async fn handle_device(&self, device: Device, tx: mpsc::Sender<CentralNotification>) {
+        let address = device.address();
+        let mut connected = false;
+        'device: loop {
+            match self.subscribe(&device, &mut connected).await {
+                Ok(mut notifications) => loop {
+                    tokio::select! {
+                        notification = notifications.next() => match notification {
+                            Some((uuid, value)) => {
+                                if tx.send((address, uuid, value)).await.is_err() {
+                                    break 'device;
+                                }
+                            }
+                            None => break,
+                        },
+                        () = tx.closed() => break 'device,
+                    }
+                },
+                Err(err) => log::debug!("Central cannot subscribe to device {}: {}", address, &err),
+            }
+
+            tokio::select! {
+                () = sleep(self.reconnect_delay) => (),
+                () = tx.closed() => break,
+            }
+
+            match self.adapter.device_addresses().await {
+                Ok(addresses) if addresses.contains(&address) => (),
+                _ => return,
+            }
+        }
+
+        if connected {
+            log::trace!("Central disconnecting device {}", address);
+            if let Err(err) = device.disconnect().await {
+                log::debug!("Central cannot disconnect device {}: {}", address, &err);
+            }
+        }
+    }
+
+    /// Connects to the device, if necessary, and subscribes to its characteristics.
+    ///
+    /// Sets `connected` when the connection has been established by the central.
+    async fn subscribe(
+        &self, device: &Device, connected: &mut bool,
+    ) -> Result<impl Stream<Item = (Uuid, Vec<u8>)> + Unpin> {
+        if !device.is_connected().await? {
+            device.connect().await?;
+            *connected = true;
+        }
+
+        let mut notifications = SelectAll::new();
+        for service in device.services().await? {
+            if service.uuid().await? != self.service {
+                continue;
+            }
+            for characteristic in service.characteristics().await? {
+                let uuid = characteristic.uuid().await?;
+                if !self.characteristics.is_empty() && !self.characteristics.contains(&uuid) {
+                    continue;
+                }
+                let flags = characteristic.flags().await?;
+                if !flags.notify && !flags.indicate {
+                    continue;
+                }
+                let values = characteristic.notify().await?;
+                notifications.push(values.map(move |value| (uuid, value)).boxed());
+            }
+        }
+
+        if notifications.is_empty() {
+            return Err(Error {
+                kind: ErrorKind::NotFound,
+                message: format!("device {} provides no matching characteristics", device.address()),
+            });
+        }
+        Ok(notifications)
+    }
+}
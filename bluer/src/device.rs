@@ -5,27 +5,46 @@ use dbus::{
     nonblock::{Proxy, SyncConnection},
     Path,
 };
-use futures::{pin_mut, select, stream, FutureExt, Stream, StreamExt};
+use futures::{stream, Future, Stream, StreamExt};
 use std::{
     collections::{HashMap, HashSet},
-    fmt,
+    fmt, mem,
     sync::Arc,
+    time::Duration,
 };
-use tokio::{sync::oneshot, time::sleep};
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::wrappers::ReceiverStream;
 use uuid::Uuid;
 
+use crate::{
+    coalesce_events, path_loss, Adapter, Address, AddressType, CancellationToken, CompanyId, DistanceEstimator,
+    Error, ErrorKind, EventWatcher, InternalErrorKind, Modalias, ObjectEvent, Profile, Proximity, Result,
+    SessionInner, StreamEvent, UuidExt, SERVICE_NAME, TIMEOUT,
+};
+
+#[cfg(feature = "gatt-client")]
 use crate::{
     all_dbus_objects,
     gatt::{self, remote::Service, SERVICE_INTERFACE},
-    Adapter, Address, AddressType, Error, ErrorKind, Event, InternalErrorKind, Modalias, Result, SessionInner,
-    SERVICE_NAME, TIMEOUT,
 };
 
+#[cfg(feature = "agent")]
+use crate::pairing::PairingProgress;
+
 pub(crate) const INTERFACE: &str = "org.bluez.Device1";
+/// Range of the 16-bit UUIDs of BR/EDR service classes discovered using SDP.
+const BREDR_SERVICE_CLASSES: std::ops::Range<u16> = 0x1100..0x1400;
 pub(crate) const BATTERY_INTERFACE: &str = "org.bluez.Battery1";
 
+/// Duration of a baseband slot, the unit of the page timeout.
+#[cfg(feature = "mgmt")]
+const PAGE_TIMEOUT_SLOT: Duration = Duration::from_micros(625);
+
+/// Time to wait for service discovery after a connection has been established.
+const SERVICES_RESOLVED_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Interface to a Bluetooth device.
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 #[derive(Clone)]
 pub struct Device {
     inner: Arc<SessionInner>,
@@ -43,15 +62,15 @@ impl fmt::Debug for Device {
 impl Device {
     /// Create Bluetooth device interface for device of specified address connected to specified adapter.
     pub(crate) fn new(inner: Arc<SessionInner>, adapter_name: Arc<String>, address: Address) -> Result<Self> {
-        Ok(Self { inner, dbus_path: Self::dbus_path(&adapter_name, address)?, adapter_name, address })
+        Ok(Self { inner, dbus_path: Self::make_dbus_path(&adapter_name, address)?, adapter_name, address })
     }
 
     fn proxy(&self) -> Proxy<'_, &SyncConnection> {
         Proxy::new(SERVICE_NAME, &self.dbus_path, TIMEOUT, &*self.inner.connection)
     }
 
-    pub(crate) fn dbus_path(adapter_name: &str, address: Address) -> Result<Path<'static>> {
-        let adapter_path = Adapter::dbus_path(adapter_name)?;
+    pub(crate) fn make_dbus_path(adapter_name: &str, address: Address) -> Result<Path<'static>> {
+        let adapter_path = Adapter::make_dbus_path(adapter_name)?;
         Ok(Path::new(format!("{}/dev_{}", adapter_path, address.to_string().replace(':', "_"))).unwrap())
     }
 
@@ -88,57 +107,296 @@ impl Device {
         self.address
     }
 
+    /// D-Bus object path of the device.
+    ///
+    /// Together with [Session::dbus_connection](crate::Session::dbus_connection) this
+    /// allows access to BlueZ interfaces of the device that are not wrapped by this library.
+    pub fn dbus_path(&self) -> &Path<'static> {
+        &self.dbus_path
+    }
+
+    /// Calls a method of a BlueZ D-Bus interface of the device.
+    ///
+    /// This allows calling methods that are not wrapped by this library.
+    /// The call is made using the D-Bus connection of the session.
+    pub async fn call_method_raw<A, R>(&self, interface: &str, method: &str, args: A) -> Result<R>
+    where
+        A: dbus::arg::AppendAll + std::fmt::Debug,
+        R: dbus::arg::ReadAll + std::fmt::Debug + 'static,
+    {
+        self.call_method_with_interface(method, args, interface).await
+    }
+
     /// Streams device property changes.
     ///
+    /// Events that are not consumed in time are subject to the
+    /// [event overflow policy](crate::Session::set_event_buffer) of the session.
+    ///
     /// The stream ends when the device is removed.
     pub async fn events(&self) -> Result<impl Stream<Item = DeviceEvent>> {
         let events = self.inner.events(self.dbus_path.clone(), false).await?;
-        let stream = events.flat_map(move |event| match event {
-            Event::PropertiesChanged { changed, .. } => {
-                stream::iter(DeviceProperty::from_prop_map(changed).into_iter().map(DeviceEvent::PropertyChanged))
-                    .boxed()
+        Ok(events.flat_map(|event| stream::iter(Self::device_events(event))))
+    }
+
+    /// Streams device property changes like [events](Self::events), but delivers
+    /// a [StreamEvent::Lagged] item when events have been lost because they were not consumed in time.
+    ///
+    /// The stream ends when the device is removed.
+    pub async fn events_with_lag(&self) -> Result<impl Stream<Item = StreamEvent<DeviceEvent>>> {
+        let events = self.inner.events(self.dbus_path.clone(), false).await?.with_lag();
+        Ok(events.flat_map(|event| match event {
+            StreamEvent::Event(event) => {
+                stream::iter(Self::device_events(event).into_iter().map(StreamEvent::Event).collect::<Vec<_>>())
             }
-            _ => stream::empty().boxed(),
-        });
+            StreamEvent::Lagged(n) => stream::iter(vec![StreamEvent::Lagged(n)]),
+        }))
+    }
 
-        Ok(stream)
+    /// Converts an object event of the device into device events.
+    fn device_events(event: ObjectEvent) -> Vec<DeviceEvent> {
+        match event {
+            ObjectEvent::PropertiesChanged { changed, .. } => {
+                DeviceProperty::from_prop_map(changed).into_iter().map(DeviceEvent::PropertyChanged).collect()
+            }
+            _ => Vec::new(),
+        }
     }
 
-    /// Wait until remote GATT services are resolved.
-    async fn wait_for_services_resolved(&self) -> Result<()> {
-        let mut changes = self.events().await?.fuse();
-        if self.is_services_resolved().await? {
-            return Ok(());
+    /// Streams device property changes, coalescing rapid successive changes of the same property.
+    ///
+    /// When a property changes, further changes of that property are collected for
+    /// the duration of `window` and then only its most recent value is delivered.
+    /// This is useful to limit the event rate of frequently changing properties,
+    /// such as [DeviceProperty::Rssi] or [DeviceProperty::ManufacturerData] when
+    /// [DiscoveryFilter::duplicate_data](crate::DiscoveryFilter::duplicate_data) is enabled.
+    ///
+    /// The stream ends when the device is removed.
+    pub async fn events_coalesced(&self, window: Duration) -> Result<impl Stream<Item = DeviceEvent>> {
+        let events = self.events().await?;
+        Ok(coalesce_events(&self.inner, events, window, |evt| match evt {
+            DeviceEvent::PropertyChanged(prop) => Some(mem::discriminant(prop)),
+        }))
+    }
+
+    /// Creates a shared watcher for device property changes.
+    ///
+    /// In contrast to [events](Self::events) the returned [EventWatcher] can be
+    /// subscribed to by multiple consumers, which all share a single event subscription.
+    pub async fn event_watcher(&self) -> Result<EventWatcher<DeviceEvent>> {
+        Ok(EventWatcher::new(&self.inner, self.events().await?))
+    }
+
+    /// Queries the state of the device in a single call to the Bluetooth daemon.
+    ///
+    /// The returned snapshot is consistent, i.e. all values are from the same point in time.
+    pub async fn summary(&self) -> Result<DeviceSummary> {
+        use dbus::nonblock::stdintf::org_freedesktop_dbus::Properties;
+        let props = self.proxy().get_all(INTERFACE).await?;
+        Ok(DeviceSummary::from_properties(self.address, DeviceProperty::from_prop_map(props)))
+    }
+
+    /// List of profiles and services that represent the available remote services.
+    ///
+    /// This is the same as [uuids](Self::uuids), but with each UUID converted to a [Profile].
+    pub async fn profiles(&self) -> Result<Option<HashSet<Profile>>> {
+        Ok(self.uuids().await?.map(|uuids| uuids.into_iter().map(Profile::from).collect()))
+    }
+
+    /// Path loss in dB computed from the received signal strength and
+    /// the advertised transmit power level.
+    ///
+    /// Returns [None] if either is unavailable.
+    pub async fn path_loss(&self) -> Result<Option<i16>> {
+        match (self.rssi().await?, self.tx_power().await?) {
+            (Some(rssi), Some(tx_power)) => Ok(Some(path_loss(rssi, tx_power))),
+            _ => Ok(None),
         }
-        if !self.is_connected().await? {
-            return Err(Error::new(ErrorKind::ServicesUnresolved));
+    }
+
+    /// Estimated distance band of the device computed from the received signal strength and
+    /// the advertised transmit power level.
+    ///
+    /// Returns [None] if either is unavailable.
+    pub async fn proximity(&self, estimator: &DistanceEstimator) -> Result<Option<Proximity>> {
+        match (self.rssi().await?, self.tx_power().await?) {
+            (Some(rssi), Some(tx_power)) => Ok(Some(estimator.proximity(rssi, tx_power))),
+            _ => Ok(None),
         }
+    }
 
-        let timeout = sleep(TIMEOUT).fuse();
-        pin_mut!(timeout);
+    /// Streams changes of the manufacturer specific advertisement data.
+    ///
+    /// Each item contains the complete manufacturer data after the change.
+    /// Sensor beacons often encode their readings there.
+    ///
+    /// The stream ends when the device is removed.
+    pub async fn manufacturer_data_changes(&self) -> Result<impl Stream<Item = HashMap<CompanyId, Vec<u8>>>> {
+        let events = self.events().await?;
+        Ok(events.filter_map(|evt| async move {
+            match evt {
+                DeviceEvent::PropertyChanged(DeviceProperty::ManufacturerData(data)) => Some(data),
+                _ => None,
+            }
+        }))
+    }
 
-        loop {
-            select! {
-                change_opt = changes.next() => {
-                    match change_opt {
-                        Some(DeviceEvent::PropertyChanged (DeviceProperty::ServicesResolved(true)) ) =>
-                            return Ok(()),
-                        Some(DeviceEvent::PropertyChanged (DeviceProperty::Connected(false)) ) =>
-                            return Err(Error::new(ErrorKind::ServicesUnresolved)),
-                        Some(_) => (),
-                        None => break,
-                    }
-                },
-                () = &mut timeout => break,
+    /// Streams changes of the service advertisement data.
+    ///
+    /// Each item contains the complete service data after the change.
+    ///
+    /// The stream ends when the device is removed.
+    pub async fn service_data_changes(&self) -> Result<impl Stream<Item = HashMap<Uuid, Vec<u8>>>> {
+        let events = self.events().await?;
+        Ok(events.filter_map(|evt| async move {
+            match evt {
+                DeviceEvent::PropertyChanged(DeviceProperty::ServiceData(data)) => Some(data),
+                _ => None,
             }
+        }))
+    }
+
+    /// Waits until the device is connected.
+    ///
+    /// Returns immediately if the device is already connected.
+    /// Fails with [ErrorKind::Timeout] if the device does not connect within `timeout`
+    /// and with [ErrorKind::DoesNotExist] if the device is removed while waiting.
+    ///
+    /// This does not initiate a connection, use [connect](Self::connect) for that.
+    pub async fn wait_connected(&self, timeout: Duration) -> Result<()> {
+        self.wait_for_state(timeout, || self.is_connected(), |prop| matches!(prop, DeviceProperty::Connected(_)))
+            .await
+    }
+
+    /// Waits until the device is disconnected.
+    ///
+    /// Returns immediately if the device is not connected.
+    /// Fails with [ErrorKind::Timeout] if the device does not disconnect within `timeout`.
+    /// Removal of the device while waiting is treated as a disconnection.
+    pub async fn wait_disconnected(&self, timeout: Duration) -> Result<()> {
+        let res = self
+            .wait_for_state(
+                timeout,
+                || async { Ok(!self.is_connected().await?) },
+                |prop| matches!(prop, DeviceProperty::Connected(_)),
+            )
+            .await;
+        match res {
+            Err(err) if matches!(err.kind, ErrorKind::DoesNotExist | ErrorKind::NotFound) => Ok(()),
+            res => res,
         }
+    }
+
+    /// Waits until the remote GATT services of the device have been resolved.
+    ///
+    /// Returns immediately if the services are already resolved.
+    /// Fails with [ErrorKind::ServicesUnresolved] if the device is not connected or
+    /// disconnects while waiting, with [ErrorKind::Timeout] if service discovery does
+    /// not complete within `timeout` and with [ErrorKind::DoesNotExist] if the device
+    /// is removed while waiting.
+    pub async fn wait_services_resolved(&self, timeout: Duration) -> Result<()> {
+        self.wait_for_state(
+            timeout,
+            || async {
+                if self.is_services_resolved().await? {
+                    Ok(true)
+                } else if !self.is_connected().await? {
+                    Err(Error::new(ErrorKind::ServicesUnresolved))
+                } else {
+                    Ok(false)
+                }
+            },
+            |prop| matches!(prop, DeviceProperty::ServicesResolved(_) | DeviceProperty::Connected(_)),
+        )
+        .await
+    }
+
+    /// Whether the device provides the service with the specified UUID.
+    ///
+    /// This checks the [UUIDs](Self::uuids) of the device, which contain the services
+    /// advertised by the device and, once services have been resolved, the services
+    /// discovered on the connected device.
+    pub async fn has_service(&self, uuid: Uuid) -> Result<bool> {
+        Ok(self.uuids().await?.is_some_and(|uuids| uuids.contains(&uuid)))
+    }
+
+    /// Waits until the device provides the service with the specified UUID.
+    ///
+    /// Returns immediately if the service is already known, either from advertisements
+    /// or from service discovery.
+    /// Fails with [ErrorKind::NotFound] if the services of the device have been resolved
+    /// and do not include the service, with [ErrorKind::Timeout] if the service
+    /// does not become available within `timeout` and with [ErrorKind::DoesNotExist]
+    /// if the device is removed while waiting.
+    pub async fn wait_for_service(&self, uuid: Uuid, timeout: Duration) -> Result<()> {
+        self.wait_for_state(
+            timeout,
+            || async {
+                if self.has_service(uuid).await? {
+                    Ok(true)
+                } else if self.is_services_resolved().await? {
+                    Err(Error::new(ErrorKind::NotFound))
+                } else {
+                    Ok(false)
+                }
+            },
+            |prop| match prop {
+                DeviceProperty::Uuids(uuids) => uuids.contains(&uuid),
+                DeviceProperty::ServicesResolved(resolved) => *resolved,
+                _ => false,
+            },
+        )
+        .await
+    }
 
-        Err(Error::new(ErrorKind::ServicesUnresolved))
+    /// Waits until the device reaches a state.
+    ///
+    /// `current` queries whether the device is in the state and `relevant` determines
+    /// whether a property change may affect the state, causing it to be queried again.
+    /// Property changes are subscribed to before the current state is queried and the
+    /// state is also queried again if property changes have been lost, so that no change is missed.
+    async fn wait_for_state<F, Fut>(
+        &self, timeout: Duration, current: F, relevant: impl Fn(&DeviceProperty) -> bool,
+    ) -> Result<()>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<bool>>,
+    {
+        let wait = async {
+            let events = self.events_with_lag().await?;
+            futures::pin_mut!(events);
+            if current().await? {
+                return Ok(());
+            }
+            while let Some(evt) = events.next().await {
+                let requery = match evt {
+                    StreamEvent::Event(DeviceEvent::PropertyChanged(prop)) => relevant(&prop),
+                    StreamEvent::Lagged(_) => true,
+                };
+                if requery && current().await? {
+                    return Ok(());
+                }
+            }
+            Err(Error::new(ErrorKind::DoesNotExist))
+        };
+        tokio::time::timeout(timeout, wait).await.map_err(|_| Error::new(ErrorKind::Timeout))?
+    }
+
+    /// Wait until remote GATT services are resolved.
+    #[cfg(feature = "gatt-client")]
+    async fn wait_for_services_resolved(&self) -> Result<()> {
+        match self.wait_services_resolved(TIMEOUT).await {
+            Err(err) if matches!(err.kind, ErrorKind::Timeout | ErrorKind::DoesNotExist) => {
+                Err(Error::new(ErrorKind::ServicesUnresolved))
+            }
+            res => res,
+        }
     }
 
     /// Remote GATT services.
     ///
     /// The device must be connected for GATT services to be resolved.
+    #[cfg(feature = "gatt-client")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gatt-client")))]
     pub async fn services(&self) -> Result<Vec<gatt::remote::Service>> {
         self.wait_for_services_resolved().await?;
 
@@ -160,10 +418,30 @@ impl Device {
     }
 
     /// Remote GATT service with specified id.
+    #[cfg(feature = "gatt-client")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gatt-client")))]
     pub async fn service(&self, service_id: u16) -> Result<gatt::remote::Service> {
         gatt::remote::Service::new(self.inner.clone(), self.adapter_name.clone(), self.address, service_id)
     }
 
+    /// Hands-Free Profile audio gateway of the device managed by oFono.
+    ///
+    /// Returns [None] if oFono does not manage the device as an audio gateway,
+    /// for example because it is not connected using the Hands-Free Profile.
+    #[cfg(feature = "ofono")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ofono")))]
+    pub async fn hfp_gateway(&self) -> Result<Option<crate::ofono::HfpGateway>> {
+        let gateways = crate::ofono::HfpGateway::all(self.inner.clone()).await?;
+        Ok(gateways.into_iter().find(|gateway| gateway.address() == self.address))
+    }
+
+    /// Health Device Profile interface of the remote device.
+    #[cfg(feature = "health")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "health")))]
+    pub fn health(&self) -> Result<crate::health::HealthDevice> {
+        crate::health::HealthDevice::new(self.inner.clone(), self.adapter_name.clone(), self.address)
+    }
+
     dbus_interface!();
     dbus_default_interface!(INTERFACE);
 
@@ -197,9 +475,170 @@ impl Device {
     ///    timestamps are the same BR/EDR takes
     ///    precedence.
     pub async fn connect(&self) -> Result<()> {
+        let _lock = self.inner.device_op_lock(&self.adapter_name, self.address).await;
         self.call_method("Connect", ()).await
     }
 
+    /// Connects to the device using the specified address type for connection establishment.
+    ///
+    /// The Bluetooth daemon connects using the address type it has cached for the device,
+    /// which can be wrong for dual-mode devices or devices rotating resolvable private addresses.
+    /// If the cached [address type](Self::address_type) differs from the specified one,
+    /// the device object is removed and the connection is established using
+    /// [Adapter::connect_device], which accepts an explicit address type.
+    /// Afterwards this device object refers to the newly created device object.
+    ///
+    /// To protect bonding information, this fails with [ErrorKind::AlreadyExists] if the
+    /// address type differs and the device is paired.
+    /// Specifying the cached LE address type is equivalent to calling [connect](Self::connect).
+    ///
+    /// Since the cached address type is always an LE address type, specifying [AddressType::BrEdr]
+    /// establishes the connection using [Adapter::connect_device] without an address type,
+    /// which selects BR/EDR.
+    /// If the device is paired, its BR/EDR profiles are connected using
+    /// [connect_profile](Self::connect_profile) instead.
+    pub async fn connect_with_address_type(&self, address_type: AddressType) -> Result<()> {
+        if address_type != AddressType::BrEdr && self.address_type().await? == address_type {
+            return self.connect().await;
+        }
+
+        let _lock = self.inner.device_op_lock(&self.adapter_name, self.address).await;
+        if self.is_paired().await? {
+            if address_type == AddressType::BrEdr {
+                return self.connect_bredr_profiles().await;
+            }
+            return Err(Error {
+                kind: ErrorKind::AlreadyExists,
+                message: format!("device {} is paired using a different address type", self.address),
+            });
+        }
+        log::trace!("{}: reconnecting using address type {}", &self.dbus_path, address_type);
+        let adapter = Adapter::new(self.inner.clone(), &self.adapter_name)?;
+        adapter.remove_device(self.address).await?;
+        adapter.connect_device(self.address, address_type).await?;
+        Ok(())
+    }
+
+    /// Connects all BR/EDR profiles provided by the device.
+    ///
+    /// Succeeds if at least one profile has been connected.
+    /// The device operation lock must be held.
+    async fn connect_bredr_profiles(&self) -> Result<()> {
+        let uuids = self.uuids().await?.unwrap_or_default();
+        let mut result = Err(Error {
+            kind: ErrorKind::NotAvailable,
+            message: format!("device {} provides no BR/EDR profiles", self.address),
+        });
+        for uuid in
+            uuids.iter().filter(|uuid| uuid.as_u16().is_some_and(|id| BREDR_SERVICE_CLASSES.contains(&id)))
+        {
+            log::trace!("{}: connecting BR/EDR profile {}", &self.dbus_path, uuid);
+            match self.call_method("ConnectProfile", (uuid.to_string(),)).await {
+                Ok(()) => result = Ok(()),
+                Err(err) if result.is_err() => result = Err(err),
+                Err(_) => (),
+            }
+        }
+        result
+    }
+
+    /// Connects like [connect](Self::connect) and streams the progress of the connection attempt.
+    ///
+    /// The stream reports
+    /// [Connecting](ConnectProgress::Connecting) when the attempt starts,
+    /// [Connected](ConnectProgress::Connected) once the link is up,
+    /// [ResolvingServices](ConnectProgress::ResolvingServices) while services are being discovered and
+    /// [ServicesResolved](ConnectProgress::ServicesResolved) once service discovery has completed.
+    /// If the connection attempt fails, the stream ends with [ConnectProgress::Failed].
+    ///
+    /// After the connection has been established, this waits for up to 30 seconds
+    /// for service discovery to complete.
+    /// The stream ends without an error if service discovery does not complete in time.
+    ///
+    /// Dropping the returned stream stops the reporting, but does not abort the connection attempt.
+    pub async fn connect_with_progress(&self) -> Result<impl Stream<Item = ConnectProgress>> {
+        let events = self.events().await?;
+        let (tx, rx) = mpsc::channel(8);
+        let device = self.clone();
+
+        self.inner.spawn(async move {
+            if let Err(err) = device.report_connect_progress(events, &tx).await {
+                let _ = tx.send(ConnectProgress::Failed(err)).await;
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    /// Connects and reports the reached milestones to `tx` until services have been resolved.
+    async fn report_connect_progress(
+        &self, events: impl Stream<Item = DeviceEvent>, tx: &mpsc::Sender<ConnectProgress>,
+    ) -> Result<()> {
+        futures::pin_mut!(events);
+        let report = |milestones: Vec<ConnectProgress>| async move {
+            for milestone in milestones {
+                log::trace!("{}: connection progress: {:?}", &self.dbus_path, &milestone);
+                let _ = tx.send(milestone).await;
+            }
+        };
+        let mut milestones = ConnectMilestones::default();
+
+        report(vec![ConnectProgress::Connecting]).await;
+        let connect = self.connect();
+        futures::pin_mut!(connect);
+        loop {
+            tokio::select! {
+                res = &mut connect => {
+                    res?;
+                    break;
+                }
+                Some(evt) = events.next() => report(milestones.update(&evt)).await,
+                () = tx.closed() => return Ok(()),
+            }
+        }
+
+        if !self.is_connected().await? {
+            return Ok(());
+        }
+        report(milestones.update(&DeviceEvent::PropertyChanged(DeviceProperty::Connected(true)))).await;
+        if self.is_services_resolved().await? {
+            report(milestones.update(&DeviceEvent::PropertyChanged(DeviceProperty::ServicesResolved(true))))
+                .await;
+        }
+
+        let timeout = tokio::time::sleep(SERVICES_RESOLVED_TIMEOUT);
+        futures::pin_mut!(timeout);
+        while !milestones.resolved {
+            tokio::select! {
+                evt = events.next() => match evt {
+                    Some(DeviceEvent::PropertyChanged(DeviceProperty::Connected(false))) | None => break,
+                    Some(evt) => report(milestones.update(&evt)).await,
+                },
+                () = &mut timeout => break,
+                () = tx.closed() => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Connects like [connect](Self::connect), but aborts the connection attempt
+    /// when the specified cancellation token is cancelled.
+    ///
+    /// Since the connection attempt is aborted by disconnecting the device,
+    /// an already established connection is terminated on cancellation.
+    /// An error of kind [ErrorKind::Cancelled] is returned in that case.
+    pub async fn connect_cancellable(&self, token: &CancellationToken) -> Result<()> {
+        tokio::select! {
+            res = self.connect() => res,
+            () = token.cancelled() => {
+                log::trace!("{}: cancelling connection attempt", &self.dbus_path);
+                let _ = self.disconnect().await;
+                Err(Error::new(ErrorKind::Cancelled))
+            }
+        }
+    }
+
     /// This method gracefully disconnects all connected
     /// profiles and then terminates low-level ACL connection.
     ///
@@ -213,7 +652,14 @@ impl Device {
     /// For non-trusted devices connected over LE bearer calling
     /// this method will disable incoming connections until
     /// Connect method is called again.
+    ///
+    /// If operations on remote devices are
+    /// [serialized](crate::Session::set_serialize_device_operations), this waits for a
+    /// connection attempt of another task to complete.
+    /// Use [connect_cancellable](Self::connect_cancellable) or drop the future returned by
+    /// [connect](Self::connect) to abort a pending connection attempt instead.
     pub async fn disconnect(&self) -> Result<()> {
+        let _lock = self.inner.device_op_lock(&self.adapter_name, self.address).await;
         self.call_method("Disconnect", ()).await
     }
 
@@ -221,6 +667,7 @@ impl Device {
     /// device. The UUID provided is the remote service
     /// UUID for the profile.
     pub async fn connect_profile(&self, uuid: &Uuid) -> Result<()> {
+        let _lock = self.inner.device_op_lock(&self.adapter_name, self.address).await;
         self.call_method("ConnectProfile", (uuid.to_string(),)).await
     }
 
@@ -234,9 +681,78 @@ impl Device {
     /// as long as the profile is registered this will always
     /// succeed.
     pub async fn disconnect_profile(&self, uuid: &Uuid) -> Result<()> {
+        let _lock = self.inner.device_op_lock(&self.adapter_name, self.address).await;
         self.call_method("DisconnectProfile", (uuid.to_string(),)).await
     }
 
+    /// Returns information about the current connection to the device.
+    ///
+    /// This reports whether the device is connected using Bluetooth LE or classic
+    /// Bluetooth (BR/EDR), the address type in use and, if supported by the
+    /// controller, the signal strength and transmit power of the connection.
+    ///
+    /// The information is obtained from the [management interface](crate::mgmt) of the kernel,
+    /// which requires the `CAP_NET_ADMIN` capability.
+    /// Fails if the device is not connected.
+    #[cfg(feature = "mgmt")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mgmt")))]
+    pub async fn connection_info(&self) -> Result<crate::mgmt::ConnectionInfo> {
+        let index = crate::mgmt::adapter_index(self.adapter_name())?;
+        let mgmt = crate::mgmt::Management::new()?;
+        let Some((_, address_type)) =
+            mgmt.connections(index).await?.into_iter().find(|(address, _)| *address == self.address)
+        else {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotConnected, "device is not connected").into());
+        };
+        Ok(mgmt.connection_info(index, self.address, address_type).await?)
+    }
+
+    /// Reads the page timeout used by the controller of the adapter when establishing
+    /// classic Bluetooth (BR/EDR) connections.
+    ///
+    /// The page timeout is a setting of the controller and thus applies to connections
+    /// to all devices of the adapter.
+    /// It is read from the default system configuration using the
+    /// [management interface](crate::mgmt) of the kernel, which requires the `CAP_NET_ADMIN` capability.
+    /// Returns [None] if the kernel does not report the page timeout.
+    #[cfg(feature = "mgmt")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mgmt")))]
+    pub async fn page_timeout(&self) -> Result<Option<Duration>> {
+        let index = crate::mgmt::adapter_index(self.adapter_name())?;
+        let config = crate::mgmt::Management::new()?.system_config(index).await?;
+        Ok(config
+            .get(&crate::mgmt::SystemConfigParameter::PageTimeout)
+            .map(|&slots| PAGE_TIMEOUT_SLOT * u32::from(slots)))
+    }
+
+    /// Sets the page timeout used by the controller of the adapter when establishing
+    /// classic Bluetooth (BR/EDR) connections.
+    ///
+    /// A longer page timeout helps when connecting to slow-responding peripherals, such as
+    /// printers and scanners waking from power saving, while a shorter timeout makes
+    /// connection attempts to absent devices fail sooner.
+    /// The timeout is specified in baseband slots of 0.625 ms and must be between
+    /// 0.625 ms and approximately 40.9 seconds.
+    ///
+    /// The page timeout is a setting of the controller and thus applies to connections
+    /// to all devices of the adapter.
+    /// The kernel applies it when the adapter is powered on; power cycle the adapter for
+    /// the change to take effect.
+    /// Requires the `CAP_NET_ADMIN` capability.
+    #[cfg(feature = "mgmt")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mgmt")))]
+    pub async fn set_page_timeout(&self, timeout: Duration) -> Result<()> {
+        let slots = match u16::try_from(timeout.as_micros() / PAGE_TIMEOUT_SLOT.as_micros()) {
+            Ok(slots) if slots > 0 => slots,
+            _ => return Err(Error::new(ErrorKind::InvalidArguments)),
+        };
+        let index = crate::mgmt::adapter_index(self.adapter_name())?;
+        crate::mgmt::Management::new()?
+            .set_system_config(index, [(crate::mgmt::SystemConfigParameter::PageTimeout, slots)])
+            .await?;
+        Ok(())
+    }
+
     /// This method will connect to the remote device,
     /// initiate pairing and then retrieve all SDP records
     /// (or GATT primary services).
@@ -258,7 +774,7 @@ impl Device {
         let (done_tx, done_rx) = oneshot::channel();
         let dbus_path = self.dbus_path.clone();
         let connection = self.inner.connection.clone();
-        tokio::spawn(async move {
+        self.inner.spawn(async move {
             if done_rx.await.is_err() {
                 let proxy = Proxy::new(SERVICE_NAME, dbus_path, TIMEOUT, &*connection);
                 let _: std::result::Result<(), dbus::Error> =
@@ -270,6 +786,86 @@ impl Device {
         let _ = done_tx.send(());
         result
     }
+
+    /// Pairs like [pair](Self::pair) and streams the progress of pairing.
+    ///
+    /// The stream starts with [PairingProgress::Initiated], followed by
+    /// [AgentRequest](PairingProgress::AgentRequest) events for each request made by
+    /// the Bluetooth daemon to an agent registered using this session and
+    /// [KeysExchanged](PairingProgress::KeysExchanged) once the device has become paired.
+    /// It ends with [PairingProgress::Bonded], [PairingProgress::Paired] or [PairingProgress::Failed].
+    ///
+    /// Requests made to agents of other programs, such as the default agent of the
+    /// desktop environment, are not reported.
+    ///
+    /// Drop the returned stream to cancel pairing.
+    #[cfg(feature = "agent")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "agent")))]
+    pub async fn pair_with_progress(&self) -> Result<impl Stream<Item = PairingProgress>> {
+        let mut agent_requests = self.inner.agent_requests.subscribe();
+        let events = self.events().await?;
+        let (tx, rx) = mpsc::channel(16);
+        let device = self.clone();
+
+        self.inner.spawn(async move {
+            if tx.send(PairingProgress::Initiated).await.is_err() {
+                return;
+            }
+            let pair = device.pair();
+            futures::pin_mut!(pair, events);
+            let mut keys_exchanged = false;
+
+            loop {
+                let evt = tokio::select! {
+                    res = &mut pair => {
+                        if res.is_ok() && !keys_exchanged {
+                            let _ = tx.send(PairingProgress::KeysExchanged).await;
+                        }
+                        let evt = match res {
+                            Ok(()) => match device.is_bonded().await {
+                                Ok(Some(false)) => PairingProgress::Paired,
+                                Ok(_) => PairingProgress::Bonded,
+                                Err(err) => PairingProgress::Failed(err),
+                            },
+                            Err(err) => PairingProgress::Failed(err),
+                        };
+                        let _ = tx.send(evt).await;
+                        break;
+                    }
+                    Ok((adapter, address, kind)) = agent_requests.recv() => {
+                        if adapter != device.adapter_name() || address != device.address {
+                            continue;
+                        }
+                        PairingProgress::AgentRequest(kind)
+                    }
+                    Some(evt) = events.next() => match evt {
+                        DeviceEvent::PropertyChanged(DeviceProperty::Paired(true)) if !keys_exchanged => {
+                            keys_exchanged = true;
+                            PairingProgress::KeysExchanged
+                        }
+                        _ => continue,
+                    },
+                    () = tx.closed() => break,
+                };
+                if tx.send(evt).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    /// Pairs like [pair](Self::pair), but cancels pairing
+    /// when the specified cancellation token is cancelled.
+    ///
+    /// An error of kind [ErrorKind::Cancelled] is returned on cancellation.
+    pub async fn pair_cancellable(&self, token: &CancellationToken) -> Result<()> {
+        tokio::select! {
+            res = self.pair() => res,
+            () = token.cancelled() => Err(Error::new(ErrorKind::Cancelled)),
+        }
+    }
 }
 
 define_properties!(
@@ -352,6 +948,19 @@ define_properties!(
             get: (is_paired, v => {v.to_owned()}),
         );
 
+        ///	Indicates if the remote device is bonded.
+        ///
+        /// Bonded means that the keys exchanged during pairing have been stored
+        /// and will be persisted, while a device may be paired without being bonded.
+        ///
+        /// Returns [None] if the Bluetooth daemon does not provide this information,
+        /// which is the case for versions before 5.67.
+        property(
+            Bonded, bool,
+            dbus: (INTERFACE, "Bonded", bool, OPTIONAL),
+            get: (is_bonded, v => {v.to_owned()}),
+        );
+
         ///	Indicates if the remote device is connected.
         property(
             Connected, bool,
@@ -457,10 +1066,10 @@ define_properties!(
         /// 16 bits Manufacturer ID followed by its byte array
         /// value.
         property(
-            ManufacturerData, HashMap<u16, Vec<u8>>,
+            ManufacturerData, HashMap<CompanyId, Vec<u8>>,
             dbus: (INTERFACE, "ManufacturerData", HashMap<u16, Variant<Box<dyn RefArg  + 'static>>>, OPTIONAL),
             get: (manufacturer_data, m => {
-                let mut mt: HashMap<u16, Vec<u8>> = HashMap::new();
+                let mut mt: HashMap<CompanyId, Vec<u8>> = HashMap::new();
                 for (k, v) in m {
                     if let Some(v) = dbus::arg::cast(&v.0).cloned() {
                         mt.insert(*k, v);
@@ -529,11 +1138,124 @@ define_properties!(
     }
 );
 
+/// Milestone of a connection attempt reported by [Device::connect_with_progress].
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ConnectProgress {
+    /// The connection attempt has started.
+    Connecting,
+    /// The link to the device has been established.
+    Connected,
+    /// Services of the device are being discovered.
+    ResolvingServices,
+    /// Service discovery has completed.
+    ///
+    /// This is the last event.
+    ServicesResolved,
+    /// The connection attempt failed for the specified reason.
+    ///
+    /// This is the last event.
+    Failed(Error),
+}
+
+/// Milestones of a connection attempt that have been reached.
+#[derive(Default)]
+struct ConnectMilestones {
+    connected: bool,
+    resolved: bool,
+}
+
+impl ConnectMilestones {
+    /// Returns the milestones newly reached by the event.
+    fn update(&mut self, evt: &DeviceEvent) -> Vec<ConnectProgress> {
+        match evt {
+            DeviceEvent::PropertyChanged(DeviceProperty::Connected(true)) if !self.connected => {
+                self.connected = true;
+                match self.resolved {
+                    true => vec![ConnectProgress::Connected],
+                    false => vec![ConnectProgress::Connected, ConnectProgress::ResolvingServices],
+                }
+            }
+            DeviceEvent::PropertyChanged(DeviceProperty::ServicesResolved(true)) if !self.resolved => {
+                self.resolved = true;
+                vec![ConnectProgress::ServicesResolved]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
 /// Bluetooth device event.
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DeviceEvent {
     /// Property changed.
     PropertyChanged(DeviceProperty),
 }
+
+/// Snapshot of the state of a Bluetooth device.
+///
+/// Use [Device::summary] to obtain a snapshot.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct DeviceSummary {
+    /// The Bluetooth device address.
+    pub address: Address,
+    /// The Bluetooth device address type.
+    pub address_type: Option<AddressType>,
+    /// The Bluetooth remote name, if known.
+    pub name: Option<String>,
+    /// The name alias of the remote device.
+    pub alias: Option<String>,
+    /// Received Signal Strength Indicator of the remote device
+    /// (inquiry or advertising), if the device is in range.
+    pub rssi: Option<i16>,
+    /// Advertised transmitted power level (inquiry or advertising).
+    pub tx_power: Option<i16>,
+    /// Indicates if the remote device is paired.
+    pub paired: bool,
+    /// Indicates if the remote device is bonded, if known.
+    pub bonded: Option<bool>,
+    /// Indicates if the remote device is currently connected.
+    pub connected: bool,
+    /// Indicates if the remote is seen as trusted.
+    pub trusted: bool,
+    /// Indicates if the remote is blocked.
+    pub blocked: bool,
+    /// List of 128-bit UUIDs that represents the available remote services.
+    pub uuids: HashSet<Uuid>,
+    /// Manufacturer specific advertisement data.
+    pub manufacturer_data: HashMap<CompanyId, Vec<u8>>,
+    /// Service advertisement data.
+    pub service_data: HashMap<Uuid, Vec<u8>>,
+}
+
+impl DeviceSummary {
+    fn from_properties(address: Address, props: Vec<DeviceProperty>) -> Self {
+        let mut summary = Self { address, ..Default::default() };
+        for prop in props {
+            match prop {
+                DeviceProperty::AddressType(v) => summary.address_type = Some(v),
+                DeviceProperty::Name(v) => summary.name = Some(v),
+                DeviceProperty::Alias(v) => summary.alias = Some(v),
+                DeviceProperty::Rssi(v) => summary.rssi = Some(v),
+                DeviceProperty::TxPower(v) => summary.tx_power = Some(v),
+                DeviceProperty::Paired(v) => summary.paired = v,
+                DeviceProperty::Bonded(v) => summary.bonded = Some(v),
+                DeviceProperty::Connected(v) => summary.connected = v,
+                DeviceProperty::Trusted(v) => summary.trusted = v,
+                DeviceProperty::Blocked(v) => summary.blocked = v,
+                DeviceProperty::Uuids(v) => summary.uuids = v,
+                DeviceProperty::ManufacturerData(v) => summary.manufacturer_data = v,
+                DeviceProperty::ServiceData(v) => summary.service_data = v,
+                _ => (),
+            }
+        }
+        summary
+    }
+}
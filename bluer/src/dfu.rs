@@ -0,0 +1,414 @@
+//! Firmware updates over GATT.
+//!
+//! Two update flows are provided:
+//!
+//! * [SecureDfu] implements the Nordic Semiconductor Secure DFU protocol
+//!   used by the nRF5 SDK bootloaders.
+//! * [ChunkedWrite] writes a firmware image in chunks to a single characteristic,
+//!   which is sufficient for many simple vendor-specific OTA protocols.
+//!
+//! Both flows report their [Progress] and can resume an interrupted transfer.
+
+use futures::{pin_mut, Stream, StreamExt};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::{
+    gatt::{
+        remote::{Characteristic, CharacteristicWriteRequest},
+        transfer::crc32,
+        WriteOp,
+    },
+    Device, Error, ErrorKind, Result, TIMEOUT,
+};
+
+pub use crate::gatt::transfer::Progress;
+
+/// UUID of the Nordic Secure DFU service.
+pub const SECURE_DFU_SERVICE: Uuid = Uuid::from_u128(0x0000fe59_0000_1000_8000_00805f9b34fb);
+
+/// UUID of the Nordic Secure DFU control point characteristic.
+pub const SECURE_DFU_CONTROL_POINT: Uuid = Uuid::from_u128(0x8ec90001_f315_4f60_9fb8_838830daea50);
+
+/// UUID of the Nordic Secure DFU packet characteristic.
+pub const SECURE_DFU_PACKET: Uuid = Uuid::from_u128(0x8ec90002_f315_4f60_9fb8_838830daea50);
+
+const OP_CREATE: u8 = 0x01;
+const OP_SET_PRN: u8 = 0x02;
+const OP_CALCULATE_CHECKSUM: u8 = 0x03;
+const OP_EXECUTE: u8 = 0x04;
+const OP_SELECT: u8 = 0x06;
+const OP_RESPONSE: u8 = 0x60;
+
+const OBJ_COMMAND: u8 = 0x01;
+const OBJ_DATA: u8 = 0x02;
+
+const RES_SUCCESS: u8 = 0x01;
+
+/// Length of the ATT header of a write operation.
+const ATT_WRITE_HEADER: usize = 3;
+
+/// Firmware update error.
+#[derive(Debug, displaydoc::Display, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum DfuError {
+    /// invalid DFU opcode
+    InvalidOpcode,
+    /// DFU opcode not supported
+    OpcodeNotSupported,
+    /// invalid DFU parameter
+    InvalidParameter,
+    /// insufficient resources for DFU
+    InsufficientResources,
+    /// invalid DFU object
+    InvalidObject,
+    /// DFU object type not supported
+    UnsupportedType,
+    /// DFU operation not permitted
+    OperationNotPermitted,
+    /// DFU operation failed
+    OperationFailed,
+    /// extended DFU error {0:#04x}
+    Extended(u8),
+    /// unknown DFU result code {0:#04x}
+    Unknown(u8),
+    /// invalid response from DFU target
+    InvalidResponse,
+    /// no response from DFU target
+    NoResponse,
+    /// checksum of transferred data does not match
+    ChecksumMismatch,
+}
+
+impl DfuError {
+    fn from_result(result: u8, ext: Option<u8>) -> Self {
+        match result {
+            0x00 => Self::InvalidOpcode,
+            0x02 => Self::OpcodeNotSupported,
+            0x03 => Self::InvalidParameter,
+            0x04 => Self::InsufficientResources,
+            0x05 => Self::InvalidObject,
+            0x07 => Self::UnsupportedType,
+            0x08 => Self::OperationNotPermitted,
+            0x0a => Self::OperationFailed,
+            0x0b => Self::Extended(ext.unwrap_or_default()),
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+impl From<DfuError> for Error {
+    fn from(err: DfuError) -> Self {
+        Error::new(ErrorKind::DfuFailed(err))
+    }
+}
+
+/// State of a Secure DFU object as reported by the target.
+#[derive(Clone, Copy, Debug)]
+struct ObjectInfo {
+    max_size: usize,
+    offset: usize,
+    crc: u32,
+}
+
+/// Nordic Semiconductor Secure DFU client.
+///
+/// Transfers an init packet and a firmware image to a device running a
+/// Secure DFU bootloader.
+/// The init packet and firmware image are usually extracted from the `.dat` and `.bin`
+/// files contained in a DFU package created by `nrfutil`.
+///
+/// The device must be connected and in bootloader mode.
+/// If the transfer of the firmware image to the same device was interrupted, the transfer is
+/// resumed from the last position that was acknowledged by the target,
+/// provided that the init packet and firmware image are the same.
+#[derive(Debug, Clone)]
+pub struct SecureDfu {
+    control_point: Characteristic,
+    packet: Characteristic,
+    chunk_size: Option<usize>,
+    response_timeout: Duration,
+}
+
+impl SecureDfu {
+    /// Creates a Secure DFU client for the specified remote device.
+    ///
+    /// Fails with [ErrorKind::NotFound] if the device does not provide the
+    /// Secure DFU service.
+    pub async fn new(device: &Device) -> Result<Self> {
+        for service in device.services().await? {
+            if service.uuid().await? != SECURE_DFU_SERVICE {
+                continue;
+            }
+
+            let mut control_point = None;
+            let mut packet = None;
+            for characteristic in service.characteristics().await? {
+                match characteristic.uuid().await? {
+                    SECURE_DFU_CONTROL_POINT => control_point = Some(characteristic),
+                    SECURE_DFU_PACKET => packet = Some(characteristic),
+                    _ => (),
+                }
+            }
+
+            if let (Some(control_point), Some(packet)) = (control_point, packet) {
+                return Ok(Self::from_characteristics(control_point, packet));
+            }
+        }
+
+        Err(Error::new(ErrorKind::NotFound))
+    }
+
+    /// Creates a Secure DFU client using the specified control point and packet characteristics.
+    pub fn from_characteristics(control_point: Characteristic, packet: Characteristic) -> Self {
+        Self { control_point, packet, chunk_size: None, response_timeout: TIMEOUT }
+    }
+
+    /// Sets the number of bytes written to the packet characteristic in one operation.
+    ///
+    /// By default this is determined from the MTU of the packet characteristic.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size.max(1));
+        self
+    }
+
+    /// Sets the time to wait for the response of the target to a control point request.
+    ///
+    /// If no response is received in time, the update fails with [DfuError::NoResponse].
+    /// By default this is 120 seconds.
+    pub fn response_timeout(mut self, response_timeout: Duration) -> Self {
+        self.response_timeout = response_timeout;
+        self
+    }
+
+    /// Transfers the init packet and firmware image to the target.
+    ///
+    /// The `progress` function is called after each chunk of the firmware image
+    /// has been written.
+    /// The target activates the new firmware and disconnects once the transfer has
+    /// completed successfully.
+    pub async fn update(
+        &self, init_packet: &[u8], firmware: &[u8], mut progress: impl FnMut(Progress) + Send,
+    ) -> Result<()> {
+        let responses = self.control_point.notify().await?;
+        pin_mut!(responses);
+
+        let chunk_size = match self.chunk_size {
+            Some(chunk_size) => chunk_size,
+            None => self.packet.mtu().await?.saturating_sub(ATT_WRITE_HEADER).max(1),
+        };
+
+        // Disable packet receipt notifications, the checksum is verified after each object instead.
+        self.request(&mut responses, &[OP_SET_PRN, 0, 0]).await?;
+
+        let command = self.select(&mut responses, OBJ_COMMAND).await?;
+        let data = self.select(&mut responses, OBJ_DATA).await?;
+
+        // Resume only if the target holds our init packet and a prefix of our firmware image.
+        let resume = command.offset == init_packet.len()
+            && command.crc == crc32(0, init_packet)
+            && data.offset <= firmware.len()
+            && data.crc == crc32(0, &firmware[..data.offset]);
+
+        let mut offset = if resume {
+            log::trace!("Resuming Secure DFU at offset {}", data.offset);
+            data.offset
+        } else {
+            self.transfer_object(&mut responses, OBJ_COMMAND, init_packet, chunk_size).await?;
+            self.execute(&mut responses).await?;
+            0
+        };
+
+        let total = firmware.len();
+        progress(Progress { transferred: offset, total });
+
+        let max_size = data.max_size.max(1);
+        if offset % max_size != 0 {
+            // Complete the partially transferred object.
+            let end = (offset - offset % max_size + max_size).min(total);
+            self.write_chunks(&firmware[offset..end], chunk_size, |n| {
+                progress(Progress { transferred: offset + n, total })
+            })
+            .await?;
+            self.verify_checksum(&mut responses, firmware, end).await?;
+            self.execute(&mut responses).await?;
+            offset = end;
+        } else if offset > 0 {
+            // The last transferred object may not have been executed.
+            match self.execute(&mut responses).await {
+                Ok(()) | Err(Error { kind: ErrorKind::DfuFailed(DfuError::OperationNotPermitted), .. }) => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        while offset < total {
+            let end = (offset + max_size).min(total);
+            self.create(&mut responses, OBJ_DATA, end - offset).await?;
+            self.write_chunks(&firmware[offset..end], chunk_size, |n| {
+                progress(Progress { transferred: offset + n, total })
+            })
+            .await?;
+            self.verify_checksum(&mut responses, firmware, end).await?;
+            self.execute(&mut responses).await?;
+            offset = end;
+        }
+
+        Ok(())
+    }
+
+    /// Creates an object on the target and transfers the data into it.
+    async fn transfer_object(
+        &self, responses: &mut (impl Stream<Item = Vec<u8>> + Unpin), obj_type: u8, data: &[u8],
+        chunk_size: usize,
+    ) -> Result<()> {
+        self.create(responses, obj_type, data.len()).await?;
+        self.write_chunks(data, chunk_size, |_| ()).await?;
+        self.verify_checksum(responses, data, data.len()).await
+    }
+
+    async fn select(
+        &self, responses: &mut (impl Stream<Item = Vec<u8>> + Unpin), obj_type: u8,
+    ) -> Result<ObjectInfo> {
+        let resp = self.request(responses, &[OP_SELECT, obj_type]).await?;
+        Ok(ObjectInfo {
+            max_size: read_u32(&resp, 0)? as usize,
+            offset: read_u32(&resp, 4)? as usize,
+            crc: read_u32(&resp, 8)?,
+        })
+    }
+
+    async fn create(
+        &self, responses: &mut (impl Stream<Item = Vec<u8>> + Unpin), obj_type: u8, size: usize,
+    ) -> Result<()> {
+        let mut req = vec![OP_CREATE, obj_type];
+        req.extend_from_slice(&(size as u32).to_le_bytes());
+        self.request(responses, &req).await?;
+        Ok(())
+    }
+
+    async fn execute(&self, responses: &mut (impl Stream<Item = Vec<u8>> + Unpin)) -> Result<()> {
+        self.request(responses, &[OP_EXECUTE]).await?;
+        Ok(())
+    }
+
+    /// Verifies that the target has received the first `len` bytes of `data` intact.
+    async fn verify_checksum(
+        &self, responses: &mut (impl Stream<Item = Vec<u8>> + Unpin), data: &[u8], len: usize,
+    ) -> Result<()> {
+        let resp = self.request(responses, &[OP_CALCULATE_CHECKSUM]).await?;
+        let offset = read_u32(&resp, 0)? as usize;
+        let crc = read_u32(&resp, 4)?;
+        if offset != len || crc != crc32(0, &data[..len]) {
+            return Err(DfuError::ChecksumMismatch.into());
+        }
+        Ok(())
+    }
+
+    async fn write_chunks(&self, data: &[u8], chunk_size: usize, mut written: impl FnMut(usize)) -> Result<()> {
+        let req = CharacteristicWriteRequest { op_type: WriteOp::Command, ..Default::default() };
+        let mut n = 0;
+        for chunk in data.chunks(chunk_size) {
+            self.packet.write_ext(chunk, &req).await?;
+            n += chunk.len();
+            written(n);
+        }
+        Ok(())
+    }
+
+    /// Writes a request to the control point and returns the data of the response.
+    async fn request(
+        &self, responses: &mut (impl Stream<Item = Vec<u8>> + Unpin), req: &[u8],
+    ) -> Result<Vec<u8>> {
+        let write_req = CharacteristicWriteRequest { op_type: WriteOp::Request, ..Default::default() };
+        self.control_point.write_ext(req, &write_req).await?;
+
+        let resp = match tokio::time::timeout(self.response_timeout, responses.next()).await {
+            Ok(Some(resp)) => resp,
+            Ok(None) => return Err(Error::new(ErrorKind::NotificationSessionStopped)),
+            Err(_) => return Err(DfuError::NoResponse.into()),
+        };
+
+        match resp.as_slice() {
+            [OP_RESPONSE, op, RES_SUCCESS, data @ ..] if *op == req[0] => Ok(data.to_vec()),
+            [OP_RESPONSE, op, result, data @ ..] if *op == req[0] => {
+                Err(DfuError::from_result(*result, data.first().copied()).into())
+            }
+            _ => Err(DfuError::InvalidResponse.into()),
+        }
+    }
+}
+
+/// Generic chunked firmware transfer.
+///
+/// Writes a firmware image in consecutive chunks to a single characteristic.
+/// By default each chunk is written using a [reliable write](WriteOp::Reliable),
+/// so that the remote device acknowledges and verifies every chunk before
+/// the next one is sent.
+///
+/// Any commands necessary to prepare the device for the transfer or to activate
+/// the new firmware afterwards must be sent by the caller.
+#[derive(Debug, Clone)]
+pub struct ChunkedWrite {
+    characteristic: Characteristic,
+    chunk_size: Option<usize>,
+    op_type: WriteOp,
+}
+
+impl ChunkedWrite {
+    /// Creates a chunked transfer to the specified characteristic.
+    pub fn new(characteristic: Characteristic) -> Self {
+        Self { characteristic, chunk_size: None, op_type: WriteOp::Reliable }
+    }
+
+    /// Sets the number of bytes written in one operation.
+    ///
+    /// By default this is determined from the MTU of the characteristic.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size.max(1));
+        self
+    }
+
+    /// Sets the write operation type used for each chunk.
+    pub fn op_type(mut self, op_type: WriteOp) -> Self {
+        self.op_type = op_type;
+        self
+    }
+
+    /// Writes the firmware image starting at the specified offset.
+    ///
+    /// To resume an interrupted transfer pass the number of bytes
+    /// [transferred](Progress::transferred) as reported by the last call to `progress`.
+    /// The `progress` function is called after each chunk has been written.
+    pub async fn upload(
+        &self, firmware: &[u8], offset: usize, mut progress: impl FnMut(Progress) + Send,
+    ) -> Result<()> {
+        let total = firmware.len();
+        if offset > total {
+            return Err(Error::new(ErrorKind::InvalidOffset));
+        }
+
+        let chunk_size = match self.chunk_size {
+            Some(chunk_size) => chunk_size,
+            None => self.characteristic.mtu().await?.saturating_sub(ATT_WRITE_HEADER).max(1),
+        };
+
+        let req = CharacteristicWriteRequest { op_type: self.op_type, ..Default::default() };
+        let mut transferred = offset;
+        progress(Progress { transferred, total });
+        for chunk in firmware[offset..].chunks(chunk_size) {
+            self.characteristic.write_ext(chunk, &req).await?;
+            transferred += chunk.len();
+            progress(Progress { transferred, total });
+        }
+
+        Ok(())
+    }
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Result<u32> {
+    match data.get(pos..pos + 4) {
+        Some(bytes) => Ok(u32::from_le_bytes(bytes.try_into().unwrap())),
+        None => Err(DfuError::InvalidResponse.into()),
+    }
+}
@@ -0,0 +1,164 @@
+//! Distance estimation from signal strength.
+
+use strum::{Display, EnumString};
+
+use crate::Address;
+
+/// Computes the path loss in dB from the received signal strength and
+/// the advertised transmit power level, both in dBm.
+pub fn path_loss(rssi: i16, tx_power: i16) -> i16 {
+    tx_power.saturating_sub(rssi)
+}
+
+/// Estimated distance band of a device.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Proximity {
+    /// Less than half a meter away.
+    #[strum(serialize = "immediate")]
+    Immediate,
+    /// Less than four meters away.
+    #[strum(serialize = "near")]
+    Near,
+    /// Four meters or more away.
+    #[strum(serialize = "far")]
+    Far,
+}
+
+/// Estimates the distance of a device from its received signal strength and
+/// advertised transmit power level using the log-distance path loss model.
+///
+/// The estimate is coarse, since signal strength is affected by obstacles,
+/// reflections and antenna orientation.
+/// Averaging the signal strength over several advertisements improves the estimate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DistanceEstimator {
+    /// Environmental factor (path loss exponent).
+    ///
+    /// This is 2.0 in free space and typically between 2.0 and 4.0 indoors.
+    pub environmental_factor: f64,
+    /// Path loss in dB at a distance of one meter.
+    ///
+    /// This is approximately 41 dB for 2.4 GHz in free space.
+    pub reference_path_loss: f64,
+    #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _non_exhaustive: (),
+}
+
+impl Default for DistanceEstimator {
+    fn default() -> Self {
+        Self { environmental_factor: 2.0, reference_path_loss: 41.0, _non_exhaustive: () }
+    }
+}
+
+impl DistanceEstimator {
+    /// Estimates the distance in meters.
+    pub fn distance(&self, rssi: i16, tx_power: i16) -> f64 {
+        let excess_loss = f64::from(path_loss(rssi, tx_power)) - self.reference_path_loss;
+        10f64.powf(excess_loss / (10.0 * self.environmental_factor))
+    }
+
+    /// Estimates the distance band.
+    pub fn proximity(&self, rssi: i16, tx_power: i16) -> Proximity {
+        match self.distance(rssi, tx_power) {
+            d if d < 0.5 => Proximity::Immediate,
+            d if d < 4.0 => Proximity::Near,
+            _ => Proximity::Far,
+        }
+    }
+}
+
+/// Signal strength of a device along with the path loss and distance derived from it.
+///
+/// Obtained from [Adapter::discover_proximity](crate::Adapter::discover_proximity).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ProximityEvent {
+    /// Device address.
+    pub address: Address,
+    /// Received signal strength in dBm.
+    pub rssi: i16,
+    /// Advertised transmit power level in dBm, if known.
+    pub tx_power: Option<i16>,
+    /// Path loss in dB, if the transmit power level is known.
+    pub path_loss: Option<i16>,
+    /// Estimated distance in meters, if the transmit power level is known.
+    pub distance: Option<f64>,
+    /// Estimated distance band, if the transmit power level is known.
+    pub proximity: Option<Proximity>,
+}
+
+impl ProximityEvent {
+    /// Computes the path loss and distance estimates for a received signal strength.
+    pub fn new(address: Address, rssi: i16, tx_power: Option<i16>, estimator: &DistanceEstimator) -> Self {
+        Self {
+            address,
+            rssi,
+            tx_power,
+            path_loss: tx_power.map(|tx_power| path_loss(rssi, tx_power)),
+            distance: tx_power.map(|tx_power| estimator.distance(rssi, tx_power)),
+            proximity: tx_power.map(|tx_power| estimator.proximity(rssi, tx_power)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn path_loss_is_difference_of_tx_power_and_rssi() {
+        assert_eq!(path_loss(-60, 4), 64);
+        assert_eq!(path_loss(-41, 0), 41);
+        assert_eq!(path_loss(10, 0), -10);
+        assert_eq!(path_loss(i16::MAX, i16::MIN), i16::MIN);
+        assert_eq!(path_loss(i16::MIN, i16::MAX), i16::MAX);
+    }
+
+    #[test]
+    fn distance_follows_log_distance_model() {
+        let estimator = DistanceEstimator::default();
+        assert_close(estimator.distance(-41, 0), 1.0);
+        assert_close(estimator.distance(-61, 0), 10.0);
+        assert_close(estimator.distance(-21, 0), 0.1);
+        assert_close(estimator.distance(-57, 4), 10.0);
+
+        let indoor = DistanceEstimator { environmental_factor: 4.0, ..Default::default() };
+        assert_close(indoor.distance(-81, 0), 10.0);
+
+        let calibrated = DistanceEstimator { reference_path_loss: 59.0, ..Default::default() };
+        assert_close(calibrated.distance(-59, 0), 1.0);
+    }
+
+    #[test]
+    fn proximity_bands() {
+        let estimator = DistanceEstimator::default();
+        assert_eq!(estimator.proximity(-34, 0), Proximity::Immediate);
+        assert_eq!(estimator.proximity(-35, 0), Proximity::Near);
+        assert_eq!(estimator.proximity(-53, 0), Proximity::Near);
+        assert_eq!(estimator.proximity(-54, 0), Proximity::Far);
+        assert_eq!(estimator.proximity(-90, 0), Proximity::Far);
+    }
+
+    #[test]
+    fn proximity_event_requires_tx_power() {
+        let estimator = DistanceEstimator::default();
+        let address = Address::new([1, 2, 3, 4, 5, 6]);
+
+        let evt = ProximityEvent::new(address, -61, Some(0), &estimator);
+        assert_eq!(evt.path_loss, Some(61));
+        assert_close(evt.distance.unwrap(), 10.0);
+        assert_eq!(evt.proximity, Some(Proximity::Far));
+
+        let evt = ProximityEvent::new(address, -61, None, &estimator);
+        assert_eq!((evt.path_loss, evt.distance, evt.proximity), (None, None, None));
+    }
+}
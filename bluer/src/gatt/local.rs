@@ -1,39 +1,48 @@
 //! Publish local GATT services to remove devices.
 
 use dbus::{
-    arg::{OwnedFd, PropMap, Variant},
+    arg::{prop_cast, OwnedFd, PropMap, Variant},
     channel::Sender,
     message::SignalArgs,
     nonblock::{stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged, Proxy, SyncConnection},
     MethodErr, Path,
 };
 use dbus_crossroads::{Crossroads, IfaceBuilder, IfaceToken};
-use futures::{channel::oneshot, lock::Mutex, Future, FutureExt, Stream};
+use futures::{
+    channel::oneshot,
+    future::{self, Either},
+    lock::Mutex,
+    stream, Future, FutureExt, Stream, StreamExt,
+};
 use pin_project::pin_project;
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fmt,
     mem::take,
     num::NonZeroU16,
+    panic::AssertUnwindSafe,
     pin::Pin,
     sync::{Arc, Weak},
     task::Poll,
 };
 use strum::{Display, EnumString, IntoStaticStr};
-use tokio::sync::{mpsc, watch};
+use tokio::sync::{broadcast, mpsc, watch};
 use tokio_stream::wrappers::ReceiverStream;
 use uuid::Uuid;
 
 use super::{
-    make_socket_pair, mtu_workaround, CharacteristicFlags, CharacteristicReader, CharacteristicWriter,
-    DescriptorFlags, WriteOp, CHARACTERISTIC_INTERFACE, DESCRIPTOR_INTERFACE, SERVICE_INTERFACE,
+    duplicate_stream, make_socket_pair, mtu_workaround, CharacteristicFlags, CharacteristicReader,
+    CharacteristicWriter, DescriptorFlags, ServerCharacteristicConfiguration, UserDescription, WriteOp,
+    CHARACTERISTIC_INTERFACE, DESCRIPTOR_INTERFACE, SERVICE_INTERFACE,
 };
 use crate::{
-    method_call, parent_path, Adapter, Address, DbusResult, Device, Error, ErrorKind, Result, SessionInner,
-    ERR_PREFIX, SERVICE_NAME, TIMEOUT,
+    clone_options, method_call, parent_path, registration_state_stream, Adapter, Address, BtUuid, DbusResult,
+    Device, Error, ErrorKind, ObjectEvent, RegistrationKeeper, RegistrationState, Result, SessionInner,
+    ERR_PREFIX, OBJECT_MANAGER_INTERFACE, SERVICE_NAME, TIMEOUT,
 };
 
 pub(crate) const MANAGER_INTERFACE: &str = "org.bluez.GattManager1";
+pub(crate) const PROFILE_INTERFACE: &str = "org.bluez.GattProfile1";
 
 /// Link type.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Display, EnumString)]
@@ -90,6 +99,27 @@ impl From<ReqError> for dbus::MethodErr {
 /// Result of a Bluetooth request to us.
 pub type ReqResult<T> = std::result::Result<T, ReqError>;
 
+/// Runs a user-provided callback, converting a panic into [ReqError::Failed].
+///
+/// This keeps a misbehaving callback from aborting the task handling the D-Bus
+/// method call, which would leave the Bluetooth daemon waiting for a reply.
+async fn catch_callback_panic<T>(
+    callback: &str, uuid: &Uuid, f: impl Future<Output = ReqResult<T>>,
+) -> ReqResult<T> {
+    match AssertUnwindSafe(f).catch_unwind().await {
+        Ok(result) => result,
+        Err(panic) => {
+            let msg = panic
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown panic payload");
+            log::error!("{} callback of GATT {} panicked: {}", callback, BtUuid(*uuid), msg);
+            Err(ReqError::Failed)
+        }
+    }
+}
+
 // ===========================================================================================
 // Service
 // ===========================================================================================
@@ -220,6 +250,12 @@ impl RegisteredService {
 // Definition
 // ----------
 
+/// Authorization function for characteristics and characteristic descriptors.
+///
+/// Return [ReqError::NotAuthorized] to reject the request.
+pub type AuthorizeFun =
+    Box<dyn Fn(AuthorizeRequest) -> Pin<Box<dyn Future<Output = ReqResult<()>> + Send>> + Send + Sync>;
+
 /// Characteristic read value function.
 pub type CharacteristicReadFun = Box<
     dyn (Fn(CharacteristicReadRequest) -> Pin<Box<dyn Future<Output = ReqResult<Vec<u8>>> + Send>>) + Send + Sync,
@@ -236,6 +272,15 @@ pub struct CharacteristicRead {
     pub encrypt_authenticated_read: bool,
     /// Require security.
     pub secure_read: bool,
+    /// Handle long reads automatically.
+    ///
+    /// If set, the read function is called with an offset of zero and
+    /// the requested offset is applied to the returned value.
+    /// When the value does not fit into a single response, it is cached for the
+    /// requesting device and subsequent reads with a non-zero offset are served
+    /// from the cached value, ensuring that the client receives a consistent value.
+    /// The cached value is discarded when the device disconnects.
+    pub cache_long_reads: bool,
     /// Function called for each read request returning value.
     #[debug(skip)]
     pub fun: CharacteristicReadFun,
@@ -250,6 +295,7 @@ impl Default for CharacteristicRead {
             encrypt_read: false,
             encrypt_authenticated_read: false,
             secure_read: false,
+            cache_long_reads: false,
             fun: Box::new(|_| async move { Err(ReqError::NotSupported) }.boxed()),
             _non_exhaustive: (),
         }
@@ -389,7 +435,7 @@ impl CharacteristicNotify {
 }
 
 /// Definition of local GATT characteristic exposed over Bluetooth.
-#[derive(Default, Debug)]
+#[derive(Default, custom_debug::Debug)]
 pub struct Characteristic {
     /// 128-bit characteristic UUID.
     pub uuid: Uuid,
@@ -399,11 +445,34 @@ pub struct Characteristic {
     pub handle: Option<NonZeroU16>,
     /// If set, permits broadcasts of the Characteristic Value using
     /// Server Characteristic Configuration Descriptor.
+    ///
+    /// The Server Characteristic Configuration descriptor is added automatically,
+    /// unless it is defined in [descriptors](Self::descriptors).
     pub broadcast: bool,
     /// If set a client can write to the Characteristic User Description Descriptor.
+    ///
+    /// The Characteristic Extended Properties descriptor is added automatically
+    /// by the Bluetooth daemon.
     pub writable_auxiliaries: bool,
+    /// Characteristic User Description.
+    ///
+    /// If set, a Characteristic User Description descriptor containing this text
+    /// is added to the characteristic.
+    /// If [writable_auxiliaries](Self::writable_auxiliaries) is set, clients can
+    /// change the description.
+    ///
+    /// Registration fails with an InvalidArguments error if [descriptors](Self::descriptors)
+    /// also contains a Characteristic User Description descriptor.
+    pub user_description: Option<String>,
     /// Authorize flag.
     pub authorize: bool,
+    /// Function called to authorize each read and write request.
+    ///
+    /// If set, the [authorize](Self::authorize) flag is set automatically and
+    /// prepare authorization requests of reliable writes are answered by this function
+    /// without calling the write function.
+    #[debug(skip)]
+    pub authorize_fun: Option<AuthorizeFun>,
     /// Characteristic descriptors.
     pub descriptors: Vec<Descriptor>,
     /// Read value of characteristic.
@@ -412,6 +481,15 @@ pub struct Characteristic {
     pub write: Option<CharacteristicWrite>,
     /// Notify client of characteristic value change.
     pub notify: Option<CharacteristicNotify>,
+    /// Value of characteristic managed by this library.
+    ///
+    /// If set, read requests are answered from the stored value instead of
+    /// calling [CharacteristicRead::fun] and notification sessions are handled
+    /// by the [CharacteristicValue] instead of calling the function of
+    /// [CharacteristicNotifyMethod::Fun].
+    /// The [read](Self::read) and [notify](Self::notify) fields must still be set
+    /// to enable the corresponding operations.
+    pub value: Option<CharacteristicValue>,
     /// Control handle for characteristic once it has been registered.
     pub control_handle: CharacteristicControlHandle,
     #[doc(hidden)]
@@ -419,10 +497,77 @@ pub struct Characteristic {
 }
 
 impl Characteristic {
+    /// Flags of the characteristic as published to the Bluetooth daemon.
+    pub fn flags(&self) -> CharacteristicFlags {
+        let mut flags = CharacteristicFlags::default();
+        self.set_characteristic_flags(&mut flags);
+        if let Some(read) = &self.read {
+            read.set_characteristic_flags(&mut flags);
+        }
+        if let Some(write) = &self.write {
+            write.set_characteristic_flags(&mut flags);
+        }
+        if let Some(notify) = &self.notify {
+            notify.set_characteristic_flags(&mut flags);
+        }
+        flags
+    }
+
+    /// Checks that the generated descriptors do not conflict with the specified descriptors.
+    fn check_generated_descriptors(&self) -> crate::Result<()> {
+        if self.user_description.is_some()
+            && self.descriptors.iter().any(|desc| desc.uuid == UserDescription::UUID)
+        {
+            return Err(Error {
+                kind: ErrorKind::InvalidArguments,
+                message: format!(
+                    "characteristic {} specifies both user_description and a user description descriptor",
+                    self.uuid
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Standard descriptors generated from the fields of the characteristic.
+    fn generated_descriptors(&self) -> Vec<Descriptor> {
+        let mut descs = Vec::new();
+
+        if let Some(user_description) = &self.user_description {
+            let value = UserDescription(user_description.clone()).to_bytes();
+            descs.push(match self.writable_auxiliaries {
+                true => Descriptor::with_stored_value(UserDescription::UUID, value),
+                false => Descriptor::with_value(UserDescription::UUID, value),
+            });
+        }
+
+        let sccd = ServerCharacteristicConfiguration::UUID;
+        if self.broadcast && !self.descriptors.iter().any(|desc| desc.uuid == sccd) {
+            let value = ServerCharacteristicConfiguration::default().to_bytes().to_vec();
+            descs.push(Descriptor::with_stored_value(sccd, value));
+        }
+
+        descs
+    }
+
     fn set_characteristic_flags(&self, f: &mut CharacteristicFlags) {
         f.broadcast = self.broadcast;
         f.writable_auxiliaries = self.writable_auxiliaries;
-        f.authorize = self.authorize;
+        f.authorize = self.authorize || self.authorize_fun.is_some();
+    }
+
+    async fn authorize(
+        &self, op: AuthorizeOp, adapter_name: &str, device_address: Address, link: Option<LinkType>,
+    ) -> ReqResult<()> {
+        match &self.authorize_fun {
+            Some(fun) => {
+                catch_callback_panic("authorize", &self.uuid, async {
+                    fun(AuthorizeRequest::new(op, adapter_name, device_address, link)).await
+                })
+                .await
+            }
+            None => Ok(()),
+        }
     }
 }
 
@@ -440,6 +585,37 @@ fn parse_device(dict: &PropMap) -> DbusResult<(String, Address)> {
     Ok((adapter.to_string(), addr))
 }
 
+/// Operation requiring authorization.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum AuthorizeOp {
+    /// Read the value.
+    Read,
+    /// Write the value.
+    Write,
+}
+
+/// Authorization request for a characteristic or characteristic descriptor.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct AuthorizeRequest {
+    /// Operation to authorize.
+    pub op: AuthorizeOp,
+    /// Name of adapter making this request.
+    pub adapter_name: String,
+    /// Address of device making this request.
+    pub device_address: Address,
+    /// Link type.
+    pub link: Option<LinkType>,
+}
+
+impl AuthorizeRequest {
+    fn new(op: AuthorizeOp, adapter_name: &str, device_address: Address, link: Option<LinkType>) -> Self {
+        Self { op, adapter_name: adapter_name.to_string(), device_address, link }
+    }
+}
+
 /// Read value request.
 #[derive(Debug, Clone)]
 #[non_exhaustive]
@@ -581,6 +757,93 @@ impl CharacteristicNotifier {
     }
 }
 
+// -------------
+// Managed value
+// -------------
+
+/// Value of a local characteristic that is stored by this library.
+///
+/// Store a clone in [Characteristic::value] to have read requests answered and
+/// notification sessions handled automatically.
+/// Subscribed clients are notified each time the value is changed
+/// using [set_value](Self::set_value).
+#[derive(Clone)]
+pub struct CharacteristicValue {
+    value: Arc<Mutex<Vec<u8>>>,
+    /// Notifier of the current notification session.
+    ///
+    /// Held while a notification is sent, so that concurrent value changes are
+    /// notified one after another in order.
+    notifier: Arc<Mutex<Option<CharacteristicNotifier>>>,
+}
+
+impl fmt::Debug for CharacteristicValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CharacteristicValue")
+    }
+}
+
+impl Default for CharacteristicValue {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl CharacteristicValue {
+    /// Creates a managed characteristic value with the specified initial value.
+    pub fn new(value: Vec<u8>) -> Self {
+        Self { value: Arc::new(Mutex::new(value)), notifier: Arc::new(Mutex::new(None)) }
+    }
+
+    /// Gets the current value.
+    pub async fn value(&self) -> Vec<u8> {
+        self.value.lock().await.clone()
+    }
+
+    /// True, if a client has subscribed to notifications or indications of the value.
+    pub async fn is_notifying(&self) -> bool {
+        match self.notifier.try_lock() {
+            Some(notifier) => notifier.as_ref().is_some_and(|notifier| !notifier.is_stopped()),
+            // A notification is being sent.
+            None => true,
+        }
+    }
+
+    /// Sets the value and notifies subscribed clients.
+    ///
+    /// If the notification session uses indications, this waits until the
+    /// indication has been confirmed.
+    /// Concurrent calls are serialized, so that each value is notified in order.
+    /// Read requests are answered with the new value while waiting for a confirmation.
+    /// Succeeds without sending a notification when no client is subscribed.
+    pub async fn set_value(&self, value: Vec<u8>) -> Result<()> {
+        let mut notifier = self.notifier.lock().await;
+        *self.value.lock().await = value.clone();
+        let Some(active) = notifier.as_mut().filter(|notifier| !notifier.is_stopped()) else {
+            return Ok(());
+        };
+        match active.notify(value).await {
+            Err(err) if err.kind == ErrorKind::NotificationSessionStopped => {
+                *notifier = None;
+                Ok(())
+            }
+            res => res,
+        }
+    }
+
+    async fn read(&self, offset: u16) -> ReqResult<Vec<u8>> {
+        let value = self.value.lock().await;
+        match value.get(usize::from(offset)..) {
+            Some(value) => Ok(value.to_vec()),
+            None => Err(ReqError::InvalidOffset),
+        }
+    }
+
+    async fn start_notify(&self, notifier: CharacteristicNotifier) {
+        *self.notifier.lock().await = Some(notifier);
+    }
+}
+
 // ------------
 // IO interface
 // ------------
@@ -620,7 +883,13 @@ impl CharacteristicWriteIoRequest {
         let CharacteristicWriteIoRequest { adapter_name, device_address, mtu, tx, .. } = self;
         let (fd, stream) = make_socket_pair(false)?;
         let _ = tx.send(Ok(fd));
-        Ok(CharacteristicReader { adapter_name, device_address, mtu: mtu.into(), stream, buf: Vec::new() })
+        Ok(CharacteristicReader {
+            adapter_name,
+            device_address,
+            mtu: mtu.into(),
+            stream,
+            buf: Default::default(),
+        })
     }
 
     /// Reject the write request.
@@ -657,6 +926,34 @@ impl fmt::Debug for CharacteristicControlEvent {
     }
 }
 
+/// Number of buffered notification subscription events per stream.
+const NOTIFY_SUBSCRIPTION_EVENTS_CAPACITY: usize = 16;
+
+/// A client that has subscribed to notifications or indications of a local characteristic.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct NotifySubscriber {
+    /// Name of adapter of the subscribed device.
+    ///
+    /// BlueZ only provides this when using [CharacteristicNotifyMethod::Io].
+    pub adapter_name: Option<String>,
+    /// Address of the subscribed device.
+    ///
+    /// BlueZ only provides this when using [CharacteristicNotifyMethod::Io].
+    pub device_address: Option<Address>,
+    /// True, if indications are used and each value is confirmed by the client.
+    pub confirming: bool,
+}
+
+/// A change of the notification subscriptions of a local characteristic.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NotifySubscriptionEvent {
+    /// A client has subscribed to notifications or indications.
+    Subscribed(NotifySubscriber),
+    /// A client has unsubscribed from notifications or indications.
+    Unsubscribed(NotifySubscriber),
+}
+
 /// An object to control a characteristic and receive events once it has been registered.
 ///
 /// Use [characteristic_control] to obtain controller and associated handle.
@@ -665,6 +962,8 @@ pub struct CharacteristicControl {
     handle_rx: watch::Receiver<Option<NonZeroU16>>,
     #[pin]
     events_rx: ReceiverStream<CharacteristicControlEvent>,
+    subscribers_rx: watch::Receiver<Vec<NotifySubscriber>>,
+    subscription_events_rx: broadcast::Receiver<NotifySubscriptionEvent>,
 }
 
 impl fmt::Debug for CharacteristicControl {
@@ -681,6 +980,40 @@ impl CharacteristicControl {
             None => Err(Error::new(ErrorKind::NotRegistered)),
         }
     }
+
+    /// Clients that are currently subscribed to notifications or indications.
+    ///
+    /// When using [CharacteristicNotifyMethod::Fun] BlueZ starts a single notification
+    /// session for all clients, which is thus reported as one subscriber without
+    /// device information.
+    pub fn notify_subscribers(&self) -> Vec<NotifySubscriber> {
+        self.subscribers_rx.borrow().clone()
+    }
+
+    /// True, if at least one client is subscribed to notifications or indications.
+    ///
+    /// Use this to stop producing values when nobody is listening.
+    pub fn is_notifying(&self) -> bool {
+        !self.subscribers_rx.borrow().is_empty()
+    }
+
+    /// Stream of notification subscription changes.
+    ///
+    /// Only changes that occur after calling this function are delivered.
+    /// The stream ends when the characteristic has been unregistered.
+    pub fn notify_subscription_events(&self) -> impl Stream<Item = NotifySubscriptionEvent> {
+        stream::unfold(self.subscription_events_rx.resubscribe(), |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(evt) => return Some((evt, rx)),
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        log::warn!("Notification subscription stream lagged behind and missed {} events", n)
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
 }
 
 impl Stream for CharacteristicControl {
@@ -698,11 +1031,57 @@ impl Stream for CharacteristicControl {
 pub struct CharacteristicControlHandle {
     handle_tx: watch::Sender<Option<NonZeroU16>>,
     events_tx: mpsc::Sender<CharacteristicControlEvent>,
+    subscribers_tx: watch::Sender<Vec<NotifySubscriber>>,
+    subscription_events_tx: broadcast::Sender<NotifySubscriptionEvent>,
 }
 
 impl Default for CharacteristicControlHandle {
     fn default() -> Self {
-        Self { handle_tx: watch::channel(None).0, events_tx: mpsc::channel(1).0 }
+        Self {
+            handle_tx: watch::channel(None).0,
+            events_tx: mpsc::channel(1).0,
+            subscribers_tx: watch::channel(Vec::new()).0,
+            subscription_events_tx: broadcast::channel(1).0,
+        }
+    }
+}
+
+impl CharacteristicControlHandle {
+    fn add_subscriber(&self, subscriber: NotifySubscriber) {
+        self.subscribers_tx.send_modify(|subscribers| subscribers.push(subscriber.clone()));
+        let _ = self.subscription_events_tx.send(NotifySubscriptionEvent::Subscribed(subscriber));
+    }
+
+    /// Removes a single subscriber, keeping other equal subscribers.
+    fn remove_subscriber(&self, subscriber: &NotifySubscriber) {
+        let mut removed = false;
+        self.subscribers_tx.send_if_modified(|subscribers| {
+            if let Some(pos) = subscribers.iter().position(|s| s == subscriber) {
+                subscribers.remove(pos);
+                removed = true;
+            }
+            removed
+        });
+        if removed {
+            let _ = self.subscription_events_tx.send(NotifySubscriptionEvent::Unsubscribed(subscriber.clone()));
+        }
+    }
+
+    fn remove_subscribers(&self, f: impl Fn(&NotifySubscriber) -> bool) {
+        let mut removed = Vec::new();
+        self.subscribers_tx.send_modify(|subscribers| {
+            subscribers.retain(|subscriber| {
+                if f(subscriber) {
+                    removed.push(subscriber.clone());
+                    false
+                } else {
+                    true
+                }
+            })
+        });
+        for subscriber in removed {
+            let _ = self.subscription_events_tx.send(NotifySubscriptionEvent::Unsubscribed(subscriber));
+        }
     }
 }
 
@@ -718,9 +1097,17 @@ impl fmt::Debug for CharacteristicControlHandle {
 pub fn characteristic_control() -> (CharacteristicControl, CharacteristicControlHandle) {
     let (handle_tx, handle_rx) = watch::channel(None);
     let (events_tx, events_rx) = mpsc::channel(1);
+    let (subscribers_tx, subscribers_rx) = watch::channel(Vec::new());
+    let (subscription_events_tx, subscription_events_rx) =
+        broadcast::channel(NOTIFY_SUBSCRIPTION_EVENTS_CAPACITY);
     (
-        CharacteristicControl { handle_rx, events_rx: ReceiverStream::new(events_rx) },
-        CharacteristicControlHandle { handle_tx, events_tx },
+        CharacteristicControl {
+            handle_rx,
+            events_rx: ReceiverStream::new(events_rx),
+            subscribers_rx,
+            subscription_events_rx,
+        },
+        CharacteristicControlHandle { handle_tx, events_tx, subscribers_tx, subscription_events_tx },
     )
 }
 
@@ -764,15 +1151,87 @@ struct CharacteristicNotifyState {
 pub(crate) struct RegisteredCharacteristic {
     c: Characteristic,
     notify: Mutex<Option<CharacteristicNotifyState>>,
+    read_cache: Mutex<HashMap<(String, Address), Vec<u8>>>,
     connection: Weak<SyncConnection>,
+    inner: Weak<SessionInner>,
 }
 
 impl RegisteredCharacteristic {
-    fn new(c: Characteristic, connection: &Arc<SyncConnection>) -> Self {
+    fn new(c: Characteristic, inner: &Arc<SessionInner>) -> Self {
         if let Some(handle) = c.handle {
             let _ = c.control_handle.handle_tx.send(Some(handle));
         }
-        Self { c, notify: Mutex::new(None), connection: Arc::downgrade(connection) }
+        Self {
+            c,
+            notify: Mutex::new(None),
+            read_cache: Mutex::new(HashMap::new()),
+            connection: Arc::downgrade(&inner.connection),
+            inner: Arc::downgrade(inner),
+        }
+    }
+
+    /// Reads the value at the offset specified in the request.
+    async fn read_value(&self, req: CharacteristicReadRequest) -> ReqResult<Vec<u8>> {
+        let Some(read) = &self.c.read else { return Err(ReqError::NotSupported) };
+        self.c.authorize(AuthorizeOp::Read, &req.adapter_name, req.device_address, req.link).await?;
+
+        if !read.cache_long_reads {
+            return match &self.c.value {
+                Some(value) => value.read(req.offset).await,
+                None => catch_callback_panic("read", &self.c.uuid, async { (read.fun)(req).await }).await,
+            };
+        }
+
+        // Serve reads with non-zero offset from the value obtained by the
+        // initial read, so that the client receives a consistent value.
+        let key = (req.adapter_name.clone(), req.device_address);
+        let offset = usize::from(req.offset);
+        let max_len = usize::from(req.mtu).saturating_sub(1).max(1);
+        let cached = match offset {
+            0 => None,
+            _ => self.read_cache.lock().await.remove(&key),
+        };
+        let value = match cached {
+            Some(value) => value,
+            None => match &self.c.value {
+                Some(value) => value.value().await,
+                None => {
+                    catch_callback_panic("read", &self.c.uuid, async {
+                        (read.fun)(CharacteristicReadRequest { offset: 0, ..req }).await
+                    })
+                    .await?
+                }
+            },
+        };
+
+        let part = value.get(offset..).ok_or(ReqError::InvalidOffset)?.to_vec();
+        if part.len() >= max_len {
+            self.read_cache.lock().await.insert(key, value);
+        }
+        Ok(part)
+    }
+
+    /// Discards the cached values of long reads of devices when they disconnect.
+    ///
+    /// Ends when all characteristics have been unpublished.
+    async fn clear_read_caches(mut events: impl Stream<Item = ObjectEvent> + Unpin, regs: Vec<Weak<Self>>) {
+        while let Some(evt) = events.next().await {
+            let ObjectEvent::PropertiesChanged { object, interface, changed } = evt else { continue };
+            if interface != crate::device::INTERFACE || prop_cast::<bool>(&changed, "Connected") != Some(&false) {
+                continue;
+            }
+            let Some((adapter_name, address)) = Device::parse_dbus_path(&object) else { continue };
+            let key = (adapter_name.to_string(), address);
+            let regs: Vec<_> = regs.iter().filter_map(Weak::upgrade).collect();
+            if regs.is_empty() {
+                break;
+            }
+            for reg in regs {
+                if reg.read_cache.lock().await.remove(&key).is_some() {
+                    log::trace!("Discarded cached long read value for disconnected device {}", address);
+                }
+            }
+        }
     }
 
     pub(crate) fn register_interface(cr: &mut Crossroads) -> IfaceToken<Arc<Self>> {
@@ -781,18 +1240,7 @@ impl RegisteredCharacteristic {
                 Some(reg.c.uuid.to_string())
             });
             cr_property!(ib, "Flags", reg => {
-                let mut flags = CharacteristicFlags::default();
-                reg.c.set_characteristic_flags(&mut flags);
-                if let Some(read) = &reg.c.read {
-                    read.set_characteristic_flags(&mut flags);
-                }
-                if let Some(write) = &reg.c.write {
-                    write.set_characteristic_flags(&mut flags);
-                }
-                if let Some(notify) = &reg.c.notify {
-                    notify.set_characteristic_flags(&mut flags);
-                }
-                Some(flags.as_vec())
+                Some(reg.c.flags().as_vec())
             });
             ib.property("Service").get(|ctx, _| Ok(parent_path(ctx.path())));
             ib.property("Handle").get(|_ctx, reg| Ok(reg.c.handle.map(|h| h.get()).unwrap_or_default())).set(
@@ -820,13 +1268,8 @@ impl RegisteredCharacteristic {
             ib.method_with_cr_async("ReadValue", ("options",), ("value",), |ctx, cr, (options,): (PropMap,)| {
                 method_call(ctx, cr, |reg: Arc<Self>| async move {
                     let options = CharacteristicReadRequest::from_dict(&options)?;
-                    match &reg.c.read {
-                        Some(read) => {
-                            let value = (read.fun)(options).await?;
-                            Ok((value,))
-                        }
-                        None => Err(ReqError::NotSupported.into()),
-                    }
+                    let value = reg.read_value(options).await?;
+                    Ok((value,))
                 })
             });
             ib.method_with_cr_async(
@@ -838,7 +1281,19 @@ impl RegisteredCharacteristic {
                         let options = CharacteristicWriteRequest::from_dict(&options)?;
                         match &reg.c.write {
                             Some(CharacteristicWrite { method: CharacteristicWriteMethod::Fun(fun), .. }) => {
-                                fun(value, options).await?;
+                                reg.c
+                                    .authorize(
+                                        AuthorizeOp::Write,
+                                        &options.adapter_name,
+                                        options.device_address,
+                                        options.link,
+                                    )
+                                    .await?;
+                                if options.prepare_authorize && reg.c.authorize_fun.is_some() {
+                                    return Ok(());
+                                }
+                                catch_callback_panic("write", &reg.c.uuid, async { fun(value, options).await })
+                                    .await?;
                                 Ok(())
                             }
                             _ => Err(ReqError::NotSupported.into()),
@@ -870,13 +1325,29 @@ impl RegisteredCharacteristic {
                                     confirm_tx,
                                 });
                             }
+                            let control_handle = &reg.c.control_handle;
+                            control_handle.remove_subscribers(|s| s.device_address.is_none());
+                            control_handle.add_subscriber(NotifySubscriber {
+                                adapter_name: None,
+                                device_address: None,
+                                confirming: confirm_rx.is_some(),
+                            });
                             let notifier = CharacteristicNotifier {
                                 connection: reg.connection.clone(),
                                 path,
                                 stop_notify_tx,
                                 confirm_rx,
                             };
-                            notify_fn(notifier).await;
+                            match &reg.c.value {
+                                Some(value) => value.start_notify(notifier).await,
+                                None => {
+                                    catch_callback_panic("notify", &reg.c.uuid, async {
+                                        notify_fn(notifier).await;
+                                        Ok(())
+                                    })
+                                    .await?
+                                }
+                            }
                             Ok(())
                         }
                         _ => Err(ReqError::NotSupported.into()),
@@ -887,6 +1358,7 @@ impl RegisteredCharacteristic {
                 method_call(ctx, cr, |reg: Arc<Self>| async move {
                     let mut notify = reg.notify.lock().await;
                     *notify = None;
+                    reg.c.control_handle.remove_subscribers(|s| s.device_address.is_none());
                     Ok(())
                 })
             });
@@ -908,6 +1380,14 @@ impl RegisteredCharacteristic {
                         let options = CharacteristicAcquireRequest::from_dict(&options)?;
                         match &reg.c.write {
                             Some(CharacteristicWrite { method: CharacteristicWriteMethod::Io, .. }) => {
+                                reg.c
+                                    .authorize(
+                                        AuthorizeOp::Write,
+                                        &options.adapter_name,
+                                        options.device_address,
+                                        options.link,
+                                    )
+                                    .await?;
                                 let (tx, rx) = oneshot::channel();
                                 let req = CharacteristicWriteIoRequest {
                                     adapter_name: options.adapter_name.clone(),
@@ -942,13 +1422,38 @@ impl RegisteredCharacteristic {
                                 // BlueZ has already confirmed the start of the notification session.
                                 // So there is no point in making this fail-able by our users.
                                 let (fd, stream) = make_socket_pair(true).map_err(|_| ReqError::Failed)?;
+                                let hangup = duplicate_stream(&stream).map_err(|_| ReqError::Failed)?;
+                                let (drop_tx, drop_rx) = oneshot::channel();
                                 let mtu = mtu_workaround(options.mtu.into());
                                 let writer = CharacteristicWriter {
                                     adapter_name: options.adapter_name.clone(),
                                     device_address: options.device_address,
                                     mtu,
                                     stream,
+                                    _drop_tx: Some(drop_tx),
                                 };
+                                let subscriber = NotifySubscriber {
+                                    adapter_name: Some(options.adapter_name.clone()),
+                                    device_address: Some(options.device_address),
+                                    confirming: false,
+                                };
+                                reg.c.control_handle.add_subscriber(subscriber.clone());
+                                let weak_reg = Arc::downgrade(&reg);
+                                let inner = reg.inner.upgrade().ok_or(ReqError::Failed)?;
+                                inner.spawn(async move {
+                                    // The socket becomes readable when BlueZ closes its end
+                                    // because the client has unsubscribed.
+                                    // Dropping the writer ends the session as well and must
+                                    // close our duplicate of the socket.
+                                    tokio::select! {
+                                        _ = hangup.readable() => (),
+                                        _ = drop_rx => (),
+                                    }
+                                    drop(hangup);
+                                    if let Some(reg) = weak_reg.upgrade() {
+                                        reg.c.control_handle.remove_subscriber(&subscriber);
+                                    }
+                                });
                                 let _ = reg
                                     .c
                                     .control_handle
@@ -1064,7 +1569,7 @@ impl DescriptorWrite {
 }
 
 /// Definition of local GATT characteristic descriptor exposed over Bluetooth.
-#[derive(Default, Debug)]
+#[derive(Default, custom_debug::Debug)]
 pub struct Descriptor {
     /// 128-bit descriptor UUID.
     pub uuid: Uuid,
@@ -1074,6 +1579,13 @@ pub struct Descriptor {
     pub handle: Option<NonZeroU16>,
     /// Authorize flag.
     pub authorize: bool,
+    /// Function called to authorize each read and write request.
+    ///
+    /// If set, the [authorize](Self::authorize) flag is set automatically and
+    /// prepare authorization requests are answered by this function
+    /// without calling the write function.
+    #[debug(skip)]
+    pub authorize_fun: Option<AuthorizeFun>,
     /// Read value of characteristic descriptor.
     pub read: Option<DescriptorRead>,
     /// Write value of characteristic descriptor.
@@ -1085,8 +1597,88 @@ pub struct Descriptor {
 }
 
 impl Descriptor {
+    /// Creates a readable descriptor with the specified UUID and constant value.
+    pub fn with_value(uuid: Uuid, value: Vec<u8>) -> Self {
+        Self {
+            uuid,
+            read: Some(DescriptorRead {
+                read: true,
+                fun: Box::new(move |req| {
+                    let value = value.get(usize::from(req.offset)..).map(|value| value.to_vec());
+                    async move { value.ok_or(ReqError::InvalidOffset) }.boxed()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a readable and writable descriptor with the specified UUID and initial value.
+    ///
+    /// Written values are stored and returned by subsequent reads.
+    fn with_stored_value(uuid: Uuid, value: Vec<u8>) -> Self {
+        let value = Arc::new(std::sync::Mutex::new(value));
+        let write_value = value.clone();
+        Self {
+            uuid,
+            read: Some(DescriptorRead {
+                read: true,
+                fun: Box::new(move |req| {
+                    let value = value.lock().unwrap().get(usize::from(req.offset)..).map(|value| value.to_vec());
+                    async move { value.ok_or(ReqError::InvalidOffset) }.boxed()
+                }),
+                ..Default::default()
+            }),
+            write: Some(DescriptorWrite {
+                write: true,
+                fun: Box::new(move |new_value, req| {
+                    let mut value = write_value.lock().unwrap();
+                    let offset = usize::from(req.offset);
+                    let result = match offset <= value.len() {
+                        true => {
+                            value.truncate(offset);
+                            value.extend_from_slice(&new_value);
+                            Ok(())
+                        }
+                        false => Err(ReqError::InvalidOffset),
+                    };
+                    async move { result }.boxed()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Flags of the descriptor as published to the Bluetooth daemon.
+    pub fn flags(&self) -> DescriptorFlags {
+        let mut flags = DescriptorFlags::default();
+        self.set_descriptor_flags(&mut flags);
+        if let Some(read) = &self.read {
+            read.set_descriptor_flags(&mut flags);
+        }
+        if let Some(write) = &self.write {
+            write.set_descriptor_flags(&mut flags);
+        }
+        flags
+    }
+
     fn set_descriptor_flags(&self, f: &mut DescriptorFlags) {
-        f.authorize = self.authorize;
+        f.authorize = self.authorize || self.authorize_fun.is_some();
+    }
+
+    async fn authorize(
+        &self, op: AuthorizeOp, adapter_name: &str, device_address: Address, link: Option<LinkType>,
+    ) -> ReqResult<()> {
+        match &self.authorize_fun {
+            Some(fun) => {
+                catch_callback_panic("authorize", &self.uuid, async {
+                    fun(AuthorizeRequest::new(op, adapter_name, device_address, link)).await
+                })
+                .await
+            }
+            None => Ok(()),
+        }
     }
 }
 
@@ -1144,7 +1736,7 @@ impl DescriptorWriteRequest {
             device_address,
             offset: read_opt_prop!(dict, "offset", u16).unwrap_or_default(),
             link: read_opt_prop!(dict, "link", String).and_then(|v| v.parse().ok()),
-            prepare_authorize: read_prop!(dict, "prepare_authorize", bool),
+            prepare_authorize: read_opt_prop!(dict, "prepare-authorize", bool).unwrap_or_default(),
         })
     }
 }
@@ -1227,15 +1819,7 @@ impl RegisteredDescriptor {
                 Some(reg.d.uuid.to_string())
             });
             cr_property!(ib, "Flags", reg => {
-                let mut flags = DescriptorFlags::default();
-                reg.d.set_descriptor_flags(&mut flags);
-                if let Some(read) = &reg.d.read {
-                    read.set_descriptor_flags(&mut flags);
-                }
-                if let Some(write) = &reg.d.write {
-                    write.set_descriptor_flags(&mut flags);
-                }
-                Some(flags.as_vec())
+                Some(reg.d.flags().as_vec())
             });
             ib.property("Characteristic").get(|ctx, _| Ok(parent_path(ctx.path())));
             ib.property("Handle").get(|_ctx, reg| Ok(reg.d.handle.map(|h| h.get()).unwrap_or_default())).set(
@@ -1251,7 +1835,17 @@ impl RegisteredDescriptor {
                     let options = DescriptorReadRequest::from_dict(&flags)?;
                     match &reg.d.read {
                         Some(read) => {
-                            let value = (read.fun)(options).await?;
+                            reg.d
+                                .authorize(
+                                    AuthorizeOp::Read,
+                                    &options.adapter_name,
+                                    options.device_address,
+                                    options.link,
+                                )
+                                .await?;
+                            let value =
+                                catch_callback_panic("read", &reg.d.uuid, async { (read.fun)(options).await })
+                                    .await?;
                             Ok((value,))
                         }
                         None => Err(ReqError::NotSupported.into()),
@@ -1267,7 +1861,21 @@ impl RegisteredDescriptor {
                         let options = DescriptorWriteRequest::from_dict(&flags)?;
                         match &reg.d.write {
                             Some(write) => {
-                                (write.fun)(value, options).await?;
+                                reg.d
+                                    .authorize(
+                                        AuthorizeOp::Write,
+                                        &options.adapter_name,
+                                        options.device_address,
+                                        options.link,
+                                    )
+                                    .await?;
+                                if options.prepare_authorize && reg.d.authorize_fun.is_some() {
+                                    return Ok(());
+                                }
+                                catch_callback_panic("write", &reg.d.uuid, async {
+                                    (write.fun)(value, options).await
+                                })
+                                .await?;
                                 Ok(())
                             }
                             None => Err(ReqError::NotSupported.into()),
@@ -1288,21 +1896,152 @@ pub(crate) const GATT_APP_PREFIX: &str = publish_path!("gatt/app/");
 /// Definition of local GATT application to publish over Bluetooth.
 #[derive(Debug, Default)]
 pub struct Application {
+    /// Name identifying the application within the session.
+    ///
+    /// Characters other than ASCII letters, digits and underscores are replaced by underscores.
+    /// If an application with the same name is already registered, a numeric suffix is appended.
+    /// If [None], a random name is generated.
+    pub name: Option<String>,
     /// Services to publish.
     pub services: Vec<Service>,
+    /// Options passed to the Bluetooth daemon when registering the application.
+    ///
+    /// Current versions of the Bluetooth daemon define no options.
+    /// This allows using options introduced by future versions, which can be
+    /// detected using [Adapter::gatt_manager_properties].
+    pub options: PropMap,
     #[doc(hidden)]
     pub _non_exhaustive: (),
 }
 
+/// Object exported by an application, used to describe registration errors.
+struct ExportedObject {
+    path: dbus::Path<'static>,
+    kind: &'static str,
+    uuid: Uuid,
+    details: String,
+}
+
+impl fmt::Display for ExportedObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} ({})", self.kind, self.uuid, &self.details)
+    }
+}
+
 impl Application {
+    /// Allocates a unique application name and reserves it in the application registry.
+    fn allocate_name(
+        &self, inner: &SessionInner, adapter_name: &str,
+    ) -> (String, oneshot::Receiver<oneshot::Sender<()>>) {
+        let base = match &self.name {
+            Some(name) if !name.is_empty() => {
+                name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect()
+            }
+            _ => Uuid::new_v4().as_simple().to_string(),
+        };
+
+        let mut apps = inner.gatt_applications.lock().unwrap();
+        let name = (1..)
+            .map(|n| if n == 1 { base.clone() } else { format!("{base}_{n}") })
+            .find(|name| !apps.contains_key(name))
+            .unwrap();
+
+        let (unregister_tx, unregister_rx) = oneshot::channel();
+        let info = ApplicationInfo {
+            name: name.clone(),
+            adapter_name: adapter_name.to_string(),
+            services: self.services.iter().map(|service| service.uuid).collect(),
+        };
+        apps.insert(name.clone(), ApplicationEntry { info, unregister_tx });
+        (name, unregister_rx)
+    }
+
+    /// Adds the rejected part of the exported object tree to a registration error.
+    ///
+    /// If the Bluetooth daemon returned one of its errors and the error message refers to
+    /// an exported object, by path or UUID, only that object is named; otherwise the whole tree is listed.
+    fn registration_error(
+        err: dbus::Error, app_path: &dbus::Path<'static>, adapter_name: &str, exported: &[ExportedObject],
+    ) -> Error {
+        let name = err.name().unwrap_or_default().to_string();
+        let rejected = match name.starts_with(ERR_PREFIX) {
+            true => {
+                let message = err.message().unwrap_or_default();
+                exported.iter().rev().find(|obj| {
+                    message.contains(&*obj.path) || message.contains(&obj.uuid.as_hyphenated().to_string())
+                })
+            }
+            false => None,
+        };
+        let err = Error::from(err);
+        match rejected {
+            Some(obj) => err.with_context(format_args!(
+                "Bluetooth daemon rejected {} at {} of application {} on {} with {}",
+                obj, &obj.path, app_path, adapter_name, name
+            )),
+            None => err.with_context(format_args!(
+                "Bluetooth daemon rejected application {} on {} exporting [{}] with {}",
+                app_path,
+                adapter_name,
+                exported.iter().map(|obj| format!("{}: {}", &obj.path, obj)).collect::<Vec<_>>().join(", "),
+                name
+            )),
+        }
+    }
+
+    /// Checks that the flags of all characteristics and descriptors are valid.
+    ///
+    /// This is not performed automatically when the application is registered, since the
+    /// Bluetooth daemon may accept flag combinations rejected by this check.
+    /// An invalid combination results in an InvalidArguments error.
+    pub fn validate(&self) -> crate::Result<()> {
+        for service in &self.services {
+            for char in &service.characteristics {
+                char.flags()
+                    .validate()
+                    .map_err(|err| err.with_context(format_args!("characteristic {}", char.uuid)))?;
+                for desc in &char.descriptors {
+                    desc.flags()
+                        .validate()
+                        .map_err(|err| err.with_context(format_args!("descriptor {}", desc.uuid)))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) async fn register(
-        mut self, inner: Arc<SessionInner>, adapter_name: Arc<String>,
+        mut self, inner: Arc<SessionInner>, adapter_name: Arc<String>, resilient: bool,
     ) -> crate::Result<ApplicationHandle> {
+        // Fail before anything is reserved or exported.
+        let adapter_path = Adapter::make_dbus_path(&adapter_name)?;
+        for char in self.services.iter().flat_map(|s| &s.characteristics) {
+            char.check_generated_descriptors()?;
+        }
+        let caches_reads = self
+            .services
+            .iter()
+            .flat_map(|s| &s.characteristics)
+            .any(|c| c.read.as_ref().is_some_and(|r| r.cache_long_reads));
+        let cache_events = match caches_reads {
+            true => Some(inner.events(adapter_path.clone(), true).await?),
+            false => None,
+        };
+        let (keeper, state_rx) = match resilient {
+            true => {
+                let (keeper, state_rx) = RegistrationKeeper::new(&inner, adapter_name.clone(), false).await?;
+                (Some(keeper), state_rx)
+            }
+            false => (None, watch::channel(RegistrationState::Active).1),
+        };
         let mut reg_paths = Vec::new();
-        let app_path = format!("{}{}", GATT_APP_PREFIX, Uuid::new_v4().as_simple());
-        let app_path = dbus::Path::new(app_path).unwrap();
+        let mut reg_caches = Vec::new();
+        let mut exported = Vec::new();
+        let (app_name, unregister_rx) = self.allocate_name(&inner, &adapter_name);
+        let app_path = dbus::Path::new(format!("{}{}", GATT_APP_PREFIX, &app_name)).unwrap();
         log::trace!("Publishing application at {}", &app_path);
 
+        let options = take(&mut self.options);
         {
             let mut cr = inner.crossroads.lock().await;
 
@@ -1310,74 +2049,201 @@ impl Application {
             reg_paths.push(app_path.clone());
             let om = cr.object_manager::<Self>();
             cr.insert(app_path.clone(), &[om], self);
+            inner.record_export(&app_path, &[OBJECT_MANAGER_INTERFACE]);
 
             for (service_idx, mut service) in services.into_iter().enumerate() {
                 let chars = take(&mut service.characteristics);
 
-                let reg_service = RegisteredService::new(service);
                 let service_path = format!("{}/service{}", &app_path, service_idx);
                 let service_path = dbus::Path::new(service_path).unwrap();
+                exported.push(ExportedObject {
+                    path: service_path.clone(),
+                    kind: "service",
+                    uuid: service.uuid,
+                    details: if service.primary { "primary" } else { "secondary" }.to_string(),
+                });
+                #[cfg(all(feature = "tracing", feature = "gatt-server"))]
+                inner.gatt_uuids.lock().unwrap().insert(service_path.to_string(), service.uuid);
+                let reg_service = RegisteredService::new(service);
                 log::trace!("Publishing service at {}", &service_path);
                 reg_paths.push(service_path.clone());
                 cr.insert(service_path.clone(), &[inner.gatt_reg_service_token], Arc::new(reg_service));
+                inner.record_export(&service_path, &[SERVICE_INTERFACE]);
 
                 for (char_idx, mut char) in chars.into_iter().enumerate() {
-                    let descs = take(&mut char.descriptors);
+                    let generated = char.generated_descriptors();
+                    let mut descs = take(&mut char.descriptors);
+                    descs.extend(generated);
 
-                    let reg_char = RegisteredCharacteristic::new(char, &inner.connection);
                     let char_path = format!("{}/char{}", &service_path, char_idx);
                     let char_path = dbus::Path::new(char_path).unwrap();
+                    let ops = [
+                        char.read.as_ref().map(|_| "read"),
+                        char.write.as_ref().map(|_| "write"),
+                        char.notify.as_ref().map(|_| "notify"),
+                    ];
+                    exported.push(ExportedObject {
+                        path: char_path.clone(),
+                        kind: "characteristic",
+                        uuid: char.uuid,
+                        details: ops.into_iter().flatten().collect::<Vec<_>>().join("/"),
+                    });
+                    #[cfg(all(feature = "tracing", feature = "gatt-server"))]
+                    inner.gatt_uuids.lock().unwrap().insert(char_path.to_string(), char.uuid);
+                    let reg_char = Arc::new(RegisteredCharacteristic::new(char, &inner));
+                    if reg_char.c.read.as_ref().is_some_and(|read| read.cache_long_reads) {
+                        reg_caches.push(Arc::downgrade(&reg_char));
+                    }
                     log::trace!("Publishing characteristic at {}", &char_path);
                     reg_paths.push(char_path.clone());
-                    cr.insert(char_path.clone(), &[inner.gatt_reg_characteristic_token], Arc::new(reg_char));
+                    cr.insert(char_path.clone(), &[inner.gatt_reg_characteristic_token], reg_char);
+                    inner.record_export(&char_path, &[CHARACTERISTIC_INTERFACE]);
 
                     for (desc_idx, desc) in descs.into_iter().enumerate() {
-                        let reg_desc = RegisteredDescriptor::new(desc);
                         let desc_path = format!("{}/desc{}", &char_path, desc_idx);
                         let desc_path = dbus::Path::new(desc_path).unwrap();
+                        let ops = [desc.read.as_ref().map(|_| "read"), desc.write.as_ref().map(|_| "write")];
+                        exported.push(ExportedObject {
+                            path: desc_path.clone(),
+                            kind: "descriptor",
+                            uuid: desc.uuid,
+                            details: ops.into_iter().flatten().collect::<Vec<_>>().join("/"),
+                        });
+                        #[cfg(all(feature = "tracing", feature = "gatt-server"))]
+                        inner.gatt_uuids.lock().unwrap().insert(desc_path.to_string(), desc.uuid);
+                        let reg_desc = RegisteredDescriptor::new(desc);
                         log::trace!("Publishing descriptor at {}", &desc_path);
                         reg_paths.push(desc_path.clone());
                         cr.insert(
-                            desc_path,
+                            desc_path.clone(),
                             &[inner.gatt_reg_characteristic_descriptor_token],
                             Arc::new(reg_desc),
                         );
+                        inner.record_export(&desc_path, &[DESCRIPTOR_INTERFACE]);
                     }
                 }
             }
         }
 
         log::trace!("Registering application at {}", &app_path);
-        let proxy =
-            Proxy::new(SERVICE_NAME, Adapter::dbus_path(&adapter_name)?, TIMEOUT, inner.connection.clone());
-        proxy.method_call(MANAGER_INTERFACE, "RegisterApplication", (app_path.clone(), PropMap::new())).await?;
-
+        let proxy = Proxy::new(SERVICE_NAME, adapter_path, TIMEOUT, inner.connection.clone());
         let (drop_tx, drop_rx) = oneshot::channel();
         let app_path_unreg = app_path.clone();
-        tokio::spawn(async move {
-            let _ = drop_rx.await;
+        let app_name_unreg = app_name.clone();
+        let unreg = |inner: Arc<SessionInner>, reg_paths: Vec<dbus::Path<'static>>| async move {
+            let mut cr = inner.crossroads.lock().await;
+            for reg_path in reg_paths.into_iter().rev() {
+                log::trace!("Unpublishing {}", &reg_path);
+                let _: Option<Self> = cr.remove(&reg_path);
+                inner.record_unexport(&reg_path);
+            }
+        };
+
+        let reregister_options = clone_options(&options);
+        if let Err(err) = proxy
+            .method_call::<(), _, _, _>(MANAGER_INTERFACE, "RegisterApplication", (app_path.clone(), options))
+            .await
+        {
+            inner.gatt_applications.lock().unwrap().remove(&app_name);
+            unreg(inner, reg_paths).await;
+            return Err(Self::registration_error(err, &app_path, &adapter_name, &exported));
+        }
+
+        if let Some(events) = cache_events {
+            inner.spawn(RegisteredCharacteristic::clear_read_caches(events, reg_caches));
+        }
+
+        inner.clone().spawn(async move {
+            let done = future::select(drop_rx, unregister_rx);
+            let done = match keeper {
+                Some(keeper) => {
+                    keeper
+                        .run(
+                            done,
+                            MANAGER_INTERFACE,
+                            "RegisterApplication",
+                            app_path_unreg.clone(),
+                            &reregister_options,
+                        )
+                        .await
+                }
+                None => done.await,
+            };
+            let done_tx = match done {
+                Either::Left(_) => {
+                    inner.gatt_applications.lock().unwrap().remove(&app_name_unreg);
+                    None
+                }
+                Either::Right((done_tx, _)) => done_tx.ok(),
+            };
 
             log::trace!("Unregistering application at {}", &app_path_unreg);
             let _: std::result::Result<(), dbus::Error> =
                 proxy.method_call(MANAGER_INTERFACE, "UnregisterApplication", (app_path_unreg,)).await;
 
-            let mut cr = inner.crossroads.lock().await;
-            for reg_path in reg_paths.into_iter().rev() {
-                log::trace!("Unpublishing {}", &reg_path);
-                let _: Option<Self> = cr.remove(&reg_path);
+            unreg(inner, reg_paths).await;
+            if let Some(done_tx) = done_tx {
+                let _ = done_tx.send(());
             }
         });
 
-        Ok(ApplicationHandle { name: app_path, _drop_tx: drop_tx })
+        Ok(ApplicationHandle { name: app_path, app_name, _drop_tx: drop_tx, state_rx })
     }
 }
 
+/// Information about a registered local GATT application.
+///
+/// Use [Session::gatt_applications](crate::Session::gatt_applications) to enumerate registered applications.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ApplicationInfo {
+    /// Name of the application.
+    pub name: String,
+    /// Name of the adapter the application is registered on.
+    pub adapter_name: String,
+    /// UUIDs of the services of the application.
+    pub services: Vec<Uuid>,
+}
+
+/// Entry of the application registry of a session.
+pub(crate) struct ApplicationEntry {
+    pub info: ApplicationInfo,
+    pub unregister_tx: oneshot::Sender<oneshot::Sender<()>>,
+}
+
 /// Handle to local GATT application published over Bluetooth.
 ///
 /// Drop this handle to unpublish.
 pub struct ApplicationHandle {
     name: dbus::Path<'static>,
+    app_name: String,
     _drop_tx: oneshot::Sender<()>,
+    state_rx: watch::Receiver<RegistrationState>,
+}
+
+impl ApplicationHandle {
+    /// Name of the application.
+    ///
+    /// This is unique within the session.
+    pub fn name(&self) -> &str {
+        &self.app_name
+    }
+
+    /// Current registration state of the application.
+    ///
+    /// This is always [RegistrationState::Active], unless the application was registered
+    /// using [Adapter::serve_gatt_application_resilient].
+    pub fn registration_state(&self) -> RegistrationState {
+        *self.state_rx.borrow()
+    }
+
+    /// Streams changes of the registration state of the application.
+    ///
+    /// The stream ends immediately, unless the application was registered
+    /// using [Adapter::serve_gatt_application_resilient].
+    pub fn registration_events(&self) -> impl Stream<Item = RegistrationState> {
+        registration_state_stream(&self.state_rx)
+    }
 }
 
 impl Drop for ApplicationHandle {
@@ -1414,7 +2280,7 @@ pub struct Profile {
 
 impl Profile {
     pub(crate) fn register_interface(cr: &mut Crossroads) -> IfaceToken<Self> {
-        cr.register("org.bluez.GattProfile1", |ib: &mut IfaceBuilder<Self>| {
+        cr.register(PROFILE_INTERFACE, |ib: &mut IfaceBuilder<Self>| {
             cr_property!(ib, "UUIDs", p => {
                 Some(p.uuids.iter().map(|uuid| uuid.to_string()).collect::<Vec<_>>())
             });
@@ -1424,6 +2290,8 @@ impl Profile {
     pub(crate) async fn register(
         self, inner: Arc<SessionInner>, adapter_name: Arc<String>,
     ) -> crate::Result<ProfileHandle> {
+        let adapter_path = Adapter::make_dbus_path(&adapter_name)?;
+        let uuids = self.uuids.clone();
         let profile_path = format!("{}{}", GATT_PROFILE_PREFIX, Uuid::new_v4().as_simple());
         let profile_path = dbus::Path::new(profile_path).unwrap();
         log::trace!("Publishing profile at {}", &profile_path);
@@ -1433,17 +2301,18 @@ impl Profile {
             let om = cr.object_manager::<Self>();
             cr.insert(profile_path.clone(), &[inner.gatt_profile_token, om], self);
         }
+        inner.record_export(&profile_path, &[PROFILE_INTERFACE, OBJECT_MANAGER_INTERFACE]);
 
         log::trace!("Registering profile at {}", &profile_path);
-        let proxy =
-            Proxy::new(SERVICE_NAME, Adapter::dbus_path(&adapter_name)?, TIMEOUT, inner.connection.clone());
+        let proxy = Proxy::new(SERVICE_NAME, adapter_path, TIMEOUT, inner.connection.clone());
         proxy
             .method_call(MANAGER_INTERFACE, "RegisterApplication", (profile_path.clone(), PropMap::new()))
             .await?;
 
         let (drop_tx, drop_rx) = oneshot::channel();
         let profile_path_unreg = profile_path.clone();
-        tokio::spawn(async move {
+        let handle_inner = inner.clone();
+        inner.clone().spawn(async move {
             let _ = drop_rx.await;
 
             log::trace!("Unregistering profile at {}", &profile_path_unreg);
@@ -1454,21 +2323,67 @@ impl Profile {
             log::trace!("Unpublishing profile at {}", &profile_path_unreg);
             let mut cr = inner.crossroads.lock().await;
             let _: Option<Self> = cr.remove(&profile_path_unreg);
+            inner.record_unexport(&profile_path_unreg);
         });
 
-        Ok(ProfileHandle { name: profile_path, _drop_tx: drop_tx })
+        Ok(ProfileHandle { name: profile_path, inner: handle_inner, adapter_name, uuids, _drop_tx: drop_tx })
     }
 }
 
+/// Connection event of a device matching a registered local profile (GATT client).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ProfileEvent {
+    /// A device providing a service of the profile has connected.
+    Connected(Address),
+    /// A device providing a service of the profile has disconnected.
+    Disconnected(Address),
+}
+
 /// Handle to published local profile (GATT client) instance.
 ///
 /// Drop this handle to unpublish.
 #[must_use = "ProfileHandle must be held for profile to be published"]
 pub struct ProfileHandle {
     name: dbus::Path<'static>,
+    inner: Arc<SessionInner>,
+    adapter_name: Arc<String>,
+    uuids: HashSet<Uuid>,
     _drop_tx: oneshot::Sender<()>,
 }
 
+impl ProfileHandle {
+    /// Streams connection events of devices providing any of the services of the profile.
+    ///
+    /// The Bluetooth daemon does not notify the profile when it automatically connects
+    /// a device.
+    /// Thus the connection state of all devices of the adapter is tracked and an event is
+    /// delivered when a device whose service UUIDs intersect the UUIDs of the profile
+    /// connects or disconnects, regardless of whether the connection was established
+    /// automatically or by an application.
+    /// Devices that are already connected are reported when the stream is started.
+    ///
+    /// The stream ends when the adapter is removed.
+    pub async fn events(&self) -> Result<impl Stream<Item = ProfileEvent>> {
+        let adapter = Adapter::new(self.inner.clone(), &self.adapter_name)?;
+        let uuids = self.uuids.clone();
+        let changes = adapter
+            .connection_changes(move |device: Device| {
+                let uuids = uuids.clone();
+                async move {
+                    let device_uuids = device.uuids().await.ok().flatten().unwrap_or_default();
+                    !device_uuids.is_disjoint(&uuids)
+                }
+            })
+            .await?;
+        Ok(changes.map(|(address, is_connected)| match is_connected {
+            true => ProfileEvent::Connected(address),
+            false => ProfileEvent::Disconnected(address),
+        }))
+    }
+}
+
 impl Drop for ProfileHandle {
     fn drop(&mut self) {
         // required for drop order
@@ -1,12 +1,10 @@
 //! Local and remote GATT services.
 
-use dbus::arg::OwnedFd;
+use bytes::{Bytes, BytesMut};
 use futures::ready;
-use libc::{AF_LOCAL, SOCK_CLOEXEC, SOCK_NONBLOCK, SOCK_SEQPACKET};
 use pin_project::pin_project;
 use std::{
-    mem::MaybeUninit,
-    os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd},
+    os::unix::io::{AsRawFd, IntoRawFd, RawFd},
     pin::Pin,
     task::{Context, Poll},
 };
@@ -16,10 +14,25 @@ use tokio::{
     net::UnixStream,
 };
 
-use crate::Address;
+use crate::{uuid16, Address};
+use uuid::Uuid;
+
+#[cfg(feature = "gatt-server")]
+use dbus::arg::OwnedFd;
+#[cfg(feature = "gatt-server")]
+use libc::{AF_LOCAL, F_DUPFD_CLOEXEC, SOCK_CLOEXEC, SOCK_NONBLOCK, SOCK_SEQPACKET};
+#[cfg(feature = "gatt-server")]
+use std::os::unix::io::FromRawFd;
 
+#[cfg(feature = "gatt-server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gatt-server")))]
 pub mod local;
+#[cfg(feature = "gatt-client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gatt-client")))]
 pub mod remote;
+#[cfg(feature = "gatt-client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gatt-client")))]
+pub mod transfer;
 
 pub(crate) const SERVICE_INTERFACE: &str = "org.bluez.GattService1";
 pub(crate) const CHARACTERISTIC_INTERFACE: &str = "org.bluez.GattCharacteristic1";
@@ -63,7 +76,7 @@ define_flags!(pub CharacteristicFlags, "Bluetooth GATT characteristic flags." =>
     authorize ("authorize"),
 });
 
-define_flags!(DescriptorFlags, "Bluetooth GATT characteristic descriptor flags." => {
+define_flags!(pub DescriptorFlags, "Bluetooth GATT characteristic descriptor flags." => {
     /// If set allows clients to read this characteristic descriptor.
     read ("read"),
     /// If set allows clients to use the Write Command ATT operation.
@@ -84,6 +97,247 @@ define_flags!(DescriptorFlags, "Bluetooth GATT characteristic descriptor flags."
     authorize ("authorize"),
 });
 
+impl CharacteristicFlags {
+    /// Checks that the combination of flags is valid.
+    ///
+    /// At least one property must be set and security requirements
+    /// must only be set for operations that are permitted.
+    pub fn validate(&self) -> crate::Result<()> {
+        let writable = self.write || self.write_without_response || self.authenticated_signed_writes;
+        let error = match () {
+            () if !(self.broadcast || self.read || writable || self.notify || self.indicate) => {
+                "no properties are set"
+            }
+            () if (self.encrypt_read || self.encrypt_authenticated_read || self.secure_read) && !self.read => {
+                "read security requires read"
+            }
+            () if (self.encrypt_write || self.encrypt_authenticated_write || self.secure_write) && !writable => {
+                "write security requires write"
+            }
+            () if self.reliable_write && !self.write => "reliable-write requires write",
+            _ => return Ok(()),
+        };
+        Err(crate::Error {
+            kind: crate::ErrorKind::InvalidArguments,
+            message: format!("invalid characteristic flags {self}: {error}"),
+        })
+    }
+}
+
+impl DescriptorFlags {
+    /// Checks that the combination of flags is valid.
+    ///
+    /// Security requirements must only be set for operations that are permitted.
+    pub fn validate(&self) -> crate::Result<()> {
+        let error = match () {
+            () if (self.encrypt_read || self.encrypt_authenticated_read || self.secure_read) && !self.read => {
+                "read security requires read"
+            }
+            () if (self.encrypt_write || self.encrypt_authenticated_write || self.secure_write)
+                && !self.write =>
+            {
+                "write security requires write"
+            }
+            _ => return Ok(()),
+        };
+        Err(crate::Error {
+            kind: crate::ErrorKind::InvalidArguments,
+            message: format!("invalid descriptor flags {self}: {error}"),
+        })
+    }
+}
+
+/// Characteristic Presentation Format descriptor value.
+///
+/// Defines the format of the value of a characteristic.
+/// Use [to_descriptor](Self::to_descriptor) to add it to a local characteristic and
+/// [remote::Characteristic::presentation_format] to read it from a remote characteristic.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PresentationFormat {
+    /// Format of the value, as defined in the Bluetooth Assigned Numbers.
+    pub format: u8,
+    /// Base 10 exponent applied to integer values.
+    pub exponent: i8,
+    /// Unit of the value, as defined in the Bluetooth Assigned Numbers.
+    pub unit: u16,
+    /// Namespace of the description.
+    ///
+    /// Use [NAMESPACE_BLUETOOTH_SIG](Self::NAMESPACE_BLUETOOTH_SIG) for descriptions
+    /// defined in the Bluetooth Assigned Numbers.
+    pub namespace: u8,
+    /// Description of the value within the namespace.
+    pub description: u16,
+}
+
+impl PresentationFormat {
+    /// UUID of the Characteristic Presentation Format descriptor.
+    pub const UUID: Uuid = uuid16!(0x2904);
+
+    /// Namespace of the Bluetooth SIG.
+    pub const NAMESPACE_BLUETOOTH_SIG: u8 = 0x01;
+
+    /// Length of the encoded descriptor value.
+    pub const LEN: usize = 7;
+
+    /// Encodes the descriptor value.
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let [unit_lo, unit_hi] = self.unit.to_le_bytes();
+        let [desc_lo, desc_hi] = self.description.to_le_bytes();
+        [self.format, self.exponent as u8, unit_lo, unit_hi, self.namespace, desc_lo, desc_hi]
+    }
+
+    /// Decodes the descriptor value.
+    pub fn from_bytes(value: &[u8]) -> crate::Result<Self> {
+        let Ok(&[format, exponent, unit_lo, unit_hi, namespace, desc_lo, desc_hi]) =
+            <&[u8; Self::LEN]>::try_from(value)
+        else {
+            return Err(crate::Error {
+                kind: crate::ErrorKind::InvalidLength,
+                message: format!(
+                    "presentation format must be {} bytes long, but is {} bytes",
+                    Self::LEN,
+                    value.len()
+                ),
+            });
+        };
+        Ok(Self {
+            format,
+            exponent: exponent as i8,
+            unit: u16::from_le_bytes([unit_lo, unit_hi]),
+            namespace,
+            description: u16::from_le_bytes([desc_lo, desc_hi]),
+        })
+    }
+
+    /// Creates a readable local descriptor containing this presentation format.
+    #[cfg(feature = "gatt-server")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gatt-server")))]
+    pub fn to_descriptor(&self) -> local::Descriptor {
+        local::Descriptor::with_value(Self::UUID, self.to_bytes().to_vec())
+    }
+}
+
+/// Decodes a descriptor value consisting of a 16-bit bit field.
+fn decode_bit_field(value: &[u8], name: &str) -> crate::Result<u16> {
+    match <[u8; 2]>::try_from(value) {
+        Ok(bytes) => Ok(u16::from_le_bytes(bytes)),
+        Err(_) => Err(crate::Error {
+            kind: crate::ErrorKind::InvalidLength,
+            message: format!("{name} must be 2 bytes long, but is {} bytes", value.len()),
+        }),
+    }
+}
+
+/// Characteristic Extended Properties descriptor value.
+///
+/// Use [remote::Characteristic::extended_properties] to read it from a remote characteristic.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtendedProperties {
+    /// The Reliable Writes procedure may be used to write the characteristic value.
+    pub reliable_write: bool,
+    /// The Characteristic User Description descriptor is writable.
+    pub writable_auxiliaries: bool,
+}
+
+impl ExtendedProperties {
+    /// UUID of the Characteristic Extended Properties descriptor.
+    pub const UUID: Uuid = uuid16!(0x2900);
+
+    /// Encodes the descriptor value.
+    pub fn to_bytes(&self) -> [u8; 2] {
+        (u16::from(self.reliable_write) | u16::from(self.writable_auxiliaries) << 1).to_le_bytes()
+    }
+
+    /// Decodes the descriptor value.
+    pub fn from_bytes(value: &[u8]) -> crate::Result<Self> {
+        let bits = decode_bit_field(value, "extended properties")?;
+        Ok(Self { reliable_write: bits & 0x01 != 0, writable_auxiliaries: bits & 0x02 != 0 })
+    }
+}
+
+/// Client Characteristic Configuration descriptor value.
+///
+/// Use [remote::Characteristic::client_configuration] to read it from a remote characteristic.
+/// The Bluetooth daemon manages this descriptor of remote characteristics and does not permit
+/// writing it directly; use [remote::Characteristic::notify] to enable notifications or indications.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClientCharacteristicConfiguration {
+    /// Notifications are enabled.
+    pub notify: bool,
+    /// Indications are enabled.
+    pub indicate: bool,
+}
+
+impl ClientCharacteristicConfiguration {
+    /// UUID of the Client Characteristic Configuration descriptor.
+    pub const UUID: Uuid = uuid16!(0x2902);
+
+    /// Encodes the descriptor value.
+    pub fn to_bytes(&self) -> [u8; 2] {
+        (u16::from(self.notify) | u16::from(self.indicate) << 1).to_le_bytes()
+    }
+
+    /// Decodes the descriptor value.
+    pub fn from_bytes(value: &[u8]) -> crate::Result<Self> {
+        let bits = decode_bit_field(value, "client characteristic configuration")?;
+        Ok(Self { notify: bits & 0x01 != 0, indicate: bits & 0x02 != 0 })
+    }
+}
+
+/// Server Characteristic Configuration descriptor value.
+///
+/// Use [remote::Characteristic::server_configuration] to read it from a remote characteristic.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServerCharacteristicConfiguration {
+    /// The characteristic value is broadcast in advertisements.
+    pub broadcast: bool,
+}
+
+impl ServerCharacteristicConfiguration {
+    /// UUID of the Server Characteristic Configuration descriptor.
+    pub const UUID: Uuid = uuid16!(0x2903);
+
+    /// Encodes the descriptor value.
+    pub fn to_bytes(&self) -> [u8; 2] {
+        u16::from(self.broadcast).to_le_bytes()
+    }
+
+    /// Decodes the descriptor value.
+    pub fn from_bytes(value: &[u8]) -> crate::Result<Self> {
+        let bits = decode_bit_field(value, "server characteristic configuration")?;
+        Ok(Self { broadcast: bits & 0x01 != 0 })
+    }
+}
+
+/// Characteristic User Description descriptor value.
+///
+/// Its value is a UTF-8 string describing the characteristic.
+/// Use [remote::Characteristic::user_description] to read it from a remote characteristic.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UserDescription(pub String);
+
+impl UserDescription {
+    /// UUID of the Characteristic User Description descriptor.
+    pub const UUID: Uuid = uuid16!(0x2901);
+
+    /// Encodes the descriptor value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.as_bytes().to_vec()
+    }
+
+    /// Decodes the descriptor value.
+    ///
+    /// Invalid UTF-8 sequences are replaced.
+    pub fn from_bytes(value: &[u8]) -> Self {
+        Self(String::from_utf8_lossy(value).into_owned())
+    }
+}
+
 /// Write operation type.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, EnumString, Display)]
 pub enum WriteOp {
@@ -113,7 +367,7 @@ pub struct CharacteristicReader {
     mtu: usize,
     #[pin]
     stream: UnixStream,
-    buf: Vec<u8>,
+    buf: Bytes,
 }
 
 impl CharacteristicReader {
@@ -160,6 +414,32 @@ impl CharacteristicReader {
         }
     }
 
+    /// Try to receive the characteristic value from a single notify or write operation
+    /// into a reference-counted buffer.
+    ///
+    /// The returned buffer can be cheaply cloned and sliced without copying.
+    /// Does not wait for new data to arrive.
+    pub fn try_recv_bytes(&self) -> std::io::Result<Bytes> {
+        let mut buf = BytesMut::with_capacity(self.mtu);
+        self.stream.try_read_buf(&mut buf)?;
+        Ok(buf.freeze())
+    }
+
+    /// Receive the characteristic value from a single notify or write operation
+    /// into a reference-counted buffer.
+    ///
+    /// The returned buffer can be cheaply cloned and sliced without copying.
+    /// Waits for data to arrive.
+    pub async fn recv_bytes(&self) -> std::io::Result<Bytes> {
+        loop {
+            self.recvable().await?;
+            match self.try_recv_bytes() {
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => continue,
+                res => return res,
+            }
+        }
+    }
+
     /// Consumes this object, returning the raw underlying file descriptor.
     pub fn into_raw_fd(self) -> std::io::Result<RawFd> {
         Ok(self.stream.into_std()?.into_raw_fd())
@@ -179,25 +459,26 @@ impl AsyncRead for CharacteristicReader {
         if !self.buf.is_empty() {
             // Return buffered data first, if any.
             let to_read = buf_space.min(self.buf.len());
-            let remaining = self.buf.split_off(to_read);
-            buf.put_slice(&self.buf);
-            self.buf = remaining;
+            let data = self.buf.split_to(to_read);
+            buf.put_slice(&data);
             Poll::Ready(Ok(()))
         } else if buf_space < self.mtu {
             let this = self.project();
 
             // If provided buffer is too small, read into temporary buffer.
-            let mut mtu_buf: Vec<MaybeUninit<u8>> = vec![MaybeUninit::uninit(); *this.mtu];
-            let mut mtu_read_buf = ReadBuf::uninit(&mut mtu_buf);
+            let mut mtu_buf = BytesMut::with_capacity(*this.mtu);
+            let mut mtu_read_buf = ReadBuf::uninit(&mut mtu_buf.spare_capacity_mut()[..*this.mtu]);
             ready!(this.stream.poll_read(cx, &mut mtu_read_buf))?;
             let n = mtu_read_buf.filled().len();
-            mtu_buf.truncate(n);
-            let mut mtu_buf: Vec<u8> = mtu_buf.into_iter().map(|v| unsafe { v.assume_init() }).collect();
+            // SAFETY: the first n bytes have been initialized by the read.
+            unsafe { mtu_buf.set_len(n) };
+            let mut mtu_buf = mtu_buf.freeze();
 
             // Then fill provided buffer appropriately and keep the rest in
             // our internal buffer.
-            *this.buf = mtu_buf.split_off(buf_space.min(n));
-            buf.put_slice(&mtu_buf);
+            let data = mtu_buf.split_to(buf_space.min(n));
+            *this.buf = mtu_buf;
+            buf.put_slice(&data);
 
             Poll::Ready(Ok(()))
         } else {
@@ -227,6 +508,7 @@ pub struct CharacteristicWriter {
     mtu: usize,
     #[pin]
     stream: UnixStream,
+    _drop_tx: Option<futures::channel::oneshot::Sender<()>>,
 }
 
 impl CharacteristicWriter {
@@ -335,6 +617,7 @@ impl IntoRawFd for CharacteristicWriter {
 }
 
 /// Creates a UNIX socket pair for communication with bluetoothd.
+#[cfg(feature = "gatt-server")]
 pub(crate) fn make_socket_pair(non_block: bool) -> std::io::Result<(OwnedFd, UnixStream)> {
     let mut sv: [RawFd; 2] = [0; 2];
     let mut ty = SOCK_SEQPACKET | SOCK_CLOEXEC;
@@ -355,6 +638,21 @@ pub(crate) fn make_socket_pair(non_block: bool) -> std::io::Result<(OwnedFd, Uni
     Ok((fd1, us))
 }
 
+/// Duplicates the file descriptor of a UNIX stream.
+///
+/// This allows observing the readiness of the stream independently of its owner.
+#[cfg(feature = "gatt-server")]
+pub(crate) fn duplicate_stream(stream: &UnixStream) -> std::io::Result<UnixStream> {
+    let fd = unsafe { libc::fcntl(stream.as_raw_fd(), F_DUPFD_CLOEXEC, 0) };
+    if fd == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let us = unsafe { std::os::unix::net::UnixStream::from_raw_fd(fd) };
+    us.set_nonblocking(true)?;
+    UnixStream::from_std(us)
+}
+
 /// Apply MTU workaround.
 ///
 /// BlueZ drops data at end of packet if full reported MTU is used, thus we
@@ -362,3 +660,145 @@ pub(crate) fn make_socket_pair(non_block: bool) -> std::io::Result<(OwnedFd, Uni
 pub(crate) fn mtu_workaround(mtu: usize) -> usize {
     mtu.saturating_sub(5)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn characteristic_flags_are_parsed_and_formatted() {
+        let flags: CharacteristicFlags = "read,write-without-response, notify".parse().unwrap();
+        assert!(flags.read && flags.write_without_response && flags.notify);
+        assert!(!flags.write && !flags.indicate);
+        assert_eq!(flags.to_string(), "read,write-without-response,notify");
+        assert_eq!(flags.to_string().parse::<CharacteristicFlags>().unwrap(), flags);
+        assert_eq!(CharacteristicFlags::from_strings(flags.to_strings()).unwrap(), flags);
+        assert_eq!("".parse::<CharacteristicFlags>().unwrap(), CharacteristicFlags::default());
+    }
+
+    #[test]
+    fn unknown_flags_are_rejected() {
+        let err = "read,bogus".parse::<CharacteristicFlags>().unwrap_err();
+        assert_eq!(err.kind, crate::ErrorKind::InvalidArguments);
+        assert!("broadcast".parse::<DescriptorFlags>().is_err());
+    }
+
+    #[test]
+    fn presentation_format_round_trips() {
+        let format = PresentationFormat {
+            format: 0x0e,
+            exponent: -2,
+            unit: 0x272f,
+            namespace: PresentationFormat::NAMESPACE_BLUETOOTH_SIG,
+            description: 0x0106,
+        };
+        let bytes = [0x0e, 0xfe, 0x2f, 0x27, 0x01, 0x06, 0x01];
+        assert_eq!(format.to_bytes(), bytes);
+        assert_eq!(PresentationFormat::from_bytes(&bytes).unwrap(), format);
+    }
+
+    #[test]
+    fn presentation_format_of_wrong_length_is_rejected() {
+        for len in [0, PresentationFormat::LEN - 1, PresentationFormat::LEN + 1] {
+            let err = PresentationFormat::from_bytes(&vec![0; len]).unwrap_err();
+            assert_eq!(err.kind, crate::ErrorKind::InvalidLength);
+        }
+    }
+
+    #[test]
+    fn extended_properties_round_trip() {
+        let props = ExtendedProperties { reliable_write: true, writable_auxiliaries: false };
+        assert_eq!(props.to_bytes(), [0x01, 0x00]);
+        assert_eq!(ExtendedProperties::from_bytes(&[0x01, 0x00]).unwrap(), props);
+
+        let props = ExtendedProperties { reliable_write: false, writable_auxiliaries: true };
+        assert_eq!(props.to_bytes(), [0x02, 0x00]);
+        assert_eq!(ExtendedProperties::from_bytes(&[0x02, 0x00]).unwrap(), props);
+
+        // Reserved bits are ignored.
+        let props = ExtendedProperties::from_bytes(&[0xff, 0xff]).unwrap();
+        assert!(props.reliable_write && props.writable_auxiliaries);
+    }
+
+    #[test]
+    fn client_configuration_round_trips() {
+        for (notify, indicate, bytes) in [
+            (false, false, [0x00, 0x00]),
+            (true, false, [0x01, 0x00]),
+            (false, true, [0x02, 0x00]),
+            (true, true, [0x03, 0x00]),
+        ] {
+            let config = ClientCharacteristicConfiguration { notify, indicate };
+            assert_eq!(config.to_bytes(), bytes);
+            assert_eq!(ClientCharacteristicConfiguration::from_bytes(&bytes).unwrap(), config);
+        }
+        assert_eq!(
+            ClientCharacteristicConfiguration::from_bytes(&[0x04, 0x80]).unwrap(),
+            ClientCharacteristicConfiguration::default()
+        );
+    }
+
+    #[test]
+    fn server_configuration_round_trips() {
+        let config = ServerCharacteristicConfiguration { broadcast: true };
+        assert_eq!(config.to_bytes(), [0x01, 0x00]);
+        assert_eq!(ServerCharacteristicConfiguration::from_bytes(&[0x01, 0x00]).unwrap(), config);
+        assert_eq!(ServerCharacteristicConfiguration::default().to_bytes(), [0x00, 0x00]);
+        assert!(!ServerCharacteristicConfiguration::from_bytes(&[0x02, 0x00]).unwrap().broadcast);
+    }
+
+    #[test]
+    fn bit_fields_of_wrong_length_are_rejected() {
+        for value in [&[][..], &[0x01], &[0x01, 0x00, 0x00]] {
+            let kinds = [
+                ExtendedProperties::from_bytes(value).unwrap_err().kind,
+                ClientCharacteristicConfiguration::from_bytes(value).unwrap_err().kind,
+                ServerCharacteristicConfiguration::from_bytes(value).unwrap_err().kind,
+            ];
+            assert!(kinds.iter().all(|kind| *kind == crate::ErrorKind::InvalidLength));
+        }
+    }
+
+    #[test]
+    fn user_description_round_trips() {
+        let desc = UserDescription("Temperature °C".to_string());
+        assert_eq!(UserDescription::from_bytes(&desc.to_bytes()), desc);
+        assert_eq!(UserDescription::from_bytes(b"ab\xffc").0, "ab\u{fffd}c");
+    }
+
+    #[test]
+    fn descriptor_uuids() {
+        assert_eq!(ExtendedProperties::UUID, Uuid::parse_str("00002900-0000-1000-8000-00805f9b34fb").unwrap());
+        assert_eq!(UserDescription::UUID, Uuid::parse_str("00002901-0000-1000-8000-00805f9b34fb").unwrap());
+        assert_eq!(
+            ClientCharacteristicConfiguration::UUID,
+            Uuid::parse_str("00002902-0000-1000-8000-00805f9b34fb").unwrap()
+        );
+        assert_eq!(
+            ServerCharacteristicConfiguration::UUID,
+            Uuid::parse_str("00002903-0000-1000-8000-00805f9b34fb").unwrap()
+        );
+        assert_eq!(PresentationFormat::UUID, Uuid::parse_str("00002904-0000-1000-8000-00805f9b34fb").unwrap());
+    }
+
+    #[test]
+    fn characteristic_flags_are_validated() {
+        let valid = |s: &str| s.parse::<CharacteristicFlags>().unwrap().validate().is_ok();
+        assert!(valid("read,encrypt-read"));
+        assert!(valid("write-without-response,secure-write"));
+        assert!(valid("write,reliable-write"));
+        assert!(!valid(""));
+        assert!(!valid("notify,encrypt-read"));
+        assert!(!valid("read,encrypt-write"));
+        assert!(!valid("write-without-response,reliable-write"));
+    }
+
+    #[test]
+    fn descriptor_flags_are_validated() {
+        let valid = |s: &str| s.parse::<DescriptorFlags>().unwrap().validate().is_ok();
+        assert!(valid(""));
+        assert!(valid("read,write,encrypt-authenticated-write"));
+        assert!(!valid("write,secure-read"));
+        assert!(!valid("read,encrypt-write"));
+    }
+}
@@ -5,18 +5,30 @@ use dbus::{
     nonblock::{Proxy, SyncConnection},
     Path,
 };
-use futures::{Stream, StreamExt};
-use std::{fmt, os::unix::prelude::FromRawFd, sync::Arc};
-use tokio::net::UnixStream;
+use futures::{future::Either, ready, stream, Future, Sink, Stream, StreamExt};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt, mem,
+    os::unix::prelude::FromRawFd,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{io::AsyncWrite, net::UnixStream};
 use uuid::Uuid;
 
 use super::{
-    mtu_workaround, CharacteristicFlags, CharacteristicReader, CharacteristicWriter, WriteOp,
-    CHARACTERISTIC_INTERFACE, DESCRIPTOR_INTERFACE, SERVICE_INTERFACE,
+    mtu_workaround, CharacteristicFlags, CharacteristicReader, CharacteristicWriter,
+    ClientCharacteristicConfiguration, DescriptorFlags, ExtendedProperties, PresentationFormat,
+    ServerCharacteristicConfiguration, UserDescription, WriteOp, CHARACTERISTIC_INTERFACE, DESCRIPTOR_INTERFACE,
+    SERVICE_INTERFACE,
 };
+#[cfg(feature = "tracing")]
+use crate::BtUuid;
 use crate::{
-    all_dbus_objects, Address, Device, Error, ErrorKind, Event, InternalErrorKind, Result, SessionInner,
-    SingleSessionToken, SERVICE_NAME, TIMEOUT,
+    all_dbus_objects, latest_events, Address, Bytes, Device, Error, ErrorKind, InternalErrorKind, ObjectEvent,
+    Result, SessionInner, SingleSessionToken, SERVICE_NAME, TIMEOUT,
 };
 
 // ===========================================================================================
@@ -63,7 +75,7 @@ impl Service {
     }
 
     pub(crate) fn dbus_path(adapter_name: &str, device_address: Address, id: u16) -> Result<Path<'static>> {
-        let device_path = Device::dbus_path(adapter_name, device_address)?;
+        let device_path = Device::make_dbus_path(adapter_name, device_address)?;
         Ok(Path::new(format!("{device_path}/service{id:04x}")).unwrap())
     }
 
@@ -126,6 +138,29 @@ impl Service {
         Ok(chars)
     }
 
+    /// Reads the values of all readable characteristics of this service concurrently.
+    ///
+    /// At most `parallelism` read requests are outstanding at any time.
+    /// Characteristics that do not have the [read](CharacteristicFlags::read) flag set are skipped.
+    /// The result of each read is returned individually, so that a failing read does not
+    /// affect the others.
+    /// If the service contains multiple characteristics with the same UUID, only the value
+    /// of one of them is returned.
+    pub async fn read_all_characteristics(&self, parallelism: usize) -> Result<HashMap<Uuid, Result<Vec<u8>>>> {
+        let mut readable = Vec::new();
+        for characteristic in self.characteristics().await? {
+            if characteristic.flags().await?.read {
+                readable.push((characteristic.uuid().await?, characteristic));
+            }
+        }
+
+        Ok(stream::iter(readable)
+            .map(|(uuid, characteristic)| async move { (uuid, characteristic.read().await) })
+            .buffer_unordered(parallelism.max(1))
+            .collect()
+            .await)
+    }
+
     /// GATT characteristics with specified id.
     pub async fn characteristic(&self, characteristic_id: u16) -> Result<Characteristic> {
         Characteristic::new(
@@ -186,6 +221,9 @@ pub struct Characteristic {
     device_address: Address,
     service_id: u16,
     id: u16,
+    timeouts: OperationTimeouts,
+    #[cfg(feature = "tracing")]
+    span_uuids: SpanUuids,
 }
 
 impl fmt::Debug for Characteristic {
@@ -212,6 +250,9 @@ impl Characteristic {
             device_address,
             service_id,
             id,
+            timeouts: OperationTimeouts::default(),
+            #[cfg(feature = "tracing")]
+            span_uuids: Default::default(),
         })
     }
 
@@ -219,6 +260,35 @@ impl Characteristic {
         Proxy::new(SERVICE_NAME, &self.dbus_path, TIMEOUT, &*self.inner.connection)
     }
 
+    /// Performs a GATT operation, applying its timeout.
+    ///
+    /// With the `tracing` feature the operation is instrumented with a span carrying
+    /// the UUIDs of the service and characteristic.
+    async fn operation<T>(
+        &self, op: &'static str, timeout: Option<Duration>, f: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let f = with_timeout(op, timeout, f);
+        #[cfg(feature = "tracing")]
+        let f = tracing::Instrument::instrument(
+            f,
+            gatt_span(&self.span_uuids, op, &self.adapter_name, self.device_address, async {
+                let service = Service::new(
+                    self.inner.clone(),
+                    self.adapter_name.clone(),
+                    self.device_address,
+                    self.service_id,
+                );
+                let service = match service {
+                    Ok(service) => service.uuid().await.ok(),
+                    Err(_) => None,
+                };
+                [service, self.uuid().await.ok(), None]
+            })
+            .await,
+        );
+        f.await
+    }
+
     pub(crate) fn dbus_path(
         adapter_name: &str, device_address: Address, service_id: u16, id: u16,
     ) -> Result<Path<'static>> {
@@ -272,6 +342,18 @@ impl Characteristic {
         self.id
     }
 
+    /// Timeouts of the GATT operations performed on this characteristic.
+    pub fn timeouts(&self) -> OperationTimeouts {
+        self.timeouts
+    }
+
+    /// Sets the timeouts of the GATT operations performed on this characteristic.
+    ///
+    /// Descriptors obtained from this characteristic afterwards inherit the timeouts.
+    pub fn set_timeouts(&mut self, timeouts: OperationTimeouts) {
+        self.timeouts = timeouts;
+    }
+
     /// GATT descriptors belonging to this characteristic.
     pub async fn descriptors(&self) -> Result<Vec<Descriptor>> {
         let mut chars = Vec::new();
@@ -292,16 +374,102 @@ impl Characteristic {
         Ok(chars)
     }
 
+    /// GATT descriptor of this characteristic with the specified UUID.
+    ///
+    /// Returns [None] if the characteristic has no such descriptor.
+    /// If the characteristic has multiple descriptors with the UUID, the first one is returned.
+    pub async fn descriptor_by_uuid(&self, uuid: Uuid) -> Result<Option<Descriptor>> {
+        let mut descriptors = self.descriptors().await?;
+        descriptors.sort_by_key(|descriptor| descriptor.id());
+        for descriptor in descriptors {
+            if descriptor.uuid().await? == uuid {
+                return Ok(Some(descriptor));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads the Characteristic Presentation Format descriptor of this characteristic.
+    ///
+    /// Returns [None] if the characteristic has no such descriptor.
+    /// If the characteristic has multiple presentation formats, the first one is returned.
+    pub async fn presentation_format(&self) -> Result<Option<PresentationFormat>> {
+        match self.descriptor_by_uuid(PresentationFormat::UUID).await? {
+            Some(descriptor) => Ok(Some(PresentationFormat::from_bytes(&descriptor.read().await?)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the Characteristic Extended Properties descriptor of this characteristic.
+    ///
+    /// Returns [None] if the characteristic has no such descriptor.
+    pub async fn extended_properties(&self) -> Result<Option<ExtendedProperties>> {
+        match self.descriptor_by_uuid(ExtendedProperties::UUID).await? {
+            Some(descriptor) => Ok(Some(ExtendedProperties::from_bytes(&descriptor.read().await?)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the Client Characteristic Configuration descriptor of this characteristic.
+    ///
+    /// Returns [None] if the characteristic has no such descriptor, i.e. it supports
+    /// neither notifications nor indications.
+    pub async fn client_configuration(&self) -> Result<Option<ClientCharacteristicConfiguration>> {
+        match self.descriptor_by_uuid(ClientCharacteristicConfiguration::UUID).await? {
+            Some(descriptor) => {
+                Ok(Some(ClientCharacteristicConfiguration::from_bytes(&descriptor.read().await?)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the Server Characteristic Configuration descriptor of this characteristic.
+    ///
+    /// Returns [None] if the characteristic has no such descriptor.
+    pub async fn server_configuration(&self) -> Result<Option<ServerCharacteristicConfiguration>> {
+        match self.descriptor_by_uuid(ServerCharacteristicConfiguration::UUID).await? {
+            Some(descriptor) => {
+                Ok(Some(ServerCharacteristicConfiguration::from_bytes(&descriptor.read().await?)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the Characteristic User Description descriptor of this characteristic.
+    ///
+    /// Returns [None] if the characteristic has no such descriptor.
+    /// Invalid UTF-8 sequences are replaced.
+    pub async fn user_description(&self) -> Result<Option<String>> {
+        match self.descriptor_by_uuid(UserDescription::UUID).await? {
+            Some(descriptor) => Ok(Some(UserDescription::from_bytes(&descriptor.read().await?).0)),
+            None => Ok(None),
+        }
+    }
+
+    /// Writes the Characteristic User Description descriptor of this characteristic.
+    ///
+    /// This requires the [writable_auxiliaries](ExtendedProperties::writable_auxiliaries)
+    /// extended property of the characteristic.
+    /// Fails with [ErrorKind::NotFound] if the characteristic has no such descriptor.
+    pub async fn set_user_description(&self, description: &str) -> Result<()> {
+        match self.descriptor_by_uuid(UserDescription::UUID).await? {
+            Some(descriptor) => descriptor.write(description.as_bytes()).await,
+            None => Err(Error::new(ErrorKind::NotFound)),
+        }
+    }
+
     /// GATT descriptor with specified id.
     pub async fn descriptor(&self, descriptor_id: u16) -> Result<Descriptor> {
-        Descriptor::new(
+        let mut descriptor = Descriptor::new(
             self.inner.clone(),
             self.adapter_name.clone(),
             self.device_address,
             self.service_id,
             self.id,
             descriptor_id,
-        )
+        )?;
+        descriptor.set_timeouts(self.timeouts);
+        Ok(descriptor)
     }
 
     /// Issues a request to read the value of the
@@ -317,8 +485,22 @@ impl Characteristic {
     ///
     /// Takes extended options for the read operation.
     pub async fn read_ext(&self, req: &CharacteristicReadRequest) -> Result<Vec<u8>> {
-        let (value,): (Vec<u8>,) = self.call_method("ReadValue", (req.to_dict(),)).await?;
-        Ok(value)
+        self.operation("read", self.timeouts.read, async {
+            let _lock = self.inner.device_op_lock(&self.adapter_name, self.device_address).await;
+            let (value,): (Vec<u8>,) = self.call_method("ReadValue", (req.to_dict(),)).await?;
+            Ok(value)
+        })
+        .await
+    }
+
+    /// Issues a request to read the value of the
+    /// characteristic and returns the value in a reference-counted buffer
+    /// if the operation was successful.
+    ///
+    /// The value received from the Bluetooth daemon is not copied and
+    /// the returned buffer can be cheaply cloned and sliced.
+    pub async fn read_bytes(&self) -> Result<Bytes> {
+        Ok(Bytes::from(self.read().await?))
     }
 
     /// Issues a request to write the value of the characteristic.
@@ -330,8 +512,21 @@ impl Characteristic {
     ///
     /// Takes extended options for the write operation.
     pub async fn write_ext(&self, value: &[u8], req: &CharacteristicWriteRequest) -> Result<()> {
-        self.call_method("WriteValue", (value, req.to_dict())).await?;
-        Ok(())
+        self.write_value(value, req, true).await
+    }
+
+    /// Writes the value, optionally serialized with other operations on the device.
+    async fn write_value(&self, value: &[u8], req: &CharacteristicWriteRequest, serialize: bool) -> Result<()> {
+        self.operation("write", self.timeouts.write, async {
+            let _lock = if serialize {
+                self.inner.device_op_lock(&self.adapter_name, self.device_address).await
+            } else {
+                None
+            };
+            self.call_method("WriteValue", (value, req.to_dict())).await?;
+            Ok(())
+        })
+        .await
     }
 
     /// Acquire writer for writing with low overhead.
@@ -360,6 +555,21 @@ impl Characteristic {
             device_address: self.device_address,
             mtu,
             stream,
+            _drop_tx: None,
+        })
+    }
+
+    /// Starts a notification or indication session from this characteristic
+    /// using the specified buffering strategy for values not consumed yet.
+    ///
+    /// Use [NotificationBuffer::Lossy] if only recent values are of interest,
+    /// so that the consumer does not have to work through a backlog after a pause.
+    /// Otherwise this is equivalent to [notify](Self::notify).
+    pub async fn notify_with_buffer(&self, buffer: NotificationBuffer) -> Result<impl Stream<Item = Vec<u8>>> {
+        let values = self.notify().await?;
+        Ok(match buffer {
+            NotificationBuffer::Lossless => Either::Left(values),
+            NotificationBuffer::Lossy(capacity) => Either::Right(latest_events(&self.inner, values, capacity)),
         })
     }
 
@@ -367,46 +577,76 @@ impl Characteristic {
     /// if it supports value notifications or indications.
     ///
     /// This will also notify after a read operation.
+    ///
+    /// Values are moved out of the received D-Bus messages without copying.
+    /// Use [notify_bytes](Self::notify_bytes) to receive them in reference-counted buffers.
     pub async fn notify(&self) -> Result<impl Stream<Item = Vec<u8>>> {
         let token = self.notify_session().await?;
         let events = self.inner.events(self.dbus_path.clone(), false).await?;
         let values = events.filter_map(move |evt| {
             let _token = &token;
             async move {
-                if let Event::PropertiesChanged { changed, .. } = evt {
-                    for property in CharacteristicProperty::from_prop_map(changed) {
-                        if let CharacteristicProperty::CachedValue(value) = property {
-                            return Some(value);
-                        }
+                match evt {
+                    // Take ownership of the value to avoid copying it.
+                    ObjectEvent::PropertiesChanged { mut changed, .. } => {
+                        let mut value = changed.remove("Value")?;
+                        value.0.as_any_mut().downcast_mut::<Vec<u8>>().map(mem::take)
                     }
+                    _ => None,
                 }
-                None
             }
         });
         Ok(values)
     }
 
+    /// Starts a notification or indication session from this characteristic
+    /// that delivers the values in reference-counted buffers.
+    ///
+    /// The values are not copied and the buffers can be cheaply cloned and sliced,
+    /// for example to pass them on to multiple consumers.
+    /// Otherwise this is equivalent to [notify](Self::notify).
+    pub async fn notify_bytes(&self) -> Result<impl Stream<Item = Bytes>> {
+        Ok(self.notify().await?.map(Bytes::from))
+    }
+
     async fn notify_session(&self) -> Result<SingleSessionToken> {
-        let dbus_path = self.dbus_path.clone();
-        let connection = self.inner.connection.clone();
         self.inner
             .single_session(
                 &self.dbus_path,
                 async move {
-                    self.call_method("StartNotify", ()).await?;
-                    Ok(())
-                },
-                async move {
-                    log::trace!("{}: {}.StopNotify ()", &dbus_path, SERVICE_NAME);
-                    let proxy = Proxy::new(SERVICE_NAME, &dbus_path, TIMEOUT, &*connection);
-                    let result: std::result::Result<(), dbus::Error> =
-                        proxy.method_call(CHARACTERISTIC_INTERFACE, "StopNotify", ()).await;
-                    log::trace!("{}: {}.StopNotify () -> {:?}", &dbus_path, SERVICE_NAME, &result);
+                    let this = self.clone();
+                    let mut start = Box::pin(async move { this.call_method::<_, ()>("StartNotify", ()).await });
+                    let started =
+                        self.operation("notification subscription", self.timeouts.notify, &mut start).await;
+                    if let Err(Error { kind: ErrorKind::Timeout, .. }) = &started {
+                        // The Bluetooth daemon may still complete the subscription,
+                        // thus wait for its reply before stopping it.
+                        let stop = self.stop_notify();
+                        self.inner.spawn(async move {
+                            if start.await.is_ok() {
+                                stop.await;
+                            }
+                        });
+                    }
+                    started
                 },
+                self.stop_notify(),
             )
             .await
     }
 
+    fn stop_notify(&self) -> impl Future<Output = ()> + Send + 'static {
+        let dbus_path = self.dbus_path.clone();
+        let connection = self.inner.connection.clone();
+        async move {
+            log::trace!("{}: {}.StopNotify ()", &dbus_path, SERVICE_NAME);
+            let proxy = Proxy::new(SERVICE_NAME, &dbus_path, TIMEOUT, &*connection);
+            let result: std::result::Result<(), dbus::Error> =
+                proxy.method_call(CHARACTERISTIC_INTERFACE, "StopNotify", ()).await;
+            log::trace!("{}: {}.StopNotify () -> {:?}", &dbus_path, SERVICE_NAME, &result);
+        }
+    }
+
     /// Acquire reader for notify with low overhead.
     ///
     /// It only works with characteristic that has
@@ -430,7 +670,11 @@ impl Characteristic {
     /// reconnections as the MTU has to be renegotiated.
     pub async fn notify_io(&self) -> Result<CharacteristicReader> {
         let options = PropMap::new();
-        let (fd, mtu): (OwnedFd, u16) = self.call_method("AcquireNotify", (options,)).await?;
+        let (fd, mtu): (OwnedFd, u16) = self
+            .operation("notification subscription", self.timeouts.notify, async {
+                self.call_method("AcquireNotify", (options,)).await
+            })
+            .await?;
         let stream = unsafe { std::os::unix::net::UnixStream::from_raw_fd(fd.into_fd()) };
         stream.set_nonblocking(true)?;
         let stream = UnixStream::from_std(stream)?;
@@ -439,14 +683,303 @@ impl Characteristic {
             device_address: self.device_address,
             mtu: mtu.into(),
             stream,
-            buf: Vec::new(),
+            buf: Default::default(),
         })
     }
 
+    /// Obtains a sink for writing a sequence of values to the characteristic.
+    ///
+    /// This allows streaming producers to [forward](futures::StreamExt::forward) their
+    /// output into the characteristic.
+    /// Values longer than the maximum value length allowed by the MTU are split into
+    /// multiple writes.
+    ///
+    /// If the characteristic has the [write_without_response](CharacteristicFlags::write_without_response)
+    /// flag set, values are written over a [CharacteristicWriter] obtained by [write_io](Self::write_io).
+    /// The sink then only accepts the next value once the socket to the Bluetooth daemon
+    /// has buffer space available, thus the producer is slowed down to the rate at which
+    /// the remote device accepts values.
+    /// Otherwise each value is written using a write request and the next value is only
+    /// accepted once the remote device has acknowledged the previous one.
+    ///
+    /// Errors are returned from the sink functions.
+    pub async fn write_sink(&self) -> Result<CharacteristicWriteSink> {
+        let flags = self.flags().await?;
+        let target = if flags.write_without_response {
+            WriteSinkTarget::Io(self.write_io().await?)
+        } else if flags.write {
+            WriteSinkTarget::Request { characteristic: self.clone(), pending: None }
+        } else {
+            return Err(Error::new(ErrorKind::NotSupported));
+        };
+        let mtu = match &target {
+            WriteSinkTarget::Io(writer) => writer.mtu(),
+            WriteSinkTarget::Request { .. } => self.mtu().await?.saturating_sub(ATT_WRITE_HEADER),
+        };
+        Ok(CharacteristicWriteSink { mtu: mtu.max(1), queue: VecDeque::new(), target })
+    }
+
     dbus_interface!();
     dbus_default_interface!(CHARACTERISTIC_INTERFACE);
 }
 
+/// Length of the ATT header of a write request.
+const ATT_WRITE_HEADER: usize = 3;
+
+type PendingWrite = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+enum WriteSinkTarget {
+    Io(CharacteristicWriter),
+    Request { characteristic: Characteristic, pending: Option<PendingWrite> },
+}
+
+/// Sink for writing a sequence of values to a remote GATT characteristic.
+///
+/// Use [Characteristic::write_sink] to obtain this.
+#[must_use = "sinks do nothing unless polled"]
+pub struct CharacteristicWriteSink {
+    mtu: usize,
+    queue: VecDeque<Vec<u8>>,
+    target: WriteSinkTarget,
+}
+
+impl fmt::Debug for CharacteristicWriteSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match &self.target {
+            WriteSinkTarget::Io(_) => "io",
+            WriteSinkTarget::Request { .. } => "request",
+        };
+        write!(f, "CharacteristicWriteSink {{ mode: {}, mtu: {}, queued: {} }}", mode, self.mtu, self.queue.len())
+    }
+}
+
+impl CharacteristicWriteSink {
+    /// Maximum number of bytes sent in a single write operation.
+    pub fn mtu(&self) -> usize {
+        self.mtu
+    }
+
+    /// Writes all queued values.
+    fn poll_write_queue(&mut self, cx: &mut Context) -> Poll<Result<()>> {
+        loop {
+            match &mut self.target {
+                WriteSinkTarget::Io(writer) => {
+                    let Some(value) = self.queue.front() else { return Poll::Ready(Ok(())) };
+                    ready!(Pin::new(&mut *writer).poll_write(cx, value))?;
+                    self.queue.pop_front();
+                }
+                WriteSinkTarget::Request { characteristic, pending } => {
+                    if let Some(write) = pending {
+                        ready!(write.as_mut().poll(cx))?;
+                        *pending = None;
+                    }
+                    let Some(value) = self.queue.pop_front() else { return Poll::Ready(Ok(())) };
+                    let characteristic = characteristic.clone();
+                    *pending = Some(Box::pin(async move {
+                        let req = CharacteristicWriteRequest { op_type: WriteOp::Request, ..Default::default() };
+                        characteristic.write_ext(&value, &req).await
+                    }));
+                }
+            }
+        }
+    }
+}
+
+impl Sink<Vec<u8>> for CharacteristicWriteSink {
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<()>> {
+        self.get_mut().poll_write_queue(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, value: Vec<u8>) -> Result<()> {
+        let this = self.get_mut();
+        if value.len() <= this.mtu {
+            this.queue.push_back(value);
+        } else {
+            this.queue.extend(value.chunks(this.mtu).map(|chunk| chunk.to_vec()));
+        }
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_queue(cx))?;
+        match &mut this.target {
+            WriteSinkTarget::Io(writer) => {
+                Poll::Ready(ready!(Pin::new(writer).poll_flush(cx)).map_err(Into::into))
+            }
+            WriteSinkTarget::Request { .. } => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_queue(cx))?;
+        match &mut this.target {
+            WriteSinkTarget::Io(writer) => {
+                Poll::Ready(ready!(Pin::new(writer).poll_shutdown(cx)).map_err(Into::into))
+            }
+            WriteSinkTarget::Request { .. } => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+/// Paced writer for writing without response over D-Bus.
+///
+/// Writing without response using [Characteristic::write_ext] does not wait for the
+/// remote device, thus a fast producer may overrun a peripheral with limited buffers.
+/// The throttled writer limits the number of writes in flight and enforces a minimum delay
+/// between the start of consecutive writes.
+///
+/// This is an alternative to [Characteristic::write_io] for cases where acquiring the
+/// characteristic is not possible, for example because another client holds it.
+/// The writer can be shared by multiple tasks; writes then proceed in the order
+/// in which they have been started.
+///
+/// Since writes without response are not acknowledged by the remote device, they bypass the
+/// [serialization of device operations](crate::Session::set_serialize_device_operations).
+/// Thus up to [max_in_flight](Self::max_in_flight) writes are pending at the Bluetooth daemon
+/// at the same time, even while other operations on the device are in progress.
+#[derive(Debug)]
+pub struct ThrottledWriter {
+    characteristic: Characteristic,
+    in_flight: tokio::sync::Semaphore,
+    delay: Duration,
+    next_write: tokio::sync::Mutex<tokio::time::Instant>,
+}
+
+impl ThrottledWriter {
+    /// Creates a throttled writer for the characteristic allowing one write in flight
+    /// and no delay between writes.
+    pub fn new(characteristic: Characteristic) -> Self {
+        Self {
+            characteristic,
+            in_flight: tokio::sync::Semaphore::new(1),
+            delay: Duration::ZERO,
+            next_write: tokio::sync::Mutex::new(tokio::time::Instant::now()),
+        }
+    }
+
+    /// Sets the maximum number of writes in flight.
+    ///
+    /// A value of zero is treated as one.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.in_flight = tokio::sync::Semaphore::new(max_in_flight.max(1));
+        self
+    }
+
+    /// Sets the minimum delay between the start of consecutive writes.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// The characteristic written to.
+    pub fn characteristic(&self) -> &Characteristic {
+        &self.characteristic
+    }
+
+    /// Writes the value without response, waiting until the pacing permits it.
+    ///
+    /// Returns once the Bluetooth daemon has accepted the write.
+    pub async fn write(&self, value: &[u8]) -> Result<()> {
+        let _permit = self.in_flight.acquire().await.map_err(|_| Error::new(ErrorKind::Cancelled))?;
+        {
+            let mut next_write = self.next_write.lock().await;
+            tokio::time::sleep_until(*next_write).await;
+            *next_write = tokio::time::Instant::now() + self.delay;
+        }
+
+        let req = CharacteristicWriteRequest { op_type: WriteOp::Command, ..Default::default() };
+        self.characteristic.write_value(value, &req, false).await
+    }
+}
+
+/// Timeouts of GATT operations on a remote characteristic or descriptor.
+///
+/// These are independent of the timeout of D-Bus method calls and allow to give up on
+/// slow remote devices early.
+/// An operation that times out fails with [ErrorKind::Timeout].
+/// The time spent waiting for other operations on the same device, if they are
+/// [serialized](crate::Session::set_serialize_device_operations), counts towards the timeout.
+///
+/// By default no timeouts are set.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OperationTimeouts {
+    /// Timeout of read operations.
+    pub read: Option<Duration>,
+    /// Timeout of write operations using write requests.
+    pub write: Option<Duration>,
+    /// Timeout of subscribing to notifications or indications.
+    ///
+    /// If the subscription times out, the request is kept pending in the background and,
+    /// once the Bluetooth daemon has completed it successfully, the subscription is stopped.
+    pub notify: Option<Duration>,
+    #[doc(hidden)]
+    pub _non_exhaustive: (),
+}
+
+/// UUIDs of the service, characteristic and descriptor of a remote GATT object,
+/// queried once for tracing spans.
+#[cfg(feature = "tracing")]
+type SpanUuids = Arc<tokio::sync::OnceCell<[Option<String>; 3]>>;
+
+/// Tracing span of an operation on a remote GATT object.
+///
+/// The UUIDs are only queried if the span is enabled.
+#[cfg(feature = "tracing")]
+async fn gatt_span(
+    uuids: &SpanUuids, op: &'static str, adapter_name: &str, device_address: Address,
+    query: impl Future<Output = [Option<Uuid>; 3]>,
+) -> tracing::Span {
+    if !tracing::enabled!(tracing::Level::DEBUG) {
+        return tracing::Span::none();
+    }
+    let [service, characteristic, descriptor] = uuids
+        .get_or_init(|| async { query.await.map(|uuid| uuid.map(|uuid| BtUuid(uuid).to_string())) })
+        .await
+        .clone();
+    tracing::debug_span!(
+        "gatt_operation",
+        op,
+        adapter = adapter_name,
+        device = %device_address,
+        service,
+        characteristic,
+        descriptor
+    )
+}
+
+/// Runs a GATT operation, failing with [ErrorKind::Timeout] if it does not complete in time.
+///
+/// The operation is cancelled by dropping it when the timeout elapses, unless it is passed by reference.
+async fn with_timeout<T>(op: &str, timeout: Option<Duration>, f: impl Future<Output = Result<T>>) -> Result<T> {
+    let Some(timeout) = timeout else { return f.await };
+    match tokio::time::timeout(timeout, f).await {
+        Ok(result) => result,
+        Err(_) => Err(Error {
+            kind: ErrorKind::Timeout,
+            message: format!("GATT {op} did not complete within {} ms", timeout.as_millis()),
+        }),
+    }
+}
+
+/// Buffering strategy for received characteristic notifications that have not been consumed yet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum NotificationBuffer {
+    /// All values are buffered until they are consumed.
+    ///
+    /// No value is lost, but memory usage grows while the consumer falls behind.
+    #[default]
+    Lossless,
+    /// Only the specified number of most recent values is buffered.
+    ///
+    /// Older values are dropped while the consumer falls behind.
+    Lossy(usize),
+}
+
 /// Read characteristic value extended request.
 #[derive(Debug, Default, Clone)]
 pub struct CharacteristicReadRequest {
@@ -559,6 +1092,9 @@ pub struct Descriptor {
     service_id: u16,
     characteristic_id: u16,
     id: u16,
+    timeouts: OperationTimeouts,
+    #[cfg(feature = "tracing")]
+    span_uuids: SpanUuids,
 }
 
 impl fmt::Debug for Descriptor {
@@ -581,9 +1117,52 @@ impl Descriptor {
             service_id,
             characteristic_id,
             id,
+            timeouts: OperationTimeouts::default(),
+            #[cfg(feature = "tracing")]
+            span_uuids: Default::default(),
         })
     }
 
+    /// Performs a GATT operation, applying its timeout.
+    ///
+    /// With the `tracing` feature the operation is instrumented with a span carrying
+    /// the UUIDs of the service, characteristic and descriptor.
+    async fn operation<T>(
+        &self, op: &'static str, timeout: Option<Duration>, f: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let f = with_timeout(op, timeout, f);
+        #[cfg(feature = "tracing")]
+        let f = tracing::Instrument::instrument(
+            f,
+            gatt_span(&self.span_uuids, op, &self.adapter_name, self.device_address, async {
+                let service = Service::new(
+                    self.inner.clone(),
+                    self.adapter_name.clone(),
+                    self.device_address,
+                    self.service_id,
+                );
+                let characteristic = Characteristic::new(
+                    self.inner.clone(),
+                    self.adapter_name.clone(),
+                    self.device_address,
+                    self.service_id,
+                    self.characteristic_id,
+                );
+                let service = match service {
+                    Ok(service) => service.uuid().await.ok(),
+                    Err(_) => None,
+                };
+                let characteristic = match characteristic {
+                    Ok(characteristic) => characteristic.uuid().await.ok(),
+                    Err(_) => None,
+                };
+                [service, characteristic, self.uuid().await.ok()]
+            })
+            .await,
+        );
+        f.await
+    }
+
     fn proxy(&self) -> Proxy<'_, &SyncConnection> {
         Proxy::new(SERVICE_NAME, &self.dbus_path, TIMEOUT, &*self.inner.connection)
     }
@@ -648,6 +1227,18 @@ impl Descriptor {
         self.id
     }
 
+    /// Timeouts of the GATT operations performed on this descriptor.
+    pub fn timeouts(&self) -> OperationTimeouts {
+        self.timeouts
+    }
+
+    /// Sets the timeouts of the GATT operations performed on this descriptor.
+    ///
+    /// The [notify](OperationTimeouts::notify) timeout does not apply to descriptors.
+    pub fn set_timeouts(&mut self, timeouts: OperationTimeouts) {
+        self.timeouts = timeouts;
+    }
+
     dbus_interface!();
     dbus_default_interface!(DESCRIPTOR_INTERFACE);
 
@@ -664,8 +1255,12 @@ impl Descriptor {
     ///
     /// Takes extended options for the read operation.
     pub async fn read_ext(&self, req: &DescriptorReadRequest) -> Result<Vec<u8>> {
-        let (value,): (Vec<u8>,) = self.call_method("ReadValue", (req.to_dict(),)).await?;
-        Ok(value)
+        self.operation("read", self.timeouts.read, async {
+            let _lock = self.inner.device_op_lock(&self.adapter_name, self.device_address).await;
+            let (value,): (Vec<u8>,) = self.call_method("ReadValue", (req.to_dict(),)).await?;
+            Ok(value)
+        })
+        .await
     }
 
     /// Issues a request to write the value of the descriptor.
@@ -677,8 +1272,12 @@ impl Descriptor {
     ///
     /// Takes extended options for the write operation.
     pub async fn write_ext(&self, value: &[u8], req: &DescriptorWriteRequest) -> Result<()> {
-        self.call_method("WriteValue", (value, req.to_dict())).await?;
-        Ok(())
+        self.operation("write", self.timeouts.write, async {
+            let _lock = self.inner.device_op_lock(&self.adapter_name, self.device_address).await;
+            self.call_method("WriteValue", (value, req.to_dict())).await?;
+            Ok(())
+        })
+        .await
     }
 }
 
@@ -730,12 +1329,15 @@ define_properties!(
             get: (uuid, v => {v.parse().map_err(|_| Error::new(ErrorKind::Internal(InternalErrorKind::InvalidUuid(v.to_string()))))?}),
         );
 
-        // /// Defines how the descriptor value can be used.
-        // property(
-        //     Flags, DescriptorFlags,
-        //     dbus: (DESCRIPTOR_INTERFACE, "Flags", Vec<String>, MANDATORY),
-        //     get: (flags, v => {DescriptorFlags::from_slice(v)}),
-        // );
+        /// Defines how the descriptor value can be used.
+        ///
+        /// Not provided by versions of the Bluetooth daemon that do not expose
+        /// the flags of remote descriptors.
+        property(
+            Flags, DescriptorFlags,
+            dbus: (DESCRIPTOR_INTERFACE, "Flags", Vec<String>, OPTIONAL),
+            get: (flags, v => {DescriptorFlags::from_slice(v)}),
+        );
 
         /// The cached value of the descriptor.
         ///
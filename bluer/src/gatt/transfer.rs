@@ -0,0 +1,555 @@
+//! Transfer of large payloads over a pair of remote GATT characteristics.
+//!
+//! Many custom device protocols need to send payloads that exceed the maximum length
+//! of a characteristic value, for example configuration blobs, audio clips or images.
+//! [Transfer] implements a generic, length-prefixed and CRC-checked protocol for this,
+//! which the firmware of the remote device can implement with little effort.
+//!
+//! The sender writes frames to a *data* characteristic and the receiver responds
+//! with notifications or indications of a *response* characteristic.
+//! Both may be the same characteristic.
+//! All integers are in little-endian byte order.
+//!
+//! * **Start** `01 <length: u32> <crc: u32>`:
+//!   announces a payload of the specified length and CRC-32 (IEEE 802.3) checksum.
+//!   The receiver responds with `81 <status: u8> <offset: u32>`, where `offset`
+//!   is the number of bytes of the same payload (identified by length and checksum)
+//!   the receiver already holds from an earlier, interrupted transfer, or zero.
+//! * **Data** `02 <offset: u32> <data>`:
+//!   transfers the data at the specified offset of the payload.
+//!   Chunks are sent in order and the receiver does not respond.
+//! * **End** `03`:
+//!   completes the transfer.
+//!   The receiver responds with `83 <status: u8> <received: u32>`, where `received` is the
+//!   number of consecutive bytes received.
+//!   If the status is [InvalidOffset](TransferError::InvalidOffset), because a chunk
+//!   has been lost, the sender resumes the transfer at the received offset.
+//!
+//! A status of zero indicates success, the other values are listed in [TransferError].
+//!
+//! [Receiver] implements the receiving side of the protocol, for example
+//! for a local GATT service or for testing firmware against.
+
+use futures::{pin_mut, Stream, StreamExt};
+
+use super::{
+    remote::{Characteristic, CharacteristicWriteRequest},
+    WriteOp,
+};
+use crate::{Error, ErrorKind, Result, TIMEOUT};
+
+const OP_START: u8 = 0x01;
+const OP_DATA: u8 = 0x02;
+const OP_END: u8 = 0x03;
+const OP_RESPONSE: u8 = 0x80;
+
+const STATUS_SUCCESS: u8 = 0x00;
+const STATUS_CHECKSUM_MISMATCH: u8 = 0x01;
+const STATUS_INVALID_OFFSET: u8 = 0x02;
+const STATUS_TOO_LARGE: u8 = 0x03;
+
+/// Length of the ATT header of a write operation.
+const ATT_WRITE_HEADER: usize = 3;
+/// Length of the header of a data frame.
+const DATA_HEADER: usize = 5;
+
+/// Progress of a transfer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Progress {
+    /// Number of bytes transferred so far.
+    ///
+    /// When resuming an interrupted transfer this includes the bytes
+    /// transferred before the interruption.
+    pub transferred: usize,
+    /// Total size of the data in bytes.
+    pub total: usize,
+}
+
+impl Progress {
+    /// Fraction of the data transferred, between 0 and 1.
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.transferred as f64 / self.total as f64
+        }
+    }
+
+    /// Whether the transfer is complete.
+    pub fn is_complete(&self) -> bool {
+        self.transferred >= self.total
+    }
+}
+
+/// Transfer error.
+#[derive(Debug, displaydoc::Display, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum TransferError {
+    /// checksum of transferred data does not match
+    ChecksumMismatch,
+    /// transferred data is not contiguous
+    InvalidOffset,
+    /// payload is too large for the receiver
+    TooLarge,
+    /// receiver is busy
+    Busy,
+    /// unknown transfer status {0:#04x}
+    Unknown(u8),
+    /// invalid response from receiver
+    InvalidResponse,
+    /// no response from receiver
+    NoResponse,
+    /// transfer did not complete after the maximum number of retries
+    RetriesExhausted,
+}
+
+impl TransferError {
+    fn from_status(status: u8) -> Self {
+        match status {
+            STATUS_CHECKSUM_MISMATCH => Self::ChecksumMismatch,
+            STATUS_INVALID_OFFSET => Self::InvalidOffset,
+            STATUS_TOO_LARGE => Self::TooLarge,
+            0x04 => Self::Busy,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+impl From<TransferError> for Error {
+    fn from(err: TransferError) -> Self {
+        Error::new(ErrorKind::TransferFailed(err))
+    }
+}
+
+/// Chunked, CRC-checked transfer of large payloads to a remote device.
+///
+/// See the [module documentation](self) for the protocol.
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    data: Characteristic,
+    response: Characteristic,
+    chunk_size: Option<usize>,
+    op_type: WriteOp,
+    max_retries: usize,
+}
+
+impl Transfer {
+    /// Creates a transfer writing frames to the `data` characteristic and receiving
+    /// responses from the `response` characteristic.
+    ///
+    /// By default data chunks are written without response and a transfer is
+    /// resumed up to three times after chunks have been lost.
+    pub fn new(data: Characteristic, response: Characteristic) -> Self {
+        Self { data, response, chunk_size: None, op_type: WriteOp::Command, max_retries: 3 }
+    }
+
+    /// Sets the number of payload bytes transferred in one data frame.
+    ///
+    /// By default this is determined from the MTU of the data characteristic.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size.max(1));
+        self
+    }
+
+    /// Sets the write operation type used for data frames.
+    ///
+    /// Start and end frames are always written using write requests.
+    pub fn op_type(mut self, op_type: WriteOp) -> Self {
+        self.op_type = op_type;
+        self
+    }
+
+    /// Sets how often the transfer is resumed when the receiver reports lost chunks.
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sends the payload to the receiver.
+    ///
+    /// If the receiver holds a part of the same payload from an interrupted transfer,
+    /// only the remaining part is sent.
+    /// The `progress` function is called after each chunk has been written.
+    pub async fn send(&self, payload: &[u8], mut progress: impl FnMut(Progress) + Send) -> Result<()> {
+        let mut sender = Sender::new(payload, self.max_retries)?;
+
+        let responses = self.response.notify().await?;
+        pin_mut!(responses);
+
+        let chunk_size = match self.chunk_size {
+            Some(chunk_size) => chunk_size,
+            None => self.data.mtu().await?.saturating_sub(ATT_WRITE_HEADER + DATA_HEADER).max(1),
+        };
+
+        let resp = self.request(&mut responses, &sender.start_frame()).await?;
+        sender.started(&resp)?;
+        if sender.offset > 0 {
+            log::trace!("Resuming transfer at offset {}", sender.offset);
+        }
+        progress(sender.progress());
+
+        let req = CharacteristicWriteRequest { op_type: self.op_type, ..Default::default() };
+        loop {
+            while let Some(frame) = sender.next_frame(chunk_size) {
+                self.data.write_ext(&frame, &req).await?;
+                progress(sender.progress());
+            }
+
+            let resp = self.request(&mut responses, &[OP_END]).await?;
+            if sender.ended(&resp)? {
+                break;
+            }
+            log::trace!("Receiver lost chunks, resuming transfer at offset {}", sender.offset);
+            progress(sender.progress());
+        }
+
+        Ok(())
+    }
+
+    /// Writes a frame and returns the response.
+    async fn request(
+        &self, responses: &mut (impl Stream<Item = Vec<u8>> + Unpin), frame: &[u8],
+    ) -> Result<Vec<u8>> {
+        let write_req = CharacteristicWriteRequest { op_type: WriteOp::Request, ..Default::default() };
+        self.data.write_ext(frame, &write_req).await?;
+
+        match tokio::time::timeout(TIMEOUT, responses.next()).await {
+            Ok(Some(resp)) => Ok(resp),
+            Ok(None) => Err(Error::new(ErrorKind::NotificationSessionStopped)),
+            Err(_) => Err(TransferError::NoResponse.into()),
+        }
+    }
+}
+
+/// Sending side of the protocol, independent of the characteristics used.
+#[derive(Debug)]
+struct Sender<'a> {
+    payload: &'a [u8],
+    max_retries: usize,
+    offset: usize,
+    retries: usize,
+}
+
+impl<'a> Sender<'a> {
+    fn new(payload: &'a [u8], max_retries: usize) -> std::result::Result<Self, TransferError> {
+        if u32::try_from(payload.len()).is_err() {
+            return Err(TransferError::TooLarge);
+        }
+        Ok(Self { payload, max_retries, offset: 0, retries: 0 })
+    }
+
+    fn progress(&self) -> Progress {
+        Progress { transferred: self.offset, total: self.payload.len() }
+    }
+
+    fn start_frame(&self) -> Vec<u8> {
+        let mut frame = vec![OP_START];
+        frame.extend_from_slice(&(self.payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&crc32(0, self.payload).to_le_bytes());
+        frame
+    }
+
+    /// Processes the response to the start frame.
+    fn started(&mut self, resp: &[u8]) -> std::result::Result<(), TransferError> {
+        match parse_response(OP_START, resp)? {
+            (STATUS_SUCCESS, offset) => {
+                self.offset = offset.min(self.payload.len());
+                Ok(())
+            }
+            (status, _) => Err(TransferError::from_status(status)),
+        }
+    }
+
+    /// Returns the next data frame, or `None` if all data has been sent.
+    fn next_frame(&mut self, chunk_size: usize) -> Option<Vec<u8>> {
+        let chunk = self.payload[self.offset..].chunks(chunk_size).next()?;
+        let mut frame = Vec::with_capacity(DATA_HEADER + chunk.len());
+        frame.push(OP_DATA);
+        frame.extend_from_slice(&(self.offset as u32).to_le_bytes());
+        frame.extend_from_slice(chunk);
+        self.offset += chunk.len();
+        Some(frame)
+    }
+
+    /// Processes the response to the end frame.
+    ///
+    /// Returns whether the transfer is complete.
+    /// Otherwise data frames must be sent again from the offset the receiver reported.
+    fn ended(&mut self, resp: &[u8]) -> std::result::Result<bool, TransferError> {
+        match parse_response(OP_END, resp)? {
+            (STATUS_SUCCESS, _) => Ok(true),
+            (STATUS_INVALID_OFFSET, received) if self.retries < self.max_retries => {
+                self.retries += 1;
+                self.offset = received.min(self.payload.len());
+                Ok(false)
+            }
+            (STATUS_INVALID_OFFSET, _) => Err(TransferError::RetriesExhausted),
+            (status, _) => Err(TransferError::from_status(status)),
+        }
+    }
+}
+
+/// Returns the status and offset of a response to a frame with opcode `op`.
+fn parse_response(op: u8, resp: &[u8]) -> std::result::Result<(u8, usize), TransferError> {
+    match resp {
+        [resp_op, status, a, b, c, d, ..] if *resp_op == OP_RESPONSE | op => {
+            Ok((*status, u32::from_le_bytes([*a, *b, *c, *d]) as usize))
+        }
+        _ => Err(TransferError::InvalidResponse),
+    }
+}
+
+/// Receiving side of the transfer protocol.
+///
+/// Pass each frame written to the data characteristic to [receive](Self::receive)
+/// and notify the returned response, if any, using the response characteristic.
+/// Partially received data is kept until a different payload is announced,
+/// so that an interrupted transfer of the same payload is resumed.
+#[derive(Debug, Clone)]
+pub struct Receiver {
+    max_len: usize,
+    transfer: Option<(usize, u32)>,
+    data: Vec<u8>,
+    payload: Option<Vec<u8>>,
+}
+
+impl Receiver {
+    /// Creates a receiver accepting payloads of up to `max_len` bytes.
+    pub fn new(max_len: usize) -> Self {
+        Self { max_len, transfer: None, data: Vec::new(), payload: None }
+    }
+
+    /// Processes a frame and returns the response to send, if any.
+    ///
+    /// Malformed frames and data frames outside of a transfer are ignored.
+    pub fn receive(&mut self, frame: &[u8]) -> Option<Vec<u8>> {
+        match frame {
+            [OP_START, l0, l1, l2, l3, c0, c1, c2, c3] => {
+                let len = u32::from_le_bytes([*l0, *l1, *l2, *l3]) as usize;
+                let crc = u32::from_le_bytes([*c0, *c1, *c2, *c3]);
+                if len > self.max_len {
+                    self.transfer = None;
+                    return Some(Self::response(OP_START, STATUS_TOO_LARGE, 0));
+                }
+                if self.transfer != Some((len, crc)) {
+                    self.transfer = Some((len, crc));
+                    self.data.clear();
+                }
+                Some(Self::response(OP_START, STATUS_SUCCESS, self.data.len()))
+            }
+            [OP_DATA, o0, o1, o2, o3, chunk @ ..] => {
+                let offset = u32::from_le_bytes([*o0, *o1, *o2, *o3]) as usize;
+                if let Some((len, _)) = self.transfer {
+                    // Chunks after a lost chunk are dropped and reported by the end response.
+                    if offset == self.data.len() && offset + chunk.len() <= len {
+                        self.data.extend_from_slice(chunk);
+                    }
+                }
+                None
+            }
+            [OP_END] => {
+                let (len, crc) = self.transfer?;
+                let received = self.data.len();
+                let status = if received < len {
+                    STATUS_INVALID_OFFSET
+                } else if crc32(0, &self.data) != crc {
+                    self.transfer = None;
+                    self.data.clear();
+                    STATUS_CHECKSUM_MISMATCH
+                } else {
+                    self.transfer = None;
+                    self.payload = Some(std::mem::take(&mut self.data));
+                    STATUS_SUCCESS
+                };
+                Some(Self::response(OP_END, status, received))
+            }
+            _ => None,
+        }
+    }
+
+    /// Progress of the current transfer.
+    pub fn progress(&self) -> Progress {
+        match self.transfer {
+            Some((total, _)) => Progress { transferred: self.data.len(), total },
+            None => Progress::default(),
+        }
+    }
+
+    /// Takes the most recently completed payload.
+    pub fn take(&mut self) -> Option<Vec<u8>> {
+        self.payload.take()
+    }
+
+    fn response(op: u8, status: u8, offset: usize) -> Vec<u8> {
+        let mut resp = vec![OP_RESPONSE | op, status];
+        resp.extend_from_slice(&(offset as u32).to_le_bytes());
+        resp
+    }
+}
+
+/// Continues the CRC-32 (IEEE 802.3) checksum `crc` over `data`.
+///
+/// Start with a checksum of zero.
+pub(crate) fn crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(0, b""), 0);
+        assert_eq!(crc32(0, b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(crc32(0, b"12345"), b"6789"), 0xcbf4_3926);
+    }
+
+    /// Runs a transfer between `sender` and `receiver`, dropping the data frames
+    /// for which `lose` returns true, and returns the reported progress.
+    fn run(
+        sender: &mut Sender, receiver: &mut Receiver, chunk_size: usize, mut lose: impl FnMut(&[u8]) -> bool,
+    ) -> std::result::Result<Vec<usize>, TransferError> {
+        let resp = receiver.receive(&sender.start_frame()).unwrap();
+        sender.started(&resp)?;
+        let mut transferred = vec![sender.progress().transferred];
+        loop {
+            while let Some(frame) = sender.next_frame(chunk_size) {
+                if !lose(&frame) {
+                    assert_eq!(receiver.receive(&frame), None);
+                }
+                transferred.push(sender.progress().transferred);
+            }
+            let resp = receiver.receive(&[OP_END]).unwrap();
+            if sender.ended(&resp)? {
+                return Ok(transferred);
+            }
+            transferred.push(sender.progress().transferred);
+        }
+    }
+
+    #[test]
+    fn frames_are_encoded() {
+        let mut sender = Sender::new(b"123456789", 3).unwrap();
+        assert_eq!(sender.start_frame(), [OP_START, 9, 0, 0, 0, 0x26, 0x39, 0xf4, 0xcb]);
+        assert_eq!(sender.next_frame(4).unwrap(), [OP_DATA, 0, 0, 0, 0, b'1', b'2', b'3', b'4']);
+        assert_eq!(sender.next_frame(4).unwrap(), [OP_DATA, 4, 0, 0, 0, b'5', b'6', b'7', b'8']);
+        assert_eq!(sender.next_frame(4).unwrap(), [OP_DATA, 8, 0, 0, 0, b'9']);
+        assert_eq!(sender.next_frame(4), None);
+        assert_eq!(sender.progress(), Progress { transferred: 9, total: 9 });
+    }
+
+    #[test]
+    fn responses_are_parsed() {
+        assert_eq!(parse_response(OP_END, &[0x83, 0x02, 0x05, 0x01, 0, 0]), Ok((0x02, 0x105)));
+        assert_eq!(parse_response(OP_START, &[0x81, 0x00, 0x05, 0, 0, 0, 0xff]), Ok((0x00, 5)));
+        assert_eq!(parse_response(OP_END, &[0x81, 0x00, 0x05, 0, 0, 0]), Err(TransferError::InvalidResponse));
+        assert_eq!(parse_response(OP_END, &[0x83, 0x00, 0x05, 0, 0]), Err(TransferError::InvalidResponse));
+        assert_eq!(parse_response(OP_END, &[]), Err(TransferError::InvalidResponse));
+
+        let mut sender = Sender::new(b"123456789", 3).unwrap();
+        assert_eq!(sender.started(&[0x81, 0x00]), Err(TransferError::InvalidResponse));
+        assert_eq!(sender.started(&[0x81, 0x04, 0, 0, 0, 0]), Err(TransferError::Busy));
+        assert_eq!(sender.ended(&[0x83, 0x01, 9, 0, 0, 0]), Err(TransferError::ChecksumMismatch));
+        assert_eq!(sender.ended(&[0x83, 0x7f, 9, 0, 0, 0]), Err(TransferError::Unknown(0x7f)));
+    }
+
+    #[test]
+    fn payload_is_received() {
+        let payload: Vec<u8> = (0..=255).collect();
+        let mut receiver = Receiver::new(1024);
+        let mut sender = Sender::new(&payload, 3).unwrap();
+        let transferred = run(&mut sender, &mut receiver, 100, |_| false).unwrap();
+        assert_eq!(transferred, [0, 100, 200, 256]);
+        assert_eq!(receiver.take(), Some(payload));
+        assert_eq!(receiver.take(), None);
+    }
+
+    #[test]
+    fn lost_chunk_is_resent() {
+        let payload: Vec<u8> = (0..=255).collect();
+        let mut receiver = Receiver::new(1024);
+        let mut sender = Sender::new(&payload, 3).unwrap();
+        let mut lost = false;
+        let transferred = run(&mut sender, &mut receiver, 100, |frame| {
+            let lose = !lost && frame[1] == 100;
+            lost |= lose;
+            lose
+        })
+        .unwrap();
+        assert_eq!(transferred, [0, 100, 200, 256, 100, 200, 256]);
+        assert_eq!(receiver.take(), Some(payload));
+    }
+
+    #[test]
+    fn retries_are_exhausted() {
+        let payload: Vec<u8> = (0..=255).collect();
+        let mut receiver = Receiver::new(1024);
+        let mut sender = Sender::new(&payload, 2).unwrap();
+        let mut sent = 0;
+        let res = run(&mut sender, &mut receiver, 100, |frame| {
+            sent += 1;
+            frame[1] == 100
+        });
+        assert_eq!(res, Err(TransferError::RetriesExhausted));
+        assert_eq!(sent, 3 + 2 * 2);
+        assert_eq!(receiver.progress(), Progress { transferred: 100, total: 256 });
+        assert_eq!(receiver.take(), None);
+    }
+
+    #[test]
+    fn interrupted_transfer_is_resumed() {
+        let payload: Vec<u8> = (0..=255).collect();
+        let mut receiver = Receiver::new(1024);
+
+        // The connection is lost after two chunks.
+        let mut sender = Sender::new(&payload, 3).unwrap();
+        receiver.receive(&sender.start_frame()).unwrap();
+        for _ in 0..2 {
+            receiver.receive(&sender.next_frame(100).unwrap());
+        }
+
+        let mut sender = Sender::new(&payload, 3).unwrap();
+        let transferred = run(&mut sender, &mut receiver, 100, |_| false).unwrap();
+        assert_eq!(transferred, [200, 256]);
+        assert_eq!(receiver.take(), Some(payload.clone()));
+
+        // A different payload starts from the beginning.
+        let other = vec![0xaa; 150];
+        receiver.receive(&Sender::new(&payload, 3).unwrap().start_frame()).unwrap();
+        let mut sender = Sender::new(&other, 3).unwrap();
+        let transferred = run(&mut sender, &mut receiver, 100, |_| false).unwrap();
+        assert_eq!(transferred, [0, 100, 150]);
+        assert_eq!(receiver.take(), Some(other));
+    }
+
+    #[test]
+    fn receiver_rejects_invalid_payloads() {
+        let payload = vec![0x55; 300];
+        let mut receiver = Receiver::new(256);
+        let mut sender = Sender::new(&payload, 3).unwrap();
+        assert_eq!(run(&mut sender, &mut receiver, 100, |_| false), Err(TransferError::TooLarge));
+
+        let mut receiver = Receiver::new(1024);
+        let mut sender = Sender::new(&payload, 3).unwrap();
+        let resp = receiver.receive(&sender.start_frame()).unwrap();
+        sender.started(&resp).unwrap();
+        while let Some(mut frame) = sender.next_frame(100) {
+            frame[DATA_HEADER] ^= 0xff;
+            receiver.receive(&frame);
+        }
+        let resp = receiver.receive(&[OP_END]).unwrap();
+        assert_eq!(sender.ended(&resp), Err(TransferError::ChecksumMismatch));
+        assert_eq!(receiver.take(), None);
+        assert_eq!(receiver.receive(&[OP_END]), None);
+    }
+}
@@ -0,0 +1,317 @@
+//! Health Device Profile (HDP).
+//!
+//! The Health Device Profile is used by medical devices, such as blood pressure monitors,
+//! thermometers and pulse oximeters, to exchange IEEE 11073 data with a manager.
+//!
+//! Register a health [Application] for a data type using
+//! [Session::create_health_application](crate::Session::create_health_application)
+//! and use [HealthDevice] to create data channels to remote devices.
+//! The data of a [Channel] is exchanged over an L2CAP socket obtained using [Channel::acquire].
+//!
+//! The Bluetooth daemon must have been built with support for the health profile
+//! (configure option `--enable-health`).
+
+use dbus::{
+    arg::{OwnedFd, PropMap, RefArg, Variant},
+    nonblock::{Proxy, SyncConnection},
+    Path,
+};
+use futures::{stream, Stream, StreamExt};
+use std::{fmt, os::unix::io::IntoRawFd, sync::Arc};
+use strum::{Display, EnumString};
+use tokio::sync::oneshot;
+
+use crate::{
+    l2cap::SeqPacket, Address, Device, Error, ErrorKind, InternalErrorKind, ObjectEvent, Result, SessionInner,
+    SERVICE_NAME, TIMEOUT,
+};
+
+pub(crate) const MANAGER_INTERFACE: &str = "org.bluez.HealthManager1";
+pub(crate) const MANAGER_PATH: &str = "/org/bluez";
+pub(crate) const DEVICE_INTERFACE: &str = "org.bluez.HealthDevice1";
+pub(crate) const CHANNEL_INTERFACE: &str = "org.bluez.HealthChannel1";
+
+/// Role of a health application.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Role {
+    /// Source of data, such as a measurement device.
+    #[strum(serialize = "source")]
+    Source,
+    /// Sink of data, such as a manager collecting measurements.
+    #[default]
+    #[strum(serialize = "sink")]
+    Sink,
+}
+
+/// Type of a health data channel.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChannelType {
+    /// Reliable channel using enhanced retransmission mode.
+    #[strum(serialize = "reliable")]
+    Reliable,
+    /// Streaming channel without retransmissions.
+    #[strum(serialize = "streaming")]
+    Streaming,
+}
+
+/// Health application definition.
+///
+/// Use [Session::create_health_application](crate::Session::create_health_application)
+/// to register an application.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Application {
+    /// IEEE 11073 device specialization (data type) of the application,
+    /// for example `0x1007` for blood pressure monitors.
+    pub data_type: u16,
+    /// Role of the application.
+    pub role: Role,
+    /// Human readable description of the application.
+    pub description: Option<String>,
+    /// Type of the data channels of the application.
+    ///
+    /// Must be set for the [source role](Role::Source).
+    pub channel_type: Option<ChannelType>,
+    #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _non_exhaustive: (),
+}
+
+impl Application {
+    fn to_dict(&self) -> PropMap {
+        let mut pm = PropMap::new();
+        pm.insert("DataType".to_string(), Variant(self.data_type.box_clone()));
+        pm.insert("Role".to_string(), Variant(self.role.to_string().box_clone()));
+        if let Some(description) = &self.description {
+            pm.insert("Description".to_string(), Variant(description.box_clone()));
+        }
+        if let Some(channel_type) = &self.channel_type {
+            pm.insert("ChannelType".to_string(), Variant(channel_type.to_string().box_clone()));
+        }
+        pm
+    }
+
+    pub(crate) async fn register(self, inner: Arc<SessionInner>) -> Result<ApplicationHandle> {
+        if self.role == Role::Source && self.channel_type.is_none() {
+            return Err(Error {
+                kind: ErrorKind::InvalidArguments,
+                message: "channel type must be specified for the source role".to_string(),
+            });
+        }
+
+        log::trace!("Creating health application {:?}", &self);
+        let proxy = Proxy::new(SERVICE_NAME, MANAGER_PATH, TIMEOUT, inner.connection.clone());
+        let (name,): (Path<'static>,) =
+            proxy.method_call(MANAGER_INTERFACE, "CreateApplication", (self.to_dict(),)).await?;
+
+        let (drop_tx, drop_rx) = oneshot::channel();
+        let unreg_name = name.clone();
+        inner.clone().spawn(async move {
+            let _ = drop_rx.await;
+
+            log::trace!("Destroying health application {}", &unreg_name);
+            let _: std::result::Result<(), dbus::Error> =
+                proxy.method_call(MANAGER_INTERFACE, "DestroyApplication", (unreg_name,)).await;
+        });
+
+        Ok(ApplicationHandle { name, _drop_tx: drop_tx })
+    }
+}
+
+/// Handle to a registered health application.
+///
+/// Drop this handle to destroy the application.
+#[must_use = "ApplicationHandle must be held for the health application to remain registered"]
+pub struct ApplicationHandle {
+    name: Path<'static>,
+    _drop_tx: oneshot::Sender<()>,
+}
+
+impl Drop for ApplicationHandle {
+    fn drop(&mut self) {
+        // required for drop order
+    }
+}
+
+impl fmt::Debug for ApplicationHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ApplicationHandle {{ {} }}", &self.name)
+    }
+}
+
+/// Health profile interface of a remote device.
+///
+/// Use [Device::health] to obtain an instance.
+#[derive(Clone)]
+pub struct HealthDevice {
+    inner: Arc<SessionInner>,
+    dbus_path: Path<'static>,
+    adapter_name: Arc<String>,
+    address: Address,
+}
+
+impl fmt::Debug for HealthDevice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HealthDevice {{ adapter_name: {}, address: {} }}", self.adapter_name(), self.address())
+    }
+}
+
+impl HealthDevice {
+    pub(crate) fn new(inner: Arc<SessionInner>, adapter_name: Arc<String>, address: Address) -> Result<Self> {
+        Ok(Self { inner, dbus_path: Device::make_dbus_path(&adapter_name, address)?, adapter_name, address })
+    }
+
+    fn proxy(&self) -> Proxy<'_, &SyncConnection> {
+        Proxy::new(SERVICE_NAME, &self.dbus_path, TIMEOUT, &*self.inner.connection)
+    }
+
+    /// The Bluetooth adapter name.
+    pub fn adapter_name(&self) -> &str {
+        &self.adapter_name
+    }
+
+    /// The Bluetooth address of the remote device.
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Sends an echo petition to the remote device.
+    ///
+    /// Returns whether the device responded with the same data.
+    pub async fn echo(&self) -> Result<bool> {
+        let (ok,): (bool,) = self.call_method("Echo", ()).await?;
+        Ok(ok)
+    }
+
+    /// Creates a new data channel to the remote device for the specified application.
+    ///
+    /// If `channel_type` is [None], the type is chosen by the remote device.
+    /// A channel type must be specified if the application has the [source role](Role::Source).
+    pub async fn create_channel(
+        &self, application: &ApplicationHandle, channel_type: Option<ChannelType>,
+    ) -> Result<Channel> {
+        let configuration = channel_type.map(|ty| ty.to_string()).unwrap_or_else(|| "any".to_string());
+        let (path,): (Path<'static>,) =
+            self.call_method("CreateChannel", (application.name.clone(), configuration)).await?;
+        Ok(Channel::new(self.inner.clone(), path))
+    }
+
+    /// Destroys the data channel.
+    pub async fn destroy_channel(&self, channel: &Channel) -> Result<()> {
+        self.call_method("DestroyChannel", (channel.dbus_path.clone(),)).await
+    }
+
+    /// The first reliable data channel opened with the remote device.
+    ///
+    /// Returns [None] if no such channel exists.
+    pub async fn main_channel(&self) -> Result<Option<Channel>> {
+        let path: Option<Path<'static>> = self.get_opt_property("MainChannel").await?;
+        Ok(path.map(|path| Channel::new(self.inner.clone(), path)))
+    }
+
+    /// Streams data channel events of the remote device.
+    ///
+    /// Channels are reported when they are created either locally or by the remote device.
+    /// The stream ends when the device is removed.
+    pub async fn events(&self) -> Result<impl Stream<Item = HealthDeviceEvent>> {
+        let inner = self.inner.clone();
+        let events = self.inner.events(self.dbus_path.clone(), true).await?;
+        let stream = events.flat_map(move |event| {
+            let evt = match event {
+                ObjectEvent::ObjectAdded { object, interfaces } if interfaces.contains(CHANNEL_INTERFACE) => {
+                    Some(HealthDeviceEvent::ChannelConnected(Channel::new(inner.clone(), object)))
+                }
+                ObjectEvent::ObjectRemoved { object, interfaces } if interfaces.contains(CHANNEL_INTERFACE) => {
+                    Some(HealthDeviceEvent::ChannelDeleted(Channel::new(inner.clone(), object)))
+                }
+                _ => None,
+            };
+            stream::iter(evt)
+        });
+        Ok(stream)
+    }
+
+    dbus_interface!();
+    dbus_default_interface!(DEVICE_INTERFACE);
+}
+
+/// Health data channel event.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum HealthDeviceEvent {
+    /// A data channel has been connected.
+    ChannelConnected(Channel),
+    /// A data channel has been deleted.
+    ChannelDeleted(Channel),
+}
+
+/// Health data channel.
+#[derive(Clone)]
+pub struct Channel {
+    inner: Arc<SessionInner>,
+    dbus_path: Path<'static>,
+}
+
+impl fmt::Debug for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Channel {{ {} }}", &self.dbus_path)
+    }
+}
+
+impl PartialEq for Channel {
+    fn eq(&self, other: &Self) -> bool {
+        self.dbus_path == other.dbus_path
+    }
+}
+
+impl Eq for Channel {}
+
+impl Channel {
+    fn new(inner: Arc<SessionInner>, dbus_path: Path<'static>) -> Self {
+        Self { inner, dbus_path }
+    }
+
+    fn proxy(&self) -> Proxy<'_, &SyncConnection> {
+        Proxy::new(SERVICE_NAME, &self.dbus_path, TIMEOUT, &*self.inner.connection)
+    }
+
+    /// The type of the channel.
+    pub async fn channel_type(&self) -> Result<ChannelType> {
+        let ty: String = self.get_property("Type").await?;
+        ty.parse().map_err(|_| Error::new(ErrorKind::Internal(InternalErrorKind::InvalidValue)))
+    }
+
+    /// The Bluetooth address of the remote device the channel belongs to.
+    pub async fn device_address(&self) -> Result<Address> {
+        let path: Path<'static> = self.get_property("Device").await?;
+        match Device::parse_dbus_path(&path) {
+            Some((_, address)) => Ok(address),
+            None => Err(Error::new(ErrorKind::Internal(InternalErrorKind::InvalidValue))),
+        }
+    }
+
+    /// Acquires the L2CAP socket of the data channel.
+    ///
+    /// IEEE 11073 data packets are exchanged using [SeqPacket::send] and [SeqPacket::recv].
+    /// Use [release](Self::release) to give up the socket.
+    pub async fn acquire(&self) -> Result<SeqPacket> {
+        let (fd,): (OwnedFd,) = self.call_method("Acquire", ()).await?;
+        let fd = fd.into_raw_fd();
+        if unsafe { libc::fcntl(fd, libc::F_SETFL, libc::O_NONBLOCK) } == -1 {
+            let err = std::io::Error::last_os_error();
+            unsafe { libc::close(fd) };
+            return Err(err.into());
+        }
+        Ok(unsafe { SeqPacket::from_raw_fd(fd) }?)
+    }
+
+    /// Releases the socket of the data channel.
+    pub async fn release(&self) -> Result<()> {
+        self.call_method("Release", ()).await
+    }
+
+    dbus_interface!();
+    dbus_default_interface!(CHANNEL_INTERFACE);
+}
@@ -139,7 +139,7 @@ impl sock::SysSockAddr for SocketAddr {
     }
 
     fn try_from_sys_sock_addr(saddr: Self::SysSockAddr) -> Result<Self> {
-        if saddr.l2_family != AF_BLUETOOTH as _ {
+        if saddr.l2_family != AF_BLUETOOTH as libc::sa_family_t {
             return Err(Error::new(ErrorKind::InvalidInput, "sockaddr_l2::l2_family is not AF_BLUETOOTH"));
         }
         Ok(Self {
@@ -271,7 +271,7 @@ impl<Type> Socket<Type> {
     /// This corresponds to the `BT_POWER` socket option.
     pub fn is_power_forced_active(&self) -> Result<bool> {
         let value: bt_power = sock::getsockopt(self.fd.get_ref(), SOL_BLUETOOTH, BT_POWER)?;
-        Ok(value.force_active == BT_POWER_FORCE_ACTIVE_ON as _)
+        Ok(value.force_active == BT_POWER_FORCE_ACTIVE_ON as u8)
     }
 
     /// Set forced power state.
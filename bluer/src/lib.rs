@@ -64,14 +64,36 @@
 //! ## Crate features
 //! The following crate features are available.
 //!
-//! * `bluetoothd`: Enables all functions requiring a running Bluetooth daemon.
+//! * `bluetoothd`: Enables all functions requiring a running Bluetooth daemon, i.e. the
+//!   `bluetoothd-core`, `adv`, `agent`, `gatt-client`, `gatt-server` and `monitor` features.
+//! * `bluetoothd-core`: Enables the core functions requiring a running Bluetooth daemon,
+//!   i.e. sessions, adapters, devices, device discovery and change events.
+//!   Use it together with the features below to build only the required subsystems.
+//! * `adv`: Enables sending of Bluetooth LE advertisements.
+//! * `agent`: Enables registration of Bluetooth authorization agents and the pairing flow for new devices.
+//! * `gatt-client`: Enables consumption of remote GATT services.
+//! * `gatt-server`: Enables publishing of local GATT services.
+//! * `health`: Enables the [Health Device Profile](health) for medical devices.
+//! * `monitor`: Enables passive LE advertisement monitoring.
+//! * `dfu`: Enables [firmware updates](dfu) over GATT.
 //! * `id`: Enables database of assigned numbers.
 //! * `l2cap`: Enables L2CAP sockets.
 //! * `rfcomm`: Enables RFCOMM sockets.
 //! * `mesh`: Enables Bluetooth mesh functionality.
+//! * `mgmt`: Enables access to the [management interface](mgmt) of the kernel.
+//! * `ofono`: Enables observing [Hands-Free Profile audio gateways](ofono) managed by oFono.
+//! * `registry`: Enables a [registry](registry) persisting metadata of known devices.
+//! * `sensors`: Enables [decoders](sensors) for sensor beacon advertisement formats.
 //! * `serde`: Enables serialization and deserialization of some data types.
+//! * `test-harness`: Enables a [test harness](test_harness) running a private Bluetooth daemon
+//!   on emulated controllers.
+//! * `tracing`: Enables instrumentation of D-Bus calls, callbacks of exported objects and
+//!   events using spans of the [tracing](https://docs.rs/tracing) crate.
+//!   Spans carry the adapter name, device address and GATT service, characteristic and
+//!   descriptor UUIDs where applicable.
 //!
-//! To enable all crate features specify the `full` crate feature.
+//! To enable all crate features except `test-harness` and `tracing`, which are meant for testing
+//! and debugging, specify the `full` crate feature.
 //!
 //! ## Basic usage
 //! Create a [Session] using [Session::new]; this establishes a connection to the Bluetooth daemon.
@@ -92,22 +114,18 @@
 #[cfg(not(target_os = "linux"))]
 compile_error!("BlueR only supports the Linux operating system.");
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 use dbus::{
-    arg::{prop_cast, AppendAll, PropMap, RefArg, Variant},
+    arg::{prop_cast, PropMap, RefArg, Variant},
     nonblock::{stdintf::org_freedesktop_dbus::ObjectManager, Proxy, SyncConnection},
     Path,
 };
-#[cfg(feature = "bluetoothd")]
-use dbus_crossroads::{Context, Crossroads};
-#[cfg(feature = "bluetoothd")]
-use futures::Future;
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 use hex::FromHex;
 use macaddr::MacAddr6;
 use num_derive::FromPrimitive;
-#[cfg(feature = "bluetoothd")]
-use std::{collections::HashMap, marker::PhantomData, sync::Arc, time::Duration};
+#[cfg(feature = "bluetoothd-core")]
+use std::{collections::HashMap, time::Duration};
 use std::{
     convert::TryInto,
     fmt::{self, Debug, Display, Formatter},
@@ -115,24 +133,31 @@ use std::{
     str::FromStr,
 };
 use strum::{Display, EnumString};
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 use tokio::task::JoinError;
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 pub(crate) const SERVICE_NAME: &str = "org.bluez";
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 pub(crate) const ERR_PREFIX: &str = "org.bluez.Error.";
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 pub(crate) const TIMEOUT: Duration = Duration::from_secs(120);
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(any(
+    feature = "adv",
+    feature = "agent",
+    feature = "gatt-server",
+    feature = "mesh",
+    feature = "monitor",
+    all(feature = "bluetoothd-core", feature = "rfcomm")
+))]
 macro_rules! publish_path {
     ($path:expr) => {
         concat!("/org/bluez/", env!("CARGO_PKG_NAME"), "/", $path)
     };
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 macro_rules! dbus_interface {
     () => {
         #[allow(dead_code)]
@@ -141,7 +166,20 @@ macro_rules! dbus_interface {
             R: for<'b> dbus::arg::Get<'b> + std::fmt::Debug + 'static,
         {
             use dbus::nonblock::stdintf::org_freedesktop_dbus::Properties;
-            let value = self.proxy().get(interface, name).await?;
+            let start = std::time::Instant::now();
+            let value = self.proxy().get(interface, name);
+            #[cfg(feature = "tracing")]
+            let value = crate::instrument_dbus_call(value, &self.proxy().path, interface, name);
+            let value: crate::Result<R> = value.await.map_err(Into::into);
+            self.inner.record_dbus_call(
+                &self.proxy().path,
+                interface,
+                "Get",
+                Some(name),
+                start.elapsed(),
+                value.as_ref().err(),
+            );
+            let value = value?;
             log::trace!("{}: {}.{} = {:?}", &self.proxy().path, &interface, &name, &value);
             Ok(value)
         }
@@ -154,7 +192,11 @@ macro_rules! dbus_interface {
             R: for<'b> dbus::arg::Get<'b> + std::fmt::Debug + 'static,
         {
             use dbus::nonblock::stdintf::org_freedesktop_dbus::Properties;
-            match self.proxy().get(interface, name).await {
+            let start = std::time::Instant::now();
+            let value = self.proxy().get(interface, name);
+            #[cfg(feature = "tracing")]
+            let value = crate::instrument_dbus_call(value, &self.proxy().path, interface, name);
+            let value = match value.await {
                 Ok(value) => {
                     log::trace!("{}: {}.{} = {:?}", &self.proxy().path, &interface, &name, &value);
                     Ok(Some(value))
@@ -164,7 +206,16 @@ macro_rules! dbus_interface {
                     Ok(None)
                 }
                 Err(err) => Err(err.into()),
-            }
+            };
+            self.inner.record_dbus_call(
+                &self.proxy().path,
+                interface,
+                "Get",
+                Some(name),
+                start.elapsed(),
+                value.as_ref().err(),
+            );
+            value
         }
 
         #[allow(dead_code)]
@@ -174,8 +225,20 @@ macro_rules! dbus_interface {
         {
             use dbus::nonblock::stdintf::org_freedesktop_dbus::Properties;
             log::trace!("{}: {}.{} := {:?}", &self.proxy().path, &interface, &name, &value);
-            self.proxy().set(interface, name, value).await?;
-            Ok(())
+            let start = std::time::Instant::now();
+            let result = self.proxy().set(interface, name, value);
+            #[cfg(feature = "tracing")]
+            let result = crate::instrument_dbus_call(result, &self.proxy().path, interface, name);
+            let result: crate::Result<()> = result.await.map_err(Into::into);
+            self.inner.record_dbus_call(
+                &self.proxy().path,
+                interface,
+                "Set",
+                Some(name),
+                start.elapsed(),
+                result.as_ref().err(),
+            );
+            result
         }
 
         #[allow(dead_code)]
@@ -185,14 +248,26 @@ macro_rules! dbus_interface {
             R: dbus::arg::ReadAll + std::fmt::Debug + 'static,
         {
             log::trace!("{}: {}.{} {:?}", &self.proxy().path, &interface, &name, &args);
-            let result = self.proxy().method_call(interface, name, args).await;
+            let start = std::time::Instant::now();
+            let result = self.proxy().method_call(interface, name, args);
+            #[cfg(feature = "tracing")]
+            let result = crate::instrument_dbus_call(result, &self.proxy().path, interface, name);
+            let result: crate::Result<R> = result.await.map_err(Into::into);
             log::trace!("{}: {}.{} (...) -> {:?}", &self.proxy().path, &interface, &name, &result);
-            Ok(result?)
+            self.inner.record_dbus_call(
+                &self.proxy().path,
+                interface,
+                name,
+                None,
+                start.elapsed(),
+                result.as_ref().err(),
+            );
+            result
         }
     };
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 macro_rules! dbus_default_interface {
     ($interface:expr) => {
         #[allow(dead_code)]
@@ -230,7 +305,7 @@ macro_rules! dbus_default_interface {
     };
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 macro_rules! define_properties {
     (@get
         $(#[$outer:meta])*
@@ -339,7 +414,7 @@ macro_rules! define_properties {
         }
 
         $(#[$enum_outer])*
-        #[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+        #[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
         #[derive(Debug, Clone)]
         #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         #[non_exhaustive]
@@ -379,7 +454,7 @@ macro_rules! define_properties {
             }
 
             #[allow(dead_code)]
-            fn from_prop_map(prop_map: dbus::arg::PropMap) -> Vec<Self> {
+            pub(crate) fn from_prop_map(prop_map: dbus::arg::PropMap) -> Vec<Self> {
                 prop_map.into_iter().filter_map(|(name, value)|
                     Self::from_variant_property(&name, value).ok().flatten()
                 ).collect()
@@ -388,32 +463,55 @@ macro_rules! define_properties {
     }
 }
 
-#[cfg(feature = "bluetoothd")]
-fn variant_hashmap<K>(a: &(dyn RefArg + 'static)) -> HashMap<K, Variant<Box<dyn RefArg + 'static>>>
-where
-    K: std::hash::Hash + std::cmp::Eq + Clone + 'static,
-{
-    let mut hm: HashMap<K, Variant<Box<dyn RefArg + 'static>>> = HashMap::new();
-
-    let mut key: Option<K> = None;
-    for i in a.as_iter().unwrap() {
-        let ib = i.box_clone();
-        match key.take() {
-            Some(key) => {
-                let value: &Variant<Box<dyn RefArg + 'static>> = dbus::arg::cast(&ib).unwrap();
-                hm.insert(key, Variant(value.0.box_clone()));
-            }
-            None => {
-                let key_ref: Option<&K> = dbus::arg::cast(&ib);
-                key = Some(key_ref.unwrap().clone());
+/// Key of a D-Bus dictionary that is converted into a [HashMap] by [variant_hashmap].
+#[cfg(feature = "bluetoothd-core")]
+trait VariantKey: std::hash::Hash + Eq + Sized + 'static {
+    fn from_ref_arg(a: &dyn RefArg) -> Option<Self>;
+}
+
+#[cfg(feature = "bluetoothd-core")]
+impl VariantKey for u8 {
+    fn from_ref_arg(a: &dyn RefArg) -> Option<Self> {
+        a.as_u64().and_then(|v| v.try_into().ok())
+    }
+}
+
+#[cfg(feature = "bluetoothd-core")]
+impl VariantKey for u16 {
+    fn from_ref_arg(a: &dyn RefArg) -> Option<Self> {
+        a.as_u64().and_then(|v| v.try_into().ok())
+    }
+}
+
+#[cfg(feature = "bluetoothd-core")]
+impl VariantKey for String {
+    fn from_ref_arg(a: &dyn RefArg) -> Option<Self> {
+        a.as_str().map(|s| s.to_string())
+    }
+}
+
+/// Converts a D-Bus dictionary with variant values into a [HashMap].
+///
+/// Only the variant values are copied.
+#[cfg(feature = "bluetoothd-core")]
+fn variant_hashmap<K: VariantKey>(a: &(dyn RefArg + 'static)) -> HashMap<K, Variant<Box<dyn RefArg + 'static>>> {
+    let mut hm = HashMap::new();
+
+    let mut items = a.as_iter().unwrap();
+    while let (Some(key), Some(value)) = (items.next(), items.next()) {
+        // The variant iterates over its single contained value.
+        match (K::from_ref_arg(key), value.as_iter().and_then(|mut v| v.next())) {
+            (Some(key), Some(value)) => {
+                hm.insert(key, Variant(value.box_clone()));
             }
+            _ => log::warn!("Ignoring malformed dictionary entry with key {:?} and value {:?}", key, value),
         }
     }
 
     hm
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 pub(crate) fn with_variant_property_cast<T, R>(a: &(dyn RefArg + 'static), f: impl FnOnce(Option<&T>) -> R) -> R
 where
     T: 'static,
@@ -441,7 +539,7 @@ where
     }
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(any(feature = "adv", feature = "gatt-server", feature = "mesh", feature = "monitor"))]
 macro_rules! cr_property {
     ($ib:expr, $dbus_name:expr, $obj:ident => $get:block) => {
         $ib.property($dbus_name).get(|ctx, $obj| {
@@ -455,7 +553,7 @@ macro_rules! cr_property {
     };
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(any(feature = "gatt-client", feature = "gatt-server"))]
 macro_rules! define_flags {
     ($vis:vis $name:ident, $doc:tt => {
         $(
@@ -496,72 +594,175 @@ macro_rules! define_flags {
                 )*
                 s
             }
+
+            /// Names of the set flags as used by BlueZ.
+            pub fn to_strings(&self) -> Vec<&'static str> {
+                let mut v = Vec::new();
+                $(
+                    if self.$field {
+                        v.push($dbus_name);
+                    }
+                )*
+                v
+            }
+
+            /// Parses flags from their names as used by BlueZ.
+            ///
+            /// Fails with [ErrorKind::InvalidArguments](crate::ErrorKind::InvalidArguments)
+            /// if a name is unknown.
+            pub fn from_strings<S: AsRef<str>>(names: impl IntoIterator<Item = S>) -> crate::Result<Self> {
+                let mut s = Self::default();
+                for name in names {
+                    match name.as_ref().trim() {
+                        $(
+                            $dbus_name => s.$field = true,
+                        )*
+                        other => return Err(crate::Error {
+                            kind: crate::ErrorKind::InvalidArguments,
+                            message: format!("unknown flag: {other}"),
+                        }),
+                    }
+                }
+                Ok(s)
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            /// Formats the names of the set flags as a comma-separated list.
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{}", self.to_strings().join(","))
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = crate::Error;
+
+            /// Parses a comma-separated list of flag names.
+            fn from_str(s: &str) -> crate::Result<Self> {
+                Self::from_strings(s.split(',').filter(|name| !name.trim().is_empty()))
+            }
         }
     };
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "gatt-server")]
 macro_rules! read_prop {
     ($dict:expr, $name:expr, $type:ty) => {
         dbus::arg::prop_cast::<$type>($dict, $name).ok_or(MethodErr::invalid_arg($name))?.to_owned()
     };
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "gatt-server")]
 macro_rules! read_opt_prop {
     ($dict:expr, $name:expr, $type:ty) => {
         dbus::arg::prop_cast::<$type>($dict, $name).cloned()
     };
 }
 
-#[cfg(any(feature = "l2cap", feature = "rfcomm"))]
+#[cfg(any(feature = "l2cap", feature = "rfcomm", feature = "mgmt"))]
+#[cfg_attr(not(any(feature = "l2cap", feature = "rfcomm")), allow(dead_code))]
 #[macro_use]
 mod sock;
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 mod adapter;
-#[cfg(feature = "bluetoothd")]
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg(feature = "bluetoothd-core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 pub mod adv;
-#[cfg(feature = "bluetoothd")]
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg(feature = "agent")]
+#[cfg_attr(docsrs, doc(cfg(feature = "agent")))]
 pub mod agent;
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+pub mod backend;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+#[cfg(feature = "gatt-client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gatt-client")))]
+pub mod central;
+#[cfg(feature = "bluetoothd-core")]
 mod device;
-#[cfg(feature = "bluetoothd")]
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg(feature = "dfu")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dfu")))]
+pub mod dfu;
+#[cfg(any(feature = "gatt-client", feature = "gatt-server"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "gatt-client", feature = "gatt-server"))))]
 pub mod gatt;
+#[cfg(feature = "health")]
+#[cfg_attr(docsrs, doc(cfg(feature = "health")))]
+pub mod health;
 #[cfg(feature = "l2cap")]
 #[cfg_attr(docsrs, doc(cfg(feature = "l2cap")))]
 pub mod l2cap;
 #[cfg(feature = "mesh")]
 #[cfg_attr(docsrs, doc(cfg(feature = "mesh")))]
 pub mod mesh;
-#[cfg(feature = "bluetoothd")]
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg(feature = "mgmt")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mgmt")))]
+pub mod mgmt;
+#[cfg(feature = "monitor")]
+#[cfg_attr(docsrs, doc(cfg(feature = "monitor")))]
 pub mod monitor;
+#[cfg(feature = "ofono")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ofono")))]
+pub mod ofono;
+#[cfg(feature = "agent")]
+#[cfg_attr(docsrs, doc(cfg(feature = "agent")))]
+pub mod pairing;
+#[cfg(all(feature = "adv", feature = "gatt-server"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "adv", feature = "gatt-server"))))]
+pub mod peripheral;
+#[cfg(feature = "bluetoothd-core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+pub mod pool;
+#[cfg(feature = "registry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "registry")))]
+pub mod registry;
 #[cfg(feature = "rfcomm")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rfcomm")))]
 pub mod rfcomm;
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
+mod scan;
+#[cfg(feature = "sensors")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sensors")))]
+pub mod sensors;
+#[cfg(feature = "bluetoothd-core")]
 mod session;
 mod sys;
-
-#[cfg(feature = "bluetoothd")]
-pub use crate::{adapter::*, device::*, session::*};
+#[cfg(feature = "test-harness")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-harness")))]
+pub mod test_harness;
+#[cfg(feature = "bluetoothd-core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+pub mod wake;
+
+#[cfg(feature = "bluetoothd-core")]
+pub use crate::{adapter::*, device::*, scan::*, session::*};
+
+#[cfg(feature = "bluetoothd-core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[doc(no_inline)]
+pub use tokio_util::sync::CancellationToken;
 
+#[doc(no_inline)]
+pub use bytes::Bytes;
 #[doc(no_inline)]
 pub use uuid::Uuid;
 mod uuid_ext;
-pub use uuid_ext::UuidExt;
+pub use uuid_ext::{uuid_from_u16, uuid_from_u32, BtUuid, InvalidUuid, UuidExt};
+mod profile;
+pub use profile::Profile;
+mod distance;
+pub use distance::{path_loss, DistanceEstimator, Proximity, ProximityEvent};
 
 #[cfg(feature = "id")]
 #[cfg_attr(docsrs, doc(cfg(feature = "id")))]
 pub mod id;
 
 /// Bluetooth error.
-#[cfg(feature = "bluetoothd")]
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg(feature = "bluetoothd-core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Error {
@@ -572,8 +773,8 @@ pub struct Error {
 }
 
 /// Bluetooth error kind.
-#[cfg(feature = "bluetoothd")]
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg(feature = "bluetoothd-core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 #[derive(Clone, Debug, displaydoc::Display, Eq, PartialEq, Ord, PartialOrd, Hash, EnumString)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[ignore_extra_doc_attributes]
@@ -621,6 +822,9 @@ pub enum ErrorKind {
     /// invalid Bluetooth adapter name: {0}
     #[strum(disabled)]
     InvalidName(String),
+    /// invalid Bluetooth adapter D-Bus path: {0}
+    #[strum(disabled)]
+    InvalidAdapterPath(String),
     /// GATT services have not been resolved for that Bluetooth device
     #[strum(disabled)]
     ServicesUnresolved,
@@ -642,6 +846,15 @@ pub enum ErrorKind {
     /// the discovery filter cannot be changed while a discovery session is active
     #[strum(disabled)]
     DiscoveryActive,
+    /// the operation was cancelled
+    #[strum(disabled)]
+    Cancelled,
+    /// the operation timed out
+    #[strum(disabled)]
+    Timeout,
+    /// the operation requires experimental interfaces of the Bluetooth daemon, start it with --experimental
+    #[strum(disabled)]
+    ExperimentalDisabled,
     /// joining the mesh network failed: {0}
     #[cfg(feature = "mesh")]
     #[cfg_attr(docsrs, doc(cfg(feature = "mesh")))]
@@ -657,6 +870,16 @@ pub enum ErrorKind {
     #[cfg_attr(docsrs, doc(cfg(feature = "mesh")))]
     #[strum(disabled)]
     MeshElementUnpublished,
+    /// GATT transfer failed: {0}
+    #[cfg(feature = "gatt-client")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gatt-client")))]
+    #[strum(disabled)]
+    TransferFailed(gatt::transfer::TransferError),
+    /// firmware update failed: {0}
+    #[cfg(feature = "dfu")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dfu")))]
+    #[strum(disabled)]
+    DfuFailed(dfu::DfuError),
     /// internal error: {0}
     #[strum(disabled)]
     Internal(InternalErrorKind),
@@ -666,8 +889,8 @@ pub enum ErrorKind {
 ///
 /// This is most likely caused by incompatibilities between this library
 /// and the version of the Bluetooth daemon.
-#[cfg(feature = "bluetoothd")]
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg(feature = "bluetoothd-core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 #[derive(Clone, Debug, displaydoc::Display, Eq, PartialEq, Ord, PartialOrd, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
@@ -692,14 +915,14 @@ pub enum InternalErrorKind {
     DBusConnectionLost,
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 impl Error {
     pub(crate) fn new(kind: ErrorKind) -> Self {
         Self { kind, message: String::new() }
     }
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.message.is_empty() {
@@ -710,10 +933,10 @@ impl fmt::Display for Error {
     }
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 impl std::error::Error for Error {}
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 impl From<dbus::Error> for Error {
     fn from(err: dbus::Error) -> Self {
         log::trace!("DBus error {}: {}", err.name().unwrap_or_default(), err.message().unwrap_or_default());
@@ -732,35 +955,67 @@ impl From<dbus::Error> for Error {
     }
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
+impl Error {
+    /// Annotates the error of a call to an experimental interface of the Bluetooth daemon.
+    ///
+    /// Experimental methods are not exported unless the Bluetooth daemon has been
+    /// started with experimental interfaces enabled, so calling them fails with an
+    /// unknown method error.
+    pub(crate) fn experimental(self) -> Self {
+        match &self.kind {
+            ErrorKind::Internal(InternalErrorKind::DBus(name))
+                if name == "org.freedesktop.DBus.Error.UnknownMethod" =>
+            {
+                Self { kind: ErrorKind::ExperimentalDisabled, message: self.message }
+            }
+            _ => self,
+        }
+    }
+}
+
+#[cfg(any(feature = "adv", feature = "gatt-server"))]
+impl Error {
+    /// Prefixes the error message with the specified context.
+    pub(crate) fn with_context(mut self, context: impl fmt::Display) -> Self {
+        self.message = match self.message.is_empty() {
+            true => context.to_string(),
+            false => format!("{}: {}", context, &self.message),
+        };
+        self
+    }
+}
+
+#[cfg(feature = "bluetoothd-core")]
 impl From<JoinError> for Error {
     fn from(err: JoinError) -> Self {
         Self { kind: ErrorKind::Internal(InternalErrorKind::JoinError), message: err.to_string() }
     }
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 impl From<strum::ParseError> for Error {
     fn from(_: strum::ParseError) -> Self {
         Self { kind: ErrorKind::Internal(InternalErrorKind::InvalidValue), message: String::new() }
     }
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
         Self { kind: ErrorKind::Internal(InternalErrorKind::Io(err.kind())), message: err.to_string() }
     }
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 impl From<InvalidAddress> for Error {
     fn from(err: InvalidAddress) -> Self {
-        Self::new(ErrorKind::InvalidAddress(err.0))
+        let message = err.reason().map(|reason| reason.to_string()).unwrap_or_default();
+        Self { kind: ErrorKind::InvalidAddress(err.0), message }
     }
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 impl From<Error> for std::io::Error {
     fn from(err: Error) -> Self {
         use std::io::ErrorKind as E;
@@ -785,12 +1040,16 @@ impl From<Error> for std::io::Error {
             ErrorKind::InvalidOffset => E::InvalidInput,
             ErrorKind::InvalidAddress(_) => E::InvalidInput,
             ErrorKind::InvalidName(_) => E::InvalidInput,
+            ErrorKind::InvalidAdapterPath(_) => E::InvalidInput,
             ErrorKind::ServicesUnresolved => E::Other,
             ErrorKind::NotRegistered => E::Other,
             ErrorKind::NotificationSessionStopped => E::ConnectionReset,
             ErrorKind::IndicationUnconfirmed => E::TimedOut,
             ErrorKind::NotFound => E::NotFound,
             ErrorKind::DiscoveryActive => E::PermissionDenied,
+            ErrorKind::Cancelled => E::Interrupted,
+            ErrorKind::Timeout => E::TimedOut,
+            ErrorKind::ExperimentalDisabled => E::Unsupported,
             ErrorKind::AdvertisementMonitorRejected => E::InvalidInput,
             #[cfg(feature = "mesh")]
             ErrorKind::MeshJoinFailed(_) => E::ConnectionRefused,
@@ -798,6 +1057,10 @@ impl From<Error> for std::io::Error {
             ErrorKind::MeshAddNodeFailed(_) => E::ConnectionRefused,
             #[cfg(feature = "mesh")]
             ErrorKind::MeshElementUnpublished => E::InvalidInput,
+            #[cfg(feature = "gatt-client")]
+            ErrorKind::TransferFailed(_) => E::Other,
+            #[cfg(feature = "dfu")]
+            ErrorKind::DfuFailed(_) => E::Other,
             ErrorKind::Internal(InternalErrorKind::Io(err)) => err,
             ErrorKind::Internal(_) => E::Other,
         };
@@ -805,7 +1068,7 @@ impl From<Error> for std::io::Error {
     }
 }
 
-#[cfg(all(feature = "bluetoothd", feature = "serde"))]
+#[cfg(all(feature = "bluetoothd-core", feature = "serde"))]
 mod io_errorkind_serde {
     pub fn serialize<S>(_kind: &std::io::ErrorKind, ser: S) -> Result<S::Ok, S::Error>
     where
@@ -826,8 +1089,8 @@ mod io_errorkind_serde {
 }
 
 /// Bluetooth result.
-#[cfg(feature = "bluetoothd")]
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg(feature = "bluetoothd-core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// Bluetooth address.
@@ -838,6 +1101,9 @@ pub struct Address(pub [u8; 6]);
 
 impl Address {
     /// Creates a new Bluetooth address with the specified value.
+    ///
+    /// The octets are specified in the order they are displayed, i.e. most significant first.
+    /// This can be used in constant expressions to declare known peer addresses.
     pub const fn new(addr: [u8; 6]) -> Self {
         Self(addr)
     }
@@ -907,26 +1173,145 @@ impl From<Address> for MacAddr6 {
 }
 
 /// Invalid Bluetooth address error.
-#[derive(Debug, Clone)]
+///
+/// Contains the input that could not be converted into an address.
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InvalidAddress(pub String);
 
+impl InvalidAddress {
+    /// The reason the input is not a valid Bluetooth address.
+    ///
+    /// Returns [None] if the input is a valid address.
+    pub fn reason(&self) -> Option<InvalidAddressReason> {
+        Address::parse(&self.0).err()
+    }
+}
+
+/// Reason a Bluetooth address is invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum InvalidAddressReason {
+    /// A character is neither a hexadecimal digit nor the separator expected at its position.
+    InvalidCharacter {
+        /// The offending character.
+        character: char,
+        /// Position of the offending character within the input, counted in characters.
+        position: usize,
+    },
+    /// The input has the wrong length.
+    ///
+    /// Contains the length of the input, counted in characters.
+    InvalidLength(usize),
+}
+
+impl fmt::Display for InvalidAddressReason {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidCharacter { character, position } => {
+                write!(f, "invalid character {character:?} at position {position}")
+            }
+            Self::InvalidLength(len) => write!(f, "invalid length {len}"),
+        }
+    }
+}
+
 impl fmt::Display for InvalidAddress {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "invalid Bluetooth address: {}", &self.0)
+        write!(f, "invalid Bluetooth address: {}", &self.0)?;
+        if let Some(reason) = self.reason() {
+            write!(f, " ({reason})")?;
+        }
+        Ok(())
     }
 }
 
 impl std::error::Error for InvalidAddress {}
 
+impl Address {
+    /// Parses a Bluetooth address in one of the notations accepted by its [FromStr] implementation.
+    fn parse(s: &str) -> std::result::Result<Self, InvalidAddressReason> {
+        let offset = s.chars().take_while(|c| c.is_whitespace()).count();
+        let chars: Vec<char> = s.trim().chars().collect();
+        let separator = chars.iter().copied().find(|c| !c.is_ascii_hexdigit()).filter(|c| *c == ':' || *c == '-');
+
+        if let Some(separator) = separator {
+            // Each separated octet consists of one or two hexadecimal digits.
+            let mut addr = [0; 6];
+            let mut octets = 0;
+            let mut start = 0;
+            for field in chars.split(|c| *c == separator) {
+                let pos = start;
+                start += field.len() + 1;
+                if let Some((i, &c)) = field.iter().enumerate().find(|&(i, c)| i >= 2 || !c.is_ascii_hexdigit()) {
+                    return Err(InvalidAddressReason::InvalidCharacter {
+                        character: c,
+                        position: offset + pos + i,
+                    });
+                }
+                if field.is_empty() {
+                    return Err(match chars.get(pos) {
+                        Some(&c) => {
+                            InvalidAddressReason::InvalidCharacter { character: c, position: offset + pos }
+                        }
+                        None => InvalidAddressReason::InvalidLength(chars.len()),
+                    });
+                }
+                if let Some(octet) = addr.get_mut(octets) {
+                    *octet = field.iter().fold(0, |octet, c| octet << 4 | c.to_digit(16).unwrap() as u8);
+                }
+                octets += 1;
+            }
+            if octets != 6 {
+                return Err(InvalidAddressReason::InvalidLength(chars.len()));
+            }
+            return Ok(Self(addr));
+        }
+
+        let digits: Vec<(usize, char)> = chars.iter().copied().enumerate().collect();
+        if let Some(&(pos, c)) = digits.iter().find(|(_, c)| !c.is_ascii_hexdigit()) {
+            return Err(InvalidAddressReason::InvalidCharacter { character: c, position: offset + pos });
+        }
+        if digits.len() != 12 {
+            return Err(InvalidAddressReason::InvalidLength(chars.len()));
+        }
+
+        let mut addr = [0; 6];
+        for (octet, pair) in addr.iter_mut().zip(digits.chunks(2)) {
+            *octet = (pair[0].1.to_digit(16).unwrap() << 4 | pair[1].1.to_digit(16).unwrap()) as u8;
+        }
+        Ok(Self(addr))
+    }
+}
+
 impl FromStr for Address {
     type Err = InvalidAddress;
+
+    /// Parses a Bluetooth address.
+    ///
+    /// The address consists of six hexadecimal octets in either case, which are either
+    /// separated by colons (`00:1A:7D:DA:71:13`), separated by dashes (`00-1a-7d-da-71-13`)
+    /// or not separated at all (`001A7DDA7113`).
+    /// Separated octets may omit their leading zero (`0:1A:7D:DA:71:13`).
+    /// Surrounding whitespace is ignored.
+    /// Use [InvalidAddress::reason] to find out why parsing failed.
     fn from_str(s: &str) -> std::result::Result<Self, InvalidAddress> {
-        let fields = s
-            .split(':')
-            .map(|s| u8::from_str_radix(s, 16).map_err(|_| InvalidAddress(s.to_string())))
-            .collect::<std::result::Result<Vec<_>, InvalidAddress>>()?;
-        Ok(Self(fields.try_into().map_err(|_| InvalidAddress(s.to_string()))?))
+        Self::parse(s).map_err(|_| InvalidAddress(s.to_string()))
+    }
+}
+
+impl TryFrom<&[u8]> for Address {
+    type Error = InvalidAddress;
+
+    /// Converts a slice of six bytes in most significant byte first order into a Bluetooth address.
+    ///
+    /// The error contains the bytes in hexadecimal notation.
+    fn try_from(bytes: &[u8]) -> std::result::Result<Self, InvalidAddress> {
+        match bytes.try_into() {
+            Ok(addr) => Ok(Self(addr)),
+            Err(_) => Err(InvalidAddress(bytes.iter().map(|b| format!("{b:02X}")).collect())),
+        }
     }
 }
 
@@ -964,6 +1349,12 @@ impl<'de> serde::Deserialize<'de> for Address {
     }
 }
 
+/// Company identifier assigned by the Bluetooth SIG.
+///
+/// Used as key of manufacturer specific data.
+/// Enable the `id` feature to convert it into an `id::Manufacturer`.
+pub type CompanyId = u16;
+
 /// Bluetooth device address type.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Display, EnumString, FromPrimitive)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -987,8 +1378,8 @@ impl Default for AddressType {
 }
 
 /// Linux kernel modalias information.
-#[cfg(feature = "bluetoothd")]
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg(feature = "bluetoothd-core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Modalias {
@@ -1002,7 +1393,7 @@ pub struct Modalias {
     pub device: u32,
 }
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 impl FromStr for Modalias {
     type Err = Error;
 
@@ -1028,8 +1419,63 @@ impl FromStr for Modalias {
     }
 }
 
+#[cfg(feature = "bluetoothd-core")]
+impl fmt::Display for Modalias {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:v{:04X}p{:04X}d{:04X}", &self.source, self.vendor, self.product, self.device)
+    }
+}
+
+#[cfg(feature = "bluetoothd-core")]
+impl Modalias {
+    /// Assigner of the vendor id.
+    pub fn source_type(&self) -> ModaliasSource {
+        match self.source.as_str() {
+            "usb" => ModaliasSource::Usb,
+            "bluetooth" => ModaliasSource::Bluetooth,
+            _ => ModaliasSource::Other,
+        }
+    }
+
+    /// Manufacturer identified by the vendor id.
+    ///
+    /// Only available if the vendor id has been assigned by the Bluetooth SIG.
+    #[cfg(feature = "id")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "id")))]
+    pub fn manufacturer(&self) -> Option<id::Manufacturer> {
+        match self.source_type() {
+            ModaliasSource::Bluetooth => id::Manufacturer::try_from(u16::try_from(self.vendor).ok()?).ok(),
+            _ => None,
+        }
+    }
+
+    /// Name of the vendor.
+    ///
+    /// Only available if the vendor id has been assigned by the Bluetooth SIG.
+    #[cfg(feature = "id")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "id")))]
+    pub fn vendor_name(&self) -> Option<String> {
+        self.manufacturer().map(|m| m.to_string())
+    }
+}
+
+/// Assigner of the vendor id of a [Modalias].
+#[cfg(feature = "bluetoothd-core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ModaliasSource {
+    /// Vendor id assigned by the USB Implementers Forum.
+    Usb,
+    /// Vendor id assigned by the Bluetooth SIG.
+    Bluetooth,
+    /// Other or unknown assigner.
+    Other,
+}
+
 /// Gets all D-Bus objects from the BlueZ service.
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 async fn all_dbus_objects(
     connection: &SyncConnection,
 ) -> Result<HashMap<Path<'static>, HashMap<String, PropMap>>> {
@@ -1038,7 +1484,7 @@ async fn all_dbus_objects(
 }
 
 /// Read value from D-Bus dictionary.
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 pub(crate) fn read_dict<'a, T: 'static>(
     dict: &'a HashMap<String, Variant<Box<dyn RefArg + 'static>>>, key: &str,
 ) -> Result<&'a T> {
@@ -1047,7 +1493,7 @@ pub(crate) fn read_dict<'a, T: 'static>(
 }
 
 /// Returns the parent path of the specified D-Bus path.
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 pub(crate) fn parent_path<'a>(path: &Path<'a>) -> Path<'a> {
     let mut comps: Vec<_> = path.split('/').collect();
     comps.pop();
@@ -1058,24 +1504,79 @@ pub(crate) fn parent_path<'a>(path: &Path<'a>) -> Path<'a> {
     }
 }
 
+/// Adapter name and device address contained in a D-Bus object path.
+#[cfg(feature = "tracing")]
+pub(crate) fn object_path_fields(path: &Path) -> (Option<String>, Option<String>) {
+    match Device::parse_dbus_path_prefix(path) {
+        Some(((adapter_name, address), _)) => (Some(adapter_name.to_string()), Some(address.to_string())),
+        None => (Adapter::parse_dbus_path_prefix(path).map(|(adapter_name, _)| adapter_name.to_string()), None),
+    }
+}
+
+/// UUIDs of the exported GATT service, characteristic and descriptor an object path belongs to.
+#[cfg(all(feature = "tracing", feature = "gatt-server"))]
+pub(crate) fn gatt_uuid_fields(uuids: &session::GattUuids, path: &Path) -> [Option<String>; 3] {
+    let uuids = uuids.lock().unwrap();
+    let mut fields = [None, None, None];
+    let mut prefix = String::new();
+    for comp in path.split('/').filter(|comp| !comp.is_empty()) {
+        prefix.push('/');
+        prefix.push_str(comp);
+        let idx = match comp {
+            _ if comp.starts_with("service") => 0,
+            _ if comp.starts_with("char") => 1,
+            _ if comp.starts_with("desc") => 2,
+            _ => continue,
+        };
+        fields[idx] = uuids.get(&prefix).map(|uuid| BtUuid(*uuid).to_string());
+    }
+    fields
+}
+
+/// Instruments a D-Bus method call on a remote object with a tracing span.
+#[cfg(feature = "tracing")]
+pub(crate) fn instrument_dbus_call<F: std::future::Future>(
+    f: F, path: &Path, interface: &str, method: &str,
+) -> tracing::instrument::Instrumented<F> {
+    let (adapter, device) = object_path_fields(path);
+    tracing::Instrument::instrument(
+        f,
+        tracing::debug_span!("dbus_call", %path, interface, method, adapter, device),
+    )
+}
+
 /// Result of calling one of our D-Bus methods.
-#[cfg(feature = "bluetoothd")]
+#[cfg(any(
+    feature = "agent",
+    feature = "gatt-server",
+    feature = "mesh",
+    feature = "monitor",
+    all(feature = "bluetoothd-core", feature = "rfcomm")
+))]
 type DbusResult<T> = std::result::Result<T, dbus::MethodErr>;
 
 /// Call method on Arc D-Bus object we are serving.
-#[cfg(feature = "bluetoothd")]
+#[cfg(any(
+    feature = "agent",
+    feature = "gatt-server",
+    feature = "mesh",
+    feature = "monitor",
+    all(feature = "bluetoothd-core", feature = "rfcomm")
+))]
 fn method_call<
     T: Send + Sync + 'static,
-    R: AppendAll + fmt::Debug,
-    F: Future<Output = DbusResult<R>> + Send + 'static,
+    R: dbus::arg::AppendAll + fmt::Debug,
+    F: std::future::Future<Output = DbusResult<R>> + Send + 'static,
 >(
-    mut ctx: Context, cr: &mut Crossroads, f: impl FnOnce(Arc<T>) -> F,
-) -> impl Future<Output = PhantomData<R>> {
-    let data_ref: &mut Arc<T> = cr.data_mut(ctx.path()).unwrap();
-    let data: Arc<T> = data_ref.clone();
+    mut ctx: dbus_crossroads::Context, cr: &mut dbus_crossroads::Crossroads,
+    f: impl FnOnce(std::sync::Arc<T>) -> F,
+) -> impl std::future::Future<Output = std::marker::PhantomData<R>> {
+    let data_ref: &mut std::sync::Arc<T> = cr.data_mut(ctx.path()).unwrap();
+    let data: std::sync::Arc<T> = data_ref.clone();
     async move {
-        if log::log_enabled!(log::Level::Trace) {
-            let mut args = Vec::new();
+        let hook = EXPORTED_CALL_HOOK.try_with(|hook| hook.clone()).ok().flatten();
+        let mut args = Vec::new();
+        if hook.is_some() || log::log_enabled!(log::Level::Trace) {
             let mut arg_iter = ctx.message().iter_init();
             while let Some(value) = arg_iter.get_refarg() {
                 args.push(format!("{value:?}"));
@@ -1089,7 +1590,30 @@ fn method_call<
                 args.join(", ")
             );
         }
-        let result = f(data).await;
+        let start = std::time::Instant::now();
+        let result = f(data);
+        #[cfg(feature = "tracing")]
+        let result = {
+            #[cfg(feature = "gatt-server")]
+            let [service, characteristic, descriptor] =
+                GATT_UUIDS.try_with(|uuids| gatt_uuid_fields(uuids, ctx.path())).unwrap_or_default();
+            #[cfg(not(feature = "gatt-server"))]
+            let [service, characteristic, descriptor]: [Option<String>; 3] = Default::default();
+            tracing::Instrument::instrument(
+                result,
+                tracing::debug_span!(
+                    "dbus_callback",
+                    path = %ctx.path(),
+                    interface = ctx.interface().map(|i| i.to_string()).unwrap_or_default(),
+                    method = %ctx.method(),
+                    service,
+                    characteristic,
+                    descriptor,
+                ),
+            )
+        };
+        let result = result.await;
+        let duration = start.elapsed();
         log::trace!(
             "{}: {}.{} (...) -> {:?}",
             ctx.path(),
@@ -1097,6 +1621,81 @@ fn method_call<
             ctx.method(),
             &result
         );
+        if let Some(hook) = hook {
+            let (reply, error) = match &result {
+                Ok(reply) => (Some(format!("{reply:?}")), None),
+                Err(err) => (None, Some(format!("{}: {}", err.errorname(), err.description()))),
+            };
+            hook.exported_call(&ExportedCallInfo {
+                path: ctx.path(),
+                interface: &ctx.interface().map(|i| i.to_string()).unwrap_or_default(),
+                method: ctx.method(),
+                arguments: &args,
+                duration,
+                reply: reply.as_deref(),
+                error: error.as_deref(),
+            });
+        }
         ctx.reply(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn address_is_parsed() {
+        let expected = Address([0x00, 0x1a, 0x7d, 0xda, 0x71, 0x13]);
+        for s in ["00:1A:7D:DA:71:13", "00-1a-7d-da-71-13", "001A7DDA7113", " 00:1a:7D:dA:71:13\n"] {
+            assert_eq!(s.parse::<Address>(), Ok(expected), "{s:?}");
+        }
+        assert_eq!(expected.to_string().parse::<Address>(), Ok(expected));
+    }
+
+    #[test]
+    fn address_with_single_digit_octets_is_parsed() {
+        assert_eq!("0:1A:7D:DA:71:13".parse::<Address>(), Ok(Address([0x00, 0x1a, 0x7d, 0xda, 0x71, 0x13])));
+        assert_eq!("a:b:c:d:e:f".parse::<Address>(), Ok(Address([0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f])));
+        assert_eq!("1-2-3-4-5-6".parse::<Address>(), Ok(Address([1, 2, 3, 4, 5, 6])));
+
+        // Octets have at most two digits and cannot be empty.
+        let err = "001:A:7D:DA:71:13".parse::<Address>().unwrap_err();
+        assert_eq!(err.reason(), Some(InvalidAddressReason::InvalidCharacter { character: '1', position: 2 }));
+        let err = "00::7D:DA:71:13".parse::<Address>().unwrap_err();
+        assert_eq!(err.reason(), Some(InvalidAddressReason::InvalidCharacter { character: ':', position: 3 }));
+        let err = "00:1A:7D:DA:71:".parse::<Address>().unwrap_err();
+        assert_eq!(err.reason(), Some(InvalidAddressReason::InvalidLength(15)));
+    }
+
+    #[test]
+    fn address_with_bad_separators_is_rejected() {
+        let err = "00:1A-7D:DA:71:13".parse::<Address>().unwrap_err();
+        assert_eq!(err.0, "00:1A-7D:DA:71:13");
+        assert_eq!(err.reason(), Some(InvalidAddressReason::InvalidCharacter { character: '-', position: 5 }));
+
+        let err = "00.1A.7D.DA.71.13".parse::<Address>().unwrap_err();
+        assert_eq!(err.reason(), Some(InvalidAddressReason::InvalidCharacter { character: '.', position: 2 }));
+    }
+
+    #[test]
+    fn address_with_bad_hex_digits_is_rejected() {
+        let err = "00:1G:7D:DA:71:13".parse::<Address>().unwrap_err();
+        assert_eq!(err.reason(), Some(InvalidAddressReason::InvalidCharacter { character: 'G', position: 4 }));
+
+        let err = " 001A7DDA711x".parse::<Address>().unwrap_err();
+        assert_eq!(err.reason(), Some(InvalidAddressReason::InvalidCharacter { character: 'x', position: 12 }));
+    }
+
+    #[test]
+    fn address_of_wrong_length_is_rejected() {
+        for (s, len) in [("", 0), ("00:1A:7D:DA:71", 14), ("00:1A:7D:DA:71:13:00", 20), ("001A7DDA71", 10)] {
+            let err = s.parse::<Address>().unwrap_err();
+            assert_eq!(err.reason(), Some(InvalidAddressReason::InvalidLength(len)), "{s:?}");
+        }
+
+        let err = Address::try_from(&[0x00, 0x1a, 0x7d][..]).unwrap_err();
+        assert_eq!(err.0, "001A7D");
+        assert_eq!(err.reason(), Some(InvalidAddressReason::InvalidLength(6)));
+    }
+}
@@ -11,16 +11,16 @@ use tokio::sync::{broadcast, mpsc, oneshot};
 use uuid::Uuid;
 
 use super::{
-    agent::{ProvisionAgent, RegisteredProvisionAgent},
+    agent::{self, ProvisionAgent, RegisteredProvisionAgent},
     management::{AddNodeFailedReason, NodeAdded},
     provisioner::{Provisioner, RegisteredProvisioner},
 };
 use crate::{
     mesh::{
-        element::{Element, RegisteredElement},
+        element::{Element, RegisteredElement, ELEMENT_INTERFACE},
         PATH, SERVICE_NAME, TIMEOUT,
     },
-    method_call, Error, ErrorKind, Result, SessionInner,
+    method_call, Error, ErrorKind, Result, SessionInner, OBJECT_MANAGER_INTERFACE,
 };
 
 pub(crate) const INTERFACE: &str = "org.bluez.mesh.Application1";
@@ -186,13 +186,16 @@ impl RegisteredApplication {
             // register object manager
             let om = cr.object_manager();
             cr.insert(root_path.clone(), &[om], ());
+            inner.record_export(&root_path, &[OBJECT_MANAGER_INTERFACE]);
 
             // register agent
+            let agent_path = Path::from(format!("{}/{}", root_path.clone(), "agent"));
             cr.insert(
-                Path::from(format!("{}/{}", root_path.clone(), "agent")),
+                agent_path.clone(),
                 &[inner.provision_agent_token],
                 Arc::new(RegisteredProvisionAgent::new(agent, inner.clone())),
             );
+            inner.record_export(&agent_path, &[agent::INTERFACE]);
 
             // register application
             let mut ifaces = vec![inner.application_token];
@@ -200,12 +203,14 @@ impl RegisteredApplication {
                 ifaces.push(inner.provisioner_token);
             }
             cr.insert(this.app_dbus_path(), &[inner.application_token], this.clone());
+            inner.record_export(&this.app_dbus_path(), &[INTERFACE]);
 
             // register elements
             for (element_idx, element) in elements.into_iter().enumerate() {
                 let element_path = this.element_dbus_path(element_idx);
                 let reg_element = RegisteredElement::new(inner.clone(), this.root_path(), element, element_idx);
                 cr.insert(element_path.clone(), &[inner.element_token], Arc::new(reg_element));
+                inner.record_export(&element_path, &[ELEMENT_INTERFACE]);
             }
         }
 
@@ -217,6 +222,7 @@ impl RegisteredApplication {
             log::trace!("Unpublishing mesh application at {}", &path_unreg);
             let mut cr = inner.crossroads.lock().await;
             cr.remove::<Self>(&path_unreg);
+            inner.record_unexport(&path_unreg);
         });
 
         Ok(ApplicationHandle {
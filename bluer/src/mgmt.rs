@@ -0,0 +1,1496 @@
+//! Bluetooth management interface of the Linux kernel.
+//!
+//! The management (mgmt) interface provides direct access to the Bluetooth controllers
+//! managed by the kernel.
+//! It offers functionality that is not available through the Bluetooth daemon,
+//! such as loading of link keys.
+//!
+//! Access to the management interface requires the `CAP_NET_ADMIN` capability.
+//! Note that the Bluetooth daemon uses the management interface too and
+//! changes made directly may interfere with its operation.
+//!
+//! Controllers are identified by their index, i.e. `0` for `hci0`.
+
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    io::{Error, ErrorKind, Result},
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+};
+use strum::{Display, EnumIter, IntoEnumIterator};
+use tokio::{
+    io::{unix::AsyncFd, ReadBuf},
+    sync::Mutex,
+};
+
+use crate::{
+    sock::{self, OwnedFd},
+    sys::{sockaddr_hci, BTPROTO_HCI, HCI_CHANNEL_CONTROL, HCI_DEV_NONE},
+    Address, AddressType,
+};
+
+/// Controller index used for commands that are not directed at a specific controller.
+pub const INDEX_NONE: u16 = HCI_DEV_NONE;
+
+/// Directory in which the Bluetooth daemon stores its persistent data.
+pub const STORAGE_DIR: &str = "/var/lib/bluetooth";
+
+const EV_CMD_COMPLETE: u16 = 0x0001;
+const EV_CMD_STATUS: u16 = 0x0002;
+const EV_DEVICE_FOUND: u16 = 0x0012;
+const EV_DISCOVERING: u16 = 0x0013;
+const EV_ADV_MONITOR_DEVICE_FOUND: u16 = 0x002f;
+
+const OP_READ_INFO: u16 = 0x0004;
+const OP_LOAD_LINK_KEYS: u16 = 0x0012;
+const OP_LOAD_LONG_TERM_KEYS: u16 = 0x0013;
+const OP_GET_CONNECTIONS: u16 = 0x0015;
+const OP_ADD_REMOTE_OOB_DATA: u16 = 0x0021;
+const OP_REMOVE_REMOTE_OOB_DATA: u16 = 0x0022;
+const OP_START_DISCOVERY: u16 = 0x0023;
+const OP_STOP_DISCOVERY: u16 = 0x0024;
+const OP_LOAD_IRKS: u16 = 0x0030;
+const OP_GET_CONN_INFO: u16 = 0x0031;
+const OP_ADD_DEVICE: u16 = 0x0033;
+const OP_REMOVE_DEVICE: u16 = 0x0034;
+const OP_READ_LOCAL_OOB_EXT_DATA: u16 = 0x003b;
+const OP_READ_ADV_FEATURES: u16 = 0x003d;
+const OP_READ_DEF_SYSTEM_CONFIG: u16 = 0x004b;
+const OP_SET_DEF_SYSTEM_CONFIG: u16 = 0x004c;
+
+/// Value reported by the controller for an unknown RSSI or transmit power.
+const INVALID_POWER: i8 = 127;
+
+const EIR_HASH_C192: u8 = 0x0e;
+const EIR_RANDOMIZER_R192: u8 = 0x0f;
+const EIR_LE_BDADDR: u8 = 0x1b;
+const EIR_HASH_C256: u8 = 0x1d;
+const EIR_RANDOMIZER_R256: u8 = 0x1e;
+const EIR_LE_SC_CONFIRM: u8 = 0x22;
+const EIR_LE_SC_RANDOM: u8 = 0x23;
+/// Flag of a found device indicating that it is not connectable.
+const DEVICE_FOUND_NOT_CONNECTABLE: u32 = 0x04;
+/// Flag of a found device indicating that the data has been received in a scan response only.
+const DEVICE_FOUND_SCAN_RSP: u32 = 0x20;
+
+/// Status of a management command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Display, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Status {
+    /// Success.
+    Success = 0x00,
+    /// Unknown command.
+    UnknownCommand = 0x01,
+    /// Not connected.
+    NotConnected = 0x02,
+    /// Failed.
+    Failed = 0x03,
+    /// Connect failed.
+    ConnectFailed = 0x04,
+    /// Authentication failed.
+    AuthenticationFailed = 0x05,
+    /// Not paired.
+    NotPaired = 0x06,
+    /// No resources.
+    NoResources = 0x07,
+    /// Timeout.
+    Timeout = 0x08,
+    /// Already connected.
+    AlreadyConnected = 0x09,
+    /// Busy.
+    Busy = 0x0a,
+    /// Rejected.
+    Rejected = 0x0b,
+    /// Not supported.
+    NotSupported = 0x0c,
+    /// Invalid parameters.
+    InvalidParameters = 0x0d,
+    /// Disconnected.
+    Disconnected = 0x0e,
+    /// Not powered.
+    NotPowered = 0x0f,
+    /// Cancelled.
+    Cancelled = 0x10,
+    /// Invalid index.
+    InvalidIndex = 0x11,
+    /// RF-kill is active.
+    RfKilled = 0x12,
+    /// Already paired.
+    AlreadyPaired = 0x13,
+    /// Permission denied.
+    PermissionDenied = 0x14,
+}
+
+/// A management command failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CommandError {
+    /// Opcode of the failed command.
+    pub opcode: u16,
+    /// Raw status code.
+    pub status: u8,
+}
+
+impl CommandError {
+    /// Status of the failed command, if known.
+    pub fn status(&self) -> Option<Status> {
+        Status::from_u8(self.status)
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.status() {
+            Some(status) => write!(f, "management command 0x{:04x} failed: {}", self.opcode, status),
+            None => {
+                write!(f, "management command 0x{:04x} failed with status 0x{:02x}", self.opcode, self.status)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl From<CommandError> for Error {
+    fn from(err: CommandError) -> Self {
+        let kind = match err.status() {
+            Some(Status::PermissionDenied) => ErrorKind::PermissionDenied,
+            Some(Status::InvalidParameters) => ErrorKind::InvalidInput,
+            Some(Status::Timeout) => ErrorKind::TimedOut,
+            Some(Status::NotConnected | Status::Disconnected) => ErrorKind::NotConnected,
+            _ => ErrorKind::Other,
+        };
+        Error::new(kind, err)
+    }
+}
+
+/// Controller index of the adapter with the specified name, e.g. `hci0`.
+pub fn adapter_index(adapter_name: &str) -> Result<u16> {
+    adapter_name
+        .strip_prefix("hci")
+        .and_then(|idx| idx.parse().ok())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("invalid adapter name: {adapter_name}")))
+}
+
+/// Builder for command parameters.
+#[derive(Default)]
+pub(crate) struct Params(Vec<u8>);
+
+impl Params {
+    pub fn u8(mut self, v: u8) -> Self {
+        self.0.push(v);
+        self
+    }
+
+    pub fn u16(mut self, v: u16) -> Self {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub fn u64(mut self, v: u64) -> Self {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub fn bytes(mut self, v: &[u8]) -> Self {
+        self.0.extend_from_slice(v);
+        self
+    }
+
+    pub fn addr(self, address: Address, address_type: AddressType) -> Self {
+        let mut b = address.0;
+        b.reverse();
+        self.bytes(&b).u8(address_type as u8)
+    }
+}
+
+/// Reader for command return parameters.
+pub(crate) struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.0.len() < n {
+            return Err(Error::new(ErrorKind::InvalidData, "management response too short"));
+        }
+        let (v, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(v)
+    }
+
+    pub fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub fn bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    pub fn i8(&mut self) -> Result<i8> {
+        Ok(self.u8()? as i8)
+    }
+
+    pub fn addr(&mut self) -> Result<(Address, AddressType)> {
+        let mut b: [u8; 6] = self.bytes()?;
+        b.reverse();
+        let address_type = AddressType::from_u8(self.u8()?)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "invalid address type"))?;
+        Ok((Address(b), address_type))
+    }
+
+    pub fn string<const N: usize>(&mut self) -> Result<String> {
+        let b: [u8; N] = self.bytes()?;
+        let len = b.iter().position(|c| *c == 0).unwrap_or(N);
+        Ok(String::from_utf8_lossy(&b[..len]).into_owned())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn rest(&mut self) -> &'a [u8] {
+        let rest = self.0;
+        self.0 = &[];
+        rest
+    }
+}
+
+/// Socket connected to the Bluetooth management interface of the kernel.
+pub struct Management {
+    fd: AsyncFd<OwnedFd>,
+    /// Receive buffer for command responses, also serializing commands.
+    cmd_buf: Mutex<Vec<u8>>,
+    /// Devices added by [set_accept_list](Self::set_accept_list) for each controller index.
+    accept_list: Mutex<BTreeMap<u16, BTreeSet<(Address, AddressType)>>>,
+}
+
+impl fmt::Debug for Management {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Management").field("fd", &self.fd.as_raw_fd()).finish()
+    }
+}
+
+impl Management {
+    /// Opens a socket to the Bluetooth management interface.
+    pub fn new() -> Result<Self> {
+        let fd = sock::socket(libc::AF_BLUETOOTH, libc::SOCK_RAW, BTPROTO_HCI)?;
+        sock::bind(&fd, ControlChannel)?;
+        Ok(Self {
+            fd: AsyncFd::new(fd)?,
+            cmd_buf: Mutex::new(Vec::new()),
+            accept_list: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// Sends a raw management command to the specified controller and returns the
+    /// return parameters of the command.
+    ///
+    /// Use [INDEX_NONE] for commands that are not directed at a specific controller.
+    /// A failure status is returned as an error containing a [CommandError].
+    pub async fn command(&self, opcode: u16, index: u16, params: &[u8]) -> Result<Vec<u8>> {
+        let mut buf = self.cmd_buf.lock().await;
+        buf.resize(u16::MAX as usize + 6, 0);
+
+        log::trace!("mgmt command 0x{:04x} for index 0x{:04x}: {:x?}", opcode, index, params);
+        self.send(&Self::command_packet(opcode, index, params)).await?;
+
+        loop {
+            let n = self.recv(&mut buf).await?;
+            let mut r = Reader(&buf[..n]);
+            let (event, evt_index, _len) = (r.u16()?, r.u16()?, r.u16()?);
+            if evt_index != index || !matches!(event, EV_CMD_COMPLETE | EV_CMD_STATUS) {
+                continue;
+            }
+            if r.u16()? != opcode {
+                continue;
+            }
+            let status = r.u8()?;
+            match (event, status) {
+                (_, 0) if event == EV_CMD_STATUS => continue,
+                (_, 0) => {
+                    let ret = r.rest().to_vec();
+                    log::trace!("mgmt command 0x{:04x} completed: {:x?}", opcode, &ret);
+                    return Ok(ret);
+                }
+                (_, status) => return Err(CommandError { opcode, status }.into()),
+            }
+        }
+    }
+
+    /// Sends a management command without waiting for its completion.
+    ///
+    /// This does not block and fails if the socket is not writable.
+    fn command_nowait(&self, opcode: u16, index: u16, params: &[u8]) -> Result<()> {
+        log::trace!("mgmt command 0x{:04x} for index 0x{:04x} without reply: {:x?}", opcode, index, params);
+        sock::send(self.fd.get_ref(), &Self::command_packet(opcode, index, params), 0)?;
+        Ok(())
+    }
+
+    fn command_packet(opcode: u16, index: u16, params: &[u8]) -> Vec<u8> {
+        let mut pkt = Params::default().u16(opcode).u16(index).u16(params.len() as u16).0;
+        pkt.extend_from_slice(params);
+        pkt
+    }
+
+    async fn send(&self, buf: &[u8]) -> Result<usize> {
+        loop {
+            let mut guard = self.fd.writable().await?;
+            match guard.try_io(|inner| sock::send(inner.get_ref(), buf, 0)) {
+                Ok(result) => return result,
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    async fn recv(&self, buf: &mut [u8]) -> Result<usize> {
+        let mut buf = ReadBuf::new(buf);
+        loop {
+            let mut guard = self.fd.readable().await?;
+            match guard.try_io(|inner| sock::recv(inner.get_ref(), &mut buf, 0)) {
+                Ok(result) => return result,
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    /// Reads information about the specified controller.
+    pub async fn controller_info(&self, index: u16) -> Result<ControllerInfo> {
+        let ret = self.command(OP_READ_INFO, index, &[]).await?;
+        let mut r = Reader(&ret);
+        let mut address: [u8; 6] = r.bytes()?;
+        address.reverse();
+        Ok(ControllerInfo {
+            address: Address(address),
+            version: r.u8()?,
+            manufacturer: r.u16()?,
+            supported_settings: Setting::from_mask(r.u32()?),
+            current_settings: Setting::from_mask(r.u32()?),
+            class: {
+                let [a, b, c] = r.bytes()?;
+                u32::from_le_bytes([a, b, c, 0])
+            },
+            name: r.string::<249>()?,
+            short_name: r.string::<11>()?,
+        })
+    }
+
+    /// Reads the advertising features supported by the specified controller.
+    pub async fn advertising_features(&self, index: u16) -> Result<AdvertisingFeatures> {
+        let ret = self.command(OP_READ_ADV_FEATURES, index, &[]).await?;
+        let mut r = Reader(&ret);
+        Ok(AdvertisingFeatures {
+            supported_flags: r.u32()?,
+            max_adv_data_len: r.u8()?,
+            max_scan_rsp_len: r.u8()?,
+            max_instances: r.u8()?,
+            instances: {
+                let n = r.u8()?;
+                (0..n).map(|_| r.u8()).collect::<Result<_>>()?
+            },
+        })
+    }
+
+    /// Reads the default system configuration parameters of the specified controller.
+    ///
+    /// Parameters unknown to this library are omitted.
+    pub async fn system_config(&self, index: u16) -> Result<BTreeMap<SystemConfigParameter, u16>> {
+        let ret = self.command(OP_READ_DEF_SYSTEM_CONFIG, index, &[]).await?;
+        let mut r = Reader(&ret);
+        let mut config = BTreeMap::new();
+        while !r.is_empty() {
+            let ty = r.u16()?;
+            let len = r.u8()?;
+            let value = r.take(len.into())?;
+            if let (Some(param), Ok(value)) = (SystemConfigParameter::from_u16(ty), value.try_into()) {
+                config.insert(param, u16::from_le_bytes(value));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Sets default system configuration parameters of the specified controller.
+    ///
+    /// Parameters not specified are left unchanged.
+    /// Intervals and windows are specified in units of 0.625 ms, connection intervals
+    /// in units of 1.25 ms and timeouts in units of 10 ms, as defined by the
+    /// Bluetooth core specification.
+    pub async fn set_system_config(
+        &self, index: u16, config: impl IntoIterator<Item = (SystemConfigParameter, u16)>,
+    ) -> Result<()> {
+        let mut p = Params::default();
+        for (param, value) in config {
+            p = p.u16(param as u16).u8(2).u16(value);
+        }
+        self.command(OP_SET_DEF_SYSTEM_CONFIG, index, &p.0).await?;
+        Ok(())
+    }
+
+    /// Loads the BR/EDR link keys of the specified controller.
+    ///
+    /// This replaces all link keys currently known to the controller.
+    /// If `debug_keys` is true, debug keys are kept and used.
+    pub async fn load_link_keys(&self, index: u16, keys: &[LinkKey], debug_keys: bool) -> Result<()> {
+        let mut p = Params::default().u8(debug_keys.into()).u16(keys.len() as u16);
+        for key in keys {
+            p = p.addr(key.address, key.address_type).u8(key.key_type).bytes(&key.value).u8(key.pin_length);
+        }
+        self.command(OP_LOAD_LINK_KEYS, index, &p.0).await?;
+        Ok(())
+    }
+
+    /// Loads the LE long term keys of the specified controller.
+    ///
+    /// This replaces all long term keys currently known to the controller.
+    pub async fn load_long_term_keys(&self, index: u16, keys: &[LongTermKey]) -> Result<()> {
+        let mut p = Params::default().u16(keys.len() as u16);
+        for key in keys {
+            p = p
+                .addr(key.address, key.address_type)
+                .u8(key.key_type)
+                .u8(key.central.into())
+                .u8(key.encryption_size)
+                .u16(key.ediv)
+                .u64(key.rand)
+                .bytes(&key.value);
+        }
+        self.command(OP_LOAD_LONG_TERM_KEYS, index, &p.0).await?;
+        Ok(())
+    }
+
+    /// Loads the identity resolving keys of the specified controller.
+    ///
+    /// This replaces all identity resolving keys currently known to the controller.
+    pub async fn load_identity_resolving_keys(&self, index: u16, keys: &[IdentityResolvingKey]) -> Result<()> {
+        let mut p = Params::default().u16(keys.len() as u16);
+        for key in keys {
+            p = p.addr(key.address, key.address_type).bytes(&key.value);
+        }
+        self.command(OP_LOAD_IRKS, index, &p.0).await?;
+        Ok(())
+    }
+
+    /// Reads the out-of-band pairing data of the local controller for the transport
+    /// of the specified address type.
+    ///
+    /// For [AddressType::BrEdr] the Secure Simple Pairing data is read, otherwise
+    /// the LE Secure Connections data is read.
+    /// The data is valid until the controller is powered off or new data is read.
+    /// Pass it to the remote device using an out-of-band channel, such as NFC or a QR code.
+    pub async fn read_local_oob_data(&self, index: u16, address_type: AddressType) -> Result<OobData> {
+        let address_types = match address_type {
+            AddressType::BrEdr => BTreeSet::from([AddressType::BrEdr]),
+            AddressType::LePublic | AddressType::LeRandom => {
+                BTreeSet::from([AddressType::LePublic, AddressType::LeRandom])
+            }
+        };
+        let p = Params::default().u8(address_type_mask(&address_types));
+        let ret = self.command(OP_READ_LOCAL_OOB_EXT_DATA, index, &p.0).await?;
+        let mut r = Reader(&ret);
+        let (_address_types, len) = (r.u8()?, r.u16()?);
+        let eir = r.rest();
+        parse_oob_eir(&eir[..eir.len().min(len.into())])
+    }
+
+    /// Provides out-of-band pairing data received from a remote device.
+    ///
+    /// The data is used by the controller when pairing with the remote device.
+    pub async fn add_remote_oob_data(
+        &self, index: u16, address: Address, address_type: AddressType, data: &OobData,
+    ) -> Result<()> {
+        let zero = [0; 16];
+        let p = Params::default()
+            .addr(address, address_type)
+            .bytes(data.hash192.as_ref().unwrap_or(&zero))
+            .bytes(data.randomizer192.as_ref().unwrap_or(&zero))
+            .bytes(data.hash256.as_ref().unwrap_or(&zero))
+            .bytes(data.randomizer256.as_ref().unwrap_or(&zero));
+        self.command(OP_ADD_REMOTE_OOB_DATA, index, &p.0).await?;
+        Ok(())
+    }
+
+    /// Removes out-of-band pairing data of a remote device.
+    pub async fn remove_remote_oob_data(
+        &self, index: u16, address: Address, address_type: AddressType,
+    ) -> Result<()> {
+        let p = Params::default().addr(address, address_type);
+        self.command(OP_REMOVE_REMOTE_OOB_DATA, index, &p.0).await?;
+        Ok(())
+    }
+
+    /// Starts discovery of devices of the specified address types.
+    ///
+    /// Found devices are reported as [DeviceFound] events, see [Observer].
+    /// LE discovery is stopped by the kernel after about ten seconds.
+    pub async fn start_discovery(&self, index: u16, address_types: &BTreeSet<AddressType>) -> Result<()> {
+        let p = Params::default().u8(address_type_mask(address_types));
+        self.command(OP_START_DISCOVERY, index, &p.0).await?;
+        Ok(())
+    }
+
+    /// Stops discovery of devices of the specified address types.
+    pub async fn stop_discovery(&self, index: u16, address_types: &BTreeSet<AddressType>) -> Result<()> {
+        let p = Params::default().u8(address_type_mask(address_types));
+        self.command(OP_STOP_DISCOVERY, index, &p.0).await?;
+        Ok(())
+    }
+
+    /// Returns the addresses and address types of all devices connected to the specified controller.
+    pub async fn connections(&self, index: u16) -> Result<Vec<(Address, AddressType)>> {
+        let ret = self.command(OP_GET_CONNECTIONS, index, &[]).await?;
+        let mut r = Reader(&ret);
+        (0..r.u16()?).map(|_| r.addr()).collect()
+    }
+
+    /// Returns information about the connection to the specified device.
+    ///
+    /// Fails with [ErrorKind::NotConnected] if the device is not connected.
+    pub async fn connection_info(
+        &self, index: u16, address: Address, address_type: AddressType,
+    ) -> Result<ConnectionInfo> {
+        let p = Params::default().addr(address, address_type);
+        let ret = self.command(OP_GET_CONN_INFO, index, &p.0).await?;
+        let mut r = Reader(&ret);
+        let (address, address_type) = r.addr()?;
+        let valid = |v: i8| if v == INVALID_POWER { None } else { Some(v) };
+        Ok(ConnectionInfo {
+            address,
+            address_type,
+            rssi: valid(r.i8()?),
+            tx_power: valid(r.i8()?),
+            max_tx_power: valid(r.i8()?),
+        })
+    }
+
+    /// Adds a device to the list of devices that the controller scans for and accepts
+    /// connections from in the background.
+    ///
+    /// For LE devices this populates the filter accept list of the controller, so that
+    /// background connections are restricted to the added devices.
+    /// Adding a device that is already in the list updates its action.
+    pub async fn add_device(
+        &self, index: u16, address: Address, address_type: AddressType, action: DeviceAction,
+    ) -> Result<()> {
+        let p = Params::default().addr(address, address_type).u8(action as u8);
+        self.command(OP_ADD_DEVICE, index, &p.0).await?;
+        Ok(())
+    }
+
+    /// Removes a device from the list of devices that the controller scans for and accepts
+    /// connections from in the background.
+    pub async fn remove_device(&self, index: u16, address: Address, address_type: AddressType) -> Result<()> {
+        let p = Params::default().addr(address, address_type);
+        self.command(OP_REMOVE_DEVICE, index, &p.0).await?;
+        Ok(())
+    }
+
+    /// Removes all devices from the list of devices that the controller scans for and accepts
+    /// connections from in the background.
+    ///
+    /// This also removes the devices added by the Bluetooth daemon, which re-adds
+    /// them on the next restart or when the device is reconnected.
+    pub async fn clear_devices(&self, index: u16) -> Result<()> {
+        self.remove_device(index, Address::any(), AddressType::BrEdr).await
+    }
+
+    /// Replaces the list of devices that the controller automatically connects to in the background
+    /// with the specified LE devices.
+    ///
+    /// This restricts background connections to known devices, see [add_device](Self::add_device).
+    /// Devices added by a previous call of this method on this management socket that are
+    /// not specified anymore are removed from the list.
+    /// Devices added by other means, for example by the Bluetooth daemon, are left in place.
+    pub async fn set_accept_list(&self, index: u16, devices: &[(Address, AddressType)]) -> Result<()> {
+        if let Some((address, _)) = devices.iter().find(|(_, address_type)| *address_type == AddressType::BrEdr) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("device {address} is not an LE device and cannot be in the accept list"),
+            ));
+        }
+
+        let mut accept_lists = self.accept_list.lock().await;
+        let accept_list = accept_lists.entry(index).or_default();
+        let devices: BTreeSet<_> = devices.iter().copied().collect();
+        let removed: Vec<_> = accept_list.difference(&devices).copied().collect();
+        let added: Vec<_> = devices.difference(accept_list).copied().collect();
+        for (address, address_type) in removed {
+            self.remove_device(index, address, address_type).await?;
+            accept_list.remove(&(address, address_type));
+        }
+        for (address, address_type) in added {
+            self.add_device(index, address, address_type, DeviceAction::AutoConnect).await?;
+            accept_list.insert((address, address_type));
+        }
+        Ok(())
+    }
+}
+
+/// Converts a set of address types into the bit mask used by discovery commands.
+fn address_type_mask(address_types: &BTreeSet<AddressType>) -> u8 {
+    address_types.iter().fold(0, |mask, address_type| mask | (1 << *address_type as u8))
+}
+
+/// Observes advertisements and inquiry results received by a controller.
+///
+/// The observer reports [DeviceFound] events of the management interface.
+/// In contrast to discovery using the Bluetooth daemon, it does not require the creation of
+/// a D-Bus object for each found device, which makes it suitable for listening to a
+/// high volume of advertisements, for example from beacons.
+///
+/// The Bluetooth daemon also receives the events of a discovery started by the observer.
+/// It only creates device objects for found devices if one of its clients is
+/// discovering or an advertisement monitor is active; this depends on its version.
+///
+/// The raw advertising data and the [event type](DeviceFound::event_type) of each
+/// received advertisement are available, which makes the observer suitable for protocol analysis.
+/// Devices matched by advertisement monitors offloaded to the controller are reported as well.
+///
+/// Since the kernel stops LE discovery after a timeout, the observer restarts it when needed.
+/// If a discovery is already active, for example because it has been started by the
+/// Bluetooth daemon, the devices found by that discovery are reported.
+///
+/// Dropping the observer stops the discovery, if it has been started by the observer.
+pub struct Observer {
+    mgmt: Management,
+    events: Management,
+    index: u16,
+    address_types: BTreeSet<AddressType>,
+    buf: Vec<u8>,
+    /// Whether the active discovery has been started by the observer.
+    started: bool,
+}
+
+impl fmt::Debug for Observer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Observer")
+            .field("index", &self.index)
+            .field("address_types", &self.address_types)
+            .finish()
+    }
+}
+
+impl Observer {
+    /// Starts observing devices of the specified address types using the specified controller.
+    pub async fn new(index: u16, address_types: BTreeSet<AddressType>) -> Result<Self> {
+        let mut this = Self {
+            mgmt: Management::new()?,
+            events: Management::new()?,
+            index,
+            address_types,
+            buf: vec![0; u16::MAX as usize + 6],
+            started: false,
+        };
+        this.started = this.start().await?;
+        Ok(this)
+    }
+
+    /// Starts discovery, unless it is already active.
+    ///
+    /// Returns whether discovery has been started.
+    async fn start(&self) -> Result<bool> {
+        match self.mgmt.start_discovery(self.index, &self.address_types).await {
+            Err(err)
+                if err
+                    .get_ref()
+                    .and_then(|err| err.downcast_ref::<CommandError>())
+                    .and_then(|err| err.status())
+                    == Some(Status::Busy) =>
+            {
+                log::trace!("mgmt discovery for index 0x{:04x} is already active", self.index);
+                Ok(false)
+            }
+            result => result.map(|()| true),
+        }
+    }
+
+    /// Waits for the next found device.
+    pub async fn next(&mut self) -> Result<DeviceFound> {
+        loop {
+            let n = self.events.recv(&mut self.buf).await?;
+            let mut r = Reader(&self.buf[..n]);
+            let (event, index, _len) = (r.u16()?, r.u16()?, r.u16()?);
+            if index != self.index {
+                continue;
+            }
+            match event {
+                EV_DEVICE_FOUND | EV_ADV_MONITOR_DEVICE_FOUND => {
+                    let monitor_handle = if event == EV_ADV_MONITOR_DEVICE_FOUND { Some(r.u16()?) } else { None };
+                    let (address, address_type) = r.addr()?;
+                    let rssi = r.i8()?;
+                    let flags = r.u32()?;
+                    let len = r.u16()?;
+                    let data = r.rest();
+                    return Ok(DeviceFound {
+                        address,
+                        address_type,
+                        rssi: if rssi == INVALID_POWER { None } else { Some(rssi) },
+                        flags,
+                        data: data[..data.len().min(len.into())].to_vec(),
+                        monitor_handle,
+                    });
+                }
+                EV_DISCOVERING => {
+                    let (_address_types, discovering) = (r.u8()?, r.u8()?);
+                    if discovering == 0 {
+                        log::trace!("Restarting mgmt discovery for index 0x{:04x}", self.index);
+                        self.started = self.start().await?;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Stops the discovery, if it has been started by the observer.
+    ///
+    /// A discovery that has been started by someone else is left running.
+    pub async fn stop(mut self) -> Result<()> {
+        if !self.started {
+            return Ok(());
+        }
+        self.started = false;
+        self.mgmt.stop_discovery(self.index, &self.address_types).await
+    }
+}
+
+impl Drop for Observer {
+    fn drop(&mut self) {
+        if self.started {
+            let p = Params::default().u8(address_type_mask(&self.address_types));
+            if let Err(err) = self.mgmt.command_nowait(OP_STOP_DISCOVERY, self.index, &p.0) {
+                log::warn!("Stopping mgmt discovery for index 0x{:04x} failed: {}", self.index, &err);
+            }
+        }
+    }
+}
+
+/// A device has been found by a discovery.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct DeviceFound {
+    /// Address of the device.
+    pub address: Address,
+    /// Address type of the device.
+    pub address_type: AddressType,
+    /// Received signal strength in dBm, if available.
+    pub rssi: Option<i8>,
+    /// Raw flags of the event.
+    pub flags: u32,
+    /// Advertising data or extended inquiry response data, including the scan response.
+    pub data: Vec<u8>,
+    /// Handle of the advertisement monitor that matched the advertisement.
+    ///
+    /// This is only set if the controller offloads advertisement monitoring and the
+    /// kernel reported the device as found by an advertisement monitor.
+    pub monitor_handle: Option<u16>,
+}
+
+impl DeviceFound {
+    /// Whether the device accepts connections.
+    pub fn is_connectable(&self) -> bool {
+        self.flags & DEVICE_FOUND_NOT_CONNECTABLE == 0
+    }
+
+    /// Type of the advertising event or inquiry result that has been received.
+    ///
+    /// The kernel merges the scan response into the data of the preceding advertisement
+    /// and does not report whether an advertisement has been sent using extended
+    /// advertising, thus these events cannot be distinguished.
+    pub fn event_type(&self) -> AdvertisingEventType {
+        if self.address_type == AddressType::BrEdr {
+            AdvertisingEventType::InquiryResult
+        } else if self.flags & DEVICE_FOUND_SCAN_RSP != 0 {
+            AdvertisingEventType::ScanResponse
+        } else if self.is_connectable() {
+            AdvertisingEventType::Connectable
+        } else {
+            AdvertisingEventType::NonConnectable
+        }
+    }
+
+    /// Splits the data into advertising data structures.
+    ///
+    /// Each structure is returned as its AD type followed by its data.
+    /// Splitting stops at the first zero-length structure, which marks the end of
+    /// significant data, or at a truncated structure.
+    pub fn ad_structures(&self) -> Vec<(u8, &[u8])> {
+        let mut structures = Vec::new();
+        let mut data = &self.data[..];
+        while let Some((&len, rest)) = data.split_first() {
+            let len = usize::from(len);
+            if len == 0 || rest.len() < len {
+                break;
+            }
+            structures.push((rest[0], &rest[1..len]));
+            data = &rest[len..];
+        }
+        structures
+    }
+}
+
+/// Type of the event through which a device has been found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum AdvertisingEventType {
+    /// Classic Bluetooth (BR/EDR) inquiry result, including the extended inquiry response.
+    InquiryResult,
+    /// Connectable LE advertisement (`ADV_IND` or `ADV_DIRECT_IND`),
+    /// possibly including the data of the scan response.
+    Connectable,
+    /// Non-connectable LE advertisement (`ADV_SCAN_IND` or `ADV_NONCONN_IND`),
+    /// possibly including the data of the scan response.
+    NonConnectable,
+    /// LE scan response (`SCAN_RSP`) received without the preceding advertisement.
+    ScanResponse,
+}
+
+/// Management control channel address.
+struct ControlChannel;
+
+impl sock::SysSockAddr for ControlChannel {
+    type SysSockAddr = sockaddr_hci;
+
+    fn into_sys_sock_addr(self) -> Self::SysSockAddr {
+        sockaddr_hci {
+            hci_family: libc::AF_BLUETOOTH as _,
+            hci_dev: HCI_DEV_NONE,
+            hci_channel: HCI_CHANNEL_CONTROL,
+        }
+    }
+
+    fn try_from_sys_sock_addr(_addr: Self::SysSockAddr) -> Result<Self> {
+        Ok(Self)
+    }
+}
+
+/// BR/EDR link key of a bonded device.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinkKey {
+    /// Address of the remote device.
+    pub address: Address,
+    /// Address type of the remote device.
+    pub address_type: AddressType,
+    /// Link key type as defined by the Bluetooth core specification.
+    pub key_type: u8,
+    /// Key value.
+    pub value: [u8; 16],
+    /// Length of the PIN code used for pairing.
+    pub pin_length: u8,
+}
+
+/// LE long term key (LTK) of a bonded device.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LongTermKey {
+    /// Address of the remote device.
+    pub address: Address,
+    /// Address type of the remote device.
+    pub address_type: AddressType,
+    /// Key type, specifying whether the key is authenticated and
+    /// whether it was generated using LE Secure Connections.
+    pub key_type: u8,
+    /// Whether the key is used when the local device is the central.
+    pub central: bool,
+    /// Encryption key size.
+    pub encryption_size: u8,
+    /// Encrypted diversifier.
+    pub ediv: u16,
+    /// Random number.
+    pub rand: u64,
+    /// Key value.
+    pub value: [u8; 16],
+}
+
+/// Identity resolving key (IRK) of a bonded device.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IdentityResolvingKey {
+    /// Identity address of the remote device.
+    pub address: Address,
+    /// Address type of the identity address.
+    pub address_type: AddressType,
+    /// Key value.
+    pub value: [u8; 16],
+}
+
+/// Action performed by the controller for a device added using [Management::add_device].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum DeviceAction {
+    /// Scan for the device in the background and report it.
+    ///
+    /// Only supported for LE devices.
+    Scan = 0,
+    /// Allow incoming connections from the device.
+    AllowIncoming = 1,
+    /// Automatically connect to the device when it advertises.
+    ///
+    /// Only supported for LE devices.
+    AutoConnect = 2,
+}
+
+/// Setting of a controller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display, EnumIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Setting {
+    /// Powered.
+    Powered = 0,
+    /// Connectable.
+    Connectable = 1,
+    /// Fast connectable.
+    FastConnectable = 2,
+    /// Discoverable.
+    Discoverable = 3,
+    /// Bondable.
+    Bondable = 4,
+    /// Link level security.
+    LinkSecurity = 5,
+    /// Secure Simple Pairing.
+    SecureSimplePairing = 6,
+    /// Classic Bluetooth (BR/EDR).
+    BrEdr = 7,
+    /// High speed.
+    HighSpeed = 8,
+    /// Bluetooth Low Energy.
+    Le = 9,
+    /// Advertising.
+    Advertising = 10,
+    /// Secure Connections.
+    SecureConnections = 11,
+    /// Debug keys.
+    DebugKeys = 12,
+    /// Privacy.
+    Privacy = 13,
+    /// Controller configuration.
+    ControllerConfiguration = 14,
+    /// Static address.
+    StaticAddress = 15,
+    /// PHY configuration.
+    PhyConfiguration = 16,
+    /// Wideband speech.
+    WidebandSpeech = 17,
+    /// Connected isochronous stream central.
+    CisCentral = 18,
+    /// Connected isochronous stream peripheral.
+    CisPeripheral = 19,
+    /// Isochronous broadcaster.
+    IsoBroadcaster = 20,
+    /// Synchronized isochronous receiver.
+    IsoSyncReceiver = 21,
+}
+
+impl Setting {
+    /// Converts a bit mask of settings into a set.
+    pub fn from_mask(mask: u32) -> BTreeSet<Self> {
+        Self::iter().filter(|s| mask & (1 << *s as u32) != 0).collect()
+    }
+
+    /// Converts a set of settings into a bit mask.
+    pub fn to_mask(settings: &BTreeSet<Self>) -> u32 {
+        settings.iter().fold(0, |mask, s| mask | (1 << *s as u32))
+    }
+}
+
+/// Information about a controller.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ControllerInfo {
+    /// Public address of the controller.
+    pub address: Address,
+    /// Bluetooth core specification version supported by the controller (HCI version).
+    ///
+    /// For example, 9 is version 5.0 and 12 is version 5.3.
+    pub version: u8,
+    /// Company identifier of the controller manufacturer.
+    pub manufacturer: u16,
+    /// Settings supported by the controller.
+    pub supported_settings: BTreeSet<Setting>,
+    /// Settings currently active.
+    pub current_settings: BTreeSet<Setting>,
+    /// Class of device.
+    pub class: u32,
+    /// Name of the controller.
+    pub name: String,
+    /// Short name of the controller.
+    pub short_name: String,
+}
+
+impl ControllerInfo {
+    /// Whether the controller supports the specified setting.
+    pub fn supports(&self, setting: Setting) -> bool {
+        self.supported_settings.contains(&setting)
+    }
+}
+
+/// Advertising features supported by a controller.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct AdvertisingFeatures {
+    /// Bit mask of supported advertising flags.
+    pub supported_flags: u32,
+    /// Maximum length of advertising data in bytes.
+    pub max_adv_data_len: u8,
+    /// Maximum length of scan response data in bytes.
+    pub max_scan_rsp_len: u8,
+    /// Maximum number of advertising instances.
+    pub max_instances: u8,
+    /// Currently used advertising instances.
+    pub instances: Vec<u8>,
+}
+
+impl AdvertisingFeatures {
+    /// Flags indicating support for secondary advertising channels on the LE 1M, 2M and Coded PHYs.
+    const SECONDARY_CHANNEL_FLAGS: u32 = (1 << 7) | (1 << 8) | (1 << 9);
+
+    /// Whether the controller supports extended advertising.
+    ///
+    /// Extended advertising allows advertising data longer than 31 bytes
+    /// and the use of secondary advertising channels.
+    pub fn supports_extended_advertising(&self) -> bool {
+        self.max_adv_data_len > 31 || self.supported_flags & Self::SECONDARY_CHANNEL_FLAGS != 0
+    }
+}
+
+/// Default system configuration parameter of a controller.
+///
+/// See [Management::set_system_config] for the units of the values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum SystemConfigParameter {
+    /// BR/EDR page scan type.
+    PageScanType = 0x0000,
+    /// BR/EDR page scan interval.
+    PageScanInterval = 0x0001,
+    /// BR/EDR page scan window.
+    PageScanWindow = 0x0002,
+    /// BR/EDR inquiry scan type.
+    InquiryScanType = 0x0003,
+    /// BR/EDR inquiry scan interval.
+    InquiryScanInterval = 0x0004,
+    /// BR/EDR inquiry scan window.
+    InquiryScanWindow = 0x0005,
+    /// BR/EDR link supervision timeout.
+    LinkSupervisionTimeout = 0x0006,
+    /// BR/EDR page timeout.
+    PageTimeout = 0x0007,
+    /// BR/EDR minimum sniff interval.
+    MinSniffInterval = 0x0008,
+    /// BR/EDR maximum sniff interval.
+    MaxSniffInterval = 0x0009,
+    /// LE minimum advertising interval.
+    AdvertisingMinInterval = 0x000a,
+    /// LE maximum advertising interval.
+    AdvertisingMaxInterval = 0x000b,
+    /// LE rotation interval of multiple advertising instances.
+    MultiAdvertisingRotationInterval = 0x000c,
+    /// LE scan interval for auto connect.
+    ScanIntervalAutoConnect = 0x000d,
+    /// LE scan window for auto connect.
+    ScanWindowAutoConnect = 0x000e,
+    /// LE scan interval for wake scenarios.
+    ScanIntervalWake = 0x000f,
+    /// LE scan window for wake scenarios.
+    ScanWindowWake = 0x0010,
+    /// LE scan interval while suspended.
+    ScanIntervalSuspend = 0x0011,
+    /// LE scan window while suspended.
+    ScanWindowSuspend = 0x0012,
+    /// LE scan interval for discovery.
+    ScanIntervalDiscovery = 0x0013,
+    /// LE scan window for discovery.
+    ScanWindowDiscovery = 0x0014,
+    /// LE scan interval for advertisement monitoring.
+    ScanIntervalAdvertisementMonitor = 0x0015,
+    /// LE scan window for advertisement monitoring.
+    ScanWindowAdvertisementMonitor = 0x0016,
+    /// LE minimum connection interval.
+    MinConnectionInterval = 0x0017,
+    /// LE maximum connection interval.
+    MaxConnectionInterval = 0x0018,
+    /// LE connection latency.
+    ConnectionLatency = 0x0019,
+    /// LE connection supervision timeout.
+    ConnectionSupervisionTimeout = 0x001a,
+    /// LE auto connect timeout.
+    AutoConnectTimeout = 0x001b,
+}
+
+/// Information about a connection to a remote device.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ConnectionInfo {
+    /// Address of the remote device.
+    pub address: Address,
+    /// Address type used by the connection.
+    ///
+    /// This is [AddressType::BrEdr] for classic Bluetooth connections and
+    /// one of the LE address types for Bluetooth LE connections.
+    pub address_type: AddressType,
+    /// Received signal strength indication in dBm.
+    pub rssi: Option<i8>,
+    /// Current transmit power in dBm.
+    pub tx_power: Option<i8>,
+    /// Maximum transmit power in dBm.
+    pub max_tx_power: Option<i8>,
+}
+
+impl ConnectionInfo {
+    /// Whether the connection uses Bluetooth LE.
+    pub fn is_le(&self) -> bool {
+        self.address_type != AddressType::BrEdr
+    }
+
+    /// Whether the connection uses classic Bluetooth (BR/EDR).
+    pub fn is_br_edr(&self) -> bool {
+        self.address_type == AddressType::BrEdr
+    }
+}
+
+/// Parses the EIR structures returned by the Read Local OOB Extended Data command.
+fn parse_oob_eir(mut eir: &[u8]) -> Result<OobData> {
+    let invalid = || Error::new(ErrorKind::InvalidData, "invalid out-of-band data");
+    let mut data = OobData::default();
+    while let [len, rest @ ..] = eir {
+        let len = usize::from(*len);
+        if len == 0 {
+            break;
+        }
+        if rest.len() < len {
+            return Err(invalid());
+        }
+        let (ty, value) = (rest[0], &rest[1..len]);
+        eir = &rest[len..];
+        let key = || <[u8; 16]>::try_from(value).map_err(|_| invalid());
+        match ty {
+            EIR_HASH_C192 => data.hash192 = Some(key()?),
+            EIR_RANDOMIZER_R192 => data.randomizer192 = Some(key()?),
+            EIR_HASH_C256 | EIR_LE_SC_CONFIRM => data.hash256 = Some(key()?),
+            EIR_RANDOMIZER_R256 | EIR_LE_SC_RANDOM => data.randomizer256 = Some(key()?),
+            EIR_LE_BDADDR => {
+                let [mut address @ .., ty]: [u8; 7] = value.try_into().map_err(|_| invalid())?;
+                address.reverse();
+                let address_type = if ty & 1 == 0 { AddressType::LePublic } else { AddressType::LeRandom };
+                data.le_address = Some((Address(address), address_type));
+            }
+            _ => (),
+        }
+    }
+    Ok(data)
+}
+
+/// Out-of-band (OOB) pairing data.
+///
+/// Values that are not present are not used for pairing.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OobData {
+    /// Hash C for P-192, used by BR/EDR Secure Simple Pairing.
+    pub hash192: Option<[u8; 16]>,
+    /// Randomizer R for P-192, used by BR/EDR Secure Simple Pairing.
+    pub randomizer192: Option<[u8; 16]>,
+    /// Confirmation value for P-256, used by Secure Connections.
+    pub hash256: Option<[u8; 16]>,
+    /// Random value for P-256, used by Secure Connections.
+    pub randomizer256: Option<[u8; 16]>,
+    /// LE address and address type of the local controller, if read for LE.
+    ///
+    /// This is not used when providing the data of a remote device.
+    pub le_address: Option<(Address, AddressType)>,
+}
+
+/// Keys of a bonded device.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bond {
+    /// Address of the remote device.
+    pub address: Address,
+    /// Address type of the remote device.
+    pub address_type: AddressType,
+    /// BR/EDR link key.
+    pub link_key: Option<LinkKey>,
+    /// LE long term keys.
+    pub long_term_keys: Vec<LongTermKey>,
+    /// Identity resolving key.
+    pub identity_resolving_key: Option<IdentityResolvingKey>,
+}
+
+impl Bond {
+    /// Reads the bonds of the adapter with the specified address from
+    /// the storage of the Bluetooth daemon.
+    pub fn read_storage(adapter_address: Address) -> Result<Vec<Self>> {
+        let dir = storage_dir(adapter_address);
+        let mut bonds = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let Some(address) = entry.file_name().to_str().and_then(|name| name.parse::<Address>().ok()) else {
+                continue;
+            };
+            let info = match fs::read_to_string(entry.path().join("info")) {
+                Ok(info) => KeyFile::parse(&info),
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+            if let Some(bond) = Self::from_key_file(address, &info)? {
+                bonds.push(bond);
+            }
+        }
+        Ok(bonds)
+    }
+
+    /// Writes the bond into the storage of the Bluetooth daemon for the
+    /// adapter with the specified address.
+    ///
+    /// Other information already stored about the device is preserved.
+    /// The Bluetooth daemon reads its storage only when it starts.
+    pub fn write_storage(&self, adapter_address: Address) -> Result<()> {
+        let dir = storage_dir(adapter_address).join(self.address.to_string());
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("info");
+        let mut info = match fs::read_to_string(&path) {
+            Ok(info) => KeyFile::parse(&info),
+            Err(err) if err.kind() == ErrorKind::NotFound => KeyFile::default(),
+            Err(err) => return Err(err),
+        };
+        self.to_key_file(&mut info);
+        fs::write(&path, info.to_string())
+    }
+
+    fn from_key_file(address: Address, info: &KeyFile) -> Result<Option<Self>> {
+        let invalid = |what: &str| Error::new(ErrorKind::InvalidData, format!("invalid {what} for {address}"));
+        let address_type = match info.get("General", "AddressType") {
+            Some("public") => AddressType::LePublic,
+            Some("static") => AddressType::LeRandom,
+            _ => AddressType::BrEdr,
+        };
+
+        let link_key = match info.get("LinkKey", "Key") {
+            Some(key) => Some(LinkKey {
+                address,
+                address_type: AddressType::BrEdr,
+                key_type: info.value("LinkKey", "Type").ok_or_else(|| invalid("link key type"))?,
+                value: parse_key(key).ok_or_else(|| invalid("link key"))?,
+                pin_length: info.value("LinkKey", "PINLength").unwrap_or_default(),
+            }),
+            None => None,
+        };
+
+        // Older versions of the Bluetooth daemon stored the peripheral key as SlaveLongTermKey.
+        let peripheral_group = if info.get("PeripheralLongTermKey", "Key").is_some() {
+            "PeripheralLongTermKey"
+        } else {
+            "SlaveLongTermKey"
+        };
+        let mut long_term_keys = Vec::new();
+        for (group, central) in [("LongTermKey", true), (peripheral_group, false)] {
+            if let Some(key) = info.get(group, "Key") {
+                long_term_keys.push(LongTermKey {
+                    address,
+                    address_type,
+                    key_type: info.value(group, "Authenticated").unwrap_or_default(),
+                    central,
+                    encryption_size: info.value(group, "EncSize").unwrap_or(16),
+                    ediv: info.value(group, "EDiv").unwrap_or_default(),
+                    rand: info.value(group, "Rand").unwrap_or_default(),
+                    value: parse_key(key).ok_or_else(|| invalid("long term key"))?,
+                });
+            }
+        }
+
+        let identity_resolving_key = match info.get("IdentityResolvingKey", "Key") {
+            Some(key) => Some(IdentityResolvingKey {
+                address,
+                address_type,
+                value: parse_key(key).ok_or_else(|| invalid("identity resolving key"))?,
+            }),
+            None => None,
+        };
+
+        if link_key.is_none() && long_term_keys.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self { address, address_type, link_key, long_term_keys, identity_resolving_key }))
+    }
+
+    fn to_key_file(&self, info: &mut KeyFile) {
+        match self.address_type {
+            AddressType::BrEdr => (),
+            AddressType::LePublic => info.set("General", "AddressType", "public"),
+            AddressType::LeRandom => info.set("General", "AddressType", "static"),
+        }
+        if let Some(key) = &self.link_key {
+            info.set("LinkKey", "Key", &hex::encode_upper(key.value));
+            info.set("LinkKey", "Type", &key.key_type.to_string());
+            info.set("LinkKey", "PINLength", &key.pin_length.to_string());
+        }
+        for key in &self.long_term_keys {
+            let group = if key.central { "LongTermKey" } else { "PeripheralLongTermKey" };
+            info.set(group, "Key", &hex::encode_upper(key.value));
+            info.set(group, "Authenticated", &key.key_type.to_string());
+            info.set(group, "EncSize", &key.encryption_size.to_string());
+            info.set(group, "EDiv", &key.ediv.to_string());
+            info.set(group, "Rand", &key.rand.to_string());
+        }
+        if let Some(key) = &self.identity_resolving_key {
+            info.set("IdentityResolvingKey", "Key", &hex::encode_upper(key.value));
+        }
+    }
+}
+
+/// Storage directory of the adapter with the specified address.
+fn storage_dir(adapter_address: Address) -> PathBuf {
+    Path::new(STORAGE_DIR).join(adapter_address.to_string())
+}
+
+fn parse_key(s: &str) -> Option<[u8; 16]> {
+    hex::decode(s.trim()).ok()?.try_into().ok()
+}
+
+/// Minimal parser and writer for the key files used by the Bluetooth daemon.
+#[derive(Default)]
+struct KeyFile {
+    groups: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl KeyFile {
+    fn parse(s: &str) -> Self {
+        let mut kf = Self::default();
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(group) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                kf.groups.push((group.to_string(), Vec::new()));
+            } else if let (Some((key, value)), Some((_, entries))) = (line.split_once('='), kf.groups.last_mut())
+            {
+                entries.push((key.trim().to_string(), value.trim().to_string()));
+            }
+        }
+        kf
+    }
+
+    fn get(&self, group: &str, key: &str) -> Option<&str> {
+        let (_, entries) = self.groups.iter().find(|(g, _)| g == group)?;
+        entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    fn value<T: std::str::FromStr>(&self, group: &str, key: &str) -> Option<T> {
+        self.get(group, key)?.parse().ok()
+    }
+
+    fn set(&mut self, group: &str, key: &str, value: &str) {
+        let idx = match self.groups.iter().position(|(g, _)| g == group) {
+            Some(idx) => idx,
+            None => {
+                self.groups.push((group.to_string(), Vec::new()));
+                self.groups.len() - 1
+            }
+        };
+        let entries = &mut self.groups[idx].1;
+        match entries.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => entries.push((key.to_string(), value.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for KeyFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (group, entries)) in self.groups.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "[{group}]")?;
+            for (key, value) in entries {
+                writeln!(f, "{key}={value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Collects all keys of the specified bonds for loading into a controller.
+#[cfg(feature = "bluetoothd-core")]
+pub(crate) fn collect_keys(bonds: &[Bond]) -> (Vec<LinkKey>, Vec<LongTermKey>, Vec<IdentityResolvingKey>) {
+    use std::collections::HashMap;
+
+    let mut link_keys = HashMap::new();
+    let mut ltks = Vec::new();
+    let mut irks = HashMap::new();
+    for bond in bonds {
+        if let Some(key) = &bond.link_key {
+            link_keys.insert(bond.address, key.clone());
+        }
+        ltks.retain(|key: &LongTermKey| key.address != bond.address);
+        ltks.extend(bond.long_term_keys.iter().cloned());
+        if let Some(key) = &bond.identity_resolving_key {
+            irks.insert(bond.address, key.clone());
+        }
+    }
+    (link_keys.into_values().collect(), ltks, irks.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_peripheral_long_term_key_is_deduplicated() {
+        let address: Address = "00:11:22:33:44:55".parse().unwrap();
+        let info = KeyFile::parse(
+            "[General]\nAddressType=public\n\n\
+             [PeripheralLongTermKey]\nKey=00112233445566778899AABBCCDDEEFF\nEDiv=1\nRand=2\n\n\
+             [SlaveLongTermKey]\nKey=FFEEDDCCBBAA99887766554433221100\nEDiv=3\nRand=4\n",
+        );
+        let bond = Bond::from_key_file(address, &info).unwrap().unwrap();
+        assert_eq!(bond.long_term_keys.len(), 1);
+        assert!(!bond.long_term_keys[0].central);
+        assert_eq!(bond.long_term_keys[0].ediv, 1);
+
+        let info = KeyFile::parse("[SlaveLongTermKey]\nKey=FFEEDDCCBBAA99887766554433221100\nEDiv=3\n");
+        let bond = Bond::from_key_file(address, &info).unwrap().unwrap();
+        assert_eq!(bond.long_term_keys.len(), 1);
+        assert!(!bond.long_term_keys[0].central);
+        assert_eq!(bond.long_term_keys[0].ediv, 3);
+    }
+
+    #[test]
+    fn le_oob_eir_is_parsed() {
+        let mut eir = vec![8, EIR_LE_BDADDR, 0x55, 0x44, 0x33, 0x22, 0x11, 0xc0, 0x01];
+        eir.extend([2, 0x1c, 0x00]);
+        eir.extend([17, EIR_LE_SC_CONFIRM]);
+        eir.extend([0xaa; 16]);
+        eir.extend([17, EIR_LE_SC_RANDOM]);
+        eir.extend([0xbb; 16]);
+        let data = parse_oob_eir(&eir).unwrap();
+        assert_eq!(data.le_address, Some(("C0:11:22:33:44:55".parse().unwrap(), AddressType::LeRandom)));
+        assert_eq!(data.hash256, Some([0xaa; 16]));
+        assert_eq!(data.randomizer256, Some([0xbb; 16]));
+        assert_eq!(data.hash192, None);
+    }
+
+    #[test]
+    fn truncated_oob_eir_is_rejected() {
+        assert!(parse_oob_eir(&[17, EIR_HASH_C192, 0, 0]).is_err());
+        assert!(parse_oob_eir(&[3, EIR_HASH_C192, 0, 0]).is_err());
+    }
+}
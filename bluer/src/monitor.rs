@@ -20,7 +20,8 @@ use tokio_stream::wrappers::ReceiverStream;
 use uuid::Uuid;
 
 use crate::{
-    method_call, Address, DbusResult, Device, Error, ErrorKind, Result, SessionInner, SERVICE_NAME, TIMEOUT,
+    method_call, Address, CompanyId, DbusResult, Device, Error, ErrorKind, Result, SessionInner, UuidExt,
+    OBJECT_MANAGER_INTERFACE, SERVICE_NAME, TIMEOUT,
 };
 
 pub(crate) const INTERFACE: &str = "org.bluez.AdvertisementMonitor1";
@@ -44,6 +45,20 @@ impl Default for Type {
     }
 }
 
+/// Advertisement monitoring feature supported by the controller.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Feature {
+    /// The controller is capable of performing advertisement
+    /// monitoring by patterns.
+    ///
+    /// Monitors of type [`Type::OrPatterns`] are then offloaded to the
+    /// controller, which saves power compared to active scanning.
+    #[strum(serialize = "controller-patterns")]
+    ControllerPatterns,
+}
+
 /// Common advertising data types for [`Pattern::data_type`].
 ///
 /// See [the GATT specification](https://www.bluetooth.com/specifications/assigned-numbers/generic-access-profile/)
@@ -85,6 +100,15 @@ pub mod data_type {
     /// TX Power Level: Contains the device's transmit power level.
     pub const TX_POWER_LEVEL: u8 = 0x0A;
 
+    /// Service Data - 16-bit UUID: Contains a 16-bit service UUID followed by additional service data.
+    pub const SERVICE_DATA_16_BIT_UUID: u8 = 0x16;
+
+    /// Service Data - 32-bit UUID: Contains a 32-bit service UUID followed by additional service data.
+    pub const SERVICE_DATA_32_BIT_UUID: u8 = 0x20;
+
+    /// Service Data - 128-bit UUID: Contains a 128-bit service UUID followed by additional service data.
+    pub const SERVICE_DATA_128_BIT_UUID: u8 = 0x21;
+
     /// Manufacturer Specific Data: Contains data specific to the manufacturer.
     pub const MANUFACTURER_SPECIFIC_DATA: u8 = 0xFF;
 }
@@ -114,6 +138,138 @@ impl Pattern {
     pub fn new(data_type: u8, start_position: u8, content: &[u8]) -> Self {
         Self { data_type, start_position, content: content.to_vec() }
     }
+
+    /// Checks that the pattern is within the limits accepted by the Bluetooth daemon.
+    ///
+    /// The content must not be empty and must fit, starting at the start position,
+    /// into the maximum advertising data length of 31 bytes.
+    pub fn validate(&self) -> Result<()> {
+        if self.content.is_empty() {
+            return Err(Error {
+                kind: ErrorKind::InvalidArguments,
+                message: "advertisement monitor pattern content is empty".to_string(),
+            });
+        }
+        if usize::from(self.start_position) + self.content.len() > MAX_AD_DATA_LEN {
+            return Err(Error {
+                kind: ErrorKind::InvalidArguments,
+                message: format!(
+                    "advertisement monitor pattern of length {} at position {} exceeds {} bytes",
+                    self.content.len(),
+                    self.start_position,
+                    MAX_AD_DATA_LEN
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Maximum length of advertising data.
+const MAX_AD_DATA_LEN: usize = 31;
+
+/// Builder for the patterns of an advertisement monitor.
+///
+/// A monitor of type [`Type::OrPatterns`] matches an advertisement if any of its
+/// patterns matches.
+/// Helpers that match a value in several advertising data types,
+/// such as [`service_uuid`](Self::service_uuid), add one pattern per data type.
+///
+/// Use [`build`](Self::build) to validate the patterns and set them on a [`Monitor`].
+#[derive(Debug, Clone, Default)]
+pub struct PatternsBuilder {
+    patterns: Vec<Pattern>,
+}
+
+impl PatternsBuilder {
+    /// Creates an empty patterns builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the specified pattern.
+    pub fn pattern(mut self, pattern: Pattern) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+
+    /// Matches advertisements listing the specified service UUID first.
+    ///
+    /// Both complete and incomplete service UUID lists of the shortest possible
+    /// UUID form are matched.
+    /// Since patterns match at a fixed position, only the first UUID of a list is considered.
+    pub fn service_uuid(self, uuid: Uuid) -> Self {
+        let (content, incomplete, complete) = match (uuid.as_u16(), uuid.as_u32()) {
+            (Some(short), _) => (
+                short.to_le_bytes().to_vec(),
+                data_type::INCOMPLETE_LIST_16_BIT_SERVICE_CLASS_UUIDS,
+                data_type::COMPLETE_LIST_16_BIT_SERVICE_CLASS_UUIDS,
+            ),
+            (None, Some(short)) => (
+                short.to_le_bytes().to_vec(),
+                data_type::INCOMPLETE_LIST_32_BIT_SERVICE_CLASS_UUIDS,
+                data_type::COMPLETE_LIST_32_BIT_SERVICE_CLASS_UUIDS,
+            ),
+            (None, None) => (
+                uuid.as_u128().to_le_bytes().to_vec(),
+                data_type::INCOMPLETE_LIST_128_BIT_SERVICE_CLASS_UUIDS,
+                data_type::COMPLETE_LIST_128_BIT_SERVICE_CLASS_UUIDS,
+            ),
+        };
+        self.pattern(Pattern::new(incomplete, 0, &content)).pattern(Pattern::new(complete, 0, &content))
+    }
+
+    /// Matches advertisements containing service data for the specified service UUID
+    /// that starts with the specified data.
+    ///
+    /// The data may be empty to match any service data of the service.
+    pub fn service_data(self, uuid: Uuid, data: &[u8]) -> Self {
+        let (mut content, data_type) = match (uuid.as_u16(), uuid.as_u32()) {
+            (Some(short), _) => (short.to_le_bytes().to_vec(), data_type::SERVICE_DATA_16_BIT_UUID),
+            (None, Some(short)) => (short.to_le_bytes().to_vec(), data_type::SERVICE_DATA_32_BIT_UUID),
+            (None, None) => (uuid.as_u128().to_le_bytes().to_vec(), data_type::SERVICE_DATA_128_BIT_UUID),
+        };
+        content.extend_from_slice(data);
+        self.pattern(Pattern::new(data_type, 0, &content))
+    }
+
+    /// Matches advertisements containing manufacturer specific data of the specified company.
+    pub fn company_id(self, company_id: CompanyId) -> Self {
+        self.manufacturer_data(company_id, &[])
+    }
+
+    /// Matches advertisements containing manufacturer specific data of the specified company
+    /// that starts with the specified data.
+    pub fn manufacturer_data(self, company_id: CompanyId, data: &[u8]) -> Self {
+        let mut content = company_id.to_le_bytes().to_vec();
+        content.extend_from_slice(data);
+        self.pattern(Pattern::new(data_type::MANUFACTURER_SPECIFIC_DATA, 0, &content))
+    }
+
+    /// Matches advertisements whose shortened or complete local name starts with the specified prefix.
+    pub fn local_name_prefix(self, prefix: &str) -> Self {
+        self.pattern(Pattern::new(data_type::SHORTENED_LOCAL_NAME, 0, prefix.as_bytes())).pattern(Pattern::new(
+            data_type::COMPLETE_LOCAL_NAME,
+            0,
+            prefix.as_bytes(),
+        ))
+    }
+
+    /// Validates the patterns and returns them.
+    ///
+    /// Fails if no pattern has been added or a pattern is invalid.
+    pub fn build(self) -> Result<Vec<Pattern>> {
+        if self.patterns.is_empty() {
+            return Err(Error {
+                kind: ErrorKind::InvalidArguments,
+                message: "no advertisement monitor pattern specified".to_string(),
+            });
+        }
+        for pattern in &self.patterns {
+            pattern.validate()?;
+        }
+        Ok(self.patterns)
+    }
 }
 
 /// Grouping rules on how to propagate the received
@@ -196,6 +352,7 @@ pub struct Monitor {
     ///
     /// Required if [`monitor_type`](Self::monitor_type) is
     /// [`Type::OrPatterns`].
+    /// Use [`PatternsBuilder`] to construct patterns for common cases.
     pub patterns: Option<Vec<Pattern>>,
 
     #[doc(hidden)]
@@ -222,6 +379,11 @@ pub enum MonitorEvent {
     /// Once receiving the event, the client
     /// should start to monitor the corresponding device to
     /// retrieve the changes on RSSI and advertisement content.
+    ///
+    /// The Bluetooth daemon provides the advertising data grouped by AD type through
+    /// [Device::advertising_data](crate::Device::advertising_data).
+    /// The raw advertising data and the advertising event type are available from
+    /// the `mgmt::Observer` of the management interface.
     DeviceFound(DeviceId),
 
     /// This event notifies the client of losing the
@@ -399,6 +561,7 @@ impl MonitorManager {
             let properties_token = cr.properties();
             cr.insert(root.clone(), [&object_manager_token, &introspectable_token, &properties_token], ());
         }
+        inner.record_export(&root, &[OBJECT_MANAGER_INTERFACE]);
 
         log::trace!("Registering advertisement monitor root at {}", &root);
         let proxy = Proxy::new(SERVICE_NAME, manager_path, TIMEOUT, inner.connection.clone());
@@ -407,7 +570,7 @@ impl MonitorManager {
         let (_drop_tx, drop_rx) = oneshot::channel();
         let unreg_root = root.clone();
         let unreg_inner = inner.clone();
-        tokio::spawn(async move {
+        inner.clone().spawn(async move {
             let _ = drop_rx.await;
 
             log::trace!("Unregistering advertisement monitor root at {}", &unreg_root);
@@ -417,6 +580,7 @@ impl MonitorManager {
             log::trace!("Unpublishing advertisement monitor root at {}", &unreg_root);
             let mut cr = unreg_inner.crossroads.lock().await;
             cr.remove::<()>(&unreg_root);
+            unreg_inner.record_unexport(&unreg_root);
         });
 
         Ok(Self { inner, root, _drop_tx })
@@ -424,8 +588,14 @@ impl MonitorManager {
 
     /// Registers an advertisement monitor target.
     ///
+    /// The patterns of the monitor are validated before registration.
+    ///
     /// Returns a handle to receive events.
     pub async fn register(&self, advertisement_monitor: Monitor) -> Result<MonitorHandle> {
+        for pattern in advertisement_monitor.patterns.iter().flatten() {
+            pattern.validate()?;
+        }
+
         let name = dbus::Path::new(format!("{}/{}", &self.root, Uuid::new_v4().as_simple())).unwrap();
 
         log::trace!("Publishing advertisement monitor target at {}", &name);
@@ -446,15 +616,17 @@ impl MonitorManager {
             let mut cr = self.inner.crossroads.lock().await;
             cr.insert(name.clone(), [&self.inner.monitor_token], Arc::new(reg));
         }
+        self.inner.record_export(&name, &[INTERFACE]);
 
         let inner = self.inner.clone();
         let unreg_name = name.clone();
-        tokio::spawn(async move {
+        inner.clone().spawn(async move {
             let _ = drop_rx.await;
 
             log::trace!("Unpublishing advertisement monitor target at {}", &unreg_name);
             let mut cr = inner.crossroads.lock().await;
             cr.remove::<Arc<RegisteredMonitor>>(&unreg_name);
+            inner.record_unexport(&unreg_name);
         });
 
         tokio::select! {
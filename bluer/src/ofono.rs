@@ -0,0 +1,294 @@
+//! Hands-Free Profile (HFP) audio gateways managed by the oFono telephony daemon.
+//!
+//! When a phone is connected using the Hands-Free Profile, oFono exposes it as a modem.
+//! This module correlates Bluetooth devices with these modems by address and allows
+//! observing the state of the audio gateway, i.e. the battery level and signal strength
+//! of the phone and the state of its calls, for example in car dashboards.
+//!
+//! Use [Session::hfp_gateways](crate::Session::hfp_gateways) to list all gateways or
+//! [Device::hfp_gateway](crate::Device::hfp_gateway) to obtain the gateway of a device.
+//! oFono must be running and handling the Hands-Free Profile.
+
+use dbus::{
+    arg::{prop_cast, PropMap, RefArg, Variant},
+    message::{MatchRule, MessageType},
+    nonblock::{Proxy, SyncConnection},
+    Path,
+};
+use futures::{Stream, StreamExt};
+use std::{fmt, sync::Arc};
+use strum::{Display, EnumString};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{Address, Device, Result, SessionInner, TIMEOUT};
+
+pub(crate) const SERVICE_NAME: &str = "org.ofono";
+const MANAGER_INTERFACE: &str = "org.ofono.Manager";
+const MODEM_INTERFACE: &str = "org.ofono.Modem";
+const HANDSFREE_INTERFACE: &str = "org.ofono.Handsfree";
+const NETWORK_REGISTRATION_INTERFACE: &str = "org.ofono.NetworkRegistration";
+const VOICE_CALL_MANAGER_INTERFACE: &str = "org.ofono.VoiceCallManager";
+const VOICE_CALL_INTERFACE: &str = "org.ofono.VoiceCall";
+
+/// Modem type of Hands-Free Profile audio gateways.
+const HFP_MODEM_TYPE: &str = "hfp";
+/// Prefix of the object paths of Hands-Free Profile modems, followed by the device path.
+const HFP_MODEM_PREFIX: &str = "/hfp";
+
+/// State of a voice call.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[strum(serialize_all = "lowercase")]
+#[non_exhaustive]
+pub enum CallState {
+    /// The call is active.
+    Active,
+    /// The call is on hold.
+    Held,
+    /// The call is being dialed.
+    Dialing,
+    /// The remote party is being alerted.
+    Alerting,
+    /// The call is incoming.
+    Incoming,
+    /// The call is waiting.
+    Waiting,
+    /// The call has been disconnected.
+    Disconnected,
+}
+
+/// Voice call of an audio gateway.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Call {
+    /// Identifier of the call, unique while the call exists.
+    pub id: String,
+    /// State of the call.
+    pub state: CallState,
+    /// Line identification, i.e. the phone number of the remote party, if known.
+    pub line_identification: Option<String>,
+}
+
+impl Call {
+    fn from_props(id: String, props: &PropMap) -> Option<Self> {
+        Some(Self {
+            id,
+            state: prop_cast::<String>(props, "State")?.parse().ok()?,
+            line_identification: prop_cast::<String>(props, "LineIdentification")
+                .filter(|number| !number.is_empty())
+                .cloned(),
+        })
+    }
+}
+
+/// State of an audio gateway.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GatewayStatus {
+    /// Battery charge level of the phone ranging from 0 to 5.
+    pub battery_level: Option<u8>,
+    /// Signal strength of the cellular network in percent.
+    pub signal_strength: Option<u8>,
+    /// Name of the network operator.
+    pub operator: Option<String>,
+    /// Current calls.
+    pub calls: Vec<Call>,
+    #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _non_exhaustive: (),
+}
+
+/// Change of the state of an audio gateway.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum GatewayEvent {
+    /// The battery charge level of the phone has changed.
+    BatteryLevel(u8),
+    /// The signal strength of the cellular network has changed.
+    SignalStrength(u8),
+    /// The network operator has changed.
+    Operator(String),
+    /// A call has been added.
+    CallAdded(Call),
+    /// The state of a call has changed.
+    CallStateChanged {
+        /// Identifier of the call.
+        id: String,
+        /// New state of the call.
+        state: CallState,
+    },
+    /// A call has been removed.
+    CallRemoved {
+        /// Identifier of the call.
+        id: String,
+    },
+}
+
+/// Hands-Free Profile audio gateway, i.e. a phone, managed by oFono.
+#[derive(Clone)]
+pub struct HfpGateway {
+    inner: Arc<SessionInner>,
+    modem: Path<'static>,
+    address: Address,
+}
+
+impl fmt::Debug for HfpGateway {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HfpGateway {{ address: {}, modem: {} }}", self.address, &self.modem)
+    }
+}
+
+impl HfpGateway {
+    /// Lists all Hands-Free Profile audio gateways known to oFono.
+    pub(crate) async fn all(inner: Arc<SessionInner>) -> Result<Vec<Self>> {
+        let proxy = Proxy::new(SERVICE_NAME, "/", TIMEOUT, &*inner.connection);
+        let (modems,): (Vec<(Path<'static>, PropMap)>,) =
+            proxy.method_call(MANAGER_INTERFACE, "GetModems", ()).await?;
+
+        Ok(modems
+            .into_iter()
+            .filter(|(_, props)| prop_cast::<String>(props, "Type").is_some_and(|ty| ty == HFP_MODEM_TYPE))
+            .filter_map(|(modem, props)| {
+                let address = Self::modem_address(&modem, &props)?;
+                Some(Self { inner: inner.clone(), modem, address })
+            })
+            .collect())
+    }
+
+    /// Determines the address of the device of a modem from its path or serial number.
+    fn modem_address(modem: &Path, props: &PropMap) -> Option<Address> {
+        let from_path = modem
+            .strip_prefix(HFP_MODEM_PREFIX)
+            .and_then(|device_path| Path::new(device_path).ok())
+            .and_then(|device_path| Device::parse_dbus_path(&device_path).map(|(_, address)| address));
+        from_path.or_else(|| prop_cast::<String>(props, "Serial")?.parse().ok())
+    }
+
+    fn proxy(&self) -> Proxy<'_, &SyncConnection> {
+        Proxy::new(SERVICE_NAME, &self.modem, TIMEOUT, &*self.inner.connection)
+    }
+
+    /// The Bluetooth address of the phone.
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Object path of the oFono modem.
+    pub fn modem_path(&self) -> &Path<'static> {
+        &self.modem
+    }
+
+    /// Whether the modem is powered and online.
+    pub async fn is_online(&self) -> Result<bool> {
+        let (props,): (PropMap,) = self.proxy().method_call(MODEM_INTERFACE, "GetProperties", ()).await?;
+        Ok(prop_cast::<bool>(&props, "Online").copied().unwrap_or_default())
+    }
+
+    /// Queries the current state of the audio gateway.
+    ///
+    /// Information provided by interfaces that the modem does not currently offer is omitted.
+    pub async fn status(&self) -> Result<GatewayStatus> {
+        let mut status = GatewayStatus::default();
+
+        let handsfree: std::result::Result<(PropMap,), dbus::Error> =
+            self.proxy().method_call(HANDSFREE_INTERFACE, "GetProperties", ()).await;
+        if let Ok((props,)) = handsfree {
+            status.battery_level = prop_cast::<u8>(&props, "BatteryChargeLevel").copied();
+        }
+
+        let network: std::result::Result<(PropMap,), dbus::Error> =
+            self.proxy().method_call(NETWORK_REGISTRATION_INTERFACE, "GetProperties", ()).await;
+        if let Ok((props,)) = network {
+            status.signal_strength = prop_cast::<u8>(&props, "Strength").copied();
+            status.operator = prop_cast::<String>(&props, "Name").cloned();
+        }
+
+        let calls: std::result::Result<(Vec<(Path<'static>, PropMap)>,), dbus::Error> =
+            self.proxy().method_call(VOICE_CALL_MANAGER_INTERFACE, "GetCalls", ()).await;
+        if let Ok((calls,)) = calls {
+            status.calls = calls
+                .into_iter()
+                .filter_map(|(path, props)| Call::from_props(path.to_string(), &props))
+                .collect();
+        }
+
+        Ok(status)
+    }
+
+    /// Streams changes of the state of the audio gateway.
+    ///
+    /// Use [status](Self::status) to obtain the state before the first change.
+    /// The stream ends when the connection to the message bus is lost.
+    pub async fn events(&self) -> Result<impl Stream<Item = GatewayEvent>> {
+        let rule = MatchRule::new()
+            .with_type(MessageType::Signal)
+            .with_sender(SERVICE_NAME)
+            .with_namespaced_path(self.modem.clone());
+        let msg_match = self.inner.connection.add_match(rule).await?;
+        let (tx, rx) = mpsc::channel(16);
+
+        let connection = self.inner.connection.clone();
+        self.inner.spawn(async move {
+            let (msg_match, mut msgs) = msg_match.msg_stream();
+            loop {
+                tokio::select! {
+                    msg = msgs.next() => {
+                        let Some(msg) = msg else { break };
+                        let (Some(interface), Some(member)) = (msg.interface(), msg.member()) else { continue };
+                        let evt = match (&*interface, &*member) {
+                            (_, "PropertyChanged") => {
+                                let Ok((name, value)) = msg.read2::<String, Variant<Box<dyn RefArg>>>() else {
+                                    continue;
+                                };
+                                Self::property_event(&interface, msg.path().as_deref(), &name, &value)
+                            }
+                            (VOICE_CALL_MANAGER_INTERFACE, "CallAdded") => msg
+                                .read2::<Path, PropMap>()
+                                .ok()
+                                .and_then(|(path, props)| Call::from_props(path.to_string(), &props))
+                                .map(GatewayEvent::CallAdded),
+                            (VOICE_CALL_MANAGER_INTERFACE, "CallRemoved") => msg
+                                .read1::<Path>()
+                                .ok()
+                                .map(|path| GatewayEvent::CallRemoved { id: path.to_string() }),
+                            _ => None,
+                        };
+                        if let Some(evt) = evt {
+                            if tx.send(evt).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    () = tx.closed() => break,
+                }
+            }
+            let _ = connection.remove_match(msg_match.token()).await;
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    /// Converts a property change of the modem or one of its calls into an event.
+    fn property_event(
+        interface: &str, path: Option<&str>, name: &str, value: &Variant<Box<dyn RefArg>>,
+    ) -> Option<GatewayEvent> {
+        match (interface, name) {
+            (HANDSFREE_INTERFACE, "BatteryChargeLevel") => {
+                Some(GatewayEvent::BatteryLevel(value.0.as_u64()?.try_into().ok()?))
+            }
+            (NETWORK_REGISTRATION_INTERFACE, "Strength") => {
+                Some(GatewayEvent::SignalStrength(value.0.as_u64()?.try_into().ok()?))
+            }
+            (NETWORK_REGISTRATION_INTERFACE, "Name") => {
+                Some(GatewayEvent::Operator(value.0.as_str()?.to_string()))
+            }
+            (VOICE_CALL_INTERFACE, "State") => Some(GatewayEvent::CallStateChanged {
+                id: path?.to_string(),
+                state: value.0.as_str()?.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,375 @@
+//! Pairing of new devices.
+//!
+//! This module provides a high-level pairing flow for onboarding user interfaces.
+//! It combines making the adapter discoverable and pairable, registering an
+//! authorization agent, discovering a device matching a filter and pairing with,
+//! trusting and connecting to it.
+//!
+//! Use [pair_new_device] to start the flow.
+//!
+//! Use [connect_with_repair] to connect to bonded devices that may have lost their keys.
+//!
+//! Use [Device::pair_with_progress] to follow the progress of pairing with a known device.
+
+use futures::{pin_mut, stream::SelectAll, Stream, StreamExt};
+use std::{collections::HashSet, fmt, time::Duration};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use uuid::Uuid;
+
+use crate::{
+    agent::{Agent, AgentRequestKind},
+    Adapter, AdapterEvent, Address, Device, DeviceEvent, DeviceProperty, Error, ErrorKind, Result,
+};
+
+/// Options for pairing a new device.
+#[derive(Default)]
+pub struct PairingOptions {
+    /// Only pair with a device whose name contains this string.
+    pub name: Option<String>,
+    /// Only pair with a device that advertises at least one of these service UUIDs.
+    ///
+    /// If empty, the device is not filtered by service UUIDs.
+    pub uuids: HashSet<Uuid>,
+    /// Make the adapter discoverable and pairable while the flow is in progress.
+    ///
+    /// This is necessary if the remote device initiates pairing.
+    pub discoverable: bool,
+    /// Discoverable timeout.
+    ///
+    /// Zero means that the adapter remains discoverable until the flow ends.
+    pub discoverable_timeout: Duration,
+    /// Authorization agent to register while the flow is in progress.
+    ///
+    /// If [None], the default agent of the system is used.
+    pub agent: Option<Agent>,
+    /// Mark the device as trusted after pairing.
+    pub trust: bool,
+    /// Connect to the device after pairing.
+    pub connect: bool,
+    #[doc(hidden)]
+    pub _non_exhaustive: (),
+}
+
+impl fmt::Debug for PairingOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PairingOptions")
+            .field("name", &self.name)
+            .field("uuids", &self.uuids)
+            .field("discoverable", &self.discoverable)
+            .field("discoverable_timeout", &self.discoverable_timeout)
+            .field("agent", &self.agent.is_some())
+            .field("trust", &self.trust)
+            .field("connect", &self.connect)
+            .finish()
+    }
+}
+
+impl PairingOptions {
+    /// Checks whether the device matches the name and service UUID filters
+    /// and is not yet paired.
+    async fn matches(&self, device: &Device) -> Result<bool> {
+        if device.is_paired().await? {
+            return Ok(false);
+        }
+        if let Some(name) = &self.name {
+            match device.name().await? {
+                Some(dev_name) if dev_name.contains(name.as_str()) => (),
+                _ => return Ok(false),
+            }
+        }
+        if !self.uuids.is_empty() {
+            match device.uuids().await? {
+                Some(dev_uuids) if !dev_uuids.is_disjoint(&self.uuids) => (),
+                _ => return Ok(false),
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Progress of pairing a new device.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum PairingEvent {
+    /// The adapter has been made discoverable and pairable.
+    Discoverable,
+    /// The authorization agent has been registered.
+    AgentRegistered,
+    /// Discovery of devices has started.
+    Discovering,
+    /// A device matching the filter has been found.
+    DeviceFound(Address),
+    /// The device has been paired.
+    Paired(Address),
+    /// The device has been marked as trusted.
+    Trusted(Address),
+    /// The device has been connected.
+    Connected(Address),
+    /// Pairing completed successfully.
+    ///
+    /// This is the last event.
+    Completed(Address),
+    /// Pairing failed.
+    ///
+    /// This is the last event.
+    Failed(Error),
+}
+
+/// Pairs with a new device that matches the specified options.
+///
+/// Returns a stream of progress events, which ends with either
+/// [PairingEvent::Completed] or [PairingEvent::Failed].
+/// If the adapter is not yet powered, it is switched on for the duration of the flow.
+///
+/// Drop the stream to cancel the pairing flow.
+/// The adapter state, including its power state, is restored and the agent is
+/// unregistered once the flow ends.
+pub async fn pair_new_device(
+    adapter: &Adapter, options: PairingOptions,
+) -> Result<impl Stream<Item = PairingEvent>> {
+    let powered = adapter.powered_session(true).await?;
+
+    let (tx, rx) = mpsc::channel(16);
+    let adapter = adapter.clone();
+    adapter.clone().spawn(async move {
+        let _powered = powered;
+        tokio::select! {
+            res = perform(&adapter, options, &tx) => {
+                let evt = match res {
+                    Ok(address) => PairingEvent::Completed(address),
+                    Err(err) => PairingEvent::Failed(err),
+                };
+                let _ = tx.send(evt).await;
+            },
+            () = tx.closed() => log::debug!("Pairing on {} canceled", adapter.name()),
+        }
+    });
+
+    Ok(ReceiverStream::new(rx))
+}
+
+async fn perform(
+    adapter: &Adapter, mut options: PairingOptions, tx: &mpsc::Sender<PairingEvent>,
+) -> Result<Address> {
+    let _discoverable = match options.discoverable {
+        true => {
+            let mut session = adapter.discoverable_session(options.discoverable_timeout).await?;
+            session.set_pairable().await?;
+            let _ = tx.send(PairingEvent::Discoverable).await;
+            Some(session)
+        }
+        false => None,
+    };
+
+    let _agent = match options.agent.take() {
+        Some(agent) => {
+            let handle = adapter.register_agent(agent).await?;
+            let _ = tx.send(PairingEvent::AgentRegistered).await;
+            Some(handle)
+        }
+        None => None,
+    };
+
+    let device = {
+        let events = adapter.discover_devices().await?;
+        pin_mut!(events);
+        let _ = tx.send(PairingEvent::Discovering).await;
+
+        // Devices often report their name and services only after they have been
+        // found, so re-evaluate the filter when these properties change.
+        let mut changes = SelectAll::new();
+        loop {
+            let address = tokio::select! {
+                evt = events.next() => match evt {
+                    Some(AdapterEvent::DeviceAdded(address)) => {
+                        if let Ok(dev_events) = adapter.device(address)?.events().await {
+                            changes.push(dev_events.map(move |evt| (address, evt)).boxed());
+                        }
+                        address
+                    }
+                    Some(_) => continue,
+                    None => return Err(Error::new(ErrorKind::NotFound)),
+                },
+                Some((address, evt)) = changes.next(), if !changes.is_empty() => match evt {
+                    DeviceEvent::PropertyChanged(
+                        DeviceProperty::Name(_)
+                        | DeviceProperty::Alias(_)
+                        | DeviceProperty::Uuids(_)
+                        | DeviceProperty::Paired(_),
+                    ) => address,
+                    _ => continue,
+                },
+            };
+            let device = adapter.device(address)?;
+            if options.matches(&device).await.unwrap_or_default() {
+                break device;
+            }
+        }
+    };
+    let address = device.address();
+    log::debug!("Pairing on {} with {}", adapter.name(), address);
+    let _ = tx.send(PairingEvent::DeviceFound(address)).await;
+
+    device.pair().await?;
+    let _ = tx.send(PairingEvent::Paired(address)).await;
+
+    if options.trust {
+        device.set_trusted(true).await?;
+        let _ = tx.send(PairingEvent::Trusted(address)).await;
+    }
+
+    if options.connect {
+        device.connect().await?;
+        let _ = tx.send(PairingEvent::Connected(address)).await;
+    }
+
+    Ok(address)
+}
+
+/// Progress of pairing with a device.
+///
+/// Obtained from [Device::pair_with_progress].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum PairingProgress {
+    /// Pairing has been initiated.
+    Initiated,
+    /// The Bluetooth daemon made a request to the authorization agent.
+    AgentRequest(AgentRequestKind),
+    /// Keys have been exchanged and the device is paired.
+    KeysExchanged,
+    /// Pairing completed successfully and the bond has been established.
+    ///
+    /// This is also reported if the Bluetooth daemon does not provide the
+    /// [bonded state](crate::Device::is_bonded), which is the case for versions before 5.67.
+    ///
+    /// This is the last event.
+    Bonded,
+    /// Pairing completed successfully, but the keys are not stored and thus no bond has been established.
+    ///
+    /// This is the last event.
+    Paired,
+    /// Pairing failed for the specified reason.
+    ///
+    /// This is the last event.
+    Failed(Error),
+}
+
+/// Function deciding whether the stale bond with a device should be removed
+/// and the device re-paired.
+///
+/// It is called with the address of the device and the error that occurred when connecting.
+pub type RepairDecisionFun = Box<dyn Fn(Address, &Error) -> bool + Send + Sync>;
+
+/// Policy for re-pairing with bonded devices that have lost their keys.
+///
+/// Many consumer devices forget their bonds, for example after a factory reset.
+/// Connecting to such a device then fails with an authentication error until
+/// the stale bond has been removed and the device has been paired again.
+///
+/// Use [connect_with_repair] to apply the policy.
+pub struct RepairPolicy {
+    /// Decides whether the stale bond should be removed and the device re-paired.
+    ///
+    /// If [None], devices are always re-paired.
+    pub decide: Option<RepairDecisionFun>,
+    /// Time to wait for the device to be discovered again after its bond has been removed.
+    pub discovery_timeout: Duration,
+    #[doc(hidden)]
+    pub _non_exhaustive: (),
+}
+
+impl Default for RepairPolicy {
+    fn default() -> Self {
+        Self { decide: None, discovery_timeout: Duration::from_secs(30), _non_exhaustive: () }
+    }
+}
+
+impl fmt::Debug for RepairPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RepairPolicy")
+            .field("decide", &self.decide.is_some())
+            .field("discovery_timeout", &self.discovery_timeout)
+            .finish()
+    }
+}
+
+/// Reason codes reported by the Bluetooth daemon in a [Failed](ErrorKind::Failed) error
+/// when the remote device does not have the keys of the bond anymore.
+const KEY_MISSING_REASONS: [&str; 2] = ["br-connection-key-missing", "le-connection-key-missing"];
+
+/// Returns whether the error of a connection attempt indicates that the remote device
+/// has lost the keys of its bond.
+///
+/// This is the case for authentication failures and for connection failures
+/// with a key-missing reason code.
+pub fn is_stale_bond_error(err: &Error) -> bool {
+    match err.kind {
+        ErrorKind::AuthenticationFailed | ErrorKind::AuthenticationRejected => true,
+        ErrorKind::Failed => KEY_MISSING_REASONS.contains(&err.message.as_str()),
+        _ => false,
+    }
+}
+
+/// Connects to a device and re-pairs with it, if it has lost the keys of its bond.
+///
+/// If connecting to a paired device fails with an error indicating a stale bond,
+/// see [is_stale_bond_error], and the policy agrees, the device is removed,
+/// discovered again, paired and connected.
+/// If the device was trusted before, it is marked as trusted again.
+///
+/// Pairing is authorized by the registered agent, which must be able to
+/// handle pairing without user interaction for the "just works" method.
+pub async fn connect_with_repair(adapter: &Adapter, address: Address, policy: &RepairPolicy) -> Result<()> {
+    let device = adapter.device(address)?;
+    let err = match device.connect().await {
+        Ok(()) => return Ok(()),
+        Err(err) => err,
+    };
+
+    if !is_stale_bond_error(&err) || !device.is_paired().await? {
+        return Err(err);
+    }
+    if let Some(decide) = &policy.decide {
+        if !decide(address, &err) {
+            return Err(err);
+        }
+    }
+
+    log::debug!("Removing stale bond with {} on {}: {}", address, adapter.name(), &err);
+    let trusted = device.is_trusted().await?;
+    adapter.remove_device(address).await?;
+
+    let device = {
+        let events = adapter.discover_devices().await?;
+        pin_mut!(events);
+        let found = async {
+            while let Some(evt) = events.next().await {
+                if let AdapterEvent::DeviceAdded(addr) = evt {
+                    if addr == address {
+                        return Ok(());
+                    }
+                }
+            }
+            Err(Error::new(ErrorKind::NotFound))
+        };
+        match tokio::time::timeout(policy.discovery_timeout, found).await {
+            Ok(res) => res?,
+            Err(_) => {
+                return Err(Error {
+                    kind: ErrorKind::NotFound,
+                    message: format!("device {address} was not discovered again after removing its bond"),
+                })
+            }
+        }
+        adapter.device(address)?
+    };
+
+    log::debug!("Re-pairing with {} on {}", address, adapter.name());
+    device.pair().await?;
+    if trusted {
+        device.set_trusted(true).await?;
+    }
+    device.connect().await
+}
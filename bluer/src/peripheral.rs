@@ -0,0 +1,212 @@
+//! High-level Bluetooth LE peripheral.
+//!
+//! A [Peripheral] bundles the registration of an LE advertisement, the publishing
+//! of a local GATT application, an optional authorization agent and the tracking
+//! of connected devices.
+//! Use [Peripheral::builder] to configure and start a peripheral.
+
+use futures::{future, Stream, StreamExt};
+use std::{
+    collections::BTreeSet,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    adv::{Advertisement, AdvertisementHandle},
+    gatt::local::{Application, ApplicationHandle},
+    Adapter, Address, EventWatcher, Result,
+};
+
+#[cfg(feature = "agent")]
+use crate::agent::{Agent, AgentHandle};
+
+/// Connection event of a [Peripheral].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum PeripheralEvent {
+    /// A remote device has connected.
+    Connected(Address),
+    /// A remote device has disconnected.
+    Disconnected(Address),
+}
+
+/// Builder for a [Peripheral].
+///
+/// Use [Peripheral::builder] to obtain an instance.
+pub struct PeripheralBuilder {
+    adapter: Adapter,
+    advertisement: Advertisement,
+    application: Application,
+    #[cfg(feature = "agent")]
+    agent: Option<Agent>,
+}
+
+impl fmt::Debug for PeripheralBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PeripheralBuilder")
+            .field("adapter", &self.adapter)
+            .field("advertisement", &self.advertisement)
+            .field("application", &self.application)
+            .finish()
+    }
+}
+
+impl PeripheralBuilder {
+    /// Sets the advertisement to register.
+    ///
+    /// If the advertisement contains no service UUIDs, the UUIDs of the primary
+    /// services of the GATT application are advertised.
+    pub fn advertisement(mut self, advertisement: Advertisement) -> Self {
+        self.advertisement = advertisement;
+        self
+    }
+
+    /// Sets the GATT application to publish.
+    pub fn application(mut self, application: Application) -> Self {
+        self.application = application;
+        self
+    }
+
+    /// Sets the authorization agent to register.
+    ///
+    /// By default no agent is registered and the default agent is used.
+    #[cfg(feature = "agent")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "agent")))]
+    pub fn agent(mut self, agent: Agent) -> Self {
+        self.agent = Some(agent);
+        self
+    }
+
+    /// Powers on the adapter, registers the agent, publishes the GATT application
+    /// and starts advertising.
+    pub async fn start(self) -> Result<Peripheral> {
+        let Self {
+            adapter,
+            mut advertisement,
+            application,
+            #[cfg(feature = "agent")]
+            agent,
+        } = self;
+
+        adapter.set_powered(true).await?;
+
+        #[cfg(feature = "agent")]
+        let agent_handle = match agent {
+            Some(agent) => Some(adapter.register_agent(agent).await?),
+            None => None,
+        };
+
+        if advertisement.service_uuids.is_empty() {
+            advertisement.service_uuids = application
+                .services
+                .iter()
+                .filter(|service| service.primary)
+                .map(|service| service.uuid)
+                .collect();
+        }
+
+        let connected = Arc::new(Mutex::new(BTreeSet::new()));
+        let events =
+            EventWatcher::new(&adapter.inner, Peripheral::connection_events(&adapter, connected.clone()).await?);
+
+        let application = adapter.serve_gatt_application(application).await?;
+        let advertisement = adapter.advertise(advertisement).await?;
+
+        Ok(Peripheral {
+            adapter,
+            application,
+            advertisement,
+            #[cfg(feature = "agent")]
+            _agent: agent_handle,
+            connected,
+            events,
+        })
+    }
+}
+
+/// Bluetooth LE peripheral.
+///
+/// Drop to stop advertising, unpublish the GATT application and unregister the agent.
+#[must_use = "the Peripheral must be held for advertising and the GATT application to remain active"]
+pub struct Peripheral {
+    adapter: Adapter,
+    application: ApplicationHandle,
+    advertisement: AdvertisementHandle,
+    #[cfg(feature = "agent")]
+    _agent: Option<AgentHandle>,
+    connected: Arc<Mutex<BTreeSet<Address>>>,
+    events: EventWatcher<PeripheralEvent>,
+}
+
+impl fmt::Debug for Peripheral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Peripheral")
+            .field("adapter", &self.adapter)
+            .field("application", &self.application)
+            .field("advertisement", &self.advertisement)
+            .finish()
+    }
+}
+
+impl Peripheral {
+    /// Creates a builder for a peripheral using the specified adapter.
+    ///
+    /// By default an empty advertisement of a connectable peripheral and
+    /// an empty GATT application are used.
+    pub fn builder(adapter: Adapter) -> PeripheralBuilder {
+        PeripheralBuilder {
+            adapter,
+            advertisement: Advertisement::default(),
+            application: Application::default(),
+            #[cfg(feature = "agent")]
+            agent: None,
+        }
+    }
+
+    /// The adapter used by the peripheral.
+    pub fn adapter(&self) -> &Adapter {
+        &self.adapter
+    }
+
+    /// Handle of the published GATT application.
+    pub fn application(&self) -> &ApplicationHandle {
+        &self.application
+    }
+
+    /// Handle of the registered advertisement.
+    pub fn advertisement(&self) -> &AdvertisementHandle {
+        &self.advertisement
+    }
+
+    /// Addresses of the currently connected remote devices.
+    pub fn connected_devices(&self) -> Vec<Address> {
+        self.connected.lock().unwrap().iter().copied().collect()
+    }
+
+    /// Streams connection events of remote devices.
+    ///
+    /// Only events that occur after subscribing are delivered.
+    /// Multiple streams can be obtained.
+    pub fn events(&self) -> impl Stream<Item = PeripheralEvent> {
+        self.events.subscribe()
+    }
+
+    /// Tracks the connection state of the devices of the adapter.
+    async fn connection_events(
+        adapter: &Adapter, connected: Arc<Mutex<BTreeSet<Address>>>,
+    ) -> Result<impl Stream<Item = PeripheralEvent>> {
+        let changes = adapter.connection_changes(|_| future::ready(true)).await?;
+        Ok(changes.map(move |(address, is_connected)| match is_connected {
+            true => {
+                connected.lock().unwrap().insert(address);
+                PeripheralEvent::Connected(address)
+            }
+            false => {
+                connected.lock().unwrap().remove(&address);
+                PeripheralEvent::Disconnected(address)
+            }
+        }))
+    }
+}
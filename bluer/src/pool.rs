@@ -0,0 +1,503 @@
+//! Pool of connections to many remote devices.
+//!
+//! Bluetooth controllers support only a limited number of simultaneous connections and
+//! many controllers misbehave when several connection attempts are performed at the same time.
+//! A [ConnectionPool] limits the number of concurrent connection attempts and the number of
+//! connections held on an adapter.
+//! Requests exceeding these limits are queued and served in the order they were made,
+//! so that no device is starved.
+//! This is useful for gateways collecting data from many peripherals.
+//!
+//! When there are more devices than the controller can be connected to simultaneously,
+//! a [RoundRobinScheduler] cycles through them, connecting to each device in turn,
+//! synchronizing data and disconnecting again.
+
+use futures::{stream::FuturesUnordered, Future, Stream, StreamExt};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+    fmt,
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, OwnedSemaphorePermit, Semaphore},
+    time::{sleep_until, Instant},
+};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{Adapter, Address, Device, Error, ErrorKind, Result};
+
+/// Semaphore whose number of permits can be changed while permits are in use.
+struct Limit {
+    semaphore: Arc<Semaphore>,
+    state: Mutex<LimitState>,
+}
+
+struct LimitState {
+    max: usize,
+    /// Number of permits in use that are withdrawn when they are released.
+    surplus: usize,
+}
+
+impl Limit {
+    fn new(max: usize) -> Arc<Self> {
+        Arc::new(Self {
+            semaphore: Arc::new(Semaphore::new(max)),
+            state: Mutex::new(LimitState { max, surplus: 0 }),
+        })
+    }
+
+    fn max(&self) -> usize {
+        self.state.lock().unwrap().max
+    }
+
+    fn set_max(&self, max: usize) {
+        let mut state = self.state.lock().unwrap();
+        if max >= state.max {
+            let grow = max - state.max;
+            let restored = grow.min(state.surplus);
+            state.surplus -= restored;
+            self.semaphore.add_permits(grow - restored);
+        } else {
+            let mut shrink = state.max - max;
+            while shrink > 0 {
+                match self.semaphore.try_acquire() {
+                    Ok(permit) => permit.forget(),
+                    Err(_) => break,
+                }
+                shrink -= 1;
+            }
+            state.surplus += shrink;
+        }
+        state.max = max;
+    }
+
+    /// Number of permits in use.
+    fn in_use(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        (state.max + state.surplus).saturating_sub(self.semaphore.available_permits())
+    }
+
+    async fn acquire(self: &Arc<Self>) -> LimitPermit {
+        let permit = self.semaphore.clone().acquire_owned().await.expect("semaphore closed");
+        LimitPermit { limit: self.clone(), permit: Some(permit) }
+    }
+}
+
+/// Permit of a [Limit], which is withdrawn on release if the limit has been lowered.
+struct LimitPermit {
+    limit: Arc<Limit>,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for LimitPermit {
+    fn drop(&mut self) {
+        let mut state = self.limit.state.lock().unwrap();
+        if state.surplus > 0 {
+            state.surplus -= 1;
+            if let Some(permit) = self.permit.take() {
+                permit.forget();
+            }
+        }
+    }
+}
+
+/// Limits and held devices of the connection pools of an adapter.
+///
+/// This is kept in the session, so that all pools of an adapter share it.
+pub(crate) struct PoolState {
+    connects: Arc<Limit>,
+    connections: Arc<Limit>,
+    held: Mutex<HashSet<Address>>,
+}
+
+impl PoolState {
+    /// Gets the state of the adapter, creating it with the default limits if necessary.
+    fn of(adapter: &Adapter) -> Arc<Self> {
+        let mut pools = adapter.inner.connection_pools.lock().unwrap();
+        pools.retain(|_, state| state.strong_count() > 0);
+        if let Some(state) = pools.get(adapter.name()).and_then(Weak::upgrade) {
+            return state;
+        }
+        let state = Arc::new(Self {
+            connects: Limit::new(DEFAULT_MAX_CONCURRENT_CONNECTS),
+            connections: Limit::new(DEFAULT_MAX_CONNECTIONS),
+            held: Mutex::new(HashSet::new()),
+        });
+        pools.insert(adapter.name().to_string(), Arc::downgrade(&state));
+        state
+    }
+}
+
+/// Marks a device as held by the pool until dropped.
+struct HeldDevice {
+    state: Arc<PoolState>,
+    address: Address,
+}
+
+impl Drop for HeldDevice {
+    fn drop(&mut self) {
+        self.state.held.lock().unwrap().remove(&self.address);
+    }
+}
+
+const DEFAULT_MAX_CONCURRENT_CONNECTS: usize = 1;
+const DEFAULT_MAX_CONNECTIONS: usize = 7;
+
+/// Manages concurrent connections to many remote devices of an adapter.
+///
+/// Use [connect](Self::connect) to obtain a [PooledConnection] to a device.
+/// At most [max_concurrent_connects](Self::max_concurrent_connects) connection attempts
+/// are made at the same time and at most [max_connections](Self::max_connections) connections
+/// are held by the pool.
+/// Further requests wait in a queue and are served in order.
+///
+/// All pools of the same adapter within a session, including clones, share their limits
+/// and held devices.
+/// Thus changing a limit of one pool changes it for all pools of the adapter.
+/// Lowering a limit takes effect once enough connections or connection attempts have been released.
+#[derive(Clone)]
+pub struct ConnectionPool {
+    adapter: Adapter,
+    connect_timeout: Option<Duration>,
+    state: Arc<PoolState>,
+}
+
+impl fmt::Debug for ConnectionPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ConnectionPool")
+            .field("adapter", &self.adapter.name())
+            .field("max_concurrent_connects", &self.state.connects.max())
+            .field("max_connections", &self.state.connections.max())
+            .field("connections", &self.connections())
+            .finish()
+    }
+}
+
+impl ConnectionPool {
+    /// Creates a connection pool for the specified adapter.
+    ///
+    /// If no other pool of the adapter exists, one connection attempt is made at a time,
+    /// up to seven connections are held and connection attempts are limited by the timeout
+    /// of the Bluetooth daemon.
+    /// Otherwise the limits of the existing pools apply.
+    pub fn new(adapter: Adapter) -> Self {
+        let state = PoolState::of(&adapter);
+        Self { adapter, connect_timeout: None, state }
+    }
+
+    /// Sets the maximum number of connection attempts performed at the same time
+    /// on the adapter.
+    pub fn max_concurrent_connects(self, max_concurrent_connects: usize) -> Self {
+        self.state.connects.set_max(max_concurrent_connects.max(1));
+        self
+    }
+
+    /// Sets the maximum number of connections held by the pools of the adapter.
+    ///
+    /// This should not exceed the number of connections supported by the controller.
+    pub fn max_connections(self, max_connections: usize) -> Self {
+        self.state.connections.set_max(max_connections.max(1));
+        self
+    }
+
+    /// Sets the time after which a connection attempt is aborted.
+    ///
+    /// The time spent waiting in the queue is not included.
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// The adapter of the pool.
+    pub fn adapter(&self) -> &Adapter {
+        &self.adapter
+    }
+
+    /// Number of connections currently held by the pools of the adapter.
+    pub fn connections(&self) -> usize {
+        self.state.connections.in_use()
+    }
+
+    /// Whether the device with the specified address is currently held by a pool of the adapter.
+    pub fn is_held(&self, address: Address) -> bool {
+        self.state.held.lock().unwrap().contains(&address)
+    }
+
+    /// Connects to the device with the specified address.
+    ///
+    /// This waits until a connection slot is available and then until no more than
+    /// the permitted number of other connection attempts are in progress.
+    /// If the device is already connected, no connection attempt is made,
+    /// but a connection slot is still occupied.
+    ///
+    /// The connection is held until the returned [PooledConnection] is dropped.
+    /// Fails with [ErrorKind::AlreadyExists] if the device is already held by a pool
+    /// and with [ErrorKind::Timeout] if the connection attempt times out.
+    pub async fn connect(&self, address: Address) -> Result<PooledConnection> {
+        let device = self.adapter.device(address)?;
+        if !self.state.held.lock().unwrap().insert(address) {
+            return Err(Error::new(ErrorKind::AlreadyExists));
+        }
+        let held = HeldDevice { state: self.state.clone(), address };
+
+        let connection = self.state.connections.acquire().await;
+        let connect = !device.is_connected().await?;
+        if connect {
+            let _connect = self.state.connects.acquire().await;
+            log::trace!("{}: connection pool connecting to {}", self.adapter.name(), address);
+            match self.connect_timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, device.connect()).await {
+                    Ok(res) => res?,
+                    Err(_) => {
+                        let _ = device.disconnect().await;
+                        return Err(Error::new(ErrorKind::Timeout));
+                    }
+                },
+                None => device.connect().await?,
+            }
+        }
+
+        Ok(PooledConnection {
+            adapter: self.adapter.clone(),
+            device,
+            connected: connect,
+            release: Some((held, connection)),
+        })
+    }
+}
+
+/// Connection to a remote device held by a [ConnectionPool].
+///
+/// Drop to disconnect the device and release its connection slot.
+/// Use [disconnect](Self::disconnect) to wait for the disconnection.
+/// A device that was already connected when it was obtained from the pool is left connected.
+pub struct PooledConnection {
+    adapter: Adapter,
+    device: Device,
+    /// The device has been connected by the pool.
+    connected: bool,
+    release: Option<(HeldDevice, LimitPermit)>,
+}
+
+impl fmt::Debug for PooledConnection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PooledConnection").field("device", &self.device).finish()
+    }
+}
+
+impl PooledConnection {
+    /// The connected device.
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Disconnects the device and releases its connection slot.
+    ///
+    /// The device is only disconnected if it has been connected by the pool.
+    pub async fn disconnect(mut self) -> Result<()> {
+        let _release = self.release.take();
+        if !self.connected {
+            return Ok(());
+        }
+        self.device.disconnect().await
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        let Some(release) = self.release.take() else { return };
+        if !self.connected {
+            return;
+        }
+        let device = self.device.clone();
+        self.adapter.spawn(async move {
+            let _ = device.disconnect().await;
+            drop(release);
+        });
+    }
+}
+
+/// Outcome of synchronizing with a device by a [RoundRobinScheduler].
+#[derive(Debug)]
+pub struct SyncReport {
+    /// Address of the device.
+    pub address: Address,
+    /// Result of connecting to the device and of the synchronization function.
+    pub result: Result<()>,
+}
+
+/// Cycles through a set of devices larger than the number of simultaneous connections
+/// supported by the controller.
+///
+/// Each device is connected to using a [ConnectionPool], data is synchronized by a
+/// user-provided function and then the device is disconnected again, freeing the connection
+/// for the next device.
+/// Devices that were already connected are left connected.
+/// Devices are visited in order and each device is synchronized again once the
+/// [interval](Self::interval) has elapsed since its last synchronization.
+/// The number of devices synchronized in parallel is limited by the pool.
+#[derive(Clone, Debug)]
+pub struct RoundRobinScheduler {
+    pool: ConnectionPool,
+    devices: Vec<Address>,
+    interval: Duration,
+    sync_timeout: Option<Duration>,
+}
+
+impl RoundRobinScheduler {
+    /// Creates a scheduler cycling through the specified devices using the connection pool.
+    ///
+    /// By default each device is synchronized once per minute and
+    /// the synchronization function is not subject to a timeout.
+    pub fn new(pool: ConnectionPool, devices: impl IntoIterator<Item = Address>) -> Self {
+        let mut seen = HashSet::new();
+        let devices = devices.into_iter().filter(|address| seen.insert(*address)).collect();
+        Self { pool, devices, interval: Duration::from_secs(60), sync_timeout: None }
+    }
+
+    /// Sets the time between the starts of two synchronizations of the same device.
+    ///
+    /// If a round through all devices takes longer, devices are synchronized as often as possible.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the time after which the synchronization function is aborted and
+    /// the device is disconnected.
+    pub fn sync_timeout(mut self, sync_timeout: Duration) -> Self {
+        self.sync_timeout = Some(sync_timeout);
+        self
+    }
+
+    /// Starts cycling through the devices and returns a stream of synchronization reports.
+    ///
+    /// The function `sync` is called with each connected device and the device
+    /// is disconnected once it returns.
+    /// Failures to connect and errors returned by `sync` are reported and the device is
+    /// tried again in the next round.
+    ///
+    /// Scheduling stops when the returned stream is dropped.
+    pub fn run<F, Fut>(self, sync: F) -> impl Stream<Item = SyncReport>
+    where
+        F: Fn(Device) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(self.devices.len().max(1));
+        let adapter = self.pool.adapter().clone();
+        adapter.spawn(self.perform(Arc::new(sync), tx));
+        ReceiverStream::new(rx)
+    }
+
+    async fn perform<F, Fut>(self, sync: Arc<F>, tx: mpsc::Sender<SyncReport>)
+    where
+        F: Fn(Device) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        // Devices ordered by the time they are due, ties are broken by the order they became due in.
+        let now = Instant::now();
+        let mut due: BinaryHeap<Reverse<(Instant, usize, Address)>> =
+            self.devices.iter().enumerate().map(|(seq, address)| Reverse((now, seq, *address))).collect();
+        let mut seq = self.devices.len();
+        let mut in_flight = FuturesUnordered::new();
+        let parallel = self.pool.state.connections.max();
+
+        loop {
+            while in_flight.len() < parallel {
+                match due.peek() {
+                    Some(Reverse((at, _, _))) if *at <= Instant::now() => {
+                        let Reverse((_, _, address)) = due.pop().unwrap();
+                        let pool = self.pool.clone();
+                        let sync = sync.clone();
+                        let sync_timeout = self.sync_timeout;
+                        in_flight.push(async move {
+                            let started = Instant::now();
+                            let result = Self::sync_device(&pool, address, &*sync, sync_timeout).await;
+                            (SyncReport { address, result }, started)
+                        });
+                    }
+                    _ => break,
+                }
+            }
+
+            let next_due = due.peek().filter(|_| in_flight.len() < parallel).map(|Reverse((at, _, _))| *at);
+            tokio::select! {
+                Some((report, started)) = in_flight.next(), if !in_flight.is_empty() => {
+                    if let Err(err) = &report.result {
+                        log::debug!("Synchronization with device {} failed: {}", report.address, err);
+                    }
+                    due.push(Reverse((started + self.interval, seq, report.address)));
+                    seq += 1;
+                    if tx.send(report).await.is_err() {
+                        break;
+                    }
+                }
+                () = sleep_until(next_due.unwrap_or_else(Instant::now)), if next_due.is_some() => (),
+                () = tx.closed() => break,
+            }
+        }
+    }
+
+    /// Connects to the device, calls the synchronization function and disconnects.
+    async fn sync_device<F, Fut>(
+        pool: &ConnectionPool, address: Address, sync: &F, sync_timeout: Option<Duration>,
+    ) -> Result<()>
+    where
+        F: Fn(Device) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let connection = pool.connect(address).await?;
+        let res = match sync_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, sync(connection.device().clone()))
+                .await
+                .unwrap_or_else(|_| Err(Error::new(ErrorKind::Timeout))),
+            None => sync(connection.device().clone()).await,
+        };
+        // The device may already have disconnected on its own.
+        let _ = connection.disconnect().await;
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn limit_is_changed_while_in_use() {
+        let limit = Limit::new(2);
+        let first = block_on(limit.acquire());
+        let second = block_on(limit.acquire());
+        assert_eq!(limit.in_use(), 2);
+
+        // The surplus permit is withdrawn when it is released.
+        limit.set_max(1);
+        assert_eq!(limit.in_use(), 2);
+        drop(first);
+        assert_eq!(limit.in_use(), 1);
+        assert_eq!(limit.semaphore.available_permits(), 0);
+
+        limit.set_max(3);
+        assert_eq!(limit.semaphore.available_permits(), 2);
+        drop(second);
+        assert_eq!(limit.in_use(), 0);
+        assert_eq!(limit.semaphore.available_permits(), 3);
+    }
+
+    #[test]
+    fn limit_restores_surplus_permits() {
+        let limit = Limit::new(3);
+        let permits: Vec<_> = (0..3).map(|_| block_on(limit.acquire())).collect();
+        limit.set_max(1);
+        limit.set_max(2);
+        drop(permits);
+        assert_eq!(limit.in_use(), 0);
+        assert_eq!(limit.semaphore.available_permits(), 2);
+
+        limit.set_max(1);
+        assert_eq!(limit.semaphore.available_permits(), 1);
+    }
+}
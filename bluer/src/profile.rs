@@ -0,0 +1,133 @@
+use std::fmt;
+use uuid::Uuid;
+
+use crate::UuidExt;
+
+macro_rules! profiles {
+    ($($(#[$attr:meta])* $name:ident => $short:expr,)*) => {
+        /// Well-known Bluetooth profile or service identified by its UUID.
+        ///
+        /// Can be converted to and from UUIDs.
+        /// UUIDs of profiles that are not known are represented by [Profile::Custom].
+        #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        #[non_exhaustive]
+        pub enum Profile {
+            $($(#[$attr])* $name,)*
+            /// Profile or service not known to this library.
+            Custom(Uuid),
+        }
+
+        impl From<Uuid> for Profile {
+            fn from(uuid: Uuid) -> Self {
+                match uuid.as_u16() {
+                    $(Some($short) => Self::$name,)*
+                    _ => Self::Custom(uuid),
+                }
+            }
+        }
+
+        impl From<Profile> for Uuid {
+            fn from(profile: Profile) -> Self {
+                match profile {
+                    $(Profile::$name => Uuid::from_u16($short),)*
+                    Profile::Custom(uuid) => uuid,
+                }
+            }
+        }
+
+        impl fmt::Display for Profile {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match self {
+                    $(Self::$name => write!(f, stringify!($name)),)*
+                    Self::Custom(uuid) => write!(f, "{uuid}"),
+                }
+            }
+        }
+    };
+}
+
+profiles! {
+    /// Serial Port Profile (SPP).
+    SerialPort => 0x1101,
+    /// OBEX Object Push Profile (OPP).
+    ObexObjectPush => 0x1105,
+    /// OBEX File Transfer Profile (FTP).
+    ObexFileTransfer => 0x1106,
+    /// Headset Profile (HSP), headset role.
+    Headset => 0x1108,
+    /// Advanced Audio Distribution Profile (A2DP), source role.
+    A2dpSource => 0x110a,
+    /// Advanced Audio Distribution Profile (A2DP), sink role.
+    A2dpSink => 0x110b,
+    /// Audio/Video Remote Control Profile (AVRCP), target role.
+    AvrcpTarget => 0x110c,
+    /// Audio/Video Remote Control Profile (AVRCP).
+    Avrcp => 0x110e,
+    /// Audio/Video Remote Control Profile (AVRCP), controller role.
+    AvrcpController => 0x110f,
+    /// Headset Profile (HSP), audio gateway role.
+    HeadsetAudioGateway => 0x1112,
+    /// Personal Area Networking Profile (PAN), user role.
+    PanUser => 0x1115,
+    /// Personal Area Networking Profile (PAN), network access point role.
+    PanNetworkAccessPoint => 0x1116,
+    /// Personal Area Networking Profile (PAN), group ad-hoc network role.
+    PanGroupNetwork => 0x1117,
+    /// Hands-Free Profile (HFP), hands-free unit role.
+    Hfp => 0x111e,
+    /// Hands-Free Profile (HFP), audio gateway role.
+    HfpAudioGateway => 0x111f,
+    /// Human Interface Device Profile (HID).
+    Hid => 0x1124,
+    /// SIM Access Profile (SAP).
+    SimAccess => 0x112d,
+    /// Phone Book Access Profile (PBAP), client role.
+    PhonebookClient => 0x112e,
+    /// Phone Book Access Profile (PBAP), server role.
+    PhonebookServer => 0x112f,
+    /// Message Access Profile (MAP), server role.
+    MessageAccessServer => 0x1132,
+    /// Message Access Profile (MAP), notification server role.
+    MessageNotificationServer => 0x1133,
+    /// Device Identification Profile (DID).
+    DeviceId => 0x1200,
+    /// Generic Access service (GAP).
+    GenericAccess => 0x1800,
+    /// Generic Attribute service (GATT).
+    GenericAttribute => 0x1801,
+    /// Immediate Alert service.
+    ImmediateAlert => 0x1802,
+    /// Link Loss service.
+    LinkLoss => 0x1803,
+    /// Tx Power service.
+    TxPower => 0x1804,
+    /// Current Time service.
+    CurrentTime => 0x1805,
+    /// Device Information service.
+    DeviceInformation => 0x180a,
+    /// Heart Rate service.
+    HeartRate => 0x180d,
+    /// Battery service.
+    Battery => 0x180f,
+    /// HID over GATT service.
+    HidOverGatt => 0x1812,
+    /// Environmental Sensing service.
+    EnvironmentalSensing => 0x181a,
+    /// Mesh Provisioning service.
+    MeshProvisioning => 0x1827,
+    /// Mesh Proxy service.
+    MeshProxy => 0x1828,
+}
+
+impl Profile {
+    /// UUID of the profile.
+    pub fn uuid(&self) -> Uuid {
+        (*self).into()
+    }
+
+    /// Whether the profile is known to this library.
+    pub fn is_known(&self) -> bool {
+        !matches!(self, Self::Custom(_))
+    }
+}
@@ -0,0 +1,483 @@
+//! Persisted registry of known devices.
+//!
+//! The [DeviceRegistry] keeps metadata about seen and paired devices,
+//! such as their names, services and user-defined labels, in a [DeviceStore].
+//! This allows applications to keep track of device identities independently
+//! of the Bluetooth daemon, for example when the Bluetooth adapter is replaced.
+//!
+//! The registry is kept synchronized with the Bluetooth daemon using
+//! [DeviceRegistry::sync] and [DeviceRegistry::track].
+
+use futures::{channel::oneshot, future, stream::SelectAll, Stream, StreamExt};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, fs,
+    io::{ErrorKind as IoErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+use tokio::time::{sleep_until, Instant};
+use uuid::Uuid;
+
+use crate::{
+    Adapter, AdapterEvent, Address, Device, DeviceEvent, DeviceProperty, Error, ErrorKind, InternalErrorKind,
+    Result,
+};
+
+/// Time changes observed by [DeviceRegistry::track] are collected before the registry is saved.
+const TRACK_SAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// Metadata about a known device.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[non_exhaustive]
+pub struct KnownDevice {
+    /// Device address.
+    pub address: Address,
+    /// Name of the adapter the device was last seen on.
+    pub adapter_name: String,
+    /// Remote name of the device.
+    pub name: Option<String>,
+    /// Alias of the device.
+    pub alias: Option<String>,
+    /// Service UUIDs of the device.
+    #[serde(default)]
+    pub uuids: HashSet<Uuid>,
+    /// Whether the device was paired when it was last seen.
+    #[serde(default)]
+    pub paired: bool,
+    /// User-defined labels.
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// Time the device was first recorded.
+    pub first_seen: SystemTime,
+    /// Time the device was last synchronized with the Bluetooth daemon.
+    pub last_seen: SystemTime,
+}
+
+impl KnownDevice {
+    fn new(address: Address, adapter_name: &str) -> Self {
+        let now = SystemTime::now();
+        Self {
+            address,
+            adapter_name: adapter_name.to_string(),
+            name: None,
+            alias: None,
+            uuids: HashSet::new(),
+            paired: false,
+            labels: BTreeMap::new(),
+            first_seen: now,
+            last_seen: now,
+        }
+    }
+}
+
+/// Storage backend of a [DeviceRegistry].
+///
+/// Implement this trait to persist the registry in a custom storage,
+/// for example an SQLite database.
+pub trait DeviceStore: Send + Sync {
+    /// Loads all known devices.
+    fn load(&self) -> Result<Vec<KnownDevice>>;
+
+    /// Replaces all stored devices by the specified devices.
+    fn save(&self, devices: &[KnownDevice]) -> Result<()>;
+}
+
+/// Stores known devices in memory only.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    devices: Mutex<Vec<KnownDevice>>,
+}
+
+impl DeviceStore for MemoryStore {
+    fn load(&self) -> Result<Vec<KnownDevice>> {
+        Ok(self.devices.lock().unwrap().clone())
+    }
+
+    fn save(&self, devices: &[KnownDevice]) -> Result<()> {
+        *self.devices.lock().unwrap() = devices.to_vec();
+        Ok(())
+    }
+}
+
+/// Stores known devices in a JSON file.
+///
+/// The file is replaced atomically when saving and its contents are flushed
+/// to disk before it replaces the previous file.
+#[derive(Debug, Clone)]
+pub struct JsonFileStore {
+    path: PathBuf,
+}
+
+impl JsonFileStore {
+    /// Uses the JSON file at the specified path.
+    ///
+    /// The file is created when the registry is saved for the first time.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self { path: path.as_ref().to_path_buf() }
+    }
+
+    /// Path of the JSON file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl DeviceStore for JsonFileStore {
+    fn load(&self) -> Result<Vec<KnownDevice>> {
+        match fs::read(&self.path) {
+            Ok(data) => Ok(serde_json::from_slice(&data)
+                .map_err(|_| Error::new(ErrorKind::Internal(InternalErrorKind::InvalidValue)))?),
+            Err(err) if err.kind() == IoErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn save(&self, devices: &[KnownDevice]) -> Result<()> {
+        let data = serde_json::to_vec_pretty(devices)
+            .map_err(|_| Error::new(ErrorKind::Internal(InternalErrorKind::InvalidValue)))?;
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&tmp_path, &self.path)?;
+        // Persist the rename itself.
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+/// Registry of known devices persisted in a [DeviceStore].
+///
+/// Devices are identified by their address only, so that their metadata
+/// is retained when they are seen on a different adapter.
+///
+/// Cloning the registry is cheap and all clones share the same state.
+///
+/// The synchronous methods modifying the registry save it to the store before
+/// they return, blocking the calling thread.
+/// The asynchronous methods save it on the blocking thread pool of Tokio.
+#[derive(Clone)]
+pub struct DeviceRegistry {
+    store: Arc<dyn DeviceStore>,
+    devices: Arc<Mutex<HashMap<Address, KnownDevice>>>,
+    save_lock: Arc<Mutex<()>>,
+    record_unpaired: bool,
+}
+
+impl fmt::Debug for DeviceRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DeviceRegistry {{ devices: {} }}", self.devices.lock().unwrap().len())
+    }
+}
+
+impl DeviceRegistry {
+    /// Opens the registry, loading the known devices from the specified store.
+    pub fn open(store: impl DeviceStore + 'static) -> Result<Self> {
+        let devices = store.load()?.into_iter().map(|dev| (dev.address, dev)).collect();
+        Ok(Self {
+            store: Arc::new(store),
+            devices: Arc::new(Mutex::new(devices)),
+            save_lock: Arc::new(Mutex::new(())),
+            record_unpaired: false,
+        })
+    }
+
+    /// Sets whether unpaired devices are recorded when synchronizing with the Bluetooth daemon.
+    ///
+    /// By default only paired devices and devices already contained in the registry are recorded.
+    pub fn record_unpaired(mut self, record_unpaired: bool) -> Self {
+        self.record_unpaired = record_unpaired;
+        self
+    }
+
+    /// All known devices.
+    pub fn devices(&self) -> Vec<KnownDevice> {
+        let mut devices: Vec<_> = self.devices.lock().unwrap().values().cloned().collect();
+        devices.sort_by_key(|dev| dev.address);
+        devices
+    }
+
+    /// Metadata of the known device with the specified address.
+    pub fn get(&self, address: Address) -> Option<KnownDevice> {
+        self.devices.lock().unwrap().get(&address).cloned()
+    }
+
+    /// Sets a user-defined label of a known device.
+    pub fn set_label(&self, address: Address, key: impl Into<String>, value: impl Into<String>) -> Result<()> {
+        self.modify(|devices| {
+            let dev = devices.get_mut(&address).ok_or_else(|| Error::new(ErrorKind::NotFound))?;
+            dev.labels.insert(key.into(), value.into());
+            Ok(())
+        })
+    }
+
+    /// Removes a user-defined label of a known device.
+    pub fn remove_label(&self, address: Address, key: &str) -> Result<()> {
+        self.modify(|devices| {
+            let dev = devices.get_mut(&address).ok_or_else(|| Error::new(ErrorKind::NotFound))?;
+            dev.labels.remove(key);
+            Ok(())
+        })
+    }
+
+    /// Removes a device from the registry.
+    ///
+    /// This does not affect the Bluetooth daemon.
+    pub fn remove(&self, address: Address) -> Result<()> {
+        self.modify(|devices| {
+            devices.remove(&address);
+            Ok(())
+        })
+    }
+
+    /// Records the current metadata of the specified device from the Bluetooth daemon.
+    ///
+    /// The device is recorded regardless of whether it is paired.
+    pub async fn record(&self, device: &Device) -> Result<()> {
+        self.update(device, true).await?;
+        self.save().await
+    }
+
+    /// Synchronizes the registry with all devices of the specified adapter.
+    pub async fn sync(&self, adapter: &Adapter) -> Result<()> {
+        let mut updated = false;
+        for address in adapter.device_addresses().await? {
+            updated |= self.update(&adapter.device(address)?, false).await?;
+        }
+        match updated {
+            true => self.save().await,
+            false => Ok(()),
+        }
+    }
+
+    /// Synchronizes the registry with the specified adapter and keeps it
+    /// synchronized while the returned handle is held.
+    ///
+    /// The registry is updated when a device is added or its name, alias, services or
+    /// pairing state change; frequently changing properties such as the signal strength
+    /// are ignored.
+    /// Changes are collected for a short time before the registry is saved.
+    ///
+    /// Drop the returned [DeviceRegistryTracker] to stop tracking.
+    pub async fn track(&self, adapter: &Adapter) -> Result<DeviceRegistryTracker> {
+        let mut events = adapter.events().await?;
+        self.sync(adapter).await?;
+
+        // Adapter events do not include devices that already exist, thus watch them explicitly.
+        let mut tracked = HashSet::new();
+        let mut changes = SelectAll::new();
+        for address in adapter.device_addresses().await? {
+            if tracked.insert(address) {
+                if let Ok(dev_changes) = Self::relevant_changes(&adapter.device(address)?).await {
+                    changes.push(dev_changes);
+                }
+            }
+        }
+
+        let (drop_tx, drop_rx) = oneshot::channel();
+        let registry = self.clone();
+        let adapter = adapter.clone();
+        adapter.clone().spawn(async move {
+            let mut drop_rx = drop_rx;
+            let mut save_at = None;
+            loop {
+                let address = tokio::select! {
+                    evt = events.next() => match evt {
+                        Some(AdapterEvent::DeviceAdded(address)) => {
+                            let Ok(device) = adapter.device(address) else { continue };
+                            if tracked.insert(address) {
+                                if let Ok(dev_changes) = Self::relevant_changes(&device).await {
+                                    changes.push(dev_changes);
+                                }
+                            }
+                            address
+                        }
+                        Some(AdapterEvent::DeviceRemoved(address)) => {
+                            // The event stream of the removed device has ended.
+                            tracked.remove(&address);
+                            continue;
+                        }
+                        Some(_) => continue,
+                        None => break,
+                    },
+                    Some(address) = changes.next(), if !changes.is_empty() => address,
+                    () = sleep_until(save_at.unwrap_or_else(Instant::now)), if save_at.is_some() => {
+                        save_at = None;
+                        if let Err(err) = registry.save().await {
+                            log::warn!("Cannot save device registry: {}", &err);
+                        }
+                        continue;
+                    },
+                    _ = &mut drop_rx => break,
+                };
+
+                let Ok(device) = adapter.device(address) else { continue };
+                match registry.update(&device, false).await {
+                    Ok(true) => {
+                        save_at.get_or_insert_with(|| Instant::now() + TRACK_SAVE_DELAY);
+                    }
+                    Ok(false) => (),
+                    Err(err) => log::warn!("Cannot record device {} in registry: {}", address, &err),
+                }
+            }
+
+            if save_at.is_some() {
+                if let Err(err) = registry.save().await {
+                    log::warn!("Cannot save device registry: {}", &err);
+                }
+            }
+        });
+
+        Ok(DeviceRegistryTracker { _drop_tx: drop_tx })
+    }
+
+    /// Streams the address of the device whenever a property recorded in the registry changes.
+    async fn relevant_changes(device: &Device) -> Result<impl Stream<Item = Address> + Send + Unpin> {
+        let address = device.address();
+        let events = device.events().await?;
+        Ok(events.filter_map(move |DeviceEvent::PropertyChanged(prop)| {
+            let relevant = matches!(
+                prop,
+                DeviceProperty::Name(_)
+                    | DeviceProperty::Alias(_)
+                    | DeviceProperty::Uuids(_)
+                    | DeviceProperty::Paired(_)
+            );
+            future::ready(relevant.then_some(address))
+        }))
+    }
+
+    /// Updates the metadata of the device from the Bluetooth daemon without saving the registry.
+    ///
+    /// Returns whether the device has been recorded.
+    async fn update(&self, device: &Device, force: bool) -> Result<bool> {
+        let address = device.address();
+        let paired = device.is_paired().await?;
+        if !force && !paired && !self.record_unpaired && !self.devices.lock().unwrap().contains_key(&address) {
+            return Ok(false);
+        }
+
+        let name = device.name().await?;
+        let alias = device.alias().await.ok();
+        let uuids = device.uuids().await?.unwrap_or_default();
+
+        let mut devices = self.devices.lock().unwrap();
+        let dev = devices.entry(address).or_insert_with(|| KnownDevice::new(address, device.adapter_name()));
+        dev.adapter_name = device.adapter_name().to_string();
+        if name.is_some() {
+            dev.name = name;
+        }
+        if alias.is_some() {
+            dev.alias = alias;
+        }
+        dev.uuids.extend(uuids);
+        dev.paired = paired;
+        dev.last_seen = SystemTime::now();
+        Ok(true)
+    }
+
+    /// Modifies the known devices and saves them to the store.
+    fn modify(&self, f: impl FnOnce(&mut HashMap<Address, KnownDevice>) -> Result<()>) -> Result<()> {
+        f(&mut self.devices.lock().unwrap())?;
+        self.save_blocking()
+    }
+
+    /// Saves the known devices to the store on the blocking thread pool.
+    async fn save(&self) -> Result<()> {
+        let registry = self.clone();
+        tokio::task::spawn_blocking(move || registry.save_blocking()).await?
+    }
+
+    /// Saves the known devices to the store.
+    ///
+    /// Saves are serialized and each saves the most recent state,
+    /// so that an older state never overwrites a newer one.
+    fn save_blocking(&self) -> Result<()> {
+        let _save_lock = self.save_lock.lock().unwrap();
+        let mut all: Vec<_> = self.devices.lock().unwrap().values().cloned().collect();
+        all.sort_by_key(|dev| dev.address);
+        self.store.save(&all)
+    }
+}
+
+/// Keeps a [DeviceRegistry] synchronized with an adapter.
+///
+/// Drop to stop tracking.
+#[must_use = "DeviceRegistryTracker must be held for the registry to be kept synchronized"]
+pub struct DeviceRegistryTracker {
+    _drop_tx: oneshot::Sender<()>,
+}
+
+impl fmt::Debug for DeviceRegistryTracker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DeviceRegistryTracker")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known_device(address: Address) -> KnownDevice {
+        let mut dev = KnownDevice::new(address, "hci0");
+        dev.name = Some("Sensor".to_string());
+        dev.uuids.insert(Uuid::from_u128(0x0000180f_0000_1000_8000_00805f9b34fb));
+        dev.paired = true;
+        dev.labels.insert("room".to_string(), "kitchen".to_string());
+        dev
+    }
+
+    #[test]
+    fn memory_store_round_trips() {
+        let store = MemoryStore::default();
+        assert!(store.load().unwrap().is_empty());
+        let devices = vec![known_device(Address::new([1, 2, 3, 4, 5, 6]))];
+        store.save(&devices).unwrap();
+        assert_eq!(store.load().unwrap(), devices);
+    }
+
+    #[test]
+    fn json_file_store_round_trips() {
+        let dir = std::env::temp_dir().join(format!("bluer-registry-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let store = JsonFileStore::new(dir.join("devices.json"));
+
+        // A missing file contains no devices.
+        assert!(store.load().unwrap().is_empty());
+
+        let devices =
+            vec![known_device(Address::new([1, 2, 3, 4, 5, 6])), known_device(Address::new([6, 5, 4, 3, 2, 1]))];
+        store.save(&devices).unwrap();
+        assert_eq!(store.load().unwrap(), devices);
+        assert!(!dir.join("devices.json.tmp").exists());
+
+        store.save(&devices[1..]).unwrap();
+        assert_eq!(store.load().unwrap(), devices[1..]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn labels_of_unknown_devices_are_rejected() {
+        let address = Address::new([1, 2, 3, 4, 5, 6]);
+        let store = MemoryStore::default();
+        store.save(&[known_device(address)]).unwrap();
+        let registry = DeviceRegistry::open(store).unwrap();
+
+        let unknown = Address::new([6, 5, 4, 3, 2, 1]);
+        assert_eq!(registry.set_label(unknown, "room", "hall").unwrap_err().kind, ErrorKind::NotFound);
+        assert_eq!(registry.remove_label(unknown, "room").unwrap_err().kind, ErrorKind::NotFound);
+
+        registry.set_label(address, "floor", "1").unwrap();
+        registry.remove_label(address, "room").unwrap();
+        let labels = registry.get(address).unwrap().labels;
+        assert_eq!(labels, BTreeMap::from([("floor".to_string(), "1".to_string())]));
+        assert_eq!(registry.store.load().unwrap()[0].labels, labels);
+    }
+}
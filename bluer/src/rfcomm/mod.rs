@@ -32,10 +32,10 @@ use std::{
 };
 use tokio::io::{unix::AsyncFd, AsyncRead, AsyncWrite, ReadBuf};
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 pub(crate) mod profile;
 
-#[cfg(feature = "bluetoothd")]
+#[cfg(feature = "bluetoothd-core")]
 pub use profile::{ConnectRequest, Profile, ProfileHandle, ReqError, ReqResult, Role};
 
 use crate::{
@@ -119,7 +119,7 @@ impl sock::SysSockAddr for SocketAddr {
     }
 
     fn try_from_sys_sock_addr(saddr: Self::SysSockAddr) -> Result<Self> {
-        if saddr.rc_family != AF_BLUETOOTH as _ {
+        if saddr.rc_family != AF_BLUETOOTH as libc::sa_family_t {
             return Err(Error::new(ErrorKind::InvalidInput, "sockaddr_rc::rc_family is not AF_BLUETOOTH"));
         }
         Ok(Self { addr: Address::from(saddr.rc_bdaddr), channel: saddr.rc_channel })
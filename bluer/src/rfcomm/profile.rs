@@ -21,7 +21,10 @@ use tokio_stream::wrappers::ReceiverStream;
 use uuid::Uuid;
 
 use super::{Socket, Stream};
-use crate::{method_call, read_dict, Address, Device, Result, SessionInner, ERR_PREFIX, SERVICE_NAME, TIMEOUT};
+use crate::{
+    method_call, read_dict, Address, Device, Error, ErrorKind, Result, SessionInner, ERR_PREFIX, SERVICE_NAME,
+    TIMEOUT,
+};
 
 pub(crate) const MANAGER_INTERFACE: &str = "org.bluez.ProfileManager1";
 pub(crate) const MANAGER_PATH: &str = "/org/bluez";
@@ -29,7 +32,7 @@ pub(crate) const PROFILE_INTERFACE: &str = "org.bluez.Profile1";
 pub(crate) const PROFILE_PREFIX: &str = publish_path!("profile/");
 
 /// Error response from us to a Bluetooth profile request.
-#[cfg_attr(docsrs, doc(cfg(all(feature = "rfcomm", feature = "bluetoothd"))))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "rfcomm", feature = "bluetoothd-core"))))]
 #[derive(Clone, Copy, Debug, displaydoc::Display, Eq, PartialEq, Ord, PartialOrd, Hash, IntoStaticStr)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
@@ -56,11 +59,11 @@ impl From<ReqError> for dbus::MethodErr {
 }
 
 /// Result of a Bluetooth profile request to us.
-#[cfg_attr(docsrs, doc(cfg(all(feature = "rfcomm", feature = "bluetoothd"))))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "rfcomm", feature = "bluetoothd-core"))))]
 pub type ReqResult<T> = std::result::Result<T, ReqError>;
 
 /// Local profile role.
-#[cfg_attr(docsrs, doc(cfg(all(feature = "rfcomm", feature = "bluetoothd"))))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "rfcomm", feature = "bluetoothd-core"))))]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Display, EnumString)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Role {
@@ -75,6 +78,7 @@ pub enum Role {
 /// Bluetooth RFCOMM profile definition.
 ///
 /// Use [Session::register_profile](crate::Session::register_profile) to register a profile.
+/// Use [validate](Self::validate) to check the options before registering.
 ///
 /// Some predefined services:
 ///
@@ -99,7 +103,7 @@ pub enum Role {
 ///     is required. Features is one bit value, specify
 ///     capability of Remote Audio Volume Control
 ///     (by default turned off).
-#[cfg_attr(docsrs, doc(cfg(all(feature = "rfcomm", feature = "bluetoothd"))))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "rfcomm", feature = "bluetoothd-core"))))]
 #[derive(Clone, Debug, Default, Eq, PartialEq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Profile {
@@ -139,6 +143,37 @@ pub struct Profile {
 }
 
 impl Profile {
+    /// Checks that the profile options are consistent and within their valid ranges.
+    ///
+    /// This verifies that the UUID is not nil, the name is not empty, the RFCOMM channel is
+    /// between 1 and 30, the PSM is a valid L2CAP PSM and that auto connection is only
+    /// requested for the client role.
+    /// The service record is passed to the Bluetooth daemon unchecked.
+    ///
+    /// This is not performed automatically when the profile is registered.
+    /// Fails with [ErrorKind::InvalidArguments] describing the first invalid option.
+    pub fn validate(&self) -> Result<()> {
+        let invalid =
+            |message: &str| Err(Error { kind: ErrorKind::InvalidArguments, message: message.to_string() });
+
+        if self.uuid.is_nil() {
+            return invalid("profile UUID must not be nil");
+        }
+        if self.name.as_deref().is_some_and(str::is_empty) {
+            return invalid("profile name must not be empty");
+        }
+        if self.channel.is_some_and(|channel| !(1..=30).contains(&channel)) {
+            return invalid("RFCOMM channel must be between 1 and 30");
+        }
+        if self.psm.is_some_and(|psm| psm & 0x0001 == 0 || psm & 0x0100 != 0) {
+            return invalid("PSM must be odd and have the least significant bit of its upper octet cleared");
+        }
+        if self.role == Some(Role::Server) && self.auto_connect == Some(true) {
+            return invalid("auto connection is only supported for the client role");
+        }
+        Ok(())
+    }
+
     fn to_dict(&self) -> PropMap {
         let mut pm = PropMap::new();
         if let Some(name) = &self.name {
@@ -181,7 +216,7 @@ impl Profile {
 /// A request to connect to this profile, either as client or server.
 ///
 /// The new service level connection has been made and authorized.
-#[cfg_attr(docsrs, doc(cfg(all(feature = "rfcomm", feature = "bluetoothd"))))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "rfcomm", feature = "bluetoothd-core"))))]
 pub struct ConnectRequest {
     device: Address,
     fd: OwnedFd,
@@ -340,6 +375,7 @@ impl RegisteredProfile {
             let mut cr = inner.crossroads.lock().await;
             cr.insert(name.clone(), &[inner.profile_token], Arc::new(self));
         }
+        inner.record_export(&name, &[PROFILE_INTERFACE]);
 
         log::trace!("Registering profile at {}", &name);
         let proxy = Proxy::new(SERVICE_NAME, MANAGER_PATH, TIMEOUT, inner.connection.clone());
@@ -353,7 +389,7 @@ impl RegisteredProfile {
 
         let (drop_tx, drop_rx) = oneshot::channel();
         let unreg_name = name.clone();
-        tokio::spawn(async move {
+        inner.clone().spawn(async move {
             let _ = drop_rx.await;
 
             log::trace!("Unregistering profile at {}", &unreg_name);
@@ -363,6 +399,7 @@ impl RegisteredProfile {
             log::trace!("Unpublishing profile at {}", &unreg_name);
             let mut cr = inner.crossroads.lock().await;
             let _: Option<Self> = cr.remove(&unreg_name);
+            inner.record_unexport(&unreg_name);
         });
 
         Ok(ProfileHandle { name, req_rx: ReceiverStream::new(req_rx), _drop_tx: drop_tx })
@@ -372,7 +409,7 @@ impl RegisteredProfile {
 /// Handle to registered Bluetooth RFCOMM profile receiving its connect requests.
 ///
 /// Drop to unregister profile.
-#[cfg_attr(docsrs, doc(cfg(all(feature = "rfcomm", feature = "bluetoothd"))))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "rfcomm", feature = "bluetoothd-core"))))]
 #[pin_project(PinnedDrop)]
 pub struct ProfileHandle {
     name: dbus::Path<'static>,
@@ -0,0 +1,698 @@
+//! Aggregation of discovery results.
+
+use futures::{stream::SelectAll, Stream, StreamExt};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    sync::mpsc,
+    time::{interval_at, Instant, MissedTickBehavior},
+};
+use tokio_stream::wrappers::ReceiverStream;
+use uuid::Uuid;
+
+use crate::{
+    path_loss, Adapter, AdapterEvent, Address, CompanyId, Device, DeviceEvent, DeviceProperty, DistanceEstimator,
+    Proximity, Result, Session,
+};
+
+/// Summary of the advertisements of a device observed by a [ScanAggregator].
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ScanSummary {
+    /// Device address.
+    pub address: Address,
+    /// Remote name of the device, if known.
+    pub name: Option<String>,
+    /// Strongest received signal strength in dBm.
+    pub best_rssi: i16,
+    /// Most recently received signal strength in dBm.
+    pub last_rssi: i16,
+    /// Advertised transmit power level in dBm, if known.
+    pub tx_power: Option<i16>,
+    /// Number of advertisement reports received.
+    ///
+    /// Each signal strength update reported by the Bluetooth daemon is counted as
+    /// one advertisement report.
+    pub advertisement_count: u64,
+    /// Time the device was first seen.
+    pub first_seen: SystemTime,
+    /// Time the device was last seen.
+    pub last_seen: SystemTime,
+    /// Advertised service UUIDs.
+    pub uuids: HashSet<Uuid>,
+    /// Service data merged from all received advertisements.
+    pub service_data: HashMap<Uuid, Vec<u8>>,
+    /// Manufacturer data merged from all received advertisements.
+    pub manufacturer_data: HashMap<CompanyId, Vec<u8>>,
+}
+
+impl ScanSummary {
+    fn new(address: Address, rssi: i16) -> Self {
+        let now = SystemTime::now();
+        Self {
+            address,
+            name: None,
+            best_rssi: rssi,
+            last_rssi: rssi,
+            tx_power: None,
+            advertisement_count: 0,
+            first_seen: now,
+            last_seen: now,
+            uuids: HashSet::new(),
+            service_data: HashMap::new(),
+            manufacturer_data: HashMap::new(),
+        }
+    }
+
+    /// Path loss in dB of the most recently received advertisement.
+    ///
+    /// Returns [None] if the device does not advertise its transmit power level.
+    pub fn path_loss(&self) -> Option<i16> {
+        self.tx_power.map(|tx_power| path_loss(self.last_rssi, tx_power))
+    }
+
+    /// Estimated distance in meters based on the most recently received advertisement.
+    ///
+    /// Returns [None] if the device does not advertise its transmit power level.
+    pub fn distance(&self, estimator: &DistanceEstimator) -> Option<f64> {
+        self.tx_power.map(|tx_power| estimator.distance(self.last_rssi, tx_power))
+    }
+
+    /// Estimated distance band based on the most recently received advertisement.
+    ///
+    /// Returns [None] if the device does not advertise its transmit power level.
+    pub fn proximity(&self, estimator: &DistanceEstimator) -> Option<Proximity> {
+        self.tx_power.map(|tx_power| estimator.proximity(self.last_rssi, tx_power))
+    }
+
+    fn seen(&mut self, rssi: i16) {
+        self.best_rssi = self.best_rssi.max(rssi);
+        self.last_rssi = rssi;
+        self.advertisement_count += 1;
+        self.last_seen = SystemTime::now();
+    }
+}
+
+/// Properties of a device that have been received before it was seen.
+#[derive(Default)]
+struct PendingProperties {
+    name: Option<String>,
+    tx_power: Option<i16>,
+    uuids: HashSet<Uuid>,
+    service_data: HashMap<Uuid, Vec<u8>>,
+    manufacturer_data: HashMap<CompanyId, Vec<u8>>,
+}
+
+impl PendingProperties {
+    async fn query(device: &Device) -> Result<Self> {
+        Ok(Self {
+            name: device.name().await?,
+            tx_power: device.tx_power().await?,
+            uuids: device.uuids().await?.unwrap_or_default(),
+            service_data: device.service_data().await?.unwrap_or_default(),
+            manufacturer_data: device.manufacturer_data().await?.unwrap_or_default(),
+        })
+    }
+
+    fn update(&mut self, property: DeviceProperty) {
+        match property {
+            DeviceProperty::Name(name) => self.name = Some(name),
+            DeviceProperty::TxPower(tx_power) => self.tx_power = Some(tx_power),
+            DeviceProperty::Uuids(uuids) => self.uuids.extend(uuids),
+            DeviceProperty::ServiceData(service_data) => self.service_data.extend(service_data),
+            DeviceProperty::ManufacturerData(manufacturer_data) => {
+                self.manufacturer_data.extend(manufacturer_data)
+            }
+            _ => (),
+        }
+    }
+
+    fn apply(&mut self, summary: &mut ScanSummary) {
+        if let Some(name) = self.name.take() {
+            summary.name = Some(name);
+        }
+        if let Some(tx_power) = self.tx_power.take() {
+            summary.tx_power = Some(tx_power);
+        }
+        summary.uuids.extend(self.uuids.drain());
+        summary.service_data.extend(self.service_data.drain());
+        summary.manufacturer_data.extend(self.manufacturer_data.drain());
+    }
+}
+
+/// Aggregates discovery results into per-device summaries.
+///
+/// The aggregator discovers devices and periodically reports a [ScanSummary]
+/// for each device that has been seen during the reporting interval.
+/// This is useful for survey and site analysis tools, which are interested in
+/// the statistics of advertisements rather than in individual events.
+///
+/// Discovery is configured using [Adapter::set_discovery_filter].
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Debug)]
+pub struct ScanAggregator {
+    adapter: Adapter,
+    interval: Duration,
+}
+
+impl ScanAggregator {
+    /// Creates a new scan aggregator for the specified adapter
+    /// with a reporting interval of one second.
+    pub fn new(adapter: Adapter) -> Self {
+        Self { adapter, interval: Duration::from_secs(1) }
+    }
+
+    /// Sets the reporting interval.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval.max(Duration::from_millis(1));
+        self
+    }
+
+    /// Starts discovery and aggregation.
+    ///
+    /// At the end of each reporting interval the summaries of all devices seen
+    /// during the interval are sent over the returned stream.
+    /// The summaries are cumulative since the device was first seen and sorted by address.
+    /// Intervals in which no device has been seen are not reported.
+    ///
+    /// Discovery is stopped when the returned stream is dropped.
+    pub async fn run(self) -> Result<impl Stream<Item = Vec<ScanSummary>>> {
+        let discovery = self.adapter.discover_devices().await?;
+        let (tx, rx) = mpsc::channel(1);
+        self.adapter.clone().spawn(self.perform(discovery, tx));
+        Ok(ReceiverStream::new(rx))
+    }
+
+    async fn perform(
+        self, discovery: impl Stream<Item = AdapterEvent> + Send, tx: mpsc::Sender<Vec<ScanSummary>>,
+    ) {
+        let discovery = discovery.fuse();
+        futures::pin_mut!(discovery);
+        let mut changes = SelectAll::new();
+        let mut tracked = HashSet::new();
+        let mut pending: HashMap<Address, PendingProperties> = HashMap::new();
+        let mut summaries: HashMap<Address, ScanSummary> = HashMap::new();
+        let mut updated = HashSet::new();
+        let mut ticker = interval_at(Instant::now() + self.interval, self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                evt = discovery.next() => match evt {
+                    Some(AdapterEvent::DeviceAdded(address)) if tracked.insert(address) => {
+                        let Ok(device) = self.adapter.device(address) else { continue };
+                        let Ok(events) = device.events().await else { continue };
+                        changes.push(events.map(move |evt| (address, evt)));
+
+                        let mut props = PendingProperties::query(&device).await.unwrap_or_default();
+                        match device.rssi().await {
+                            Ok(Some(rssi)) => {
+                                let summary =
+                                    summaries.entry(address).or_insert_with(|| ScanSummary::new(address, rssi));
+                                summary.seen(rssi);
+                                props.apply(summary);
+                                updated.insert(address);
+                            }
+                            _ => {
+                                pending.insert(address, props);
+                            }
+                        }
+                    }
+                    Some(AdapterEvent::DeviceRemoved(address)) => {
+                        // Start a new summary if the device is found again.
+                        tracked.remove(&address);
+                        pending.remove(&address);
+                        summaries.remove(&address);
+                        updated.remove(&address);
+                    }
+                    Some(_) => (),
+                    None => break,
+                },
+                Some((address, evt)) = changes.next(), if !changes.is_empty() => {
+                    if !tracked.contains(&address) {
+                        continue;
+                    }
+                    let DeviceEvent::PropertyChanged(property) = evt;
+                    let props = pending.entry(address).or_default();
+                    match property {
+                        DeviceProperty::Rssi(rssi) => {
+                            let summary =
+                                summaries.entry(address).or_insert_with(|| ScanSummary::new(address, rssi));
+                            summary.seen(rssi);
+                            props.apply(summary);
+                            updated.insert(address);
+                        }
+                        property => {
+                            props.update(property);
+                            if let Some(summary) = summaries.get_mut(&address) {
+                                props.apply(summary);
+                            }
+                        }
+                    }
+                },
+                _ = ticker.tick() => {
+                    if updated.is_empty() {
+                        continue;
+                    }
+                    let mut report: Vec<_> =
+                        updated.drain().filter_map(|address| summaries.get(&address).cloned()).collect();
+                    report.sort_by_key(|summary| summary.address);
+                    if tx.send(report).await.is_err() {
+                        break;
+                    }
+                },
+                () = tx.closed() => break,
+            }
+        }
+    }
+}
+
+/// Event of a [MultiAdapterScanner].
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum MultiScanEvent {
+    /// A device has been found.
+    ///
+    /// This is reported once per device, by the adapter that found it first.
+    DeviceAdded {
+        /// Device address.
+        address: Address,
+        /// Name of the adapter that found the device.
+        adapter: String,
+    },
+    /// A device has been received by an adapter with the specified signal strength.
+    Rssi {
+        /// Device address.
+        address: Address,
+        /// Name of the receiving adapter.
+        adapter: String,
+        /// Received signal strength in dBm.
+        rssi: i16,
+    },
+    /// A device is no longer known by any of the adapters.
+    DeviceRemoved {
+        /// Device address.
+        address: Address,
+    },
+}
+
+/// Signal strength sample of an [RssiSeries].
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RssiSample {
+    /// Time the sample was received, relative to the [epoch](RssiBatch::epoch).
+    pub timestamp: Duration,
+    /// Received signal strength in dBm.
+    pub rssi: i16,
+}
+
+/// Signal strength time series of a device received by an adapter.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct RssiSeries {
+    /// Name of the receiving adapter.
+    pub adapter: String,
+    /// Device address.
+    pub address: Address,
+    /// Samples received during the reporting interval in chronological order.
+    pub samples: Vec<RssiSample>,
+}
+
+/// Signal strength time series reported by [MultiAdapterScanner::rssi_series].
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct RssiBatch {
+    /// System time at which the scan was started.
+    ///
+    /// Sample timestamps are measured from this point in time using a monotonic clock.
+    /// It is the same for all batches of a scan.
+    pub epoch: SystemTime,
+    /// Series of all adapter and device pairs that received samples during the
+    /// reporting interval, sorted by device address and adapter name.
+    pub series: Vec<RssiSeries>,
+}
+
+/// Discovers devices using multiple adapters simultaneously.
+///
+/// Devices found by more than one adapter are reported only once, while
+/// signal strength updates are tagged with the receiving adapter.
+/// This is useful for localization systems, which compare the signal strength
+/// of a device received by multiple radios.
+///
+/// Discovery of each adapter is configured using [Adapter::set_discovery_filter].
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Debug)]
+pub struct MultiAdapterScanner {
+    session: Session,
+    adapter_names: Vec<String>,
+}
+
+impl MultiAdapterScanner {
+    /// Creates a new scanner that uses all adapters of the session.
+    pub fn new(session: Session) -> Self {
+        Self { session, adapter_names: Vec::new() }
+    }
+
+    /// Uses the adapter with the specified name.
+    ///
+    /// If adapters are specified, only these adapters are used.
+    pub fn adapter(mut self, adapter_name: impl Into<String>) -> Self {
+        let adapter_name = adapter_name.into();
+        if !self.adapter_names.contains(&adapter_name) {
+            self.adapter_names.push(adapter_name);
+        }
+        self
+    }
+
+    /// Starts discovery on all selected adapters and returns a stream of events.
+    ///
+    /// Adapters that are present when discovery is started are used;
+    /// adapters added later are not.
+    /// Discovery is stopped on all adapters when the returned stream is dropped.
+    pub async fn run(self) -> Result<impl Stream<Item = MultiScanEvent>> {
+        let adapter_names = match self.adapter_names.is_empty() {
+            true => self.session.adapter_names().await?,
+            false => self.adapter_names.clone(),
+        };
+
+        let mut adapters = Vec::new();
+        let mut discoveries = SelectAll::new();
+        for adapter_name in adapter_names {
+            let adapter = self.session.adapter(&adapter_name)?;
+            let discovery = adapter.discover_devices().await?;
+            let name = adapter_name.clone();
+            discoveries.push(discovery.map(move |evt| (name.clone(), evt)).boxed());
+            adapters.push(adapter);
+        }
+
+        let (tx, rx) = mpsc::channel(64);
+        self.session.spawn(Self::perform(adapters, discoveries, tx));
+        Ok(ReceiverStream::new(rx))
+    }
+
+    /// Starts discovery like [run](Self::run) and returns a stream of signal strength time series.
+    ///
+    /// Signal strength updates are collected per adapter and device and the series
+    /// of all pairs that received updates are sent at the end of each reporting interval.
+    /// All samples are timestamped when they are received using a single monotonic clock,
+    /// so that timestamps of different adapters are directly comparable and not affected by
+    /// changes of the system time.
+    ///
+    /// Discovery is stopped on all adapters when the returned stream is dropped.
+    pub async fn rssi_series(self, interval: Duration) -> Result<impl Stream<Item = RssiBatch>> {
+        let interval = interval.max(Duration::from_millis(1));
+        let session = self.session.clone();
+        let events = self.run().await?;
+        let epoch = SystemTime::now();
+        let start = Instant::now();
+        let (tx, rx) = mpsc::channel(1);
+
+        session.spawn(async move {
+            futures::pin_mut!(events);
+            let mut series: HashMap<(String, Address), Vec<RssiSample>> = HashMap::new();
+            let mut ticker = interval_at(start + interval, interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    evt = events.next() => match evt {
+                        Some(MultiScanEvent::Rssi { address, adapter, rssi }) => {
+                            let sample = RssiSample { timestamp: start.elapsed(), rssi };
+                            series.entry((adapter, address)).or_default().push(sample);
+                        }
+                        Some(_) => (),
+                        None => break,
+                    },
+                    _ = ticker.tick() => {
+                        if series.is_empty() {
+                            continue;
+                        }
+                        let mut batch: Vec<_> = series
+                            .drain()
+                            .map(|((adapter, address), samples)| RssiSeries { adapter, address, samples })
+                            .collect();
+                        batch.sort_by(|a, b| (a.address, &a.adapter).cmp(&(b.address, &b.adapter)));
+                        if tx.send(RssiBatch { epoch, series: batch }).await.is_err() {
+                            break;
+                        }
+                    },
+                    () = tx.closed() => break,
+                }
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    async fn perform(
+        adapters: Vec<Adapter>,
+        mut discoveries: SelectAll<futures::stream::BoxStream<'static, (String, AdapterEvent)>>,
+        tx: mpsc::Sender<MultiScanEvent>,
+    ) {
+        let adapters: HashMap<String, Adapter> =
+            adapters.into_iter().map(|adapter| (adapter.name().to_string(), adapter)).collect();
+        let mut changes = SelectAll::new();
+        let mut seen_by: HashMap<Address, HashSet<String>> = HashMap::new();
+        let mut subscribed = HashSet::new();
+
+        loop {
+            let evt = tokio::select! {
+                evt = discoveries.next(), if !discoveries.is_empty() => match evt {
+                    Some((adapter_name, AdapterEvent::DeviceAdded(address))) => {
+                        let receivers = seen_by.entry(address).or_default();
+                        let first = receivers.is_empty();
+                        if !receivers.insert(adapter_name.clone()) {
+                            continue;
+                        }
+
+                        let Some(device) = adapters.get(&adapter_name).and_then(|a| a.device(address).ok()) else {
+                            continue;
+                        };
+                        if subscribed.insert((adapter_name.clone(), address)) {
+                            if let Ok(events) = device.events().await {
+                                let name = adapter_name.clone();
+                                changes.push(events.map(move |evt| (name.clone(), address, evt)).boxed());
+                            }
+                        }
+
+                        if first {
+                            let evt = MultiScanEvent::DeviceAdded { address, adapter: adapter_name.clone() };
+                            if tx.send(evt).await.is_err() {
+                                break;
+                            }
+                        }
+                        match device.rssi().await {
+                            Ok(Some(rssi)) => MultiScanEvent::Rssi { address, adapter: adapter_name, rssi },
+                            _ => continue,
+                        }
+                    }
+                    Some((adapter_name, AdapterEvent::DeviceRemoved(address))) => {
+                        // The event stream of the removed device has ended.
+                        subscribed.remove(&(adapter_name.clone(), address));
+                        let Some(receivers) = seen_by.get_mut(&address) else { continue };
+                        if !receivers.remove(&adapter_name) || !receivers.is_empty() {
+                            continue;
+                        }
+                        seen_by.remove(&address);
+                        MultiScanEvent::DeviceRemoved { address }
+                    }
+                    Some(_) => continue,
+                    None => break,
+                },
+                Some((adapter_name, address, evt)) = changes.next(), if !changes.is_empty() => {
+                    match evt {
+                        DeviceEvent::PropertyChanged(DeviceProperty::Rssi(rssi))
+                            if seen_by.get(&address).is_some_and(|r| r.contains(&adapter_name)) =>
+                        {
+                            MultiScanEvent::Rssi { address, adapter: adapter_name, rssi }
+                        }
+                        _ => continue,
+                    }
+                },
+                () = tx.closed() => break,
+            };
+
+            if tx.send(evt).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// Parser of manufacturer specific advertisement data registered in a [ManufacturerDataRegistry].
+type ManufacturerDataParser<T> = Arc<dyn Fn(&[u8]) -> Option<T> + Send + Sync>;
+
+/// Registry of parsers for manufacturer specific advertisement data keyed by company identifier.
+///
+/// Applications install a parser for each manufacturer data format they understand,
+/// for example the formats of sensor beacons.
+/// All parsers produce the same application-defined type `T`, typically an enum with
+/// one variant per supported format.
+///
+/// Use [discover](Self::discover) to start device discovery and receive the decoded
+/// manufacturer data of discovered devices along with the discovery events.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+pub struct ManufacturerDataRegistry<T> {
+    parsers: HashMap<CompanyId, Vec<ManufacturerDataParser<T>>>,
+}
+
+impl<T> Default for ManufacturerDataRegistry<T> {
+    fn default() -> Self {
+        Self { parsers: HashMap::new() }
+    }
+}
+
+impl<T> Clone for ManufacturerDataRegistry<T> {
+    fn clone(&self) -> Self {
+        Self { parsers: self.parsers.clone() }
+    }
+}
+
+impl<T> fmt::Debug for ManufacturerDataRegistry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut company_ids: Vec<_> = self.parsers.keys().collect();
+        company_ids.sort();
+        f.debug_struct("ManufacturerDataRegistry").field("company_ids", &company_ids).finish()
+    }
+}
+
+impl<T> ManufacturerDataRegistry<T>
+where
+    T: Send + 'static,
+{
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Installs a parser for manufacturer data of the specified company identifier.
+    ///
+    /// The parser returns [None] if it does not recognize the data.
+    /// Multiple parsers can be installed for the same company identifier;
+    /// each of them is invoked.
+    pub fn parser(
+        mut self, company_id: CompanyId, parser: impl Fn(&[u8]) -> Option<T> + Send + Sync + 'static,
+    ) -> Self {
+        self.parsers.entry(company_id).or_default().push(Arc::new(parser));
+        self
+    }
+
+    /// Decodes manufacturer data using the parsers installed for the company identifier.
+    pub fn decode(&self, company_id: CompanyId, data: &[u8]) -> Vec<T> {
+        match self.parsers.get(&company_id) {
+            Some(parsers) => parsers.iter().filter_map(|parser| parser(data)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Decodes all manufacturer data of a device.
+    fn decode_all(&self, address: Address, data: &HashMap<CompanyId, Vec<u8>>) -> Vec<DecodedDiscoveryEvent<T>> {
+        let mut company_ids: Vec<_> = data.keys().copied().filter(|id| self.parsers.contains_key(id)).collect();
+        company_ids.sort_unstable();
+        company_ids
+            .into_iter()
+            .flat_map(|company_id| {
+                self.decode(company_id, &data[&company_id])
+                    .into_iter()
+                    .map(move |value| DecodedDiscoveryEvent::Decoded { address, company_id, value })
+            })
+            .collect()
+    }
+
+    /// Starts device discovery on the adapter and decodes the manufacturer data of discovered devices.
+    ///
+    /// Discovery events are passed through as [DecodedDiscoveryEvent::Adapter].
+    /// Each time a device reports manufacturer data of a company identifier with installed parsers,
+    /// the decoded values are delivered as [DecodedDiscoveryEvent::Decoded].
+    /// Enable [duplicate data](crate::DiscoveryFilter::duplicate_data) in the discovery filter to
+    /// receive every advertisement, even if its data is unchanged.
+    ///
+    /// Discovery is stopped when the returned stream is dropped.
+    pub async fn discover(self, adapter: &Adapter) -> Result<impl Stream<Item = DecodedDiscoveryEvent<T>>> {
+        let discovery = adapter.discover_devices().await?;
+        let (tx, rx) = mpsc::channel(16);
+        adapter.spawn(self.perform(adapter.clone(), discovery, tx));
+        Ok(ReceiverStream::new(rx))
+    }
+
+    async fn perform(
+        self, adapter: Adapter, discovery: impl Stream<Item = AdapterEvent> + Send,
+        tx: mpsc::Sender<DecodedDiscoveryEvent<T>>,
+    ) {
+        let discovery = discovery.fuse();
+        futures::pin_mut!(discovery);
+        let mut changes = SelectAll::new();
+        let mut tracked = HashSet::new();
+
+        loop {
+            let events = tokio::select! {
+                evt = discovery.next() => match evt {
+                    Some(AdapterEvent::DeviceAdded(address)) => {
+                        let mut events = vec![DecodedDiscoveryEvent::Adapter(AdapterEvent::DeviceAdded(address))];
+                        if tracked.insert(address) {
+                            if let Ok(device) = adapter.device(address) {
+                                if let Ok(device_events) = device.events().await {
+                                    changes.push(device_events.map(move |evt| (address, evt)));
+                                }
+                                if let Ok(Some(data)) = device.manufacturer_data().await {
+                                    events.extend(self.decode_all(address, &data));
+                                }
+                            }
+                        }
+                        events
+                    }
+                    Some(AdapterEvent::DeviceRemoved(address)) => {
+                        tracked.remove(&address);
+                        vec![DecodedDiscoveryEvent::Adapter(AdapterEvent::DeviceRemoved(address))]
+                    }
+                    Some(evt) => vec![DecodedDiscoveryEvent::Adapter(evt)],
+                    None => break,
+                },
+                Some((address, evt)) = changes.next(), if !changes.is_empty() => match evt {
+                    DeviceEvent::PropertyChanged(DeviceProperty::ManufacturerData(data)) => {
+                        self.decode_all(address, &data)
+                    }
+                    _ => continue,
+                },
+                () = tx.closed() => break,
+            };
+
+            for evt in events {
+                if tx.send(evt).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Event of a discovery started by [ManufacturerDataRegistry::discover].
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum DecodedDiscoveryEvent<T> {
+    /// Discovery event of the adapter.
+    Adapter(AdapterEvent),
+    /// Manufacturer data of a device has been decoded.
+    Decoded {
+        /// Device address.
+        address: Address,
+        /// Company identifier of the manufacturer data.
+        company_id: CompanyId,
+        /// Decoded value.
+        value: T,
+    },
+}
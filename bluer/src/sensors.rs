@@ -0,0 +1,427 @@
+//! Decoders for sensor beacon advertisement formats.
+//!
+//! Many Bluetooth LE sensors broadcast their readings in advertisements instead of
+//! requiring a connection.
+//! This module decodes the following widely-used formats into typed [measurements](Measurement):
+//!
+//!   * [BTHome v2](https://bthome.io/format/), sent as service data of UUID `fcd2`,
+//!     see [decode_bthome],
+//!   * Xiaomi MiBeacon, sent as service data of UUID `fe95`, see [decode_mibeacon],
+//!   * RuuviTag data format 5 (RAWv2), sent as manufacturer data of Ruuvi Innovations,
+//!     see [decode_ruuvi].
+//!
+//! Encrypted advertisements are not supported.
+//! Use [decode_service_data] and [decode_manufacturer_data] to select the decoder
+//! automatically.
+
+use uuid::Uuid;
+
+use crate::{uuid16, CompanyId};
+
+/// Service data UUID of BTHome advertisements.
+pub const BTHOME_UUID: Uuid = uuid16!(0xfcd2);
+/// Service data UUID of Xiaomi MiBeacon advertisements.
+pub const MIBEACON_UUID: Uuid = uuid16!(0xfe95);
+/// Company identifier of Ruuvi Innovations.
+pub const RUUVI_COMPANY_ID: CompanyId = 0x0499;
+
+/// Sensor beacon advertisement format.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum SensorFormat {
+    /// BTHome version 2.
+    BtHome,
+    /// Xiaomi MiBeacon.
+    MiBeacon,
+    /// RuuviTag data format 5 (RAWv2).
+    RuuviRawV2,
+}
+
+/// Measurement decoded from a sensor beacon advertisement.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Measurement {
+    /// Packet identifier or measurement sequence number.
+    PacketId(u16),
+    /// Battery level in percent.
+    Battery(u8),
+    /// Temperature in degrees Celsius.
+    Temperature(f64),
+    /// Relative humidity in percent.
+    Humidity(f64),
+    /// Atmospheric pressure in hectopascal.
+    Pressure(f64),
+    /// Illuminance in lux.
+    Illuminance(f64),
+    /// Dew point in degrees Celsius.
+    DewPoint(f64),
+    /// Supply voltage in volts.
+    Voltage(f64),
+    /// Power in watts.
+    Power(f64),
+    /// Soil moisture in percent.
+    Moisture(f64),
+    /// Soil conductivity in µS/cm.
+    Conductivity(u16),
+    /// Formaldehyde concentration in mg/m³.
+    Formaldehyde(f64),
+    /// CO₂ concentration in ppm.
+    Co2(u16),
+    /// Concentration of volatile organic compounds in µg/m³.
+    Tvoc(u16),
+    /// Concentration of particulate matter up to 2.5 µm in µg/m³.
+    Pm2_5(u16),
+    /// Concentration of particulate matter up to 10 µm in µg/m³.
+    Pm10(u16),
+    /// Generic counter value.
+    Count(u32),
+    /// Acceleration in units of standard gravity.
+    Acceleration {
+        /// Acceleration along the X axis.
+        x: f64,
+        /// Acceleration along the Y axis.
+        y: f64,
+        /// Acceleration along the Z axis.
+        z: f64,
+    },
+    /// Transmit power in dBm.
+    TxPower(i16),
+    /// Number of movements detected by the sensor.
+    MovementCounter(u8),
+}
+
+/// Measurements decoded from a sensor beacon advertisement.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SensorData {
+    /// Advertisement format.
+    pub format: SensorFormat,
+    /// Decoded measurements in the order they appear in the advertisement.
+    pub measurements: Vec<Measurement>,
+}
+
+/// Reads little-endian unsigned integers of up to four bytes.
+fn uint_le(data: &[u8]) -> u32 {
+    data.iter().rev().fold(0, |value, &byte| value << 8 | u32::from(byte))
+}
+
+/// Reads a little-endian signed 16-bit integer.
+fn i16_le(data: &[u8]) -> i16 {
+    i16::from_le_bytes([data[0], data[1]])
+}
+
+/// Length of the data of a BTHome object, excluding the object identifier.
+///
+/// Returns [None] for unknown objects and 0 for objects prefixed by their length.
+fn bthome_object_len(id: u8) -> Option<usize> {
+    match id {
+        0x00 | 0x01 | 0x09 | 0x0f..=0x11 | 0x15..=0x2f | 0x3a | 0x46 | 0x57..=0x59 | 0x60 => Some(1),
+        0x02 | 0x03 | 0x06..=0x08 | 0x0c..=0x0e | 0x12..=0x14 | 0x3c | 0x3d | 0x3f..=0x41 => Some(2),
+        0x43..=0x45 | 0x47..=0x4a | 0x51 | 0x52 | 0x56 | 0x5a | 0x5d..=0x5f => Some(2),
+        0x04 | 0x05 | 0x0a | 0x0b | 0x42 | 0x4b => Some(3),
+        0x3e | 0x4c..=0x50 | 0x55 | 0x5b | 0x5c => Some(4),
+        0x53 | 0x54 => Some(0),
+        _ => None,
+    }
+}
+
+/// Decodes BTHome version 2 service data.
+///
+/// Objects that have no corresponding [Measurement] are skipped.
+/// Decoding stops at the first unknown object, since its length cannot be determined.
+/// Returns [None] if the data is encrypted or not of version 2.
+pub fn decode_bthome(service_data: &[u8]) -> Option<SensorData> {
+    let (&info, mut objects) = service_data.split_first()?;
+    if info & 0x01 != 0 || info >> 5 != 2 {
+        return None;
+    }
+
+    let mut measurements = Vec::new();
+    while let Some((&id, rest)) = objects.split_first() {
+        let len = match bthome_object_len(id) {
+            Some(0) => usize::from(*rest.first()?) + 1,
+            Some(len) => len,
+            None => break,
+        };
+        if rest.len() < len {
+            break;
+        }
+        let (v, next) = rest.split_at(len);
+        objects = next;
+
+        let measurement = match id {
+            0x00 => Measurement::PacketId(v[0].into()),
+            0x01 => Measurement::Battery(v[0]),
+            0x02 => Measurement::Temperature(f64::from(i16_le(v)) * 0.01),
+            0x03 => Measurement::Humidity(f64::from(uint_le(v)) * 0.01),
+            0x04 => Measurement::Pressure(f64::from(uint_le(v)) * 0.01),
+            0x05 => Measurement::Illuminance(f64::from(uint_le(v)) * 0.01),
+            0x08 => Measurement::DewPoint(f64::from(i16_le(v)) * 0.01),
+            0x09 | 0x3d | 0x3e => Measurement::Count(uint_le(v)),
+            0x0b => Measurement::Power(f64::from(uint_le(v)) * 0.01),
+            0x0c => Measurement::Voltage(f64::from(uint_le(v)) * 0.001),
+            0x0d => Measurement::Pm2_5(uint_le(v) as u16),
+            0x0e => Measurement::Pm10(uint_le(v) as u16),
+            0x12 => Measurement::Co2(uint_le(v) as u16),
+            0x13 => Measurement::Tvoc(uint_le(v) as u16),
+            0x14 => Measurement::Moisture(f64::from(uint_le(v)) * 0.01),
+            0x2e => Measurement::Humidity(v[0].into()),
+            0x2f => Measurement::Moisture(v[0].into()),
+            0x45 => Measurement::Temperature(f64::from(i16_le(v)) * 0.1),
+            0x4a => Measurement::Voltage(f64::from(uint_le(v)) * 0.1),
+            0x56 => Measurement::Conductivity(uint_le(v) as u16),
+            _ => continue,
+        };
+        measurements.push(measurement);
+    }
+
+    Some(SensorData { format: SensorFormat::BtHome, measurements })
+}
+
+/// Decodes Xiaomi MiBeacon service data.
+///
+/// Returns [None] if the data is encrypted, malformed or contains no object.
+pub fn decode_mibeacon(service_data: &[u8]) -> Option<SensorData> {
+    const ENCRYPTED: u16 = 0x0008;
+    const MAC_PRESENT: u16 = 0x0010;
+    const CAPABILITY_PRESENT: u16 = 0x0020;
+    const OBJECT_PRESENT: u16 = 0x0040;
+    const IO_CAPABILITY: u8 = 0x20;
+
+    let frame_control = u16::from_le_bytes(service_data.get(0..2)?.try_into().ok()?);
+    if frame_control & ENCRYPTED != 0 || frame_control & OBJECT_PRESENT == 0 {
+        return None;
+    }
+
+    // Frame control, product identifier and frame counter.
+    let mut pos = 5;
+    if frame_control & MAC_PRESENT != 0 {
+        pos += 6;
+    }
+    if frame_control & CAPABILITY_PRESENT != 0 {
+        let capability = *service_data.get(pos)?;
+        pos += 1;
+        if capability & IO_CAPABILITY != 0 {
+            pos += 2;
+        }
+    }
+
+    let mut objects = service_data.get(pos..)?;
+    let mut measurements = Vec::new();
+    while objects.len() >= 3 {
+        let id = u16::from_le_bytes([objects[0], objects[1]]);
+        let len = usize::from(objects[2]);
+        let Some(v) = objects.get(3..3 + len) else { break };
+        objects = &objects[3 + len..];
+
+        match (id, len) {
+            (0x1004, 2) => measurements.push(Measurement::Temperature(f64::from(i16_le(v)) * 0.1)),
+            (0x1006, 2) => measurements.push(Measurement::Humidity(f64::from(uint_le(v)) * 0.1)),
+            (0x1007, 3) => measurements.push(Measurement::Illuminance(uint_le(v).into())),
+            (0x1008, 1) => measurements.push(Measurement::Moisture(v[0].into())),
+            (0x1009, 2) => measurements.push(Measurement::Conductivity(uint_le(v) as u16)),
+            (0x100a, 1) => measurements.push(Measurement::Battery(v[0])),
+            (0x100d, 4) => {
+                measurements.push(Measurement::Temperature(f64::from(i16_le(&v[0..2])) * 0.1));
+                measurements.push(Measurement::Humidity(f64::from(uint_le(&v[2..4])) * 0.1));
+            }
+            (0x1010, 2) => measurements.push(Measurement::Formaldehyde(f64::from(uint_le(v)) * 0.01)),
+            _ => (),
+        }
+    }
+
+    if measurements.is_empty() {
+        return None;
+    }
+    Some(SensorData { format: SensorFormat::MiBeacon, measurements })
+}
+
+/// Decodes RuuviTag data format 5 (RAWv2) manufacturer data.
+///
+/// Values the sensor marks as invalid are omitted.
+/// Returns [None] if the data is not of data format 5.
+pub fn decode_ruuvi(manufacturer_data: &[u8]) -> Option<SensorData> {
+    let d = manufacturer_data;
+    if d.len() < 18 || d[0] != 5 {
+        return None;
+    }
+    let u16_be = |pos: usize| u16::from_be_bytes([d[pos], d[pos + 1]]);
+    let i16_be = |pos: usize| i16::from_be_bytes([d[pos], d[pos + 1]]);
+
+    let mut measurements = Vec::new();
+    if i16_be(1) != i16::MIN {
+        measurements.push(Measurement::Temperature(f64::from(i16_be(1)) * 0.005));
+    }
+    if u16_be(3) != u16::MAX {
+        measurements.push(Measurement::Humidity(f64::from(u16_be(3)) * 0.0025));
+    }
+    if u16_be(5) != u16::MAX {
+        measurements.push(Measurement::Pressure((f64::from(u16_be(5)) + 50_000.0) / 100.0));
+    }
+    if [7, 9, 11].iter().all(|&pos| i16_be(pos) != i16::MIN) {
+        measurements.push(Measurement::Acceleration {
+            x: f64::from(i16_be(7)) / 1000.0,
+            y: f64::from(i16_be(9)) / 1000.0,
+            z: f64::from(i16_be(11)) / 1000.0,
+        });
+    }
+    let power = u16_be(13);
+    if power >> 5 != 0x7ff {
+        measurements.push(Measurement::Voltage((f64::from(power >> 5) + 1600.0) / 1000.0));
+    }
+    if power & 0x1f != 0x1f {
+        measurements.push(Measurement::TxPower((power & 0x1f) as i16 * 2 - 40));
+    }
+    if d[15] != u8::MAX {
+        measurements.push(Measurement::MovementCounter(d[15]));
+    }
+    if u16_be(16) != u16::MAX {
+        measurements.push(Measurement::PacketId(u16_be(16)));
+    }
+
+    Some(SensorData { format: SensorFormat::RuuviRawV2, measurements })
+}
+
+/// Decodes service data using the decoder for the service UUID.
+///
+/// Returns [None] if no decoder for the UUID exists or decoding fails.
+pub fn decode_service_data(uuid: &Uuid, data: &[u8]) -> Option<SensorData> {
+    match *uuid {
+        BTHOME_UUID => decode_bthome(data),
+        MIBEACON_UUID => decode_mibeacon(data),
+        _ => None,
+    }
+}
+
+/// Decodes manufacturer data using the decoder for the company identifier.
+///
+/// Returns [None] if no decoder for the company identifier exists or decoding fails.
+pub fn decode_manufacturer_data(company_id: CompanyId, data: &[u8]) -> Option<SensorData> {
+    match company_id {
+        RUUVI_COMPANY_ID => decode_ruuvi(data),
+        _ => None,
+    }
+}
+
+/// Decodes all supported sensor data advertised by a device.
+#[cfg(feature = "bluetoothd-core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+pub fn decode_device(device: &crate::DeviceSummary) -> Vec<SensorData> {
+    let service_data = device.service_data.iter().filter_map(|(uuid, data)| decode_service_data(uuid, data));
+    let manufacturer_data = device
+        .manufacturer_data
+        .iter()
+        .filter_map(|(&company_id, data)| decode_manufacturer_data(company_id, data));
+    service_data.chain(manufacturer_data).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    /// Rounds floating point values to four decimals for comparison.
+    fn rounded(data: SensorData) -> Vec<Measurement> {
+        let r = |v: f64| (v * 1e4).round() / 1e4;
+        data.measurements
+            .into_iter()
+            .map(|m| match m {
+                Measurement::Temperature(v) => Measurement::Temperature(r(v)),
+                Measurement::Humidity(v) => Measurement::Humidity(r(v)),
+                Measurement::Pressure(v) => Measurement::Pressure(r(v)),
+                Measurement::Voltage(v) => Measurement::Voltage(r(v)),
+                Measurement::Acceleration { x, y, z } => Measurement::Acceleration { x: r(x), y: r(y), z: r(z) },
+                m => m,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bthome() {
+        let data = decode_service_data(&BTHOME_UUID, &hex("4002ca0903bf13")).unwrap();
+        assert_eq!(data.format, SensorFormat::BtHome);
+        assert_eq!(rounded(data), [Measurement::Temperature(25.06), Measurement::Humidity(50.55)]);
+
+        let data = decode_bthome(&hex("40000c01640c020c")).unwrap();
+        assert_eq!(
+            rounded(data),
+            [Measurement::PacketId(12), Measurement::Battery(100), Measurement::Voltage(3.074)]
+        );
+
+        // Decoding stops at an unknown object.
+        let data = decode_bthome(&hex("400161ff0102")).unwrap();
+        assert_eq!(data.measurements, [Measurement::Battery(0x61)]);
+
+        // Encrypted and version 1.
+        assert_eq!(decode_bthome(&hex("4102ca09")), None);
+        assert_eq!(decode_bthome(&hex("2002ca09")), None);
+        assert_eq!(decode_bthome(&[]), None);
+    }
+
+    #[test]
+    fn mibeacon() {
+        let data = decode_service_data(&MIBEACON_UUID, &hex("5020aa0117a1b2c3d4e5f60d1004fe004802")).unwrap();
+        assert_eq!(data.format, SensorFormat::MiBeacon);
+        assert_eq!(rounded(data), [Measurement::Temperature(25.4), Measurement::Humidity(58.4)]);
+
+        let data = decode_mibeacon(&hex("5020aa0118a1b2c3d4e5f60a100155")).unwrap();
+        assert_eq!(data.measurements, [Measurement::Battery(0x55)]);
+
+        // Encrypted, without object and truncated.
+        assert_eq!(decode_mibeacon(&hex("5830aa0119a1b2c3d4e5f60a100155")), None);
+        assert_eq!(decode_mibeacon(&hex("1020aa0119a1b2c3d4e5f6")), None);
+        assert_eq!(decode_mibeacon(&hex("50")), None);
+    }
+
+    #[test]
+    fn ruuvi() {
+        // Test vectors of the RuuviTag data format 5 specification.
+        let data =
+            decode_manufacturer_data(RUUVI_COMPANY_ID, &hex("0512fc5394c37c0004fffc040cac364200cdcbb8334c884f"))
+                .unwrap();
+        assert_eq!(data.format, SensorFormat::RuuviRawV2);
+        assert_eq!(
+            rounded(data),
+            [
+                Measurement::Temperature(24.3),
+                Measurement::Humidity(53.49),
+                Measurement::Pressure(1000.44),
+                Measurement::Acceleration { x: 0.004, y: -0.004, z: 1.036 },
+                Measurement::Voltage(2.977),
+                Measurement::TxPower(4),
+                Measurement::MovementCounter(66),
+                Measurement::PacketId(205),
+            ]
+        );
+
+        let data = decode_ruuvi(&hex("057ffffffefffe7fff7fff7fffffdefefffecbb8334c884f")).unwrap();
+        assert_eq!(
+            rounded(data),
+            [
+                Measurement::Temperature(163.835),
+                Measurement::Humidity(163.835),
+                Measurement::Pressure(1155.34),
+                Measurement::Acceleration { x: 32.767, y: 32.767, z: 32.767 },
+                Measurement::Voltage(3.646),
+                Measurement::TxPower(20),
+                Measurement::MovementCounter(254),
+                Measurement::PacketId(65534),
+            ]
+        );
+
+        let data = decode_ruuvi(&hex("058000ffffffff800080008000ffffffffffffffffffffffff")).unwrap();
+        assert_eq!(data.measurements, []);
+
+        assert_eq!(decode_ruuvi(&hex("0312fc5394c37c0004fffc040cac364200cd")), None);
+        assert_eq!(decode_ruuvi(&hex("0512fc5394c37c")), None);
+        assert_eq!(
+            decode_manufacturer_data(0x004c, &hex("0512fc5394c37c0004fffc040cac364200cdcbb8334c884f")),
+            None
+        );
+    }
+}
@@ -2,43 +2,78 @@
 
 use dbus::{
     arg::Variant,
+    channel::Channel,
     message::MatchRule,
     nonblock::{
         stdintf::org_freedesktop_dbus::{
             ObjectManagerInterfacesAdded, ObjectManagerInterfacesRemoved, PropertiesPropertiesChanged,
         },
-        SyncConnection,
+        Proxy, SyncConnection,
     },
     strings::BusName,
     Message,
 };
-use dbus_crossroads::{Crossroads, IfaceToken};
+use dbus_crossroads::Crossroads;
 use dbus_tokio::connection;
 use futures::{
     channel::{mpsc, oneshot},
+    future,
     lock::Mutex,
-    Future, SinkExt, Stream, StreamExt,
+    stream,
+    task::AtomicWaker,
+    Future, FutureExt, SinkExt, Stream, StreamExt,
 };
 use lazy_static::lazy_static;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     fmt::{Debug, Formatter},
-    sync::{Arc, Weak},
+    mem,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex as SyncMutex, RwLock, Weak,
+    },
+    task::{Context, Poll},
+    time::Duration,
 };
 use tokio::{
     select,
+    sync::broadcast,
     task::{spawn_blocking, JoinHandle},
+    time::{sleep_until, Instant},
 };
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
 
 use crate::{
-    adapter,
-    adv::Advertisement,
-    agent::{Agent, AgentHandle, RegisteredAgent},
-    all_dbus_objects, gatt,
-    monitor::RegisteredMonitor,
-    parent_path, Adapter, DiscoveryFilter, Error, ErrorKind, InternalErrorKind, Result, SERVICE_NAME,
+    adapter, all_dbus_objects, parent_path, Adapter, Address, DiscoveryFilter, Error, ErrorKind,
+    InternalErrorKind, Result, SERVICE_NAME, TIMEOUT,
 };
 
+#[cfg(feature = "adv")]
+use crate::adv::Advertisement;
+#[cfg(feature = "agent")]
+use crate::agent::{Agent, AgentHandle, AgentRequestKind, RegisteredAgent};
+#[cfg(feature = "gatt-server")]
+use crate::gatt;
+#[cfg(feature = "monitor")]
+use crate::monitor::RegisteredMonitor;
+#[cfg(any(feature = "adv", feature = "gatt-server"))]
+use dbus::arg::{prop_cast, RefArg};
+#[cfg(any(
+    feature = "adv",
+    feature = "agent",
+    feature = "gatt-server",
+    feature = "monitor",
+    feature = "mesh",
+    feature = "rfcomm"
+))]
+use dbus_crossroads::IfaceToken;
+#[cfg(any(feature = "adv", feature = "gatt-server"))]
+use tokio::sync::watch;
+#[cfg(feature = "agent")]
+use uuid::Uuid;
+
 #[cfg(feature = "mesh")]
 use crate::mesh::{
     agent::RegisteredProvisionAgent, application::RegisteredApplication, element::RegisteredElement,
@@ -51,15 +86,29 @@ use crate::rfcomm::{profile::RegisteredProfile, Profile, ProfileHandle};
 /// Terminate TX and terminated RX for single session.
 type SingleSessionTerm = (Weak<oneshot::Sender<()>>, oneshot::Receiver<()>);
 
+/// Operation locks of devices identified by adapter name and address.
+type DeviceOpLocks = HashMap<(String, Address), Weak<tokio::sync::Mutex<()>>>;
+
+/// State of the connection pools of adapters identified by adapter name.
+type ConnectionPools = HashMap<String, Weak<crate::pool::PoolState>>;
+
 /// Shared state of all objects in a Bluetooth session.
 pub(crate) struct SessionInner {
     pub connection: Arc<SyncConnection>,
     pub crossroads: Mutex<Crossroads>,
+    #[cfg(feature = "adv")]
     pub le_advertisment_token: IfaceToken<Advertisement>,
+    #[cfg(feature = "gatt-server")]
     pub gatt_reg_service_token: IfaceToken<Arc<gatt::local::RegisteredService>>,
+    #[cfg(feature = "gatt-server")]
     pub gatt_reg_characteristic_token: IfaceToken<Arc<gatt::local::RegisteredCharacteristic>>,
+    #[cfg(feature = "gatt-server")]
     pub gatt_reg_characteristic_descriptor_token: IfaceToken<Arc<gatt::local::RegisteredDescriptor>>,
+    #[cfg(feature = "gatt-server")]
     pub gatt_profile_token: IfaceToken<gatt::local::Profile>,
+    #[cfg(feature = "gatt-server")]
+    pub gatt_applications: SyncMutex<HashMap<String, gatt::local::ApplicationEntry>>,
+    #[cfg(feature = "agent")]
     pub agent_token: IfaceToken<Arc<RegisteredAgent>>,
     #[cfg(feature = "mesh")]
     pub application_token: IfaceToken<Arc<RegisteredApplication>>,
@@ -69,13 +118,30 @@ pub(crate) struct SessionInner {
     pub provisioner_token: IfaceToken<Arc<RegisteredApplication>>,
     #[cfg(feature = "mesh")]
     pub provision_agent_token: IfaceToken<Arc<RegisteredProvisionAgent>>,
+    #[cfg(feature = "monitor")]
     pub monitor_token: IfaceToken<Arc<RegisteredMonitor>>,
     #[cfg(feature = "rfcomm")]
     pub profile_token: IfaceToken<Arc<RegisteredProfile>>,
     pub single_sessions: Mutex<HashMap<dbus::Path<'static>, SingleSessionTerm>>,
     pub event_sub_tx: mpsc::Sender<SubscriptionReq>,
     dbus_task: JoinHandle<connection::IOResourceError>,
+    event_task: JoinHandle<()>,
+    tasks: TaskTracker,
+    tasks_cancel: CancellationToken,
     pub adapter_discovery_filter: Mutex<HashMap<String, DiscoveryFilter>>,
+    pub device_op_locks: SyncMutex<DeviceOpLocks>,
+    pub serialize_device_ops: AtomicBool,
+    pub connection_pools: SyncMutex<ConnectionPools>,
+    pub event_buffer: SyncMutex<(usize, EventOverflowPolicy)>,
+    pub metrics_hook: RwLock<Option<Arc<dyn MetricsHook>>>,
+    pub exported_call_hook: Arc<RwLock<Option<Arc<dyn ExportedCallHook>>>>,
+    pub exported_objects: SyncMutex<BTreeMap<dbus::Path<'static>, Vec<&'static str>>>,
+    #[cfg(all(feature = "tracing", feature = "gatt-server"))]
+    pub gatt_uuids: GattUuids,
+    #[cfg(feature = "agent")]
+    pub service_authorizations: SyncMutex<HashMap<Address, HashSet<Uuid>>>,
+    #[cfg(feature = "agent")]
+    pub agent_requests: broadcast::Sender<(String, Address, AgentRequestKind)>,
 }
 
 impl SessionInner {
@@ -108,7 +174,7 @@ impl SessionInner {
         single_sessions.insert(path.clone(), (Arc::downgrade(&term_tx), termed_rx));
 
         let path = path.clone();
-        tokio::spawn(async move {
+        self.spawn(async move {
             let _ = term_rx.await;
             log::trace!("Terminating single session for {}", &path);
             stop_fn.await;
@@ -137,15 +203,96 @@ impl SessionInner {
         }
     }
 
-    pub async fn events(
-        &self, path: dbus::Path<'static>, child_objects: bool,
-    ) -> Result<mpsc::UnboundedReceiver<Event>> {
-        Event::subscribe(&mut self.event_sub_tx.clone(), path, child_objects).await
+    /// Acquires the operation lock of the specified device.
+    ///
+    /// Returns [None] if serialization of device operations is disabled.
+    pub async fn device_op_lock(
+        &self, adapter_name: &str, address: Address,
+    ) -> Option<tokio::sync::OwnedMutexGuard<()>> {
+        if !self.serialize_device_ops.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        let lock = {
+            let mut locks = self.device_op_locks.lock().unwrap();
+            locks.retain(|_, lock| lock.strong_count() > 0);
+            let key = (adapter_name.to_string(), address);
+            match locks.get(&key).and_then(Weak::upgrade) {
+                Some(lock) => lock,
+                None => {
+                    let lock = Arc::new(tokio::sync::Mutex::new(()));
+                    locks.insert(key, Arc::downgrade(&lock));
+                    lock
+                }
+            }
+        };
+
+        Some(lock.lock_owned().await)
+    }
+
+    /// Reports a completed D-Bus method call or property access to the metrics hook.
+    pub fn record_dbus_call(
+        &self, path: &dbus::Path, interface: &str, method: &str, property: Option<&str>, duration: Duration,
+        error: Option<&Error>,
+    ) {
+        if let Some(hook) = &*self.metrics_hook.read().unwrap() {
+            hook.dbus_call(&DbusCallInfo { path, interface, method, property, duration, error });
+        }
+    }
+
+    pub async fn events(&self, path: dbus::Path<'static>, child_objects: bool) -> Result<EventReceiver> {
+        let (capacity, policy) = *self.event_buffer.lock().unwrap();
+        ObjectEvent::subscribe(&mut self.event_sub_tx.clone(), path, child_objects, capacity, policy).await
+    }
+
+    /// Records that an object implementing the specified interfaces has been exported.
+    #[cfg(any(
+        feature = "adv",
+        feature = "agent",
+        feature = "gatt-server",
+        feature = "monitor",
+        feature = "mesh",
+        feature = "rfcomm"
+    ))]
+    pub fn record_export(&self, path: &dbus::Path<'static>, interfaces: &[&'static str]) {
+        self.exported_objects.lock().unwrap().insert(path.clone(), interfaces.to_vec());
+    }
+
+    /// Records that an exported object has been removed.
+    #[cfg(any(
+        feature = "adv",
+        feature = "agent",
+        feature = "gatt-server",
+        feature = "monitor",
+        feature = "mesh",
+        feature = "rfcomm"
+    ))]
+    pub fn record_unexport(&self, path: &dbus::Path<'static>) {
+        self.exported_objects.lock().unwrap().remove(path);
+        #[cfg(all(feature = "tracing", feature = "gatt-server"))]
+        self.gatt_uuids.lock().unwrap().remove(&**path);
+    }
+
+    /// Spawns a task owned by the session.
+    ///
+    /// The task is cancelled when the session is dropped or [shut down](Session::shutdown).
+    pub fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        let cancel = self.tasks_cancel.clone();
+        self.tasks.spawn(async move {
+            select! {
+                () = cancel.cancelled() => (),
+                () = task => (),
+            }
+        });
     }
 }
 
 impl Drop for SessionInner {
     fn drop(&mut self) {
+        self.tasks_cancel.cancel();
+        self.tasks.close();
+        self.event_task.abort();
+
         // documentation for dbus_tokio::connection::IOResource indicates it is abortable
         self.dbus_task.abort();
     }
@@ -161,23 +308,219 @@ impl Drop for SingleSessionToken {
     }
 }
 
+/// State of a registration with the Bluetooth daemon that is restored automatically.
+///
+/// Obtained from handles returned by [Adapter::advertise_resilient] and
+/// [Adapter::serve_gatt_application_resilient].
+#[cfg(any(feature = "adv", feature = "gatt-server"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "adv", feature = "gatt-server"))))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum RegistrationState {
+    /// Registered since it was initially registered.
+    Active,
+    /// Registration has been lost, because the adapter was removed or the Bluetooth daemon exited.
+    ///
+    /// Advertisements are also lost when the adapter is powered off,
+    /// while GATT applications remain registered.
+    ///
+    /// It is restored once the adapter becomes available again.
+    Suspended,
+    /// Registration has been restored after it was lost.
+    Reregistered,
+}
+
+/// Streams the changes of a registration state.
+#[cfg(any(feature = "adv", feature = "gatt-server"))]
+pub(crate) fn registration_state_stream(
+    state_rx: &watch::Receiver<RegistrationState>,
+) -> impl Stream<Item = RegistrationState> {
+    let mut state_rx = state_rx.clone();
+    state_rx.mark_unchanged();
+    stream::unfold(state_rx, |mut state_rx| async move {
+        state_rx.changed().await.ok()?;
+        let state = *state_rx.borrow_and_update();
+        Some((state, state_rx))
+    })
+}
+
+/// Clones the options of a registration, so that they can be passed again when re-registering.
+#[cfg(any(feature = "adv", feature = "gatt-server"))]
+pub(crate) fn clone_options(options: &dbus::arg::PropMap) -> dbus::arg::PropMap {
+    options.iter().map(|(key, value)| (key.clone(), Variant(value.0.box_clone()))).collect()
+}
+
+/// Restores a registration with the Bluetooth daemon when it has been lost
+/// because the adapter was removed or the daemon restarted and, if requested,
+/// when the adapter was powered off.
+#[cfg(any(feature = "adv", feature = "gatt-server"))]
+pub(crate) struct RegistrationKeeper {
+    connection: Arc<SyncConnection>,
+    adapter_name: Arc<String>,
+    adapter_events: EventReceiver,
+    owner_match: dbus::nonblock::MsgMatch,
+    state_tx: watch::Sender<RegistrationState>,
+    lost_on_power_off: bool,
+}
+
+#[cfg(any(feature = "adv", feature = "gatt-server"))]
+impl RegistrationKeeper {
+    /// Subscribes to the events of the adapter and the Bluetooth daemon.
+    ///
+    /// This must be done before registering to not miss any event.
+    /// `lost_on_power_off` specifies whether the Bluetooth daemon drops the registration
+    /// when the adapter is powered off.
+    pub async fn new(
+        inner: &SessionInner, adapter_name: Arc<String>, lost_on_power_off: bool,
+    ) -> Result<(Self, watch::Receiver<RegistrationState>)> {
+        let adapter_events = inner.events(Adapter::make_dbus_path(&adapter_name)?, false).await?;
+        let owner_rule = MatchRule::new_signal("org.freedesktop.DBus", "NameOwnerChanged");
+        let owner_match = inner.connection.add_match(owner_rule).await?;
+        let (state_tx, state_rx) = watch::channel(RegistrationState::Active);
+        Ok((
+            Self {
+                connection: inner.connection.clone(),
+                adapter_name,
+                adapter_events,
+                owner_match,
+                state_tx,
+                lost_on_power_off,
+            },
+            state_rx,
+        ))
+    }
+
+    /// Re-registers the object at `path` using the specified method of the manager
+    /// interface of the adapter whenever the registration has been lost, until `done` completes.
+    pub async fn run<T>(
+        self, done: impl Future<Output = T>, interface: &str, method: &str, path: dbus::Path<'static>,
+        options: &dbus::arg::PropMap,
+    ) -> T {
+        let Self { connection, adapter_name, mut adapter_events, owner_match, state_tx, lost_on_power_off } =
+            self;
+        let token = owner_match.token();
+        let (_owner_match, mut owner_changes) = owner_match.msg_stream();
+        futures::pin_mut!(done);
+        let mut suspended = false;
+        let mut prev_state = RegistrationState::Active;
+        let is_adapter = |interfaces: &HashSet<String>| interfaces.contains(adapter::INTERFACE);
+
+        let result = loop {
+            let available = select! {
+                result = &mut done => break result,
+                Some(evt) = adapter_events.next() => match evt {
+                    ObjectEvent::ObjectAdded { interfaces, .. } if is_adapter(&interfaces) => true,
+                    ObjectEvent::ObjectRemoved { interfaces, .. } if is_adapter(&interfaces) => false,
+                    ObjectEvent::PropertiesChanged { changed, .. } if lost_on_power_off => {
+                        match prop_cast::<bool>(&changed, "Powered") {
+                            Some(powered) => *powered,
+                            None => continue,
+                        }
+                    }
+                    _ => continue,
+                },
+                Some(msg) = owner_changes.next() => match msg.read3::<String, String, String>() {
+                    Ok((name, _, new_owner)) if name == SERVICE_NAME && new_owner.is_empty() => false,
+                    _ => continue,
+                },
+            };
+
+            match (available, suspended) {
+                (false, false) => {
+                    log::debug!("Registration of {} on {} lost", &path, &adapter_name);
+                    suspended = true;
+                    prev_state = state_tx.send_replace(RegistrationState::Suspended);
+                }
+                (true, true) => {
+                    let Ok(adapter_path) = Adapter::make_dbus_path(&adapter_name) else { continue };
+                    let proxy = Proxy::new(SERVICE_NAME, adapter_path, TIMEOUT, connection.clone());
+                    let result: Result<()> = proxy
+                        .method_call(interface, method, (path.clone(), clone_options(options)))
+                        .await
+                        .map_err(Error::from);
+                    let state = match result {
+                        Ok(()) => RegistrationState::Reregistered,
+                        Err(err) if err.kind == ErrorKind::AlreadyExists => {
+                            log::debug!("Registration of {} on {} has not been lost", &path, &adapter_name);
+                            suspended = false;
+                            state_tx.send_replace(prev_state);
+                            continue;
+                        }
+                        Err(err) => {
+                            log::debug!(
+                                "Cannot restore registration of {} on {}: {}",
+                                &path,
+                                &adapter_name,
+                                &err
+                            );
+                            continue;
+                        }
+                    };
+                    log::debug!("Registration of {} on {} restored", &path, &adapter_name);
+                    suspended = false;
+                    state_tx.send_replace(state);
+                }
+                _ => (),
+            }
+        };
+
+        let _ = connection.remove_match(token).await;
+        result
+    }
+}
+
 /// Bluetooth session.
 ///
 /// Encapsulates a connection to the system Bluetooth daemon.
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+///
+/// The session, as well as the [Adapter] and [Device](crate::Device) interfaces obtained from it,
+/// are [Send], [Sync] and cheap to clone, since all clones share the same connection.
+/// The connection and all background tasks of the session are terminated once the
+/// last clone of the session and of all interfaces obtained from it has been dropped.
+/// Use [shutdown](Self::shutdown) to terminate the background tasks explicitly.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 #[derive(Clone)]
 pub struct Session {
     inner: Arc<SessionInner>,
 }
 
+// Interfaces must remain usable from multiple tasks.
+const _: () = {
+    const fn assert_send_sync_clone<T: Send + Sync + Clone>() {}
+    assert_send_sync_clone::<Session>();
+    assert_send_sync_clone::<Adapter>();
+    assert_send_sync_clone::<crate::Device>();
+};
+
 impl Debug for Session {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         write!(f, "Session {{ {} }}", self.inner.connection.unique_name())
     }
 }
 
+/// Filter for [D-Bus object events](Session::object_events).
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ObjectEventFilter {
+    /// Also provide addition and removal events of direct child objects.
+    pub child_objects: bool,
+    /// Only provide events concerning these interfaces.
+    ///
+    /// If empty, events of all interfaces are provided.
+    pub interfaces: HashSet<String>,
+    /// Start the stream with [ObjectAdded](ObjectEvent::ObjectAdded) events for
+    /// all currently existing child objects.
+    ///
+    /// Only effective if [child_objects](Self::child_objects) is set.
+    /// An object added while the stream is created may be reported twice.
+    pub replay_existing: bool,
+    #[doc(hidden)]
+    pub _non_exhaustive: (),
+}
+
 /// Bluetooth session event.
-#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SessionEvent {
@@ -193,27 +536,65 @@ impl Session {
     /// This establishes a connection to the system Bluetooth daemon over D-Bus.
     pub async fn new() -> Result<Self> {
         let (resource, connection) = spawn_blocking(connection::new_system_sync).await??;
+        Self::with_connection(resource, connection).await
+    }
+
+    /// Create a new Bluetooth session using the D-Bus daemon at the specified address.
+    ///
+    /// This is useful for connecting to a Bluetooth daemon that is not running
+    /// on the system bus, for example a private instance used for testing.
+    /// The address must be in D-Bus address format, for example `unix:path=/tmp/bus`.
+    pub async fn with_bus_address(address: &str) -> Result<Self> {
+        let address = address.to_string();
+        let (resource, connection) = spawn_blocking(move || {
+            let mut channel = Channel::open_private(&address)?;
+            channel.register()?;
+            connection::from_channel(channel)
+        })
+        .await??;
+        Self::with_connection(resource, connection).await
+    }
+
+    async fn with_connection(
+        resource: connection::IOResource<SyncConnection>, connection: Arc<SyncConnection>,
+    ) -> Result<Self> {
         let dbus_task = tokio::spawn(resource);
         log::trace!("Connected to D-Bus with unique name {}", &connection.unique_name());
 
+        let exported_call_hook: Arc<RwLock<Option<Arc<dyn ExportedCallHook>>>> = Arc::default();
+        let spawn_hook = exported_call_hook.clone();
+        #[cfg(all(feature = "tracing", feature = "gatt-server"))]
+        let gatt_uuids = GattUuids::default();
+        #[cfg(all(feature = "tracing", feature = "gatt-server"))]
+        let spawn_gatt_uuids = gatt_uuids.clone();
         let mut crossroads = Crossroads::new();
         crossroads.set_async_support(Some((
             connection.clone(),
-            Box::new(|x| {
-                tokio::spawn(x);
+            Box::new(move |x| {
+                let hook = spawn_hook.read().unwrap().clone();
+                #[cfg(all(feature = "tracing", feature = "gatt-server"))]
+                let x = GATT_UUIDS.scope(spawn_gatt_uuids.clone(), x);
+                tokio::spawn(EXPORTED_CALL_HOOK.scope(hook, x));
             }),
         )));
 
         crossroads.set_object_manager_support(Some(connection.clone()));
 
+        #[cfg(feature = "adv")]
         let le_advertisment_token = Advertisement::register_interface(&mut crossroads);
+        #[cfg(feature = "gatt-server")]
         let gatt_service_token = gatt::local::RegisteredService::register_interface(&mut crossroads);
+        #[cfg(feature = "gatt-server")]
         let gatt_reg_characteristic_token =
             gatt::local::RegisteredCharacteristic::register_interface(&mut crossroads);
+        #[cfg(feature = "gatt-server")]
         let gatt_reg_characteristic_descriptor_token =
             gatt::local::RegisteredDescriptor::register_interface(&mut crossroads);
+        #[cfg(feature = "gatt-server")]
         let gatt_profile_token = gatt::local::Profile::register_interface(&mut crossroads);
+        #[cfg(feature = "agent")]
         let agent_token = RegisteredAgent::register_interface(&mut crossroads);
+        #[cfg(feature = "monitor")]
         let monitor_token = RegisteredMonitor::register_interface(&mut crossroads);
         #[cfg(feature = "rfcomm")]
         let profile_token = RegisteredProfile::register_interface(&mut crossroads);
@@ -227,16 +608,24 @@ impl Session {
         let provision_agent_token = RegisteredProvisionAgent::register_interface(&mut crossroads);
 
         let (event_sub_tx, event_sub_rx) = mpsc::channel(1);
-        Event::handle_connection(connection.clone(), event_sub_rx).await?;
+        let event_task = ObjectEvent::handle_connection(connection.clone(), event_sub_rx).await?;
 
         let inner = Arc::new(SessionInner {
             connection: connection.clone(),
             crossroads: Mutex::new(crossroads),
+            #[cfg(feature = "adv")]
             le_advertisment_token,
+            #[cfg(feature = "gatt-server")]
             gatt_reg_service_token: gatt_service_token,
+            #[cfg(feature = "gatt-server")]
             gatt_reg_characteristic_token,
+            #[cfg(feature = "gatt-server")]
             gatt_reg_characteristic_descriptor_token,
+            #[cfg(feature = "gatt-server")]
             gatt_profile_token,
+            #[cfg(feature = "gatt-server")]
+            gatt_applications: SyncMutex::new(HashMap::new()),
+            #[cfg(feature = "agent")]
             agent_token,
             #[cfg(feature = "mesh")]
             application_token,
@@ -246,18 +635,35 @@ impl Session {
             provisioner_token,
             #[cfg(feature = "mesh")]
             provision_agent_token,
+            #[cfg(feature = "monitor")]
             monitor_token,
             #[cfg(feature = "rfcomm")]
             profile_token,
             single_sessions: Mutex::new(HashMap::new()),
             event_sub_tx,
             dbus_task,
+            event_task,
+            tasks: TaskTracker::new(),
+            tasks_cancel: CancellationToken::new(),
             adapter_discovery_filter: Mutex::new(HashMap::new()),
+            device_op_locks: SyncMutex::new(HashMap::new()),
+            serialize_device_ops: AtomicBool::new(true),
+            connection_pools: SyncMutex::new(HashMap::new()),
+            event_buffer: SyncMutex::new((DEFAULT_EVENT_CAPACITY, EventOverflowPolicy::default())),
+            metrics_hook: RwLock::new(None),
+            exported_call_hook,
+            exported_objects: SyncMutex::new(BTreeMap::new()),
+            #[cfg(all(feature = "tracing", feature = "gatt-server"))]
+            gatt_uuids,
+            #[cfg(feature = "agent")]
+            service_authorizations: SyncMutex::new(HashMap::new()),
+            #[cfg(feature = "agent")]
+            agent_requests: broadcast::channel(16).0,
         });
 
         let mc_callback = connection.add_match(MatchRule::new_method_call()).await?;
         let mc_inner = Arc::downgrade(&inner);
-        tokio::spawn(async move {
+        inner.spawn(async move {
             let (_mc_callback, mut mc_stream) = mc_callback.msg_stream();
             while let Some(msg) = mc_stream.next().await {
                 let mc_inner = match mc_inner.upgrade() {
@@ -292,6 +698,22 @@ impl Session {
         }
     }
 
+    /// Whether the Bluetooth daemon has been started with experimental interfaces enabled,
+    /// i.e. using the `--experimental` command line option.
+    ///
+    /// Experimental interfaces are required for some functionality, for example
+    /// [Adapter::connect_device], advertising arbitrary data types
+    /// and some features of advertisement monitors.
+    /// Calling [Adapter::connect_device] fails with [ErrorKind::ExperimentalDisabled] when
+    /// they are not enabled, whereas experimental properties of advertisements and
+    /// advertisement monitors are ignored or rejected by the Bluetooth daemon.
+    ///
+    /// This is determined by introspecting the [default adapter](Self::default_adapter),
+    /// thus an error of kind [ErrorKind::NotFound] is returned if the system has no Bluetooth adapter.
+    pub async fn is_experimental(&self) -> Result<bool> {
+        self.default_adapter().await?.has_experimental_interfaces().await
+    }
+
     /// Enumerate connected Bluetooth adapters and return their names.
     pub async fn adapter_names(&self) -> Result<Vec<String>> {
         let mut names = Vec::new();
@@ -307,10 +729,23 @@ impl Session {
     }
 
     /// Create an interface to the Bluetooth adapter with the specified name.
+    ///
+    /// The name must consist only of ASCII letters, digits and underscores,
+    /// otherwise an error of kind [ErrorKind::InvalidName] is returned.
+    /// The adapter need not be present, use [Adapter::exists] to check.
     pub fn adapter(&self, adapter_name: &str) -> Result<Adapter> {
         Adapter::new(self.inner.clone(), adapter_name)
     }
 
+    /// Create an interface to the Bluetooth adapter with the specified D-Bus object path,
+    /// for example `/org/bluez/hci0`.
+    ///
+    /// An error of kind [ErrorKind::InvalidAdapterPath] is returned if the path
+    /// is not the path of an adapter object of the Bluetooth daemon.
+    pub fn adapter_from_dbus_path(&self, path: &dbus::Path) -> Result<Adapter> {
+        Adapter::from_dbus_path(self.inner.clone(), path)
+    }
+
     /// Create an interface for the Bluetooth mesh network.
     #[cfg(feature = "mesh")]
     #[cfg_attr(docsrs, doc(cfg(feature = "mesh")))]
@@ -334,16 +769,105 @@ impl Session {
     /// agents per application are not supported.
     ///
     /// Drop the returned [AgentHandle] to unregister the agent.
+    #[cfg(feature = "agent")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "agent")))]
     pub async fn register_agent(&self, agent: Agent) -> Result<AgentHandle> {
-        let reg_agent = RegisteredAgent::new(agent);
+        let reg_agent = RegisteredAgent::new(agent, Arc::downgrade(&self.inner));
         reg_agent.register(self.inner.clone()).await
     }
 
+    /// Objects currently exported by this session to the Bluetooth daemon.
+    ///
+    /// This includes advertisements, GATT applications with their services, characteristics and descriptors,
+    /// agents, profiles, advertisement monitors and mesh applications.
+    /// Objects are sorted by path.
+    ///
+    /// This is intended for debugging and health checks, for example to detect objects that
+    /// remain exported although their handles have been dropped.
+    pub fn exported_objects(&self) -> Vec<ExportedObject> {
+        self.inner
+            .exported_objects
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, interfaces)| ExportedObject {
+                path: path.to_string(),
+                interfaces: interfaces.iter().map(|i| i.to_string()).collect(),
+            })
+            .collect()
+    }
+
+    /// Local GATT applications currently registered using this session.
+    ///
+    /// Applications are registered using [Adapter::serve_gatt_application].
+    #[cfg(feature = "gatt-server")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gatt-server")))]
+    pub fn gatt_applications(&self) -> Vec<gatt::local::ApplicationInfo> {
+        let mut apps: Vec<_> =
+            self.inner.gatt_applications.lock().unwrap().values().map(|entry| entry.info.clone()).collect();
+        apps.sort_by(|a, b| a.name.cmp(&b.name));
+        apps
+    }
+
+    /// Unregisters the local GATT application with the specified name.
+    ///
+    /// The [ApplicationHandle](gatt::local::ApplicationHandle) of the application
+    /// has no effect afterwards.
+    /// Returns an error of kind [ErrorKind::NotFound] if no application with that name is registered.
+    #[cfg(feature = "gatt-server")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gatt-server")))]
+    pub async fn unregister_gatt_application(&self, name: &str) -> Result<()> {
+        let entry = self.inner.gatt_applications.lock().unwrap().remove(name);
+        let Some(entry) = entry else { return Err(Error::new(ErrorKind::NotFound)) };
+        let (done_tx, done_rx) = oneshot::channel();
+        if entry.unregister_tx.send(done_tx).is_ok() {
+            let _ = done_rx.await;
+        }
+        Ok(())
+    }
+
+    /// Pre-authorizes the specified services of a remote device.
+    ///
+    /// Service authorization requests of the device for these services are accepted
+    /// by agents registered using this session without invoking their
+    /// [authorize_service](Agent::authorize_service) handler.
+    /// This allows headless devices to accept, for example, A2DP connections from known
+    /// devices without interactive prompts.
+    ///
+    /// An agent must be registered using [register_agent](Self::register_agent) for
+    /// authorizations to take effect; [Agent::default] is sufficient.
+    /// Authorizations are kept for the lifetime of this session.
+    /// Marking a device as trusted authorizes all of its services instead.
+    #[cfg(feature = "agent")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "agent")))]
+    pub fn authorize_services(&self, device: Address, uuids: impl IntoIterator<Item = Uuid>) {
+        self.inner.service_authorizations.lock().unwrap().entry(device).or_default().extend(uuids);
+    }
+
+    /// Revokes all service pre-authorizations of a remote device.
+    #[cfg(feature = "agent")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "agent")))]
+    pub fn revoke_service_authorizations(&self, device: Address) {
+        self.inner.service_authorizations.lock().unwrap().remove(&device);
+    }
+
+    /// Services of a remote device that are pre-authorized.
+    ///
+    /// See [authorize_services](Self::authorize_services) for details.
+    #[cfg(feature = "agent")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "agent")))]
+    pub fn authorized_services(&self, device: Address) -> HashSet<Uuid> {
+        self.inner.service_authorizations.lock().unwrap().get(&device).cloned().unwrap_or_default()
+    }
+
     /// This registers a [Bluetooth profile implementation](Profile) for RFCOMM connections.
     ///
     /// The returned [ProfileHandle] provides a stream of
     /// [connection requests](crate::rfcomm::ConnectRequest).
     ///
+    /// The profile is not validated before it is registered; use
+    /// [Profile::validate](crate::rfcomm::Profile::validate) to check its options beforehand.
+    ///
     /// Drop the handle to unregister the profile.
     #[cfg(feature = "rfcomm")]
     #[cfg_attr(docsrs, doc(cfg(feature = "rfcomm")))]
@@ -353,17 +877,186 @@ impl Session {
         reg_profile.register(self.inner.clone(), profile, req_rx).await
     }
 
+    /// Lists the Hands-Free Profile audio gateways managed by oFono.
+    ///
+    /// Each gateway is correlated with a Bluetooth device by its address.
+    #[cfg(feature = "ofono")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ofono")))]
+    pub async fn hfp_gateways(&self) -> Result<Vec<crate::ofono::HfpGateway>> {
+        crate::ofono::HfpGateway::all(self.inner.clone()).await
+    }
+
+    /// Registers a [health application](crate::health::Application) with the
+    /// Health Device Profile.
+    ///
+    /// Drop the returned handle to destroy the application.
+    #[cfg(feature = "health")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "health")))]
+    pub async fn create_health_application(
+        &self, application: crate::health::Application,
+    ) -> Result<crate::health::ApplicationHandle> {
+        application.register(self.inner.clone()).await
+    }
+
+    /// Enables or disables serialization of operations on remote devices.
+    ///
+    /// By default, connecting to and disconnecting from a device, connecting and disconnecting
+    /// its profiles and reading or writing its remote GATT characteristics and descriptors
+    /// is serialized per device, so that concurrent tasks using the same device do not fail with
+    /// an [InProgress](ErrorKind::InProgress) error.
+    ///
+    /// Consequently, [Device::disconnect](crate::Device::disconnect) waits for a pending
+    /// connection attempt of another task to complete.
+    /// Pairing is never serialized, since it may take until the user has confirmed it.
+    ///
+    /// Advanced users who perform their own coordination can disable serialization.
+    /// This setting applies to all adapters and devices of this session.
+    pub fn set_serialize_device_operations(&self, enabled: bool) {
+        self.inner.serialize_device_ops.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Whether operations on remote devices are serialized.
+    ///
+    /// See [set_serialize_device_operations](Self::set_serialize_device_operations) for details.
+    pub fn is_serializing_device_operations(&self) -> bool {
+        self.inner.serialize_device_ops.load(Ordering::SeqCst)
+    }
+
+    /// Sets the number of events buffered for each event stream and the policy applied
+    /// when the buffer is full.
+    ///
+    /// Events of the Bluetooth daemon are buffered until they are consumed.
+    /// If a consumer does not keep up, for example during discovery in an environment
+    /// with many advertising devices, the buffer fills up and the `policy` determines
+    /// which events are lost.
+    /// By default the buffer is [unbounded](EventOverflowPolicy::Unbounded), so that no
+    /// event is lost, and `capacity` is 1024.
+    ///
+    /// This applies to event streams of adapters, devices and GATT attributes
+    /// created afterwards.
+    pub fn set_event_buffer(&self, capacity: usize, policy: EventOverflowPolicy) {
+        *self.inner.event_buffer.lock().unwrap() = (capacity.max(1), policy);
+    }
+
+    /// Number of events buffered for each event stream and the policy applied when the buffer is full.
+    ///
+    /// See [set_event_buffer](Self::set_event_buffer) for details.
+    pub fn event_buffer(&self) -> (usize, EventOverflowPolicy) {
+        *self.inner.event_buffer.lock().unwrap()
+    }
+
+    /// Sets the hook that is informed about all D-Bus method calls and property accesses made to
+    /// the Bluetooth daemon.
+    ///
+    /// This can be used to export metrics about the interaction with BlueZ,
+    /// such as the duration of connection attempts or the rate of timeouts.
+    /// Pass [None] to remove a previously set hook.
+    pub fn set_metrics_hook(&self, hook: Option<Arc<dyn MetricsHook>>) {
+        *self.inner.metrics_hook.write().unwrap() = hook;
+    }
+
+    /// Sets the hook that is informed about all D-Bus method calls made by the Bluetooth daemon
+    /// to objects exported by this session.
+    ///
+    /// This covers advertisements, GATT services, characteristics and descriptors,
+    /// agents, profiles and advertisement monitors.
+    /// Each call, for example a `ReadValue`, `WriteValue`, `StartNotify` or `Release` call
+    /// made on behalf of a remote client, is reported with its arguments and the time it took to handle.
+    /// Pass [None] to remove a previously set hook.
+    pub fn set_exported_call_hook(&self, hook: Option<Arc<dyn ExportedCallHook>>) {
+        *self.inner.exported_call_hook.write().unwrap() = hook;
+    }
+
+    /// Spawns a task owned by the session.
+    pub(crate) fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        self.inner.spawn(task)
+    }
+
+    /// Shuts down all background tasks of the session and waits for their termination.
+    ///
+    /// This includes the tasks dispatching events and serving exported objects, such as
+    /// agents, advertisements and GATT applications, of the session and all interfaces obtained from it.
+    /// Afterwards all event streams end, exported objects stop handling requests and
+    /// no new event streams can be created.
+    /// Calling methods of the Bluetooth daemon remains possible.
+    ///
+    /// Without calling this method, the tasks are cancelled without waiting once the
+    /// last clone of the session and of all interfaces obtained from it has been dropped.
+    pub async fn shutdown(&self) {
+        self.inner.tasks_cancel.cancel();
+        self.inner.tasks.close();
+        self.inner.event_task.abort();
+        self.inner.tasks.wait().await;
+    }
+
+    /// The D-Bus connection used by this session.
+    ///
+    /// This allows access to BlueZ interfaces that are not wrapped by this library
+    /// without opening a second connection to the D-Bus daemon.
+    pub fn dbus_connection(&self) -> Arc<SyncConnection> {
+        self.inner.connection.clone()
+    }
+
+    /// Calls a method of a D-Bus interface of the BlueZ object with the specified path.
+    ///
+    /// This allows calling methods that are not wrapped by this library.
+    pub async fn call_method_raw<A, R>(
+        &self, path: &dbus::Path<'_>, interface: &str, method: &str, args: A,
+    ) -> Result<R>
+    where
+        A: dbus::arg::AppendAll + Debug,
+        R: dbus::arg::ReadAll + Debug + 'static,
+    {
+        RawObject { inner: &self.inner, dbus_path: path }
+            .call_method_with_interface(method, args, interface)
+            .await
+    }
+
+    /// Streams D-Bus object events of the Bluetooth daemon.
+    ///
+    /// This allows building watchers for BlueZ interfaces that are not wrapped by this library.
+    /// The stream provides property changes of the object with the specified path.
+    /// If [child_objects](ObjectEventFilter::child_objects) is set, it also provides
+    /// the addition and removal of its direct child objects.
+    ///
+    /// The stream ends when the object is removed.
+    pub async fn object_events(
+        &self, path: dbus::Path<'static>, filter: ObjectEventFilter,
+    ) -> Result<impl Stream<Item = ObjectEvent>> {
+        let ObjectEventFilter { child_objects, interfaces, replay_existing, .. } = filter;
+
+        // Subscribe before enumerating existing objects, so that no object is missed.
+        let events = self.inner.events(path.clone(), child_objects).await?;
+
+        let mut existing = Vec::new();
+        if child_objects && replay_existing {
+            for (object, object_interfaces) in all_dbus_objects(&self.inner.connection).await? {
+                if object != path && parent_path(&object) == path {
+                    existing.push(ObjectEvent::ObjectAdded {
+                        object,
+                        interfaces: object_interfaces.into_keys().collect(),
+                    });
+                }
+            }
+            existing.sort_by(|a, b| a.object().cmp(b.object()));
+        }
+
+        Ok(stream::iter(existing)
+            .chain(events)
+            .filter_map(move |evt| future::ready(evt.filter_interfaces(&interfaces))))
+    }
+
     /// Stream adapter added and removed events.
     pub async fn events(&self) -> Result<impl Stream<Item = SessionEvent>> {
         let obj_events = self.inner.events(adapter::PATH.into(), true).await?;
         let events = obj_events.filter_map(|evt| async move {
             match evt {
-                Event::ObjectAdded { object, interfaces }
+                ObjectEvent::ObjectAdded { object, interfaces }
                     if interfaces.iter().any(|i| i == adapter::INTERFACE) =>
                 {
                     Adapter::parse_dbus_path(&object).map(|name| SessionEvent::AdapterAdded(name.to_string()))
                 }
-                Event::ObjectRemoved { object, interfaces }
+                ObjectEvent::ObjectRemoved { object, interfaces }
                     if interfaces.iter().any(|i| i == adapter::INTERFACE) =>
                 {
                     Adapter::parse_dbus_path(&object).map(|name| SessionEvent::AdapterRemoved(name.to_string()))
@@ -375,18 +1068,384 @@ impl Session {
     }
 }
 
-/// A D-Bus object or property event.
+/// Object exported by a [Session] to the Bluetooth daemon.
+///
+/// Use [Session::exported_objects] to enumerate exported objects.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ExportedObject {
+    /// D-Bus object path.
+    pub path: String,
+    /// Names of the D-Bus interfaces implemented by the object,
+    /// excluding the standard introspection and properties interfaces.
+    pub interfaces: Vec<String>,
+}
+
+/// Information about a completed D-Bus method call to the Bluetooth daemon.
+///
+/// Property accesses are reported as calls of the `Get` and `Set` methods
+/// with the name of the accessed property.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct DbusCallInfo<'a> {
+    /// D-Bus object path.
+    pub path: &'a str,
+    /// D-Bus interface name.
+    ///
+    /// For property accesses this is the interface the property belongs to.
+    pub interface: &'a str,
+    /// Method name.
+    pub method: &'a str,
+    /// Property name, if this is a property access.
+    pub property: Option<&'a str>,
+    /// Time the call took to complete.
+    pub duration: Duration,
+    /// Error, if the call failed.
+    pub error: Option<&'a Error>,
+}
+
+impl<'a> DbusCallInfo<'a> {
+    /// Whether the call succeeded.
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Hook for collecting metrics about D-Bus method calls made to the Bluetooth daemon.
+///
+/// Set it using [Session::set_metrics_hook].
+/// The hook is called synchronously after each call completes and thus should return quickly.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+pub trait MetricsHook: Send + Sync {
+    /// A D-Bus method call has completed.
+    fn dbus_call(&self, info: &DbusCallInfo);
+}
+
+/// D-Bus object manager interface name.
+#[cfg(any(feature = "gatt-server", feature = "monitor", feature = "mesh"))]
+pub(crate) const OBJECT_MANAGER_INTERFACE: &str = "org.freedesktop.DBus.ObjectManager";
+
+tokio::task_local! {
+    /// Exported call hook of the session within tasks handling incoming method calls.
+    pub(crate) static EXPORTED_CALL_HOOK: Option<Arc<dyn ExportedCallHook>>;
+
+    /// UUIDs of the exported GATT objects of the session within tasks handling incoming method calls.
+    #[cfg(all(feature = "tracing", feature = "gatt-server"))]
+    pub(crate) static GATT_UUIDS: GattUuids;
+}
+
+/// UUIDs of exported GATT services, characteristics and descriptors by object path.
+#[cfg(all(feature = "tracing", feature = "gatt-server"))]
+pub(crate) type GattUuids = Arc<SyncMutex<HashMap<String, uuid::Uuid>>>;
+
+/// Information about a handled D-Bus method call made by the Bluetooth daemon to an exported object.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ExportedCallInfo<'a> {
+    /// D-Bus object path of the exported object.
+    pub path: &'a str,
+    /// D-Bus interface name.
+    pub interface: &'a str,
+    /// Method name.
+    pub method: &'a str,
+    /// Debug representations of the call arguments.
+    pub arguments: &'a [String],
+    /// Time the call took to handle.
+    pub duration: Duration,
+    /// Debug representation of the reply, if the call succeeded.
+    pub reply: Option<&'a str>,
+    /// D-Bus error name and message, if the call failed.
+    pub error: Option<&'a str>,
+}
+
+impl<'a> ExportedCallInfo<'a> {
+    /// Whether the call succeeded.
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Hook for observing D-Bus method calls made by the Bluetooth daemon to exported objects.
+///
+/// Set it using [Session::set_exported_call_hook].
+/// The hook is called synchronously after each call has been handled and before
+/// the reply is sent, thus it should return quickly.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+pub trait ExportedCallHook: Send + Sync {
+    /// A method call to an exported object has been handled.
+    fn exported_call(&self, info: &ExportedCallInfo);
+}
+
+/// Number of events buffered per subscriber of an [EventWatcher].
+const EVENT_WATCHER_CAPACITY: usize = 256;
+
+/// Shared watcher of adapter or device events.
+///
+/// The watcher consumes a single event stream and broadcasts
+/// each event to all of its subscribers.
+/// This allows multiple components of an application to observe
+/// the same events without each of them creating its own event subscription.
+///
+/// Cloning the watcher is cheap and all clones share the same underlying subscription.
+/// It is terminated once all clones of the watcher have been dropped.
+///
+/// Use [Adapter::event_watcher] or [Device::event_watcher](crate::Device::event_watcher)
+/// to obtain a watcher.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone)]
+pub struct EventWatcher<T> {
+    tx: Arc<SyncMutex<Option<broadcast::Sender<T>>>>,
+    _drop_tx: Arc<oneshot::Sender<()>>,
+}
+
+impl<T> Debug for EventWatcher<T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.debug_struct("EventWatcher").field("subscribers", &self.subscriber_count()).finish()
+    }
+}
+
+impl<T> EventWatcher<T> {
+    /// Number of currently active subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        self.tx.lock().unwrap().as_ref().map(|tx| tx.receiver_count()).unwrap_or_default()
+    }
+}
+
+impl<T> EventWatcher<T>
+where
+    T: Clone + Send + 'static,
+{
+    /// Spawns a task owned by the session that broadcasts the events of the specified stream.
+    pub(crate) fn new(inner: &SessionInner, events: impl Stream<Item = T> + Send + 'static) -> Self {
+        /// Ends the streams of all subscribers when the task ends or is cancelled.
+        struct CloseOnDrop<T>(Arc<SyncMutex<Option<broadcast::Sender<T>>>>);
+
+        impl<T> Drop for CloseOnDrop<T> {
+            fn drop(&mut self) {
+                // Dropping the sender ends the streams of all subscribers.
+                self.0.lock().unwrap().take();
+            }
+        }
+
+        let (tx, _) = broadcast::channel(EVENT_WATCHER_CAPACITY);
+        let tx = Arc::new(SyncMutex::new(Some(tx)));
+        let (drop_tx, drop_rx) = oneshot::channel();
+
+        let task_tx = CloseOnDrop(tx.clone());
+        inner.spawn(async move {
+            let task_tx = &task_tx.0;
+            let events = events.fuse();
+            futures::pin_mut!(events);
+            let mut drop_rx = drop_rx.fuse();
+            loop {
+                futures::select! {
+                    evt = events.next() => match evt {
+                        Some(evt) => {
+                            if let Some(tx) = &*task_tx.lock().unwrap() {
+                                let _ = tx.send(evt);
+                            }
+                        }
+                        None => break,
+                    },
+                    _ = drop_rx => break,
+                }
+            }
+        });
+
+        Self { tx, _drop_tx: Arc::new(drop_tx) }
+    }
+
+    /// Subscribe to the events observed by this watcher.
+    ///
+    /// Only events that occur after subscribing are delivered.
+    /// If a subscriber falls too far behind, the oldest undelivered events are
+    /// skipped and a warning is logged.
+    ///
+    /// The stream ends when the underlying object has been removed.
+    pub fn subscribe(&self) -> impl Stream<Item = T> {
+        let rx = self.tx.lock().unwrap().as_ref().map(|tx| tx.subscribe());
+        stream::unfold(rx, |rx| async move {
+            let mut rx = rx?;
+            loop {
+                match rx.recv().await {
+                    Ok(evt) => return Some((evt, Some(rx))),
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        log::warn!("Event subscriber lagged behind and missed {} events", n)
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+
+    /// Subscribe to the events observed by this watcher, reporting lost events.
+    ///
+    /// In contrast to [subscribe](Self::subscribe), a [StreamEvent::Lagged] item is delivered
+    /// when the subscriber has fallen too far behind and events have been skipped.
+    pub fn subscribe_with_lag(&self) -> impl Stream<Item = StreamEvent<T>> {
+        let rx = self.tx.lock().unwrap().as_ref().map(|tx| tx.subscribe());
+        stream::unfold(rx, |rx| async move {
+            let mut rx = rx?;
+            match rx.recv().await {
+                Ok(evt) => Some((StreamEvent::Event(evt), Some(rx))),
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    Some((StreamEvent::Lagged(n.try_into().unwrap_or(usize::MAX)), Some(rx)))
+                }
+                Err(broadcast::error::RecvError::Closed) => None,
+            }
+        })
+    }
+}
+
+/// Coalesces events of the same kind that occur within the specified time window.
+///
+/// `key` returns the kind of an event or [None] if the event must not be coalesced.
+/// The first event of a kind starts a window; when the window elapses the most recent
+/// event of that kind received during the window is delivered.
+/// Events that must not be coalesced are delivered immediately.
+pub(crate) fn coalesce_events<T, K>(
+    inner: &SessionInner, events: impl Stream<Item = T> + Send + 'static, window: Duration,
+    key: impl Fn(&T) -> Option<K> + Send + 'static,
+) -> impl Stream<Item = T>
+where
+    T: Send + 'static,
+    K: Eq + Send + 'static,
+{
+    let (tx, rx) = tokio::sync::mpsc::channel(1);
+
+    inner.spawn(async move {
+        let events = events.fuse();
+        futures::pin_mut!(events);
+        let mut pending: VecDeque<(K, Instant, T)> = VecDeque::new();
+
+        loop {
+            let deadline = pending.front().map(|(_, deadline, _)| *deadline);
+            select! {
+                evt = events.next() => {
+                    match evt {
+                        Some(evt) => match key(&evt) {
+                            Some(k) => match pending.iter_mut().find(|(pk, _, _)| *pk == k) {
+                                Some(entry) => entry.2 = evt,
+                                None => pending.push_back((k, Instant::now() + window, evt)),
+                            },
+                            None => {
+                                if tx.send(evt).await.is_err() {
+                                    return;
+                                }
+                            }
+                        },
+                        None => break,
+                    }
+                },
+                () = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    if let Some((_, _, evt)) = pending.pop_front() {
+                        if tx.send(evt).await.is_err() {
+                            return;
+                        }
+                    }
+                },
+                () = tx.closed() => return,
+            }
+        }
+
+        for (_, _, evt) in pending {
+            if tx.send(evt).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    ReceiverStream::new(rx)
+}
+
+/// Buffers only the most recent events of a stream that have not been consumed yet.
+///
+/// If more than `capacity` events are pending, the oldest pending event is dropped.
+#[cfg(feature = "gatt-client")]
+pub(crate) fn latest_events<T>(
+    inner: &SessionInner, events: impl Stream<Item = T> + Send + 'static, capacity: usize,
+) -> impl Stream<Item = T>
+where
+    T: Send + 'static,
+{
+    let capacity = capacity.max(1);
+    let (tx, rx) = tokio::sync::mpsc::channel(1);
+
+    inner.spawn(async move {
+        let events = events.fuse();
+        futures::pin_mut!(events);
+        let mut pending: VecDeque<T> = VecDeque::new();
+
+        loop {
+            select! {
+                evt = events.next() => {
+                    match evt {
+                        Some(evt) => {
+                            if pending.len() == capacity {
+                                pending.pop_front();
+                            }
+                            pending.push_back(evt);
+                        }
+                        None => break,
+                    }
+                },
+                permit = tx.reserve(), if !pending.is_empty() => {
+                    match permit {
+                        Ok(permit) => permit.send(pending.pop_front().unwrap()),
+                        Err(_) => return,
+                    }
+                },
+                () = tx.closed() => return,
+            }
+        }
+
+        for evt in pending {
+            if tx.send(evt).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    ReceiverStream::new(rx)
+}
+
+/// A D-Bus object or property event of the Bluetooth daemon.
+///
+/// Use [Session::object_events] to obtain a stream of these events.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
 #[derive(Debug)]
-pub(crate) enum Event {
+#[non_exhaustive]
+pub enum ObjectEvent {
     /// Object or object interfaces added.
-    ObjectAdded { object: dbus::Path<'static>, interfaces: HashSet<String> },
+    ObjectAdded {
+        /// Path of the object.
+        object: dbus::Path<'static>,
+        /// Names of the added interfaces.
+        interfaces: HashSet<String>,
+    },
     /// Object or object interfaces removed.
-    ObjectRemoved { object: dbus::Path<'static>, interfaces: HashSet<String> },
+    ObjectRemoved {
+        /// Path of the object.
+        object: dbus::Path<'static>,
+        /// Names of the removed interfaces.
+        interfaces: HashSet<String>,
+    },
     /// Properties changed.
-    PropertiesChanged { object: dbus::Path<'static>, interface: String, changed: dbus::arg::PropMap },
+    PropertiesChanged {
+        /// Path of the object.
+        object: dbus::Path<'static>,
+        /// Name of the interface the properties belong to.
+        interface: String,
+        /// Changed properties and their new values.
+        changed: dbus::arg::PropMap,
+    },
 }
 
-impl Clone for Event {
+impl Clone for ObjectEvent {
     fn clone(&self) -> Self {
         match self {
             Self::ObjectAdded { object, interfaces } => {
@@ -404,19 +1463,356 @@ impl Clone for Event {
     }
 }
 
+/// Policy applied when the event buffer of an event stream is full.
+///
+/// Events are buffered for each event stream until they are consumed.
+/// Use [Session::set_event_buffer] to configure the size of the buffer and the policy.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum EventOverflowPolicy {
+    /// The buffer grows beyond its capacity and no event is lost.
+    #[default]
+    Unbounded,
+    /// The oldest buffered property change is dropped.
+    ///
+    /// The addition and removal of objects is never dropped, thus the buffer may
+    /// exceed its capacity if it holds no property change.
+    DropOldest,
+    /// A property change is merged into a buffered property change of the same object
+    /// and interface, keeping the most recent value of each property.
+    ///
+    /// A property change is only merged if no object has been added or removed since,
+    /// so that the order of events is retained.
+    /// If it cannot be merged, the oldest buffered property change is dropped like
+    /// with [DropOldest](Self::DropOldest).
+    Coalesce,
+    /// The overflow is treated as an error and the event stream ends after
+    /// the buffered events have been delivered.
+    ///
+    /// Streams that report lost events deliver a [Lagged](StreamEvent::Lagged) item
+    /// before they end.
+    Error,
+}
+
+/// Item of an event stream that reports lost events.
+///
+/// When the consumer of an event stream does not keep up, events are dropped or coalesced
+/// according to the [overflow policy](EventOverflowPolicy) and a [Lagged](Self::Lagged)
+/// item is delivered in their place.
+/// Applications that mirror the state of the Bluetooth daemon should then query
+/// the current state again, since they may have missed updates.
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetoothd-core")))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StreamEvent<T> {
+    /// An event.
+    Event(T),
+    /// The specified number of events has been dropped or coalesced because
+    /// the consumer did not keep up.
+    Lagged(usize),
+}
+
+impl<T> StreamEvent<T> {
+    /// The event, or [None] if events have been lost.
+    pub fn event(self) -> Option<T> {
+        match self {
+            Self::Event(evt) => Some(evt),
+            Self::Lagged(_) => None,
+        }
+    }
+
+    /// Applies a function to the event.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> StreamEvent<U> {
+        match self {
+            Self::Event(evt) => StreamEvent::Event(f(evt)),
+            Self::Lagged(n) => StreamEvent::Lagged(n),
+        }
+    }
+}
+
+/// Default number of events buffered per event stream.
+const DEFAULT_EVENT_CAPACITY: usize = 1024;
+
+/// Shared state of a bounded channel of object events.
+#[derive(Default)]
+struct EventQueue {
+    state: SyncMutex<EventQueueState>,
+    waker: AtomicWaker,
+}
+
+#[derive(Default)]
+struct EventQueueState {
+    events: VecDeque<ObjectEvent>,
+    /// Number of events dropped or coalesced since the last event was received.
+    lagged: usize,
+    /// No further events will be sent.
+    closed: bool,
+    /// The channel has been closed because the buffer overflowed.
+    overflowed: bool,
+    receiver_dropped: bool,
+}
+
+impl EventQueueState {
+    /// Queues an event into the full buffer, dropping the oldest property change.
+    ///
+    /// Added and removed objects are never dropped.
+    fn push_evicting(&mut self, evt: ObjectEvent) {
+        self.events.push_back(evt);
+        if let Some(oldest) =
+            self.events.iter().position(|evt| matches!(evt, ObjectEvent::PropertiesChanged { .. }))
+        {
+            self.events.remove(oldest);
+            self.lagged += 1;
+        }
+    }
+}
+
+/// Creates a bounded channel of object events.
+fn event_channel(capacity: usize, policy: EventOverflowPolicy) -> (EventSender, EventReceiver) {
+    let queue = Arc::new(EventQueue::default());
+    (EventSender { queue: queue.clone(), capacity: capacity.max(1), policy }, EventReceiver { queue })
+}
+
+/// Sending half of a bounded channel of object events.
+pub(crate) struct EventSender {
+    queue: Arc<EventQueue>,
+    capacity: usize,
+    policy: EventOverflowPolicy,
+}
+
+impl EventSender {
+    /// Queues an event, applying the overflow policy if the buffer is full.
+    ///
+    /// Returns false if the receiver has been dropped or the channel has been closed
+    /// because of an overflow.
+    fn send(&self, evt: ObjectEvent) -> bool {
+        let mut state = self.queue.state.lock().unwrap();
+        if state.closed || state.receiver_dropped {
+            return false;
+        }
+
+        match self.policy {
+            _ if state.events.len() < self.capacity => state.events.push_back(evt),
+            EventOverflowPolicy::Unbounded => state.events.push_back(evt),
+            EventOverflowPolicy::DropOldest => state.push_evicting(evt),
+            EventOverflowPolicy::Coalesce => match evt.coalesce_into(&mut state.events) {
+                Some(evt) => state.push_evicting(evt),
+                None => state.lagged += 1,
+            },
+            EventOverflowPolicy::Error => {
+                log::warn!("Event subscriber overflowed its buffer of {} events", self.capacity);
+                state.lagged += 1;
+                state.overflowed = true;
+                state.closed = true;
+            }
+        }
+        let open = !state.closed;
+        drop(state);
+
+        self.queue.waker.wake();
+        open
+    }
+}
+
+impl Drop for EventSender {
+    fn drop(&mut self) {
+        self.queue.state.lock().unwrap().closed = true;
+        self.queue.waker.wake();
+    }
+}
+
+/// Receiving half of a bounded channel of object events.
+pub(crate) struct EventReceiver {
+    queue: Arc<EventQueue>,
+}
+
+impl EventReceiver {
+    /// Converts into a stream that reports lost events using [StreamEvent::Lagged]
+    /// instead of logging them.
+    pub(crate) fn with_lag(self) -> LagReportingEventReceiver {
+        LagReportingEventReceiver(self)
+    }
+
+    fn poll_event(&self, cx: &mut Context) -> Poll<Option<StreamEvent<ObjectEvent>>> {
+        self.queue.waker.register(cx.waker());
+        let mut state = self.queue.state.lock().unwrap();
+        if state.lagged > 0 && !state.overflowed {
+            return Poll::Ready(Some(StreamEvent::Lagged(mem::take(&mut state.lagged))));
+        }
+        match state.events.pop_front() {
+            Some(evt) => Poll::Ready(Some(StreamEvent::Event(evt))),
+            // The overflow is reported after the buffered events.
+            None if state.lagged > 0 => Poll::Ready(Some(StreamEvent::Lagged(mem::take(&mut state.lagged)))),
+            None if state.closed => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl Stream for EventReceiver {
+    type Item = ObjectEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<ObjectEvent>> {
+        loop {
+            match self.poll_event(cx) {
+                Poll::Ready(Some(StreamEvent::Lagged(n))) => {
+                    log::warn!("Event subscriber lagged behind and missed {} events", n)
+                }
+                Poll::Ready(Some(StreamEvent::Event(evt))) => return Poll::Ready(Some(evt)),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Receiving half of a bounded channel of object events that reports lost events.
+pub(crate) struct LagReportingEventReceiver(EventReceiver);
+
+impl Stream for LagReportingEventReceiver {
+    type Item = StreamEvent<ObjectEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<StreamEvent<ObjectEvent>>> {
+        self.0.poll_event(cx)
+    }
+}
+
+impl Drop for EventReceiver {
+    fn drop(&mut self) {
+        let mut state = self.queue.state.lock().unwrap();
+        state.receiver_dropped = true;
+        state.events.clear();
+    }
+}
+
+/// D-Bus object of the Bluetooth daemon with an arbitrary path.
+struct RawObject<'a> {
+    inner: &'a SessionInner,
+    dbus_path: &'a dbus::Path<'a>,
+}
+
+impl RawObject<'_> {
+    fn proxy(&self) -> Proxy<'_, &SyncConnection> {
+        Proxy::new(SERVICE_NAME, self.dbus_path, TIMEOUT, &*self.inner.connection)
+    }
+
+    dbus_interface!();
+}
+
 /// D-Bus events subscription request.
 pub(crate) struct SubscriptionReq {
     path: dbus::Path<'static>,
     child_objects: bool,
-    tx: mpsc::UnboundedSender<Event>,
+    tx: EventSender,
     ready_tx: oneshot::Sender<()>,
 }
 
-impl Event {
+/// Event subscription of the event loop.
+struct Subscription {
+    child_objects: bool,
+    tx: EventSender,
+}
+
+impl ObjectEvent {
+    /// Path of the object the event concerns.
+    pub fn object(&self) -> &dbus::Path<'static> {
+        match self {
+            Self::ObjectAdded { object, .. }
+            | Self::ObjectRemoved { object, .. }
+            | Self::PropertiesChanged { object, .. } => object,
+        }
+    }
+
+    /// Restricts the event to the specified interfaces.
+    ///
+    /// Returns [None] if the event does not concern any of the interfaces.
+    /// An empty set of interfaces matches all events.
+    fn filter_interfaces(self, filter: &HashSet<String>) -> Option<Self> {
+        if filter.is_empty() {
+            return Some(self);
+        }
+        match self {
+            Self::ObjectAdded { object, interfaces } => {
+                let interfaces: HashSet<_> = interfaces.into_iter().filter(|i| filter.contains(i)).collect();
+                (!interfaces.is_empty()).then_some(Self::ObjectAdded { object, interfaces })
+            }
+            Self::ObjectRemoved { object, interfaces } => {
+                let interfaces: HashSet<_> = interfaces.into_iter().filter(|i| filter.contains(i)).collect();
+                (!interfaces.is_empty()).then_some(Self::ObjectRemoved { object, interfaces })
+            }
+            Self::PropertiesChanged { ref interface, .. } => filter.contains(interface).then_some(self),
+        }
+    }
+
+    /// Merges a property change into the most recent buffered property change of the same
+    /// object and interface.
+    ///
+    /// The property change is not merged across the addition or removal of an object,
+    /// since that would deliver it ahead of the structural event.
+    /// Returns the event if it has not been merged.
+    fn coalesce_into(self, events: &mut VecDeque<Self>) -> Option<Self> {
+        let Self::PropertiesChanged { object, interface, changed } = self else { return Some(self) };
+        let buffered =
+            events.iter_mut().rev().take_while(|evt| matches!(evt, Self::PropertiesChanged { .. })).find_map(
+                |evt| match evt {
+                    Self::PropertiesChanged { object: o, interface: i, changed: c }
+                        if *o == object && *i == interface =>
+                    {
+                        Some(c)
+                    }
+                    _ => None,
+                },
+            );
+        match buffered {
+            Some(buffered) => {
+                buffered.extend(changed);
+                None
+            }
+            None => Some(Self::PropertiesChanged { object, interface, changed }),
+        }
+    }
+
+    /// Sends the event to the subscriptions, removing closed subscriptions.
+    ///
+    /// If `child_event` is true, only subscriptions for child objects receive the event.
+    /// The event is moved into the last receiving subscription to avoid a deep copy of its
+    /// properties in the common case of a single subscription.
+    fn dispatch(subs: &mut Vec<Subscription>, evt: Self, child_event: bool) {
+        let receives = |sub: &Subscription| !child_event || sub.child_objects;
+        let last = subs.iter().rposition(receives);
+        let mut evt = Some(evt);
+        let mut idx = 0;
+        subs.retain(|sub| {
+            let this = idx;
+            idx += 1;
+            if !receives(sub) {
+                return true;
+            }
+            let evt = if Some(this) == last { evt.take() } else { evt.clone() };
+            evt.map(|evt| sub.tx.send(evt)).unwrap_or(true)
+        });
+    }
+
+    /// Sends the event to the specified number of new subscriptions of the object
+    /// and returns their receivers.
+    #[cfg(feature = "bench")]
+    pub(crate) fn dispatch_to(self, subscriptions: usize) -> Vec<EventReceiver> {
+        let (mut subs, rxs): (Vec<_>, Vec<_>) = (0..subscriptions)
+            .map(|_| {
+                let (tx, rx) = event_channel(DEFAULT_EVENT_CAPACITY, EventOverflowPolicy::default());
+                (Subscription { child_objects: false, tx }, rx)
+            })
+            .unzip();
+        Self::dispatch(&mut subs, self, false);
+        rxs
+    }
+
     /// Spawns a task that handles events for the specified connection.
     pub(crate) async fn handle_connection(
         connection: Arc<SyncConnection>, mut sub_rx: mpsc::Receiver<SubscriptionReq>,
-    ) -> Result<()> {
+    ) -> Result<JoinHandle<()>> {
         use dbus::message::SignalArgs;
         lazy_static! {
             static ref SERVICE_NAME_BUS: BusName<'static> = BusName::new(SERVICE_NAME).unwrap();
@@ -438,13 +1834,9 @@ impl Event {
         let rule_prop = PropertiesPropertiesChanged::match_rule(*SERVICE_NAME_REF, None);
         let msg_match_prop = connection.add_match(rule_prop).await?.msg_cb(handle_msg.clone());
 
-        tokio::spawn(async move {
+        let task = tokio::spawn(async move {
             log::trace!("Starting event loop for {}", &connection.unique_name());
 
-            struct Subscription {
-                child_objects: bool,
-                tx: mpsc::UnboundedSender<Event>,
-            }
             let mut subs: HashMap<String, Vec<Subscription>> = HashMap::new();
 
             loop {
@@ -453,20 +1845,24 @@ impl Event {
                         match msg_opt {
                             Some(msg) => {
                                 // Properties changed.
-                                if let (Some(object), Some(PropertiesPropertiesChanged { interface_name, changed_properties, .. })) =
-                                    (msg.path(), PropertiesPropertiesChanged::from_message(&msg))
-                                {
-                                    // Check for direct path match for PropertiesChanged event.
+                                // Check for direct path match for PropertiesChanged event before decoding
+                                // the message, since properties of objects without subscriptions,
+                                // such as RSSI of discovered devices, may change at a high rate.
+                                if let Some(object) = msg.path() {
                                     if let Some(path_subs) = subs.get_mut(&*object) {
-                                        let evt = Self::PropertiesChanged {
-                                            object: object.clone().into_static(),
-                                            interface: interface_name,
-                                            changed: changed_properties,
-                                        };
-                                        log::trace!("Event: {:?}", &evt);
-                                        path_subs.retain(|sub| sub.tx.unbounded_send(evt.clone()).is_ok());
-                                        if path_subs.is_empty() {
-                                            subs.remove(&*object);
+                                        if let Some(PropertiesPropertiesChanged { interface_name, changed_properties, .. }) =
+                                            PropertiesPropertiesChanged::from_message(&msg)
+                                        {
+                                            let evt = Self::PropertiesChanged {
+                                                object: object.clone().into_static(),
+                                                interface: interface_name,
+                                                changed: changed_properties,
+                                            };
+                                            evt.trace();
+                                            Self::dispatch(path_subs, evt, false);
+                                            if path_subs.is_empty() {
+                                                subs.remove(&*object);
+                                            }
                                         }
                                     }
                                 }
@@ -482,14 +1878,8 @@ impl Event {
                                             object,
                                             interfaces: interfaces.into_keys().collect(),
                                         };
-                                        log::trace!("Event: {:?}", &evt);
-                                        parent_subs.retain(|sub| {
-                                            if sub.child_objects {
-                                                sub.tx.unbounded_send(evt.clone()).is_ok()
-                                            } else {
-                                                true
-                                            }
-                                        });
+                                        evt.trace();
+                                        Self::dispatch(parent_subs, evt, true);
                                         if parent_subs.is_empty() {
                                             subs.remove(&*parent);
                                         }
@@ -510,14 +1900,8 @@ impl Event {
                                     let parent = parent_path(&object);
                                     if let Some(parent_subs) = subs.get_mut(&*parent) {
                                         let evt = Self::ObjectRemoved { object, interfaces: interfaces.into_iter().collect() };
-                                        log::trace!("Event: {:?}", &evt);
-                                        parent_subs.retain(|sub| {
-                                            if sub.child_objects {
-                                                sub.tx.unbounded_send(evt.clone()).is_ok()
-                                            } else {
-                                                true
-                                            }
-                                        });
+                                        evt.trace();
+                                        Self::dispatch(parent_subs, evt, true);
                                         if parent_subs.is_empty() {
                                             subs.remove(&*parent);
                                         }
@@ -549,17 +1933,36 @@ impl Event {
             log::trace!("Terminated event loop for {}", &connection.unique_name());
         });
 
-        Ok(())
+        Ok(task)
+    }
+
+    /// Logs the event.
+    fn trace(&self) {
+        log::trace!("Event: {:?}", self);
+
+        #[cfg(feature = "tracing")]
+        {
+            let object = match self {
+                Self::ObjectAdded { object, .. }
+                | Self::ObjectRemoved { object, .. }
+                | Self::PropertiesChanged { object, .. } => object,
+            };
+            let (adapter, device) = crate::object_path_fields(object);
+            let span = tracing::trace_span!("dbus_event", path = %object, adapter, device);
+            tracing::trace!(parent: &span, event = ?self, "D-Bus event");
+        }
     }
 
     /// Subscribe to D-Bus events for specified path.
     ///
     /// If `child_objects` is [true] events about *direct* child objects being added and removed
     /// will also be delivered.
+    /// At most `capacity` events are buffered, applying `policy` when the buffer is full.
     pub(crate) async fn subscribe(
         sub_tx: &mut mpsc::Sender<SubscriptionReq>, path: dbus::Path<'static>, child_objects: bool,
-    ) -> Result<mpsc::UnboundedReceiver<Event>> {
-        let (tx, rx) = mpsc::unbounded();
+        capacity: usize, policy: EventOverflowPolicy,
+    ) -> Result<EventReceiver> {
+        let (tx, rx) = event_channel(capacity, policy);
         let (ready_tx, ready_rx) = oneshot::channel();
         sub_tx
             .send(SubscriptionReq { path, child_objects, tx, ready_tx })
@@ -569,3 +1972,85 @@ impl Event {
         Ok(rx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn added(n: u8) -> ObjectEvent {
+        ObjectEvent::ObjectAdded { object: format!("/obj{n}").into(), interfaces: HashSet::new() }
+    }
+
+    fn changed(n: u8, value: u8) -> ObjectEvent {
+        let mut changed = dbus::arg::PropMap::new();
+        changed.insert("Value".to_string(), Variant(Box::new(value)));
+        ObjectEvent::PropertiesChanged { object: format!("/obj{n}").into(), interface: "If".to_string(), changed }
+    }
+
+    /// Sends the events and collects the received events, describing them as strings.
+    fn transfer(capacity: usize, policy: EventOverflowPolicy, events: Vec<ObjectEvent>) -> Vec<String> {
+        let (tx, rx) = event_channel(capacity, policy);
+        for evt in events {
+            tx.send(evt);
+        }
+        drop(tx);
+        let received = futures::executor::block_on(rx.with_lag().collect::<Vec<_>>());
+        received
+            .into_iter()
+            .map(|evt| match evt {
+                StreamEvent::Event(ObjectEvent::ObjectAdded { object, .. }) => format!("added {object}"),
+                StreamEvent::Event(ObjectEvent::ObjectRemoved { object, .. }) => format!("removed {object}"),
+                StreamEvent::Event(ObjectEvent::PropertiesChanged { object, changed, .. }) => {
+                    format!("changed {object} {}", changed["Value"].0.as_u64().unwrap())
+                }
+                StreamEvent::Lagged(n) => format!("lagged {n}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unbounded_is_lossless() {
+        let events = (0..10).map(|n| changed(0, n)).collect();
+        assert_eq!(transfer(2, EventOverflowPolicy::Unbounded, events).len(), 10);
+    }
+
+    #[test]
+    fn drop_oldest_keeps_structural_events() {
+        let events = vec![added(1), changed(1, 1), added(2), changed(2, 2)];
+        assert_eq!(
+            transfer(3, EventOverflowPolicy::DropOldest, events),
+            ["lagged 1", "added /obj1", "added /obj2", "changed /obj2 2"]
+        );
+
+        let events = vec![added(1), added(2), added(3)];
+        assert_eq!(
+            transfer(2, EventOverflowPolicy::DropOldest, events),
+            ["added /obj1", "added /obj2", "added /obj3"]
+        );
+    }
+
+    #[test]
+    fn coalesce_retains_order() {
+        let events = vec![changed(1, 1), changed(2, 2), changed(1, 3)];
+        assert_eq!(
+            transfer(2, EventOverflowPolicy::Coalesce, events),
+            ["lagged 1", "changed /obj1 3", "changed /obj2 2"]
+        );
+
+        // The change is not merged across the addition of an object.
+        let events = vec![changed(1, 1), added(2), changed(1, 3)];
+        assert_eq!(
+            transfer(2, EventOverflowPolicy::Coalesce, events),
+            ["lagged 1", "added /obj2", "changed /obj1 3"]
+        );
+    }
+
+    #[test]
+    fn error_reports_overflow() {
+        let events = vec![changed(1, 1), changed(1, 2), changed(1, 3)];
+        assert_eq!(
+            transfer(2, EventOverflowPolicy::Error, events),
+            ["changed /obj1 1", "changed /obj1 2", "lagged 1"]
+        );
+    }
+}
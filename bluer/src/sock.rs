@@ -279,7 +279,7 @@ pub fn getsockopt<T>(socket: &OwnedFd, level: c_int, optname: c_int) -> Result<T
     {
         return Err(Error::last_os_error());
     }
-    if optlen != size_of::<T>() as _ {
+    if optlen != size_of::<T>() as socklen_t {
         return Err(Error::new(ErrorKind::InvalidInput, "invalid size"));
     }
     let optval = unsafe { optval.assume_init() };
@@ -319,6 +319,7 @@ pub fn ioctl_write<T>(socket: &OwnedFd, request: Ioctl, value: &T) -> Result<c_i
 }
 
 /// Private socket implementation functions.
+#[cfg(any(feature = "l2cap", feature = "rfcomm"))]
 macro_rules! sock_priv {
     () => {
         async fn accept_priv(&self) -> Result<(Self, SocketAddr)> {
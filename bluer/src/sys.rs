@@ -72,6 +72,7 @@ pub const LECODEDTX: i32 = 1 << 13;
 pub const LECODEDRX: i32 = 1 << 14;
 
 pub const BTPROTO_L2CAP: i32 = 0;
+pub const BTPROTO_HCI: i32 = 1;
 pub const BTPROTO_RFCOMM: i32 = 3;
 
 /// Bluetooth address.
@@ -198,3 +199,15 @@ pub struct rfcomm_dev_req {
     pub dst: bdaddr_t,
     pub channel: u8,
 }
+
+pub const HCI_DEV_NONE: u16 = 0xffff;
+pub const HCI_CHANNEL_CONTROL: u16 = 3;
+
+/// HCI socket address.
+#[repr(C)]
+#[derive(Clone)]
+pub struct sockaddr_hci {
+    pub hci_family: sa_family_t,
+    pub hci_dev: c_ushort,
+    pub hci_channel: c_ushort,
+}
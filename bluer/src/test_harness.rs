@@ -0,0 +1,263 @@
+//! Test harness running a private Bluetooth daemon on emulated controllers.
+//!
+//! A [TestBed] spawns a private D-Bus daemon, creates virtual Bluetooth controllers
+//! using `btvirt` from the BlueZ emulator and runs a scratch `bluetoothd` instance
+//! connected to the private D-Bus daemon.
+//! A [Session] connected to that instance is provided, allowing end-to-end
+//! tests of advertising, discovery and GATT without Bluetooth hardware.
+//!
+//! The following is required on the machine running the tests:
+//!
+//! * the `hci_vhci` kernel module,
+//! * the `dbus-daemon`, `btvirt` and `bluetoothd` binaries,
+//! * permission to access `/dev/vhci` and the Bluetooth management interface,
+//!   which usually means running as root.
+//!
+//! Virtual controllers are created in the kernel and are thus visible system-wide.
+//! Stop the system Bluetooth daemon while running tests, otherwise it will
+//! also try to manage the virtual controllers.
+
+use std::{
+    ffi::OsString,
+    fmt,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    time::Duration,
+};
+use tokio::time::{sleep, Instant};
+use uuid::Uuid;
+
+use crate::{Error, ErrorKind, InternalErrorKind, Result, Session};
+
+/// Type of emulated controllers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ControllerType {
+    /// Dual mode controllers supporting both BR/EDR and LE.
+    #[default]
+    DualMode,
+    /// LE only controllers.
+    Le,
+    /// BR/EDR only controllers.
+    BrEdr,
+}
+
+/// Builder for a [TestBed].
+#[derive(Clone, Debug)]
+pub struct TestBedBuilder {
+    controllers: u8,
+    controller_type: ControllerType,
+    experimental: bool,
+    dbus_daemon: PathBuf,
+    btvirt: PathBuf,
+    bluetoothd: PathBuf,
+    timeout: Duration,
+}
+
+impl Default for TestBedBuilder {
+    fn default() -> Self {
+        Self {
+            controllers: 1,
+            controller_type: ControllerType::default(),
+            experimental: false,
+            dbus_daemon: "dbus-daemon".into(),
+            btvirt: "btvirt".into(),
+            bluetoothd: "bluetoothd".into(),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl TestBedBuilder {
+    /// Creates a new builder with one dual mode controller.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of emulated controllers.
+    pub fn controllers(mut self, controllers: u8) -> Self {
+        self.controllers = controllers;
+        self
+    }
+
+    /// Sets the type of the emulated controllers.
+    pub fn controller_type(mut self, controller_type: ControllerType) -> Self {
+        self.controller_type = controller_type;
+        self
+    }
+
+    /// Enables experimental interfaces of the Bluetooth daemon.
+    pub fn experimental(mut self, experimental: bool) -> Self {
+        self.experimental = experimental;
+        self
+    }
+
+    /// Sets the path of the `dbus-daemon` binary.
+    pub fn dbus_daemon(mut self, path: impl Into<PathBuf>) -> Self {
+        self.dbus_daemon = path.into();
+        self
+    }
+
+    /// Sets the path of the `btvirt` binary.
+    pub fn btvirt(mut self, path: impl Into<PathBuf>) -> Self {
+        self.btvirt = path.into();
+        self
+    }
+
+    /// Sets the path of the `bluetoothd` binary.
+    pub fn bluetoothd(mut self, path: impl Into<PathBuf>) -> Self {
+        self.bluetoothd = path.into();
+        self
+    }
+
+    /// Sets the time to wait for all emulated controllers to become available.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Starts the test bed.
+    ///
+    /// Returns once all emulated controllers are available as Bluetooth adapters.
+    pub async fn start(self) -> Result<TestBed> {
+        let dir = std::env::temp_dir().join(format!("bluer-test-{}", Uuid::new_v4().simple()));
+        fs::create_dir_all(&dir)?;
+        let mut test_bed = TestBed { dir, processes: Vec::new(), bus_address: String::new(), session: None };
+
+        let socket = test_bed.dir.join("bus");
+        test_bed.bus_address = format!("unix:path={}", socket.display());
+        let config = test_bed.dir.join("bus.conf");
+        File::create(&config)?.write_all(dbus_config(&test_bed.bus_address).as_bytes())?;
+        let mut config_arg = OsString::from("--config-file=");
+        config_arg.push(&config);
+        test_bed.spawn(Command::new(&self.dbus_daemon).arg(config_arg).arg("--nofork"))?;
+        wait_for_path(&socket, self.timeout).await?;
+
+        let mut btvirt = Command::new(&self.btvirt);
+        btvirt.arg(format!("--local={}", self.controllers));
+        match self.controller_type {
+            ControllerType::DualMode => (),
+            ControllerType::Le => {
+                btvirt.arg("--le");
+            }
+            ControllerType::BrEdr => {
+                btvirt.arg("--bredr");
+            }
+        }
+        test_bed.spawn(&mut btvirt)?;
+
+        let mut bluetoothd = Command::new(&self.bluetoothd);
+        bluetoothd.arg("--nodetach").env("DBUS_SYSTEM_BUS_ADDRESS", &test_bed.bus_address);
+        if self.experimental {
+            bluetoothd.arg("--experimental");
+        }
+        test_bed.spawn(&mut bluetoothd)?;
+
+        let session = Session::with_bus_address(&test_bed.bus_address).await?;
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            match session.adapter_names().await {
+                Ok(names) if names.len() >= self.controllers.into() => break,
+                _ if Instant::now() >= deadline => {
+                    return Err(Error {
+                        kind: ErrorKind::Internal(InternalErrorKind::Io(std::io::ErrorKind::TimedOut)),
+                        message: "emulated controllers did not become available".to_string(),
+                    })
+                }
+                _ => sleep(Duration::from_millis(100)).await,
+            }
+        }
+        test_bed.session = Some(session);
+
+        Ok(test_bed)
+    }
+}
+
+/// Private Bluetooth daemon running on emulated controllers.
+///
+/// Use [TestBedBuilder] to start a test bed.
+/// All spawned processes are terminated when the test bed is dropped.
+pub struct TestBed {
+    dir: PathBuf,
+    processes: Vec<Child>,
+    bus_address: String,
+    session: Option<Session>,
+}
+
+impl fmt::Debug for TestBed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TestBed").field("bus_address", &self.bus_address).finish()
+    }
+}
+
+impl TestBed {
+    /// Starts a test bed with one dual mode controller.
+    pub async fn new() -> Result<Self> {
+        TestBedBuilder::new().start().await
+    }
+
+    /// Session connected to the private Bluetooth daemon.
+    pub fn session(&self) -> &Session {
+        self.session.as_ref().unwrap()
+    }
+
+    /// Address of the private D-Bus daemon.
+    pub fn bus_address(&self) -> &str {
+        &self.bus_address
+    }
+
+    fn spawn(&mut self, cmd: &mut Command) -> Result<()> {
+        log::trace!("Spawning {:?}", cmd);
+        let child = cmd.stdin(Stdio::null()).stdout(Stdio::null()).spawn()?;
+        self.processes.push(child);
+        Ok(())
+    }
+}
+
+impl Drop for TestBed {
+    fn drop(&mut self) {
+        self.session.take();
+        for mut child in self.processes.drain(..).rev() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Configuration of a private D-Bus daemon listening on the specified address.
+fn dbus_config(address: &str) -> String {
+    format!(
+        r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-Bus Bus Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+<busconfig>
+  <type>system</type>
+  <listen>{address}</listen>
+  <auth>EXTERNAL</auth>
+  <policy context="default">
+    <allow user="*"/>
+    <allow own="*"/>
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+  </policy>
+</busconfig>
+"#
+    )
+}
+
+/// Waits until the specified path exists.
+async fn wait_for_path(path: &Path, timeout: Duration) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    while !path.exists() {
+        if Instant::now() >= deadline {
+            return Err(Error {
+                kind: ErrorKind::Internal(InternalErrorKind::Io(std::io::ErrorKind::TimedOut)),
+                message: format!("{} was not created", path.display()),
+            });
+        }
+        sleep(Duration::from_millis(50)).await;
+    }
+    Ok(())
+}
@@ -1,3 +1,8 @@
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    ops::Deref,
+    str::FromStr,
+};
 use uuid::Uuid;
 
 /// UUID extension trait to convert to and from Bluetooth short UUIDs.
@@ -36,10 +41,141 @@ impl UuidExt for Uuid {
     }
 
     fn from_u32(v: u32) -> Uuid {
-        Uuid::from_u128(BASE_UUID | ((v as u128) << 96))
+        uuid_from_u32(v)
     }
 
     fn from_u16(v: u16) -> Uuid {
-        Uuid::from_u128(BASE_UUID | ((v as u128) << 96))
+        uuid_from_u16(v)
+    }
+}
+
+/// Long form of 32-bit short form Bluetooth UUID.
+///
+/// In contrast to [UuidExt::from_u32] this can be used in constant expressions.
+pub const fn uuid_from_u32(v: u32) -> Uuid {
+    Uuid::from_u128(BASE_UUID | ((v as u128) << 96))
+}
+
+/// Long form of 16-bit short form Bluetooth UUID.
+///
+/// In contrast to [UuidExt::from_u16] this can be used in constant expressions.
+/// The [uuid16](crate::uuid16) macro provides a shorthand.
+pub const fn uuid_from_u16(v: u16) -> Uuid {
+    Uuid::from_u128(BASE_UUID | ((v as u128) << 96))
+}
+
+/// Long form of 16-bit short form Bluetooth UUID, usable in constant expressions.
+///
+/// This allows declaring tables of known services and characteristics as constants,
+/// for example `const BATTERY_SERVICE: Uuid = uuid16!(0x180f);`.
+#[macro_export]
+macro_rules! uuid16 {
+    ($v:expr) => {
+        $crate::uuid_from_u16($v)
+    };
+}
+
+/// Long form of 32-bit short form Bluetooth UUID, usable in constant expressions.
+#[macro_export]
+macro_rules! uuid32 {
+    ($v:expr) => {
+        $crate::uuid_from_u32($v)
+    };
+}
+
+/// Bluetooth UUID that is displayed in short form when possible.
+///
+/// UUIDs derived from the Bluetooth base UUID are displayed in their 16-bit or 32-bit
+/// short form, for example `180f` for the battery service, all other UUIDs in full.
+/// Parsing accepts both short forms, with or without a `0x` prefix, and the full form.
+///
+/// This dereferences to and converts from and into [Uuid].
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+pub struct BtUuid(pub Uuid);
+
+impl Deref for BtUuid {
+    type Target = Uuid;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for BtUuid {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match (self.0.as_u16(), self.0.as_u32()) {
+            (Some(short), _) => write!(f, "{short:04x}"),
+            (None, Some(short)) => write!(f, "{short:08x}"),
+            (None, None) => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl Debug for BtUuid {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+impl From<Uuid> for BtUuid {
+    fn from(uuid: Uuid) -> Self {
+        Self(uuid)
+    }
+}
+
+impl From<BtUuid> for Uuid {
+    fn from(uuid: BtUuid) -> Self {
+        uuid.0
+    }
+}
+
+impl BtUuid {
+    /// Bluetooth UUID of 16-bit short form, usable in constant expressions.
+    pub const fn from_u16(v: u16) -> Self {
+        Self(uuid_from_u16(v))
+    }
+
+    /// Bluetooth UUID of 32-bit short form, usable in constant expressions.
+    pub const fn from_u32(v: u32) -> Self {
+        Self(uuid_from_u32(v))
+    }
+}
+
+impl From<u16> for BtUuid {
+    fn from(short: u16) -> Self {
+        Self(Uuid::from_u16(short))
+    }
+}
+
+impl From<u32> for BtUuid {
+    fn from(short: u32) -> Self {
+        Self(Uuid::from_u32(short))
+    }
+}
+
+/// Invalid Bluetooth UUID error.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InvalidUuid(pub String);
+
+impl Display for InvalidUuid {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "invalid Bluetooth UUID: {}", &self.0)
+    }
+}
+
+impl std::error::Error for InvalidUuid {}
+
+impl FromStr for BtUuid {
+    type Err = InvalidUuid;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidUuid(s.to_string());
+        let trimmed = s.trim();
+        let hex = trimmed.strip_prefix("0x").or_else(|| trimmed.strip_prefix("0X")).unwrap_or(trimmed);
+        match hex.len() {
+            4 => u16::from_str_radix(hex, 16).map(Self::from).map_err(|_| invalid()),
+            8 => u32::from_str_radix(hex, 16).map(Self::from).map_err(|_| invalid()),
+            _ => Uuid::parse_str(trimmed).map(Self).map_err(|_| invalid()),
+        }
     }
 }
@@ -0,0 +1,151 @@
+//! Wake-on-Bluetooth configuration.
+//!
+//! Allows remote devices, such as remote controls and sensors, to wake the host from suspend.
+//! This requires that wakeup is enabled for the Bluetooth controller, see
+//! [set_adapter_wake_enabled], and that waking is allowed for the remote device, see [enable_device_wake].
+//!
+//! Using an advertisement monitor, see [WakeMonitor], devices matching advertisement patterns
+//! can be allowed to wake the host once they are found.
+
+use std::{fs, io::ErrorKind as IoErrorKind, path::PathBuf};
+use tokio::task::spawn_blocking;
+
+use crate::{Adapter, Device, Error, ErrorKind, Result};
+
+#[cfg(feature = "monitor")]
+use crate::monitor::{Monitor, MonitorEvent, MonitorManager, Pattern};
+#[cfg(feature = "monitor")]
+use futures::StreamExt;
+#[cfg(feature = "monitor")]
+use std::fmt;
+#[cfg(feature = "monitor")]
+use tokio::sync::oneshot;
+
+/// Path of the sysfs wakeup attribute of the Bluetooth controller.
+fn wakeup_path(adapter_name: &str) -> PathBuf {
+    PathBuf::from(format!("/sys/class/bluetooth/{adapter_name}/device/power/wakeup"))
+}
+
+/// Returns whether the Bluetooth controller of the adapter may wake the host from suspend.
+///
+/// Returns [None] if the controller does not support waking the host.
+pub async fn is_adapter_wake_enabled(adapter: &Adapter) -> Result<Option<bool>> {
+    let path = wakeup_path(adapter.name());
+    match spawn_blocking(move || fs::read_to_string(path)).await? {
+        Ok(value) => Ok(Some(value.trim() == "enabled")),
+        Err(err) if err.kind() == IoErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Sets whether the Bluetooth controller of the adapter may wake the host from suspend.
+///
+/// This modifies the wakeup setting of the controller in sysfs and thus requires
+/// root privileges.
+/// The setting is not persisted across reboots.
+pub async fn set_adapter_wake_enabled(adapter: &Adapter, enabled: bool) -> Result<()> {
+    let path = wakeup_path(adapter.name());
+    let value = if enabled { "enabled" } else { "disabled" };
+    match spawn_blocking(move || fs::write(path, value)).await? {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == IoErrorKind::NotFound => Err(Error {
+            kind: ErrorKind::NotSupported,
+            message: "Bluetooth controller does not support waking the host".to_string(),
+        }),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Allows the device to wake the host from suspend.
+///
+/// The device must be paired and support remote wakeup.
+/// It is marked as trusted, since the Bluetooth daemon only reconnects
+/// trusted devices after resume.
+///
+/// Wakeup must also be enabled for the Bluetooth controller, see [set_adapter_wake_enabled].
+pub async fn enable_device_wake(device: &Device) -> Result<()> {
+    if device.is_wake_allowed().await?.is_none() {
+        return Err(Error {
+            kind: ErrorKind::NotSupported,
+            message: format!("device {} does not support remote wakeup", device.address()),
+        });
+    }
+    if !device.is_paired().await? {
+        return Err(Error {
+            kind: ErrorKind::NotReady,
+            message: format!("device {} must be paired to wake the host", device.address()),
+        });
+    }
+    device.set_trusted(true).await?;
+    device.set_wake_allowed(true).await
+}
+
+/// Forbids the device to wake the host from suspend.
+pub async fn disable_device_wake(device: &Device) -> Result<()> {
+    match device.is_wake_allowed().await? {
+        Some(true) => device.set_wake_allowed(false).await,
+        _ => Ok(()),
+    }
+}
+
+/// Allows paired devices matching advertisement patterns to wake the host from suspend.
+///
+/// An advertisement monitor with the specified patterns is registered and
+/// waking is enabled using [enable_device_wake] for each paired device that it finds.
+/// Devices that do not support remote wakeup are skipped.
+///
+/// Waking is not disabled again when the monitor is dropped.
+///
+/// Drop to unregister the advertisement monitor.
+#[cfg(feature = "monitor")]
+#[cfg_attr(docsrs, doc(cfg(feature = "monitor")))]
+#[must_use = "the WakeMonitor must be held for the advertisement monitor to be active"]
+pub struct WakeMonitor {
+    _drop_tx: oneshot::Sender<()>,
+}
+
+#[cfg(feature = "monitor")]
+impl fmt::Debug for WakeMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WakeMonitor")
+    }
+}
+
+#[cfg(feature = "monitor")]
+impl WakeMonitor {
+    /// Registers an advertisement monitor matching any of the specified patterns
+    /// on the adapter.
+    ///
+    /// Use [PatternsBuilder](crate::monitor::PatternsBuilder) to construct the patterns.
+    pub async fn new(adapter: &Adapter, patterns: Vec<Pattern>) -> Result<Self> {
+        let manager: MonitorManager = adapter.monitor().await?;
+        let mut handle = manager.register(Monitor { patterns: Some(patterns), ..Default::default() }).await?;
+
+        let (drop_tx, mut drop_rx) = oneshot::channel();
+        let adapter = adapter.clone();
+        adapter.clone().spawn(async move {
+            let _manager = manager;
+            loop {
+                tokio::select! {
+                    evt = handle.next() => match evt {
+                        Some(MonitorEvent::DeviceFound(id)) if id.adapter == adapter.name() => {
+                            let Ok(device) = adapter.device(id.device) else { continue };
+                            if !device.is_paired().await.unwrap_or_default() {
+                                continue;
+                            }
+                            match enable_device_wake(&device).await {
+                                Ok(()) => log::trace!("Enabled wake for device {}", id.device),
+                                Err(err) => log::debug!("Cannot enable wake for device {}: {}", id.device, &err),
+                            }
+                        }
+                        Some(_) => (),
+                        None => break,
+                    },
+                    _ = &mut drop_rx => break,
+                }
+            }
+        });
+
+        Ok(Self { _drop_tx: drop_tx })
+    }
+}
@@ -0,0 +1,180 @@
+//! End-to-end tests running against a private Bluetooth daemon on emulated controllers.
+//!
+//! These tests require the `hci_vhci` kernel module, the `dbus-daemon`, `btvirt` and
+//! `bluetoothd` binaries and root privileges, see the documentation of `bluer::test_harness`.
+//! The paths of the `btvirt` and `bluetoothd` binaries can be overridden using the
+//! `BLUER_TEST_BTVIRT` and `BLUER_TEST_BLUETOOTHD` environment variables.
+//!
+//! Since they are ignored by default, run them using `cargo test --test integration -- --ignored`.
+
+use bluer::{
+    adv::Advertisement,
+    gatt::local::{
+        Application, Characteristic, CharacteristicRead, CharacteristicWrite, CharacteristicWriteMethod, Service,
+    },
+    test_harness::{TestBed, TestBedBuilder},
+    Adapter, AdapterEvent, Address, Result,
+};
+use futures::{FutureExt, StreamExt};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::timeout;
+use uuid::Uuid;
+
+const SERVICE_UUID: Uuid = Uuid::from_u128(0x7a1b5c3e_2f4d_4e6a_9b8c_0d1e2f3a4b5c);
+const CHARACTERISTIC_UUID: Uuid = Uuid::from_u128(0x7a1b5c3f_2f4d_4e6a_9b8c_0d1e2f3a4b5c);
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+async fn start(controllers: u8) -> Result<TestBed> {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut builder = TestBedBuilder::new().controllers(controllers);
+    if let Ok(btvirt) = std::env::var("BLUER_TEST_BTVIRT") {
+        builder = builder.btvirt(btvirt);
+    }
+    if let Ok(bluetoothd) = std::env::var("BLUER_TEST_BLUETOOTHD") {
+        builder = builder.bluetoothd(bluetoothd);
+    }
+    builder.start().await
+}
+
+async fn powered_adapters(test_bed: &TestBed) -> Result<Vec<Adapter>> {
+    let session = test_bed.session();
+    let mut adapters = Vec::new();
+    for name in session.adapter_names().await? {
+        let adapter = session.adapter(&name)?;
+        adapter.set_powered(true).await?;
+        adapters.push(adapter);
+    }
+    Ok(adapters)
+}
+
+async fn discover(adapter: &Adapter, address: Address) -> Result<()> {
+    let events = adapter.discover_devices().await?;
+    let mut events = Box::pin(events);
+    let found = timeout(TIMEOUT, async {
+        while let Some(evt) = events.next().await {
+            if let AdapterEvent::DeviceAdded(addr) = evt {
+                if addr == address {
+                    return true;
+                }
+            }
+        }
+        false
+    })
+    .await;
+    assert!(matches!(found, Ok(true)), "device {address} was not discovered");
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires emulated Bluetooth controllers"]
+async fn emulated_controllers_are_available() -> Result<()> {
+    let test_bed = start(2).await?;
+    let adapters = powered_adapters(&test_bed).await?;
+    assert_eq!(adapters.len(), 2);
+    for adapter in adapters {
+        assert!(adapter.is_powered().await?);
+        assert_ne!(adapter.address().await?, Address::any());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires emulated Bluetooth controllers"]
+async fn advertisement_is_discovered() -> Result<()> {
+    let test_bed = start(2).await?;
+    let adapters = powered_adapters(&test_bed).await?;
+    let (peripheral, central) = (&adapters[0], &adapters[1]);
+
+    let _adv = peripheral
+        .advertise(Advertisement {
+            service_uuids: [SERVICE_UUID].into_iter().collect(),
+            discoverable: Some(true),
+            local_name: Some("bluer-test".to_string()),
+            ..Default::default()
+        })
+        .await?;
+
+    let address = peripheral.address().await?;
+    discover(central, address).await?;
+    let device = central.device(address)?;
+    assert_eq!(device.name().await?.as_deref(), Some("bluer-test"));
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires emulated Bluetooth controllers"]
+async fn gatt_characteristic_is_read_and_written() -> Result<()> {
+    let test_bed = start(2).await?;
+    let adapters = powered_adapters(&test_bed).await?;
+    let (peripheral, central) = (&adapters[0], &adapters[1]);
+
+    let value = Arc::new(Mutex::new(vec![1, 2, 3]));
+    let value_read = value.clone();
+    let value_write = value.clone();
+    let _app = peripheral
+        .serve_gatt_application(Application {
+            services: vec![Service {
+                uuid: SERVICE_UUID,
+                primary: true,
+                characteristics: vec![Characteristic {
+                    uuid: CHARACTERISTIC_UUID,
+                    read: Some(CharacteristicRead {
+                        read: true,
+                        fun: Box::new(move |_req| {
+                            let value = value_read.lock().unwrap().clone();
+                            async move { Ok(value) }.boxed()
+                        }),
+                        ..Default::default()
+                    }),
+                    write: Some(CharacteristicWrite {
+                        write: true,
+                        method: CharacteristicWriteMethod::Fun(Box::new(move |new_value, _req| {
+                            *value_write.lock().unwrap() = new_value;
+                            async move { Ok(()) }.boxed()
+                        })),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        })
+        .await?;
+    let _adv = peripheral
+        .advertise(Advertisement {
+            service_uuids: [SERVICE_UUID].into_iter().collect(),
+            discoverable: Some(true),
+            ..Default::default()
+        })
+        .await?;
+
+    let address = peripheral.address().await?;
+    discover(central, address).await?;
+    let device = central.device(address)?;
+    device.connect().await?;
+    device.wait_services_resolved(TIMEOUT).await?;
+
+    let mut characteristic = None;
+    for service in device.services().await? {
+        if service.uuid().await? == SERVICE_UUID {
+            for char in service.characteristics().await? {
+                if char.uuid().await? == CHARACTERISTIC_UUID {
+                    characteristic = Some(char);
+                }
+            }
+        }
+    }
+    let characteristic = characteristic.expect("characteristic not found");
+
+    assert_eq!(characteristic.read().await?, vec![1, 2, 3]);
+    characteristic.write(&[4, 5]).await?;
+    assert_eq!(*value.lock().unwrap(), vec![4, 5]);
+    assert_eq!(characteristic.read().await?, vec![4, 5]);
+
+    device.disconnect().await?;
+    Ok(())
+}